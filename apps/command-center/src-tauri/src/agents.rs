@@ -0,0 +1,573 @@
+//! Agent execution
+//!
+//! Launches the agent CLI from `agentExecution.claudeCodePath` on a task, in
+//! the task's project repository. Output is streamed to the frontend as
+//! `agent-output` events while the agent works, and each finished run is
+//! recorded on the task with its exit code, duration and log file.
+//!
+//! Runs are normally queued as jobs: a scheduler thread starts them within
+//! the configured concurrency limits, retries failures, and reports every
+//! change as an `agent-job` event and, per `agentExecution.notifications`,
+//! as a notification.
+//!
+//! Inbox instructions addressed to Claude start the agent on their own (see
+//! `inbox_trigger`), and its answer comes back as an inbox reply.
+//!
+//! The prompt for a task includes the definition of the agent suited to it
+//! (see `router`): designer, architect, qa, dev or walkthrough.
+//!
+//! Every run, and every agent session reported by a CLI hook, is recorded
+//! under `sessions/` with the files and board entries it touched.
+
+mod inbox_trigger;
+mod prompt;
+mod queue;
+mod router;
+mod runner;
+mod sessions;
+
+pub use inbox_trigger::InboxTrigger;
+pub use queue::{AgentJob, JobQueue};
+pub use router::AgentSuggestion;
+pub use runner::AgentRun;
+pub use sessions::{AgentSession, BoardMutation, HOOKS_DIR, SESSIONS_DIR};
+
+use queue::{Executor, JobState, Listener, QueuePolicy};
+use runner::{AgentCommand, RunStatus, RunTarget, RUNS_DIR};
+
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::AtomicBool;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager, State};
+
+use crate::audit::{self, Action, Actor};
+use crate::config::{self, AgentExecutionConfig, AppConfig};
+use crate::data::{self, Project, Task};
+use crate::notifications::{self, EntityLink, NotificationSource};
+use crate::watcher::WriteRegistry;
+use crate::AppState;
+
+/// How often the scheduler looks for jobs it can start
+const SCHEDULE_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Payload of `agent-queue-error`
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct QueueErrorEvent {
+    pub message: String,
+}
+
+fn report_queue_error(app: &AppHandle, message: String) {
+    let _ = app.emit("agent-queue-error", QueueErrorEvent { message });
+}
+
+/// Payload of `agent-run-started`
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct AgentRunStarted {
+    pub run_id: String,
+    pub task_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub inbox_item_id: Option<String>,
+    pub project_id: String,
+}
+
+impl AgentRunStarted {
+    fn new(run_id: &str, target: &RunTarget) -> Self {
+        Self {
+            run_id: run_id.to_string(),
+            task_id: target.task_id.clone(),
+            inbox_item_id: target.inbox_item_id.clone(),
+            project_id: target.project_id.clone(),
+        }
+    }
+}
+
+/// The task and its project
+fn load_task(data_dir: &Path, task_id: &str) -> Result<(Task, Project), String> {
+    let task = data::load_tasks(data_dir)?
+        .tasks
+        .into_iter()
+        .find(|t| t.id == task_id)
+        .ok_or_else(|| format!("Task not found: {}", task_id))?;
+    let project = data::load_projects(data_dir)?
+        .projects
+        .into_iter()
+        .find(|p| p.id == task.project_id)
+        .ok_or_else(|| format!("Project not found: {}", task.project_id))?;
+
+    Ok((task, project))
+}
+
+/// The project's repository, where the agent runs
+fn repo_path(project: &Project) -> Result<PathBuf, String> {
+    let repo = project
+        .extra
+        .get("repoPath")
+        .and_then(|v| v.as_str())
+        .filter(|p| !p.is_empty())
+        .map(PathBuf::from)
+        .ok_or_else(|| format!("Project {} has no repoPath", project.id))?;
+    if !repo.is_dir() {
+        return Err(format!("Repository not found: {}", repo.display()));
+    }
+    Ok(repo)
+}
+
+/// The CLI invocation for `prompt` in `repo`, per the agentExecution settings
+fn agent_command(settings: &AgentExecutionConfig, repo: PathBuf, prompt: String) -> AgentCommand {
+    AgentCommand {
+        program: settings.claude_code_path.clone(),
+        args: settings.args.clone(),
+        cwd: repo,
+        prompt,
+        timeout: Duration::from_millis(settings.timeout),
+    }
+}
+
+/// The full prompt for `task`: the master instructions, the definition of
+/// `agent` (or of the task's assigned agent, or of the one the router
+/// picks) and the task itself
+fn task_prompt(
+    data_dir: &Path,
+    config: &AppConfig,
+    project: &Project,
+    task: &Task,
+    agent: Option<&str>,
+) -> Result<String, String> {
+    let definition = match agent {
+        Some(agent) => Some(router::load_definition(
+            &config.agents,
+            &router::agent_name(agent),
+        )?),
+        // An assigned or suggested agent without a definition file just
+        // gets no definition
+        None => prompt::assigned_agent(task)
+            .map(router::agent_name)
+            .or_else(|| router::suggest(task, &config.agents).agent)
+            .and_then(|agent| router::load_definition(&config.agents, &agent).ok()),
+    };
+    let instructions = prompt::load_instructions(data_dir);
+    let preamble = router::preamble(instructions.as_deref(), definition.as_deref());
+
+    Ok(prompt::build_prompt(preamble.as_deref(), project, task))
+}
+
+/// Run the agent on a task and record the run. Blocks until it finishes
+/// or `cancel` is set.
+pub fn run_task(
+    app: &AppHandle,
+    data_dir: &Path,
+    writes: &WriteRegistry,
+    task_id: &str,
+    cancel: &AtomicBool,
+) -> Result<AgentRun, String> {
+    let result = run_and_record(app, data_dir, writes, task_id, cancel);
+
+    let project_id = result.as_ref().ok().map(|run| run.project_id.as_str());
+    let mut action = Action::new(Actor::Agent, "run_agent").entity("task", task_id, project_id);
+    let outcome = match &result {
+        Ok(run) => {
+            action = action.detail(&run.id);
+            match run.status {
+                RunStatus::Succeeded => Ok(()),
+                status => Err(format!("Agent run {}", status.as_str())),
+            }
+        }
+        Err(e) => Err(e.clone()),
+    };
+    let _ = audit::record(data_dir, action, outcome);
+    result
+}
+
+fn run_and_record(
+    app: &AppHandle,
+    data_dir: &Path,
+    writes: &WriteRegistry,
+    task_id: &str,
+    cancel: &AtomicBool,
+) -> Result<AgentRun, String> {
+    let config = config::load_config(data_dir);
+    let (task, project) = load_task(data_dir, task_id)?;
+    let repo = repo_path(&project)?;
+    let prompt = task_prompt(data_dir, &config, &project, &task, None)?;
+    let command = agent_command(&config.agent_execution, repo, prompt);
+
+    let run_id = data::generate_id("agent-run");
+    let target = RunTarget::task(&task);
+    let _ = app.emit("agent-run-started", &AgentRunStarted::new(&run_id, &target));
+
+    let agent = prompt::assigned_agent(&task).unwrap_or("claude");
+    let run = sessions::run_recorded(
+        data_dir,
+        &run_id,
+        &target,
+        agent,
+        &command,
+        cancel,
+        &|output| {
+            let _ = app.emit("agent-output", &output);
+        },
+    )?;
+    runner::record_run(data_dir, writes, &run)?;
+
+    let _ = app.emit("agent-run-finished", &run);
+    Ok(run)
+}
+
+fn data_context(app: &AppHandle) -> (PathBuf, WriteRegistry) {
+    let state = app.state::<Mutex<AppState>>();
+    let state = state.lock().unwrap();
+    (state.data_dir.clone(), state.writes.clone())
+}
+
+/// Report a job's new state to the frontend, and as a notification when
+/// agentExecution.notifications asks for it
+fn announce_job(app: &AppHandle, job: &AgentJob) {
+    let _ = app.emit("agent-job", job);
+
+    let (data_dir, _) = data_context(app);
+    let wanted = config::load_config(&data_dir).agent_execution.notifications;
+    let title = match job.state {
+        JobState::Running if wanted.on_start => "Agent started",
+        JobState::Succeeded if wanted.on_complete => "Agent finished",
+        JobState::Failed if wanted.on_error => "Agent failed",
+        _ => return,
+    };
+
+    let task_title = data::load_tasks(&data_dir)
+        .ok()
+        .and_then(|tasks| tasks.tasks.into_iter().find(|t| t.id == job.task_id))
+        .map(|t| t.title)
+        .unwrap_or_else(|| job.task_id.clone());
+    let body = match &job.error {
+        Some(error) if job.state == JobState::Failed => format!("{}: {}", task_title, error),
+        _ => task_title,
+    };
+    notifications::notify(
+        app,
+        NotificationSource::Agent,
+        Some(EntityLink::Task(job.task_id.clone())),
+        title,
+        &body,
+    );
+}
+
+/// Close sessions the last run of the app left open, load the job queue
+/// and start the scheduler that runs its jobs
+pub fn start_queue(app: AppHandle) {
+    let (data_dir, _) = data_context(&app);
+    sessions::close_interrupted(&data_dir);
+    let queue = app.state::<JobQueue>().inner().clone();
+    reload_policy(&app);
+    match queue.open(&data_dir) {
+        Ok(requeued) => {
+            for job in requeued {
+                let _ = app.emit("agent-job", &job);
+            }
+        }
+        Err(e) => report_queue_error(&app, e),
+    }
+
+    let handle = app.clone();
+    let executor: Executor = Arc::new(move |job, cancel| {
+        let (data_dir, writes) = data_context(&handle);
+        run_task(&handle, &data_dir, &writes, &job.task_id, cancel)
+    });
+    let handle = app.clone();
+    let listener: Listener = Arc::new(move |job| announce_job(&handle, job));
+
+    std::thread::spawn(move || {
+        // Reported once until the queue saves again, not every tick
+        let mut failing = None;
+        loop {
+            let policy = queue.policy();
+            match queue.tick(chrono::Utc::now(), &policy, &executor, &listener) {
+                Ok(_) => failing = None,
+                Err(e) if failing.as_ref() != Some(&e) => {
+                    report_queue_error(&app, e.clone());
+                    failing = Some(e);
+                }
+                Err(_) => {}
+            }
+            std::thread::sleep(SCHEDULE_INTERVAL);
+        }
+    });
+}
+
+/// Read the scheduler's limits from config.json again, on start, after a
+/// workspace switch and whenever the file changes
+pub fn reload_policy(app: &AppHandle) {
+    let (data_dir, _) = data_context(app);
+    let config = config::load_config(&data_dir).agent_execution;
+    app.state::<JobQueue>()
+        .set_policy(QueuePolicy::from_config(&config));
+}
+
+/// Queue every inbox item the agent should pick up. `resume` also takes
+/// items whose run was cut short when the app last closed.
+pub fn scan_inbox(app: &AppHandle, resume: bool) {
+    let (data_dir, _) = data_context(app);
+    if !inbox_trigger::enabled(&config::load_config(&data_dir).agent_execution) {
+        return;
+    }
+    let Ok(inbox) = data::load_inbox(&data_dir) else {
+        return;
+    };
+
+    let trigger = app.state::<InboxTrigger>();
+    for item_id in inbox_trigger::pending(&inbox, resume) {
+        let handle = app.clone();
+        trigger.push(item_id, move |item_id, cancel| {
+            answer_inbox_item(&handle, item_id, cancel)
+        });
+    }
+}
+
+/// Run the agent on an inbox item and report the outcome as an
+/// `agent-inbox-trigger` event and, per agentExecution.notifications, as a
+/// notification
+fn answer_inbox_item(app: &AppHandle, item_id: &str, cancel: &AtomicBool) {
+    let (data_dir, writes) = data_context(app);
+    let outcome = inbox_trigger::process(
+        &data_dir,
+        &writes,
+        item_id,
+        cancel,
+        &|started| {
+            let _ = app.emit("agent-run-started", started);
+        },
+        &|output| {
+            let _ = app.emit("agent-output", &output);
+        },
+    );
+    let outcome = match outcome {
+        Ok(Some(outcome)) => outcome,
+        Ok(None) => return,
+        Err(e) => inbox_trigger::TriggerOutcome::failed(item_id, e),
+    };
+    let action = Action::new(Actor::Claude, "answer_inbox_item")
+        .entity("inboxItem", item_id, outcome.project_id.as_deref())
+        .detail(&outcome.state);
+    let answered = match (outcome.state.as_str(), &outcome.error) {
+        ("succeeded" | "dryRun", _) => Ok(()),
+        (_, Some(error)) => Err(error.clone()),
+        (state, None) => Err(format!("Not answered ({})", state)),
+    };
+    let _ = audit::record(&data_dir, action, answered);
+
+    if let Some(run) = &outcome.run {
+        let _ = app.emit("agent-run-finished", run);
+    }
+    let _ = app.emit("agent-inbox-trigger", &outcome);
+
+    let wanted = config::load_config(&data_dir).agent_execution.notifications;
+    let title = match outcome.state.as_str() {
+        "succeeded" if wanted.on_complete => "Claude replied",
+        "failed" | "timedOut" | "unrouted" if wanted.on_error => "Claude couldn't finish",
+        _ => return,
+    };
+    let text = data::load_inbox(&data_dir)
+        .ok()
+        .and_then(|inbox| inbox.items.into_iter().find(|i| i.id == item_id))
+        .map(|item| item.text)
+        .unwrap_or_default();
+    notifications::notify(
+        app,
+        NotificationSource::Agent,
+        Some(EntityLink::InboxItem(item_id.to_string())),
+        title,
+        &text,
+    );
+}
+
+/// Credit board changes made outside the app to the agent sessions open
+/// at the time
+pub fn record_board_mutations(app: &AppHandle, mutations: &[BoardMutation]) {
+    let (data_dir, _) = data_context(app);
+    for session in sessions::record_mutations(&data_dir, mutations) {
+        let _ = app.emit("agent-session", &session);
+    }
+}
+
+/// Apply a payload an agent CLI hook dropped into `sessions/hooks/`, then
+/// remove it. A payload that cannot be applied is removed all the same.
+pub fn ingest_session_hook(app: &AppHandle, path: &Path) -> Result<(), String> {
+    let (data_dir, _) = data_context(app);
+    let Some(payload) = fs::read_to_string(path)
+        .ok()
+        .and_then(|content| serde_json::from_str::<serde_json::Value>(&content).ok())
+    else {
+        // Still being written; the next event for it tries again
+        return Ok(());
+    };
+
+    let projects = data::load_projects(&data_dir)
+        .map(|p| p.projects)
+        .unwrap_or_default();
+    let tasks = data::load_tasks(&data_dir)
+        .map(|t| t.tasks)
+        .unwrap_or_default();
+    let applied = sessions::apply_hook(&data_dir, &payload, &projects, &tasks);
+    let _ = fs::remove_file(path);
+    let session =
+        applied.map_err(|e| format!("Ignoring agent hook payload {}: {}", path.display(), e))?;
+    let _ = app.emit("agent-session", &session);
+    Ok(())
+}
+
+// Tauri commands
+
+/// Run the agent CLI on a task in its project's repository. The run is
+/// queued like any other job, so it can be cancelled and counts towards the
+/// limits. Output arrives as `agent-output` events; the last run is
+/// returned once the job is done and also sent as `agent-run-finished`.
+#[tauri::command]
+pub async fn run_agent(
+    task_id: String,
+    app: AppHandle,
+    state: State<'_, Mutex<AppState>>,
+    queue: State<'_, JobQueue>,
+) -> Result<AgentRun, String> {
+    let job = enqueue_agent_job(task_id, app, state, queue.clone())?;
+    let done = queue.watch(&job.id);
+
+    tokio::task::spawn_blocking(move || {
+        done.recv()
+            .unwrap_or_else(|_| Err(format!("Agent job {} was dropped", job.id)))
+    })
+    .await
+    .map_err(|e| format!("Agent run failed: {}", e))?
+}
+
+/// Queue an agent run of a task; it starts when the concurrency limits
+/// allow and is reported through `agent-job` events
+#[tauri::command]
+pub fn enqueue_agent_job(
+    task_id: String,
+    app: AppHandle,
+    state: State<'_, Mutex<AppState>>,
+    queue: State<'_, JobQueue>,
+) -> Result<AgentJob, String> {
+    let data_dir = data::get_data_dir(&state);
+    let (task, project) = load_task(&data_dir, &task_id)?;
+    repo_path(&project)?;
+    let policy = queue.policy();
+
+    let result = queue.enqueue(&task.id, &task.project_id, &policy);
+    let mut action = Action::new(Actor::User, "enqueue_agent_job").entity(
+        "task",
+        &task.id,
+        Some(&task.project_id),
+    );
+    if let Ok(job) = &result {
+        action = action.entity("job", &job.id, Some(&job.project_id));
+    }
+    let job = audit::record(&data_dir, action, result)?;
+    let _ = app.emit("agent-job", &job);
+    Ok(job)
+}
+
+/// Queued, running and finished agent jobs, newest first
+#[tauri::command]
+pub fn list_agent_jobs(queue: State<'_, JobQueue>) -> Vec<AgentJob> {
+    queue.list()
+}
+
+/// Cancel a queued job, or stop a running one
+#[tauri::command]
+pub fn cancel_agent_job(
+    job_id: String,
+    app: AppHandle,
+    state: State<'_, Mutex<AppState>>,
+    queue: State<'_, JobQueue>,
+) -> Result<AgentJob, String> {
+    let result = queue.cancel(&job_id);
+    let project_id = result.as_ref().ok().map(|job| job.project_id.as_str());
+    let action = Action::new(Actor::User, "cancel_agent_job").entity("job", &job_id, project_id);
+    let job = audit::record(&data::get_data_dir(&state), action, result)?;
+    let _ = app.emit("agent-job", &job);
+    Ok(job)
+}
+
+/// Full output of a past run
+#[tauri::command]
+pub fn read_agent_log(run_id: String, state: State<'_, Mutex<AppState>>) -> Result<String, String> {
+    if !run_id
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '-')
+    {
+        return Err(format!("Invalid run id: {}", run_id));
+    }
+
+    let path = data::get_data_dir(&state)
+        .join(RUNS_DIR)
+        .join(format!("{}.log", run_id));
+    std::fs::read_to_string(path).map_err(|_| format!("No log for run {}", run_id))
+}
+
+/// Switch starting the agent from inbox items on or off (the kill switch,
+/// which also stops the item in progress), and optionally dry-run mode
+#[tauri::command]
+pub fn set_inbox_trigger(
+    enabled: bool,
+    dry_run: Option<bool>,
+    app: AppHandle,
+    state: State<'_, Mutex<AppState>>,
+    trigger: State<'_, InboxTrigger>,
+) -> Result<(), String> {
+    let data_dir = data::get_data_dir(&state);
+    let saved = config::save_inbox_trigger(&data_dir, &data::get_writes(&state), enabled, dry_run);
+    let detail = if enabled { "on" } else { "off" };
+    let action = Action::new(Actor::User, "set_inbox_trigger").detail(detail);
+    audit::record(&data_dir, action, saved)?;
+
+    if enabled {
+        scan_inbox(&app, false);
+    } else {
+        trigger.stop();
+    }
+    Ok(())
+}
+
+/// The agent the orchestrator's rules pick for a task, and why
+#[tauri::command]
+pub fn suggest_agent(
+    task_id: String,
+    state: State<'_, Mutex<AppState>>,
+) -> Result<AgentSuggestion, String> {
+    let data_dir = data::get_data_dir(&state);
+    let (task, _) = load_task(&data_dir, &task_id)?;
+    Ok(router::suggest(
+        &task,
+        &config::load_config(&data_dir).agents,
+    ))
+}
+
+/// The full prompt an agent gets for a task: master instructions, agent
+/// definition and task. Uses the task's assigned agent, or the suggested
+/// one, unless `agent` names one.
+#[tauri::command]
+pub fn build_agent_prompt(
+    task_id: String,
+    agent: Option<String>,
+    state: State<'_, Mutex<AppState>>,
+) -> Result<String, String> {
+    let data_dir = data::get_data_dir(&state);
+    let (task, project) = load_task(&data_dir, &task_id)?;
+    let config = config::load_config(&data_dir);
+    task_prompt(&data_dir, &config, &project, &task, agent.as_deref())
+}
+
+/// Recorded agent sessions for a task, newest first: prompt, output, files
+/// touched and board changes
+#[tauri::command]
+pub fn list_agent_sessions(
+    task_id: String,
+    state: State<'_, Mutex<AppState>>,
+) -> Vec<AgentSession> {
+    sessions::list(&data::get_data_dir(&state), Some(&task_id))
+}
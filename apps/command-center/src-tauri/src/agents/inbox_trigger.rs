@@ -0,0 +1,561 @@
+//! Agent runs started from the inbox
+//!
+//! With `agentExecution.autoTrigger` set to "inbox", an instruction addressed
+//! to Claude ("@claude fix the rounding bug in AnyCalc") starts the agent CLI
+//! on its own. The item is routed to a project the way the orchestrator
+//! does it: the item's own project, the project of the task it refers to,
+//! "for {PROJECT}: …", "{PROJECT} to {STAGE}", or the one project the text
+//! names. The agent's output is posted back as a reply from `claude`.
+//!
+//! Items are handled one at a time. Each is marked with `agentTrigger` so it
+//! is never picked up twice. `autoTriggerEnabled: false` is the kill switch;
+//! `dryRun` replies with what would have run instead of running it.
+
+use std::collections::HashSet;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Sender};
+use std::sync::{Arc, Mutex, OnceLock};
+
+use regex::Regex;
+use serde::Serialize;
+use serde_json::{json, Value};
+
+use super::runner::{AgentOutput, AgentRun, OutputStream, RunStatus, RunTarget};
+use super::{agent_command, prompt, repo_path, sessions, AgentRunStarted};
+use crate::config::{self, AgentExecutionConfig};
+use crate::data::{self, InboxFile, InboxItem, InboxReply, Project, Task};
+use crate::quick_commands::matcher::{self, Match};
+use crate::watcher::{WriteOrigin, WriteRegistry};
+
+/// Key of the trigger's bookkeeping in the item's untyped fields
+const MARKER: &str = "agentTrigger";
+
+/// Longest reply posted to the inbox; the run log has the rest
+const MAX_REPLY_CHARS: usize = 4000;
+
+/// What happened to an inbox item, sent as `agent-inbox-trigger`
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct TriggerOutcome {
+    pub item_id: String,
+    /// "succeeded", "failed", "timedOut", "cancelled", "unrouted" or "dryRun"
+    pub state: String,
+    pub project_id: Option<String>,
+    pub run: Option<AgentRun>,
+    /// Empty when nothing was replied
+    pub reply_id: String,
+    /// Why the item could not be processed at all
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl TriggerOutcome {
+    /// An item the trigger failed on before the agent could answer it
+    pub fn failed(item_id: &str, error: String) -> Self {
+        Self {
+            item_id: item_id.to_string(),
+            state: "failed".to_string(),
+            project_id: None,
+            run: None,
+            reply_id: String::new(),
+            error: Some(error),
+        }
+    }
+}
+
+/// Whether inbox items should start the agent at all
+pub fn enabled(settings: &AgentExecutionConfig) -> bool {
+    settings.auto_trigger == "inbox"
+        && settings.auto_trigger_enabled
+        && matches!(settings.mode.as_str(), "auto" | "hybrid")
+}
+
+/// "@claude …" anywhere, or "claude, …" / "hey claude …" at the start
+fn addressed_to_claude(text: &str) -> bool {
+    static ADDRESS: OnceLock<Regex> = OnceLock::new();
+    ADDRESS
+        .get_or_init(|| {
+            Regex::new(r"(?i)(?:^\s*(?:hey |ok |okay )?claude\b[\s,:]|(?:^|[^\w@])@claude\b)")
+                .unwrap()
+        })
+        .is_match(text)
+}
+
+fn marker_state(item: &InboxItem) -> Option<&str> {
+    item.extra.get(MARKER)?.get("state")?.as_str()
+}
+
+/// Whether the trigger should pick `item` up. With `resume`, items whose
+/// run was cut short by the app closing count too.
+pub fn qualifies(item: &InboxItem, resume: bool) -> bool {
+    if item.author != "user"
+        || item.status != "pending"
+        || item.parent_id.is_some()
+        || !addressed_to_claude(&item.text)
+    {
+        return false;
+    }
+
+    match marker_state(item) {
+        None => true,
+        // Picked up again once someone sets the project
+        Some("unrouted") => item.project.is_some(),
+        Some("running") => resume,
+        Some(_) => false,
+    }
+}
+
+/// Ids of the items the trigger should pick up, oldest first
+pub fn pending(inbox: &InboxFile, resume: bool) -> Vec<String> {
+    inbox
+        .items
+        .iter()
+        .rev()
+        .filter(|item| qualifies(item, resume))
+        .map(|item| item.id.clone())
+        .collect()
+}
+
+/// The project an item is about, by the orchestrator's routing rules
+pub fn route<'a>(item: &InboxItem, projects: &'a [Project], tasks: &[Task]) -> Option<&'a Project> {
+    static PATTERNS: OnceLock<[Regex; 3]> = OnceLock::new();
+    let [address, for_project, to_stage] = PATTERNS.get_or_init(|| {
+        [
+            Regex::new(r"(?i)^\s*(?:hey |ok |okay )?@?claude\b[\s,:]*").unwrap(),
+            Regex::new(r"(?i)\bfor (?:the )?(?P<project>[^:,]+?)(?: project)?\s*:").unwrap(),
+            Regex::new(r"(?i)^(?P<project>.+?) to (?:the )?\w+(?: stage)?[.!]?$").unwrap(),
+        ]
+    });
+    let by_id = |id: &str| projects.iter().find(|p| p.id == id);
+
+    if let Some(project) = item.project.as_deref() {
+        return by_id(project);
+    }
+    if let Some(task_id) = item.extra.get("taskRef").and_then(Value::as_str) {
+        if let Some(task) = tasks.iter().find(|t| t.id == task_id) {
+            return by_id(&task.project_id);
+        }
+    }
+
+    let text = address.replace(item.text.trim(), "");
+    let named = |query: &str| match matcher::find(query, projects, |p| {
+        vec![p.name.as_str(), p.id.as_str()]
+    }) {
+        Match::Found(project, _) => Some(project),
+        _ => None,
+    };
+    for pattern in [for_project, to_stage] {
+        if let Some(project) = pattern.captures(&text).and_then(|c| named(&c["project"])) {
+            return Some(project);
+        }
+    }
+
+    let words = words(&text);
+    let mut mentioned = projects
+        .iter()
+        .filter(|p| contains_words(&words, &p.name) || contains_words(&words, &p.id));
+    match (mentioned.next(), mentioned.next()) {
+        (Some(project), None) => Some(project),
+        _ => None,
+    }
+}
+
+fn words(s: &str) -> Vec<String> {
+    s.to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Whether all of `name`'s words appear in `text`, in order and together
+fn contains_words(text: &[String], name: &str) -> bool {
+    let name = words(name);
+    !name.is_empty() && text.windows(name.len()).any(|w| w == name.as_slice())
+}
+
+/// Handle one inbox item: route it, run the agent (or describe the run in
+/// dry-run mode) and post the reply. Returns None when the item no longer
+/// qualifies or the trigger has been switched off. Blocks while the agent
+/// runs, until `cancel` is set.
+pub fn process(
+    data_dir: &Path,
+    writes: &WriteRegistry,
+    item_id: &str,
+    cancel: &AtomicBool,
+    started: &dyn Fn(&AgentRunStarted),
+    sink: &dyn Fn(AgentOutput),
+) -> Result<Option<TriggerOutcome>, String> {
+    let settings = config::load_config(data_dir).agent_execution;
+    let inbox = data::load_inbox(data_dir)?;
+    let item = match inbox.items.iter().find(|i| i.id == item_id) {
+        Some(item) if enabled(&settings) && qualifies(item, true) => item.clone(),
+        _ => return Ok(None),
+    };
+
+    let projects = data::load_projects(data_dir)?.projects;
+    let tasks = data::load_tasks(data_dir)?.tasks;
+    let Some(project) = route(&item, &projects, &tasks) else {
+        let reply = "I couldn't tell which project this is for. Set the item's project \
+                     and I'll pick it up.";
+        return finish(data_dir, writes, &item.id, None, "unrouted", None, reply);
+    };
+    let repo = match repo_path(project) {
+        Ok(repo) => repo,
+        Err(e) => {
+            let reply = format!("I couldn't start on this: {}.", e);
+            return finish(
+                data_dir,
+                writes,
+                &item.id,
+                Some(project),
+                "failed",
+                None,
+                &reply,
+            );
+        }
+    };
+
+    let instructions = prompt::load_instructions(data_dir);
+    let command = agent_command(
+        &settings,
+        repo,
+        prompt::build_inbox_prompt(instructions.as_deref(), project, &item),
+    );
+
+    if settings.dry_run {
+        let reply = format!(
+            "Dry run: would run `{} {}` in {} for {}.\n\nPrompt:\n\n{}",
+            command.program,
+            command.args.join(" "),
+            command.cwd.display(),
+            project.name,
+            command.prompt.trim_end()
+        );
+        return finish(
+            data_dir,
+            writes,
+            &item.id,
+            Some(project),
+            "dryRun",
+            None,
+            &reply,
+        );
+    }
+
+    mark(
+        data_dir,
+        writes,
+        &item.id,
+        json!({ "state": "running", "projectId": project.id }),
+    )?;
+
+    let run_id = data::generate_id("agent-run");
+    let target = RunTarget::inbox_item(&item.id, &project.id);
+    started(&AgentRunStarted::new(&run_id, &target));
+
+    let stdout = Mutex::new(Vec::new());
+    let run = sessions::run_recorded(
+        data_dir,
+        &run_id,
+        &target,
+        "claude",
+        &command,
+        cancel,
+        &|output| {
+            if output.stream == OutputStream::Stdout {
+                stdout.lock().unwrap().push(output.line.clone());
+            }
+            sink(output);
+        },
+    );
+    let run = match run {
+        Ok(run) => run,
+        Err(e) => {
+            let reply = format!("I couldn't start on this: {}.", e);
+            return finish(
+                data_dir,
+                writes,
+                &item.id,
+                Some(project),
+                "failed",
+                None,
+                &reply,
+            );
+        }
+    };
+
+    let output = stdout.into_inner().unwrap().join("\n");
+    let reply = reply_text(&run, output.trim());
+    let state = run.status.as_str().to_string();
+    finish(
+        data_dir,
+        writes,
+        &item.id,
+        Some(project),
+        &state,
+        Some(run),
+        &reply,
+    )
+}
+
+/// The agent's output, or what went wrong, cut to fit a reply
+fn reply_text(run: &AgentRun, output: &str) -> String {
+    let note = match run.status {
+        RunStatus::Succeeded => None,
+        RunStatus::Failed => Some(match run.exit_code {
+            Some(code) => format!("The agent failed (exit code {}).", code),
+            None => "The agent failed.".to_string(),
+        }),
+        RunStatus::TimedOut => Some("The agent timed out.".to_string()),
+        RunStatus::Cancelled => Some("The agent was stopped.".to_string()),
+    };
+
+    let mut text = match (note, output.is_empty()) {
+        (None, true) => "Done, with no output.".to_string(),
+        (None, false) => output.to_string(),
+        (Some(note), true) => note,
+        (Some(note), false) => format!("{}\n\n{}", note, output),
+    };
+    if let Some((cut, _)) = text.char_indices().nth(MAX_REPLY_CHARS) {
+        text.truncate(cut);
+        text.push_str("\n\n… (full output in the run log)");
+    }
+    text
+}
+
+/// Set the item's trigger marker
+fn mark(
+    data_dir: &Path,
+    writes: &WriteRegistry,
+    item_id: &str,
+    marker: Value,
+) -> Result<(), String> {
+    let mut inbox = data::load_inbox(data_dir)?;
+    inbox.item_mut(item_id)?.extra.insert(MARKER.into(), marker);
+    data::save_inbox(data_dir, writes, &mut inbox, WriteOrigin::Backend)
+}
+
+/// Post Claude's reply and record how the item ended. A successful run
+/// also marks the item done.
+fn finish(
+    data_dir: &Path,
+    writes: &WriteRegistry,
+    item_id: &str,
+    project: Option<&Project>,
+    state: &str,
+    run: Option<AgentRun>,
+    reply: &str,
+) -> Result<Option<TriggerOutcome>, String> {
+    let reply = InboxReply {
+        id: data::generate_id("reply"),
+        author: "claude".to_string(),
+        text: reply.to_string(),
+        created_at: chrono::Utc::now().to_rfc3339(),
+        extra: Default::default(),
+    };
+
+    let mut inbox = data::load_inbox(data_dir)?;
+    let item = inbox.item_mut(item_id)?;
+    item.replies.push(reply.clone());
+    item.extra.insert(
+        MARKER.into(),
+        json!({
+            "state": state,
+            "projectId": project.map(|p| &p.id),
+            "runId": run.as_ref().map(|r| &r.id),
+            "at": reply.created_at,
+        }),
+    );
+    if state == "succeeded" {
+        item.set_status("done");
+    }
+    data::save_inbox(data_dir, writes, &mut inbox, WriteOrigin::Backend)?;
+
+    Ok(Some(TriggerOutcome {
+        item_id: item_id.to_string(),
+        state: state.to_string(),
+        project_id: project.map(|p| p.id.clone()),
+        run,
+        reply_id: reply.id,
+        error: None,
+    }))
+}
+
+/// Items waiting for the worker, which starts with the first one and
+/// handles them one at a time
+#[derive(Clone, Default)]
+pub struct InboxTrigger {
+    inner: Arc<Mutex<TriggerState>>,
+}
+
+#[derive(Default)]
+struct TriggerState {
+    tx: Option<Sender<String>>,
+    /// Queued or in progress; further pushes of these are ignored
+    queued: HashSet<String>,
+    /// Cancels the item in progress
+    current: Option<Arc<AtomicBool>>,
+}
+
+impl InboxTrigger {
+    /// Queue an item. `handler` processes every item and is only used when
+    /// this push starts the worker.
+    pub fn push<F>(&self, item_id: String, handler: F)
+    where
+        F: Fn(&str, &AtomicBool) + Send + 'static,
+    {
+        let mut state = self.inner.lock().unwrap();
+        if !state.queued.insert(item_id.clone()) {
+            return;
+        }
+
+        let inner = self.inner.clone();
+        let tx = state.tx.get_or_insert_with(|| {
+            let (tx, rx) = mpsc::channel::<String>();
+            std::thread::spawn(move || {
+                for item_id in rx {
+                    let cancel = Arc::new(AtomicBool::new(false));
+                    inner.lock().unwrap().current = Some(cancel.clone());
+                    handler(&item_id, &cancel);
+
+                    let mut state = inner.lock().unwrap();
+                    state.current = None;
+                    state.queued.remove(&item_id);
+                }
+            });
+            tx
+        });
+        let _ = tx.send(item_id);
+    }
+
+    /// Stop the item in progress. Queued items are skipped by `process`
+    /// once the trigger is switched off.
+    pub fn stop(&self) {
+        if let Some(cancel) = &self.inner.lock().unwrap().current {
+            cancel.store(true, Ordering::SeqCst);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(text: &str) -> InboxItem {
+        serde_json::from_value(json!({ "id": "inbox-1", "text": text })).unwrap()
+    }
+
+    fn projects() -> Vec<Project> {
+        serde_json::from_value(json!([
+            { "id": "anycalc", "name": "AnyCalc" },
+            { "id": "taskboard", "name": "Task Board" }
+        ]))
+        .unwrap()
+    }
+
+    #[test]
+    fn only_unhandled_instructions_for_claude_qualify() {
+        assert!(qualifies(&item("@claude fix the tip rounding"), false));
+        assert!(qualifies(&item("Claude, tidy up the README"), false));
+        assert!(!qualifies(&item("Email claude@example.com"), false));
+        assert!(!qualifies(&item("Buy milk"), false));
+
+        let mut handled = item("@claude fix the tip rounding");
+        handled
+            .extra
+            .insert(MARKER.into(), json!({ "state": "running" }));
+        assert!(!qualifies(&handled, false));
+        assert!(qualifies(&handled, true));
+
+        handled
+            .extra
+            .insert(MARKER.into(), json!({ "state": "unrouted" }));
+        assert!(!qualifies(&handled, false));
+        handled.project = Some("anycalc".into());
+        assert!(qualifies(&handled, false));
+    }
+
+    #[test]
+    fn routes_by_the_orchestrator_patterns() {
+        let projects = projects();
+        let route_id = |text: &str| route(&item(text), &projects, &[]).map(|p| p.id.as_str());
+
+        assert_eq!(
+            route_id("@claude create task for AnyCalc: dark mode"),
+            Some("anycalc")
+        );
+        assert_eq!(route_id("@claude task board to launch"), Some("taskboard"));
+        assert_eq!(
+            route_id("@claude the anycalc build is red, fix it"),
+            Some("anycalc")
+        );
+        assert_eq!(route_id("@claude compare anycalc with task board"), None);
+        assert_eq!(route_id("@claude what's next?"), None);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn posts_the_agent_output_as_a_reply() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let writes = WriteRegistry::default();
+        let stub = dir.path().join("agent-stub");
+        std::fs::write(
+            &stub,
+            "#!/bin/sh\ncat > /dev/null\necho 'Fixed the rounding.'\n",
+        )
+        .unwrap();
+        std::fs::set_permissions(&stub, std::fs::Permissions::from_mode(0o755)).unwrap();
+        std::fs::write(
+            dir.path().join("config.json"),
+            json!({ "agentExecution": {
+                "autoTriggerEnabled": true, "claudeCodePath": stub, "args": []
+            } })
+            .to_string(),
+        )
+        .unwrap();
+
+        let mut projects = data::load_projects(dir.path()).unwrap();
+        projects.projects = projects_with_repo(dir.path());
+        data::save_projects(dir.path(), &writes, &mut projects, WriteOrigin::App).unwrap();
+        let mut inbox = data::load_inbox(dir.path()).unwrap();
+        inbox
+            .items
+            .push(item("@claude fix the tip rounding in AnyCalc"));
+        data::save_inbox(dir.path(), &writes, &mut inbox, WriteOrigin::App).unwrap();
+
+        let outcome = process(
+            dir.path(),
+            &writes,
+            "inbox-1",
+            &AtomicBool::new(false),
+            &|_| {},
+            &|_| {},
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(outcome.state, "succeeded");
+
+        let item = &data::load_inbox(dir.path()).unwrap().items[0];
+        assert_eq!(item.status, "done");
+        assert_eq!(item.replies.len(), 1);
+        assert_eq!(
+            (
+                item.replies[0].author.as_str(),
+                item.replies[0].text.as_str()
+            ),
+            ("claude", "Fixed the rounding.")
+        );
+        assert!(!qualifies(item, true));
+    }
+
+    #[cfg(unix)]
+    fn projects_with_repo(repo: &Path) -> Vec<Project> {
+        let mut projects = projects();
+        projects[0].extra.insert("repoPath".into(), json!(repo));
+        projects
+    }
+}
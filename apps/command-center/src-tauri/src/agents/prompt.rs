@@ -0,0 +1,197 @@
+//! Prompts for agent runs
+//!
+//! The agent is handed the master AGENT_INSTRUCTIONS.md from the data
+//! directory, followed by the task: where it sits on the board, its
+//! description and subtasks, and any comments left for Claude that are
+//! still open. Instructions picked up from the inbox are handed over the
+//! same way, with the thread of replies so far.
+
+use std::fmt::Write;
+use std::fs;
+use std::path::Path;
+
+use serde_json::Value;
+
+use crate::data::{InboxItem, Project, Task};
+
+/// Master instructions every agent reads first
+pub const INSTRUCTIONS_FILE: &str = "AGENT_INSTRUCTIONS.md";
+
+/// The master instructions, if the data directory has them
+pub fn load_instructions(data_dir: &Path) -> Option<String> {
+    fs::read_to_string(data_dir.join(INSTRUCTIONS_FILE))
+        .ok()
+        .filter(|s| !s.trim().is_empty())
+}
+
+/// The prompt for working on `task`
+pub fn build_prompt(instructions: Option<&str>, project: &Project, task: &Task) -> String {
+    let mut prompt = String::new();
+    if let Some(instructions) = instructions {
+        prompt.push_str(instructions.trim_end());
+        prompt.push_str("\n\n---\n\n");
+    }
+
+    let _ = writeln!(prompt, "# Task {}: {}\n", task.id, task.title);
+    let _ = writeln!(prompt, "- Project: {} ({})", project.name, project.id);
+    let _ = writeln!(prompt, "- Stage: {} ({} phase)", task.stage, task.phase);
+    let _ = writeln!(prompt, "- Priority: {}", task.priority);
+    if let Some(agent) = assigned_agent(task) {
+        let _ = writeln!(prompt, "- Agent: {}", agent);
+    }
+    if let Some(due) = text(task, "dueDate") {
+        let _ = writeln!(prompt, "- Due: {}", due);
+    }
+    let tags = strings(task, "tags");
+    if !tags.is_empty() {
+        let _ = writeln!(prompt, "- Tags: {}", tags.join(", "));
+    }
+
+    if let Some(description) = text(task, "description") {
+        let _ = write!(prompt, "\n## Description\n\n{}\n", description.trim());
+    }
+
+    let subtasks: Vec<(&str, bool)> = array(task, "subtasks")
+        .filter_map(|s| {
+            let title = s.get("title")?.as_str()?;
+            Some((
+                title,
+                s.get("completed").and_then(Value::as_bool) == Some(true),
+            ))
+        })
+        .collect();
+    if !subtasks.is_empty() {
+        prompt.push_str("\n## Subtasks\n\n");
+        for (title, done) in subtasks {
+            let _ = writeln!(prompt, "- [{}] {}", if done { "x" } else { " " }, title);
+        }
+    }
+
+    let comments: Vec<_> = task
+        .comments
+        .iter()
+        .filter(|c| c.for_claude && !c.resolved)
+        .collect();
+    if !comments.is_empty() {
+        prompt.push_str("\n## Open comments for you\n\n");
+        for comment in comments {
+            let _ = writeln!(prompt, "- {}: {}", comment.author, comment.content.trim());
+        }
+    }
+
+    let docs = strings(task, "linkedDocs");
+    if !docs.is_empty() {
+        prompt.push_str("\n## Linked documents\n\n");
+        for doc in docs {
+            let _ = writeln!(prompt, "- {}", doc);
+        }
+    }
+
+    prompt
+}
+
+/// The prompt for answering an inbox instruction in `project`
+pub fn build_inbox_prompt(
+    instructions: Option<&str>,
+    project: &Project,
+    item: &InboxItem,
+) -> String {
+    let mut prompt = String::new();
+    if let Some(instructions) = instructions {
+        prompt.push_str(instructions.trim_end());
+        prompt.push_str("\n\n---\n\n");
+    }
+
+    let _ = writeln!(prompt, "# Inbox item {}\n", item.id);
+    let _ = writeln!(prompt, "- Project: {} ({})", project.name, project.id);
+    if let Some(priority) = &item.priority {
+        let _ = writeln!(prompt, "- Priority: {}", priority);
+    }
+    if let Some(task_id) = item.extra.get("taskRef").and_then(Value::as_str) {
+        let _ = writeln!(prompt, "- Task: {}", task_id);
+    }
+    let _ = write!(prompt, "\n## Instruction\n\n{}\n", item.text.trim());
+
+    if !item.replies.is_empty() {
+        prompt.push_str("\n## Replies so far\n\n");
+        for reply in &item.replies {
+            let _ = writeln!(prompt, "- {}: {}", reply.author, reply.text.trim());
+        }
+    }
+
+    prompt.push_str(
+        "\nYour output is posted back to the inbox as your reply, so finish with a short \
+         summary of what you did.\n",
+    );
+    prompt
+}
+
+/// The agent the task is assigned to
+pub fn assigned_agent(task: &Task) -> Option<&str> {
+    text(task, "assignedAgent")
+}
+
+/// A non-empty string field from the task's untyped fields
+fn text<'a>(task: &'a Task, key: &str) -> Option<&'a str> {
+    task.extra
+        .get(key)
+        .and_then(Value::as_str)
+        .filter(|s| !s.trim().is_empty())
+}
+
+fn array<'a>(task: &'a Task, key: &str) -> impl Iterator<Item = &'a Value> {
+    task.extra
+        .get(key)
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+}
+
+fn strings<'a>(task: &'a Task, key: &str) -> Vec<&'a str> {
+    array(task, key).filter_map(Value::as_str).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn includes_instructions_then_the_task() {
+        let project: Project =
+            serde_json::from_value(json!({ "id": "anycalc", "name": "AnyCalc" })).unwrap();
+        let task: Task = serde_json::from_value(json!({
+            "id": "t-1",
+            "projectId": "anycalc",
+            "title": "Add tip calculator",
+            "stage": "development",
+            "phase": "build",
+            "priority": "P1",
+            "description": "Split bills with a tip.\n",
+            "assignedAgent": "dev-agent",
+            "tags": ["calculators"],
+            "subtasks": [
+                { "id": "st-1", "title": "Layout", "completed": true },
+                { "id": "st-2", "title": "Rounding", "completed": false }
+            ],
+            "comments": [
+                { "id": "c-1", "type": "comment", "author": "arun", "content": "Use INR",
+                  "createdAt": "", "forClaude": true },
+                { "id": "c-2", "type": "comment", "author": "arun", "content": "Done already",
+                  "createdAt": "", "forClaude": true, "resolved": true }
+            ]
+        }))
+        .unwrap();
+
+        let prompt = build_prompt(Some("Follow the rules.\n"), &project, &task);
+
+        assert!(prompt.starts_with("Follow the rules.\n\n---\n\n# Task t-1: Add tip calculator\n"));
+        assert!(prompt.contains("- Project: AnyCalc (anycalc)\n"));
+        assert!(prompt.contains("- Agent: dev-agent\n"));
+        assert!(prompt.contains("## Description\n\nSplit bills with a tip.\n"));
+        assert!(prompt.contains("- [x] Layout\n- [ ] Rounding\n"));
+        assert!(prompt.contains("- arun: Use INR\n"));
+        assert!(!prompt.contains("Done already"));
+        assert!(!prompt.contains("Due:"));
+    }
+}
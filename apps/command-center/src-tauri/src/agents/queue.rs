@@ -0,0 +1,668 @@
+//! Agent job queue
+//!
+//! Agent runs are queued as jobs and started by the scheduler as capacity
+//! frees up: at most `maxConcurrent` at once, and `maxConcurrentPerProject`
+//! within one project, oldest first. A failed or timed-out run is tried
+//! again after `retryDelay` until the job has had `maxAttempts` runs.
+//!
+//! Jobs are kept in `agent-jobs.json` so they survive a restart; any that
+//! were running when the app quit are queued again when it starts. A file
+//! that cannot be read is moved aside to `agent-jobs.unreadable.json`
+//! rather than saved over. No job starts unless the queue could be saved.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::runner::{AgentRun, RunStatus};
+use crate::config::AgentExecutionConfig;
+
+/// File in the data directory holding the queue
+pub const JOBS_FILE: &str = "agent-jobs.json";
+
+/// Where an unreadable jobs file is moved
+const UNREADABLE_FILE: &str = "agent-jobs.unreadable.json";
+
+/// Finished jobs kept in the file; older ones are dropped
+const MAX_FINISHED: usize = 200;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum JobState {
+    Queued,
+    Running,
+    Succeeded,
+    Failed,
+    Cancelled,
+}
+
+impl JobState {
+    pub fn is_finished(self) -> bool {
+        matches!(self, Self::Succeeded | Self::Failed | Self::Cancelled)
+    }
+}
+
+/// A queued agent run; payload of `agent-job`
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct AgentJob {
+    pub id: String,
+    pub task_id: String,
+    pub project_id: String,
+    pub state: JobState,
+    /// Runs started so far
+    pub attempts: u32,
+    pub max_attempts: u32,
+    pub created_at: String,
+    pub updated_at: String,
+    /// A retry waits until then
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub not_before: Option<DateTime<Utc>>,
+    /// Ids of the job's runs, oldest first
+    #[serde(default)]
+    pub runs: Vec<String>,
+    #[serde(default)]
+    pub error: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+struct JobsFile {
+    #[serde(default)]
+    jobs: Vec<AgentJob>,
+}
+
+/// Concurrency limits and retry policy
+#[derive(Debug, Clone, Copy)]
+pub struct QueuePolicy {
+    pub max_concurrent: usize,
+    pub per_project: usize,
+    pub max_attempts: u32,
+    pub retry_delay: chrono::Duration,
+}
+
+impl QueuePolicy {
+    pub fn from_config(config: &AgentExecutionConfig) -> Self {
+        Self {
+            max_concurrent: config.max_concurrent.max(1),
+            per_project: config.max_concurrent_per_project.max(1),
+            max_attempts: config.max_attempts.max(1),
+            retry_delay: chrono::Duration::milliseconds(config.retry_delay as i64),
+        }
+    }
+}
+
+impl Default for QueuePolicy {
+    fn default() -> Self {
+        Self::from_config(&AgentExecutionConfig::default())
+    }
+}
+
+/// Runs one attempt of a job, stopping early once the flag is set. Blocks.
+pub type Executor = Arc<dyn Fn(&AgentJob, &AtomicBool) -> Result<AgentRun, String> + Send + Sync>;
+
+/// Told about every change of a job's state
+pub type Listener = Arc<dyn Fn(&AgentJob) + Send + Sync>;
+
+/// The queue, shared by the scheduler, the job threads and commands
+#[derive(Clone, Default)]
+pub struct JobQueue {
+    inner: Arc<Mutex<QueueInner>>,
+    /// The scheduler's policy, kept until config.json changes
+    policy: Arc<Mutex<QueuePolicy>>,
+}
+
+#[derive(Default)]
+struct QueueInner {
+    /// The jobs file; jobs are only kept in memory until `open`
+    path: Option<PathBuf>,
+    jobs: Vec<AgentJob>,
+    /// Jobs with a run in progress, as they started, and their cancel
+    /// flags. Kept across `open`, so runs started in another workspace can
+    /// still be cancelled and still count towards the limits.
+    running: HashMap<String, (AgentJob, Arc<AtomicBool>)>,
+    /// Why saving a finished run failed; the next tick tries again
+    unsaved: Option<String>,
+    /// Where to send the last run of a job once it is done
+    waiters: HashMap<String, Sender<Result<AgentRun, String>>>,
+}
+
+impl QueueInner {
+    fn save(&mut self) -> Result<(), String> {
+        let finished = self.jobs.iter().filter(|j| j.state.is_finished()).count();
+        let mut excess = finished.saturating_sub(MAX_FINISHED);
+        self.jobs.retain(|j| {
+            let drop = excess > 0 && j.state.is_finished();
+            if drop {
+                excess -= 1;
+            }
+            !drop
+        });
+
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        let file = JobsFile {
+            jobs: self.jobs.clone(),
+        };
+        let content = serde_json::to_string_pretty(&file)
+            .map_err(|e| format!("Failed to serialize agent jobs: {}", e))?;
+        crate::data::write_atomic(path, &content)
+            .map_err(|e| format!("Failed to write {}: {}", JOBS_FILE, e))
+    }
+
+    fn job_mut(&mut self, id: &str) -> Option<&mut AgentJob> {
+        self.jobs.iter_mut().find(|j| j.id == id)
+    }
+
+    fn notify(&mut self, id: &str, result: Result<AgentRun, String>) {
+        if let Some(waiter) = self.waiters.remove(id) {
+            let _ = waiter.send(result);
+        }
+    }
+}
+
+impl JobQueue {
+    /// Load the queue from `data_dir`, queueing again any job that was
+    /// running when the app last quit. An unreadable file is moved aside
+    /// and the queue starts empty, with the parse error returned.
+    pub fn open(&self, data_dir: &Path) -> Result<Vec<AgentJob>, String> {
+        let path = data_dir.join(JOBS_FILE);
+        let mut inner = self.inner.lock().unwrap();
+        inner.unsaved = None;
+        let mut file = match read_jobs(&path) {
+            Ok(file) => file,
+            Err(e) => {
+                // Nothing is saved until the file is out of the way
+                inner.path = None;
+                inner.jobs.clear();
+                fs::rename(&path, data_dir.join(UNREADABLE_FILE))
+                    .map_err(|r| format!("{}; failed to move it aside: {}", e, r))?;
+                inner.path = Some(path);
+                return Err(format!("{}; moved it to {}", e, UNREADABLE_FILE));
+            }
+        };
+
+        let mut requeued = Vec::new();
+        for job in file
+            .jobs
+            .iter_mut()
+            .filter(|j| j.state == JobState::Running && !inner.running.contains_key(&j.id))
+        {
+            job.state = JobState::Queued;
+            job.updated_at = Utc::now().to_rfc3339();
+            requeued.push(job.clone());
+        }
+
+        // Jobs left behind in the other workspace will not finish here
+        let QueueInner {
+            waiters, running, ..
+        } = &mut *inner;
+        waiters.retain(|id, _| running.contains_key(id));
+
+        inner.path = Some(path);
+        inner.jobs = file.jobs;
+        inner.save()?;
+        Ok(requeued)
+    }
+
+    /// Queue a run of `task_id`, unless one is already queued or running
+    pub fn enqueue(
+        &self,
+        task_id: &str,
+        project_id: &str,
+        policy: &QueuePolicy,
+    ) -> Result<AgentJob, String> {
+        let mut inner = self.inner.lock().unwrap();
+        if let Some(job) = inner
+            .jobs
+            .iter()
+            .find(|j| j.task_id == task_id && !j.state.is_finished())
+        {
+            return Err(format!("Task {} already has agent job {}", task_id, job.id));
+        }
+
+        let now = Utc::now().to_rfc3339();
+        let job = AgentJob {
+            id: crate::data::generate_id("agent-job"),
+            task_id: task_id.to_string(),
+            project_id: project_id.to_string(),
+            state: JobState::Queued,
+            attempts: 0,
+            max_attempts: policy.max_attempts,
+            created_at: now.clone(),
+            updated_at: now,
+            not_before: None,
+            runs: vec![],
+            error: None,
+        };
+        inner.jobs.push(job.clone());
+        inner.save()?;
+        Ok(job)
+    }
+
+    /// The policy last read from config.json
+    pub fn policy(&self) -> QueuePolicy {
+        *self.policy.lock().unwrap()
+    }
+
+    pub fn set_policy(&self, policy: QueuePolicy) {
+        *self.policy.lock().unwrap() = policy;
+    }
+
+    /// Wait for a job to finish, retries included. Yields its last run, or
+    /// why it has none.
+    pub fn watch(&self, id: &str) -> Receiver<Result<AgentRun, String>> {
+        let (tx, rx) = mpsc::channel();
+        let mut inner = self.inner.lock().unwrap();
+        let pending = inner.running.contains_key(id)
+            || inner
+                .jobs
+                .iter()
+                .any(|j| j.id == id && !j.state.is_finished());
+        if pending {
+            inner.waiters.insert(id.to_string(), tx);
+        } else {
+            let _ = tx.send(Err(format!("Agent job {} has already finished", id)));
+        }
+        rx
+    }
+
+    /// All jobs, newest first
+    pub fn list(&self) -> Vec<AgentJob> {
+        let inner = self.inner.lock().unwrap();
+        inner.jobs.iter().rev().cloned().collect()
+    }
+
+    /// Cancel a job. A queued job is cancelled at once; a running one once
+    /// its agent has been stopped.
+    pub fn cancel(&self, id: &str) -> Result<AgentJob, String> {
+        let mut inner = self.inner.lock().unwrap();
+        let started = inner.running.get(id).map(|(job, flag)| {
+            flag.store(true, Ordering::SeqCst);
+            job.clone()
+        });
+
+        let Some(job) = inner.job_mut(id) else {
+            // Running in a workspace the app has since switched away from
+            return started.ok_or_else(|| format!("Agent job not found: {}", id));
+        };
+        match job.state {
+            JobState::Queued => {
+                job.state = JobState::Cancelled;
+                job.updated_at = Utc::now().to_rfc3339();
+            }
+            JobState::Running => {}
+            _ => return Err(format!("Agent job {} has already finished", id)),
+        }
+        let job = job.clone();
+        inner.save()?;
+        if job.state == JobState::Cancelled {
+            inner.notify(id, Err("Cancelled".to_string()));
+        }
+        Ok(job)
+    }
+
+    /// Start every queued job the policy has room for, each on its own
+    /// thread. Returns the jobs started, or why the queue could not be
+    /// saved, in which case nothing starts.
+    pub fn tick(
+        &self,
+        now: DateTime<Utc>,
+        policy: &QueuePolicy,
+        executor: &Executor,
+        listener: &Listener,
+    ) -> Result<Vec<AgentJob>, String> {
+        let mut inner = self.inner.lock().unwrap();
+        if inner.unsaved.is_some() {
+            inner.save()?;
+            inner.unsaved = None;
+        }
+
+        let mut running = inner.running.len();
+        let mut per_project: HashMap<String, usize> = HashMap::new();
+        for (job, _) in inner.running.values() {
+            *per_project.entry(job.project_id.clone()).or_default() += 1;
+        }
+
+        let before = inner.jobs.clone();
+        let mut started = Vec::new();
+        for job in inner.jobs.iter_mut() {
+            if running >= policy.max_concurrent {
+                break;
+            }
+            let project_running = per_project.entry(job.project_id.clone()).or_default();
+            if job.state != JobState::Queued
+                || job.not_before.is_some_and(|t| t > now)
+                || *project_running >= policy.per_project
+            {
+                continue;
+            }
+
+            job.state = JobState::Running;
+            job.attempts += 1;
+            job.not_before = None;
+            job.error = None;
+            job.updated_at = now.to_rfc3339();
+            *project_running += 1;
+            running += 1;
+            started.push(job.clone());
+        }
+        if started.is_empty() {
+            return Ok(started);
+        }
+        if let Err(e) = inner.save() {
+            // A run must not start without being on record
+            inner.jobs = before;
+            return Err(e);
+        }
+
+        for job in &started {
+            let cancel = Arc::new(AtomicBool::new(false));
+            inner
+                .running
+                .insert(job.id.clone(), (job.clone(), cancel.clone()));
+
+            let (queue, job, policy) = (self.clone(), job.clone(), *policy);
+            let (executor, listener) = (executor.clone(), listener.clone());
+            std::thread::spawn(move || {
+                listener(&job);
+                let result = executor(&job, &cancel);
+                let cancelled = cancel.load(Ordering::SeqCst);
+                if let Some(job) = queue.finish(&job.id, result, cancelled, &policy) {
+                    listener(&job);
+                }
+            });
+        }
+        Ok(started)
+    }
+
+    /// Settle a job after a run: done, cancelled, or queued for a retry
+    fn finish(
+        &self,
+        id: &str,
+        result: Result<AgentRun, String>,
+        cancelled: bool,
+        policy: &QueuePolicy,
+    ) -> Option<AgentJob> {
+        let mut inner = self.inner.lock().unwrap();
+        inner.running.remove(id);
+        // Gone if the workspace was switched meanwhile
+        let Some(job) = inner.job_mut(id) else {
+            inner.notify(id, result);
+            return None;
+        };
+        let now = Utc::now();
+
+        let error = match &result {
+            Ok(run) => {
+                job.runs.push(run.id.clone());
+                match run.status {
+                    RunStatus::Succeeded => None,
+                    RunStatus::Failed => Some(match run.exit_code {
+                        Some(code) => format!("Agent exited with code {}", code),
+                        None => "Agent was killed".to_string(),
+                    }),
+                    RunStatus::TimedOut => Some("Agent timed out".to_string()),
+                    RunStatus::Cancelled => Some("Cancelled".to_string()),
+                }
+            }
+            Err(e) => Some(e.clone()),
+        };
+
+        job.state = match (&error, cancelled) {
+            (_, true) => JobState::Cancelled,
+            (None, false) => JobState::Succeeded,
+            (Some(_), false) if job.attempts < job.max_attempts => {
+                job.not_before = Some(now + policy.retry_delay);
+                JobState::Queued
+            }
+            (Some(_), false) => JobState::Failed,
+        };
+        job.error = error.filter(|_| !cancelled);
+        job.updated_at = now.to_rfc3339();
+
+        let job = job.clone();
+        if let Err(e) = inner.save() {
+            inner.unsaved = Some(e);
+        }
+        if job.state.is_finished() {
+            inner.notify(id, result);
+        }
+        Some(job)
+    }
+}
+
+/// The jobs file; a missing one is an empty queue
+fn read_jobs(path: &Path) -> Result<JobsFile, String> {
+    match fs::read_to_string(path) {
+        Ok(content) => serde_json::from_str(&content)
+            .map_err(|e| format!("Failed to parse {}: {}", JOBS_FILE, e)),
+        Err(_) => Ok(JobsFile::default()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc;
+    use std::time::{Duration, Instant};
+
+    fn policy(per_project: usize, max_attempts: u32) -> QueuePolicy {
+        QueuePolicy {
+            max_concurrent: 4,
+            per_project,
+            max_attempts,
+            retry_delay: chrono::Duration::zero(),
+        }
+    }
+
+    fn agent_run(job: &AgentJob, status: RunStatus) -> AgentRun {
+        AgentRun {
+            id: format!("{}-run-{}", job.id, job.attempts),
+            task_id: Some(job.task_id.clone()),
+            inbox_item_id: None,
+            project_id: job.project_id.clone(),
+            started_at: String::new(),
+            finished_at: String::new(),
+            duration_ms: 0,
+            exit_code: (status == RunStatus::Succeeded).then_some(0),
+            status,
+            log_file: String::new(),
+        }
+    }
+
+    /// Runs that last until cancelled
+    fn until_cancelled() -> Executor {
+        Arc::new(|job, cancel| {
+            while !cancel.load(Ordering::SeqCst) {
+                std::thread::sleep(Duration::from_millis(10));
+            }
+            Ok(agent_run(job, RunStatus::Cancelled))
+        })
+    }
+
+    fn quiet() -> Listener {
+        Arc::new(|_| {})
+    }
+
+    fn wait_for(queue: &JobQueue, id: &str, state: JobState) -> AgentJob {
+        let deadline = Instant::now() + Duration::from_secs(5);
+        loop {
+            let job = queue.list().into_iter().find(|j| j.id == id).unwrap();
+            if job.state == state || Instant::now() > deadline {
+                return job;
+            }
+            std::thread::sleep(Duration::from_millis(10));
+        }
+    }
+
+    #[test]
+    fn limits_running_jobs_per_project() {
+        let queue = JobQueue::default();
+        let policy = policy(1, 1);
+        let a1 = queue.enqueue("t-1", "anycalc", &policy).unwrap();
+        let a2 = queue.enqueue("t-2", "anycalc", &policy).unwrap();
+        let b1 = queue.enqueue("t-3", "orbit", &policy).unwrap();
+        assert!(queue.enqueue("t-1", "anycalc", &policy).is_err());
+
+        let executor = until_cancelled();
+        let started = queue
+            .tick(Utc::now(), &policy, &executor, &quiet())
+            .unwrap();
+        let ids: Vec<&str> = started.iter().map(|j| j.id.as_str()).collect();
+        assert_eq!(ids, [a1.id.as_str(), b1.id.as_str()]);
+        assert!(queue
+            .tick(Utc::now(), &policy, &executor, &quiet())
+            .unwrap()
+            .is_empty());
+
+        // The next job in the project starts once the first is done
+        queue.cancel(&a1.id).unwrap();
+        wait_for(&queue, &a1.id, JobState::Cancelled);
+        let started = queue
+            .tick(Utc::now(), &policy, &executor, &quiet())
+            .unwrap();
+        assert_eq!(started[0].id, a2.id);
+
+        queue.cancel(&a2.id).unwrap();
+        queue.cancel(&b1.id).unwrap();
+    }
+
+    #[test]
+    fn retries_failed_runs_until_max_attempts() {
+        let queue = JobQueue::default();
+        let policy = policy(1, 2);
+        let job = queue.enqueue("t-1", "anycalc", &policy).unwrap();
+        let executor: Executor = Arc::new(|job, _| match job.attempts {
+            1 => Ok(agent_run(job, RunStatus::TimedOut)),
+            _ => Err("spawn failed".to_string()),
+        });
+        let (tx, rx) = mpsc::channel();
+        let tx = Mutex::new(tx);
+        let listener: Listener = Arc::new(move |job| {
+            let _ = tx.lock().unwrap().send(job.clone());
+        });
+        let next = || rx.recv_timeout(Duration::from_secs(5)).unwrap();
+
+        queue
+            .tick(Utc::now(), &policy, &executor, &listener)
+            .unwrap();
+        assert_eq!(next().state, JobState::Running);
+        let retry = next();
+        assert_eq!(retry.state, JobState::Queued);
+        assert_eq!(retry.error.as_deref(), Some("Agent timed out"));
+        assert!(retry.not_before.is_some());
+
+        queue
+            .tick(Utc::now(), &policy, &executor, &listener)
+            .unwrap();
+        assert_eq!(next().state, JobState::Running);
+        let failed = next();
+        assert_eq!(failed.id, job.id);
+        assert_eq!(failed.state, JobState::Failed);
+        assert_eq!(failed.attempts, 2);
+        assert_eq!(failed.runs.len(), 1);
+        assert_eq!(failed.error.as_deref(), Some("spawn failed"));
+    }
+
+    #[test]
+    fn watchers_get_the_last_run_of_a_job() {
+        let queue = JobQueue::default();
+        let policy = policy(1, 2);
+        let job = queue.enqueue("t-1", "anycalc", &policy).unwrap();
+        let done = queue.watch(&job.id);
+        let executor: Executor = Arc::new(|job, _| match job.attempts {
+            1 => Ok(agent_run(job, RunStatus::TimedOut)),
+            _ => Ok(agent_run(job, RunStatus::Succeeded)),
+        });
+
+        // Not after the first attempt, which is retried
+        queue
+            .tick(Utc::now(), &policy, &executor, &quiet())
+            .unwrap();
+        wait_for(&queue, &job.id, JobState::Queued);
+        assert!(done.try_recv().is_err());
+        queue
+            .tick(Utc::now(), &policy, &executor, &quiet())
+            .unwrap();
+        let run = done.recv_timeout(Duration::from_secs(5)).unwrap().unwrap();
+        assert_eq!(run.status, RunStatus::Succeeded);
+
+        let queued = queue.enqueue("t-2", "anycalc", &policy).unwrap();
+        let done = queue.watch(&queued.id);
+        queue.cancel(&queued.id).unwrap();
+        assert_eq!(done.recv().unwrap().unwrap_err(), "Cancelled");
+        assert!(queue.watch(&queued.id).recv().unwrap().is_err());
+    }
+
+    #[test]
+    fn running_jobs_are_queued_again_after_a_restart() {
+        let dir = tempfile::tempdir().unwrap();
+        let policy = policy(1, 1);
+        let queue = JobQueue::default();
+        queue.open(dir.path()).unwrap();
+        let running = queue.enqueue("t-1", "anycalc", &policy).unwrap();
+        let cancelled = queue.enqueue("t-2", "anycalc", &policy).unwrap();
+        queue.cancel(&cancelled.id).unwrap();
+        queue
+            .tick(Utc::now(), &policy, &until_cancelled(), &quiet())
+            .unwrap();
+
+        // A fresh queue, as after relaunching the app
+        let restarted = JobQueue::default();
+        let requeued = restarted.open(dir.path()).unwrap();
+        assert_eq!(requeued.len(), 1);
+        assert_eq!(requeued[0].id, running.id);
+        let states: Vec<JobState> = restarted.list().iter().map(|j| j.state).collect();
+        assert_eq!(states, [JobState::Cancelled, JobState::Queued]);
+
+        queue.cancel(&running.id).unwrap();
+        assert!(restarted.cancel(&cancelled.id).is_err());
+    }
+
+    #[test]
+    fn running_jobs_stay_cancellable_after_switching_workspace() {
+        let first = tempfile::tempdir().unwrap();
+        let second = tempfile::tempdir().unwrap();
+        let policy = policy(1, 1);
+        let queue = JobQueue::default();
+        queue.open(first.path()).unwrap();
+        let job = queue.enqueue("t-1", "anycalc", &policy).unwrap();
+        queue
+            .tick(Utc::now(), &policy, &until_cancelled(), &quiet())
+            .unwrap();
+
+        // Switching away and back neither requeues it nor loses its flag
+        queue.open(second.path()).unwrap();
+        assert!(queue.open(first.path()).unwrap().is_empty());
+        queue.open(second.path()).unwrap();
+        assert_eq!(queue.cancel(&job.id).unwrap().state, JobState::Running);
+    }
+
+    #[test]
+    fn an_unreadable_jobs_file_is_moved_aside() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join(JOBS_FILE), "{ not json").unwrap();
+        let queue = JobQueue::default();
+
+        let err = queue.open(dir.path()).unwrap_err();
+        assert!(err.contains(UNREADABLE_FILE));
+        assert!(queue.list().is_empty());
+        let kept = fs::read_to_string(dir.path().join(UNREADABLE_FILE)).unwrap();
+        assert_eq!(kept, "{ not json");
+
+        // The queue still works, from an empty file
+        queue.enqueue("t-1", "anycalc", &policy(1, 1)).unwrap();
+        assert_eq!(JobQueue::default().open(dir.path()).unwrap().len(), 0);
+        assert_eq!(
+            read_jobs(&dir.path().join(JOBS_FILE)).unwrap().jobs.len(),
+            1
+        );
+    }
+}
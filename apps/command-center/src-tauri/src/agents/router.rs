@@ -0,0 +1,258 @@
+//! Picking the agent for a task
+//!
+//! Follows the orchestrator's routing table: design work goes to the
+//! designer, engineering to the architect (QA planning to qa), build to dev
+//! (testing to qa), and launch and closure to walkthrough. A tag naming one
+//! of the available agents, or the kind of work one does ("testing",
+//! "docs"), wins over the stage. Agents are the `<name>.md` definitions in
+//! `agents.unifiedAgentsPath`.
+
+use std::fs;
+use std::path::PathBuf;
+
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::config::AgentsConfig;
+use crate::data::{self, Task};
+
+/// Tags that name a kind of work, and the agent that does it
+const TAG_AGENTS: [(&str, &str); 12] = [
+    ("design", "designer"),
+    ("ux", "designer"),
+    ("ui", "designer"),
+    ("architecture", "architect"),
+    ("research", "architect"),
+    ("qa", "qa"),
+    ("test", "qa"),
+    ("tests", "qa"),
+    ("testing", "qa"),
+    ("docs", "walkthrough"),
+    ("documentation", "walkthrough"),
+    ("readme", "walkthrough"),
+];
+
+/// The agent picked for a task, and why
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct AgentSuggestion {
+    /// None when no available agent fits; the orchestrator handles those
+    pub agent: Option<String>,
+    pub reason: String,
+    /// The agent's definition file, when it exists
+    pub definition: Option<String>,
+}
+
+/// "@dev", "dev-agent" or "Design-Agent" → the agent's short name
+pub fn agent_name(name: &str) -> String {
+    let name = name.trim().trim_start_matches('@').to_lowercase();
+    let name = name.strip_suffix("-agent").unwrap_or(&name);
+    match name {
+        "design" => "designer".to_string(),
+        "docs" => "walkthrough".to_string(),
+        _ => name.to_string(),
+    }
+}
+
+/// The agent for `task` by the orchestrator's rules
+pub fn suggest(task: &Task, settings: &AgentsConfig) -> AgentSuggestion {
+    let available = |agent: &str| {
+        settings
+            .available
+            .agents
+            .iter()
+            .any(|a| agent_name(a) == agent)
+    };
+    let suggestion = |agent: Option<String>, reason: String| AgentSuggestion {
+        definition: agent
+            .as_deref()
+            .map(|a| definition_path(settings, a))
+            .filter(|path| path.is_file())
+            .map(|path| path.to_string_lossy().to_string()),
+        agent,
+        reason,
+    };
+
+    if !settings.enabled {
+        return suggestion(None, "Agents are turned off (agents.enabled)".to_string());
+    }
+
+    let tags = task
+        .extra
+        .get("tags")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(Value::as_str);
+    for tag in tags {
+        let named = agent_name(tag);
+        let agent = TAG_AGENTS
+            .iter()
+            .find(|(work, _)| *work == named)
+            .map(|(_, agent)| agent.to_string())
+            .unwrap_or(named);
+        if available(&agent) {
+            return suggestion(Some(agent), format!("Tagged \"{}\"", tag));
+        }
+    }
+
+    let phase = if task.phase.is_empty() {
+        data::phase_for_stage(&task.stage)
+    } else {
+        task.phase.as_str()
+    };
+    let agent = match (phase, task.stage.as_str()) {
+        ("design", _) => "designer",
+        ("engineering", "qa-planning") | ("build", "testing") => "qa",
+        ("engineering", _) => "architect",
+        ("build", _) => "dev",
+        ("launch", _) | ("closure", _) => "walkthrough",
+        _ => return suggestion(None, format!("No agent for the {} phase", phase)),
+    };
+
+    if available(agent) {
+        let reason = format!("{} stage ({} phase)", task.stage, phase);
+        suggestion(Some(agent.to_string()), reason)
+    } else {
+        let reason = format!("{} would take this, but is not in agents.available", agent);
+        suggestion(None, reason)
+    }
+}
+
+/// Where `agent`'s definition lives
+pub fn definition_path(settings: &AgentsConfig, agent: &str) -> PathBuf {
+    let dir = &settings.unified_agents_path;
+    let dir = match dir.strip_prefix("~/").or(dir.strip_prefix("~\\")) {
+        Some(rest) => dirs::home_dir().unwrap_or_default().join(rest),
+        None if dir == "~" => dirs::home_dir().unwrap_or_default(),
+        None => PathBuf::from(dir),
+    };
+    dir.join(format!("{}.md", agent))
+}
+
+/// Whether `agent` is a plain name, which can only point at a file
+/// directly inside unifiedAgentsPath
+pub fn is_plain_name(agent: &str) -> bool {
+    !agent.is_empty()
+        && agent
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+/// The agent's markdown definition
+pub fn load_definition(settings: &AgentsConfig, agent: &str) -> Result<String, String> {
+    if !is_plain_name(agent) {
+        return Err(format!("Invalid agent name: {}", agent));
+    }
+    let path = definition_path(settings, agent);
+    fs::read_to_string(&path).map_err(|e| {
+        format!(
+            "Failed to read agent {} at {}: {}",
+            agent,
+            path.display(),
+            e
+        )
+    })
+}
+
+/// The master instructions followed by the agent's definition, whichever
+/// of them there are
+pub fn preamble(instructions: Option<&str>, definition: Option<&str>) -> Option<String> {
+    let parts: Vec<&str> = [instructions, definition]
+        .into_iter()
+        .flatten()
+        .map(str::trim_end)
+        .filter(|part| !part.is_empty())
+        .collect();
+    (!parts.is_empty()).then(|| parts.join("\n\n---\n\n"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn task(stage: &str, tags: &[&str]) -> Task {
+        serde_json::from_value(json!({
+            "id": "t-1",
+            "projectId": "anycalc",
+            "title": "Add tip calculator",
+            "stage": stage,
+            "phase": data::phase_for_stage(stage),
+            "tags": tags
+        }))
+        .unwrap()
+    }
+
+    fn agent(task: &Task, settings: &AgentsConfig) -> Option<String> {
+        suggest(task, settings).agent
+    }
+
+    #[test]
+    fn routes_by_stage_unless_a_tag_says_otherwise() {
+        let settings = AgentsConfig::default();
+        let by_stage = [
+            ("discovery", "designer"),
+            ("architecture", "architect"),
+            ("qa-planning", "qa"),
+            ("development", "dev"),
+            ("testing", "qa"),
+            ("retrospective", "walkthrough"),
+        ];
+        for (stage, expected) in by_stage {
+            assert_eq!(
+                agent(&task(stage, &[]), &settings).as_deref(),
+                Some(expected)
+            );
+        }
+
+        assert_eq!(
+            agent(&task("development", &["calculators", "Testing"]), &settings).as_deref(),
+            Some("qa")
+        );
+        assert_eq!(
+            agent(&task("development", &["@architect"]), &settings).as_deref(),
+            Some("architect")
+        );
+
+        let only_dev = AgentsConfig {
+            available: crate::config::AvailableAgents {
+                agents: vec!["dev".into()],
+            },
+            ..AgentsConfig::default()
+        };
+        assert_eq!(agent(&task("testing", &[]), &only_dev), None);
+    }
+
+    #[test]
+    fn finds_the_definition_and_puts_it_after_the_instructions() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("qa.md"), "# QA\nWrite the test plan.\n").unwrap();
+        let settings = AgentsConfig {
+            unified_agents_path: dir.path().to_string_lossy().to_string(),
+            ..AgentsConfig::default()
+        };
+
+        let suggestion = suggest(&task("testing", &[]), &settings);
+        assert_eq!(
+            suggestion.definition.map(PathBuf::from),
+            Some(dir.path().join("qa.md"))
+        );
+        assert!(load_definition(&settings, "dev").is_err());
+        fs::write(dir.path().join("secret.md"), "not an agent").unwrap();
+        fs::create_dir(dir.path().join("agents")).unwrap();
+        let nested = AgentsConfig {
+            unified_agents_path: dir.path().join("agents").to_string_lossy().to_string(),
+            ..AgentsConfig::default()
+        };
+        let error = load_definition(&nested, "../secret").unwrap_err();
+        assert_eq!(error, "Invalid agent name: ../secret");
+
+        let definition = load_definition(&settings, "qa").unwrap();
+        assert_eq!(
+            preamble(Some("Follow the rules.\n"), Some(&definition)).as_deref(),
+            Some("Follow the rules.\n\n---\n\n# QA\nWrite the test plan.")
+        );
+        assert_eq!(preamble(None, None), None);
+    }
+}
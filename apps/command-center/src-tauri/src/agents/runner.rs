@@ -0,0 +1,457 @@
+//! Running the agent CLI
+//!
+//! A run starts the configured CLI (Claude Code by default) in the project's
+//! repository and writes the prompt to its stdin. Stdout and stderr are
+//! passed on line by line as they arrive and copied to a log file in
+//! `agent-runs/`. A run still going at the timeout, or cancelled, is killed.
+//! Each finished task run is recorded on its task under `agentRuns`.
+
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::data::{self, Task};
+use crate::watcher::{WriteOrigin, WriteRegistry};
+
+/// Folder in the data directory holding run logs
+pub const RUNS_DIR: &str = "agent-runs";
+
+/// How often the child is checked while no output arrives
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// After the CLI exits, how long to wait for output still in the pipes
+/// (a leftover grandchild can hold them open indefinitely)
+const DRAIN_GRACE: Duration = Duration::from_millis(500);
+
+/// Runs kept on a task; older ones are dropped from tasks.json
+const MAX_RUNS_PER_TASK: usize = 20;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum RunStatus {
+    Succeeded,
+    Failed,
+    TimedOut,
+    Cancelled,
+}
+
+impl RunStatus {
+    /// The status as serialized
+    pub fn as_str(self) -> &'static str {
+        match self {
+            RunStatus::Succeeded => "succeeded",
+            RunStatus::Failed => "failed",
+            RunStatus::TimedOut => "timedOut",
+            RunStatus::Cancelled => "cancelled",
+        }
+    }
+}
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum OutputStream {
+    Stdout,
+    Stderr,
+}
+
+/// Payload of `agent-output`, one per line the CLI prints
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct AgentOutput {
+    pub run_id: String,
+    pub task_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub inbox_item_id: Option<String>,
+    pub stream: OutputStream,
+    pub line: String,
+}
+
+/// What to launch and where
+pub struct AgentCommand {
+    pub program: String,
+    pub args: Vec<String>,
+    /// The project's repository
+    pub cwd: PathBuf,
+    pub prompt: String,
+    pub timeout: Duration,
+}
+
+/// What a run works on: a task, or an instruction from the inbox
+#[derive(Debug, Clone, PartialEq)]
+pub struct RunTarget {
+    pub task_id: Option<String>,
+    pub inbox_item_id: Option<String>,
+    pub project_id: String,
+}
+
+impl RunTarget {
+    pub fn task(task: &Task) -> Self {
+        Self {
+            task_id: Some(task.id.clone()),
+            inbox_item_id: None,
+            project_id: task.project_id.clone(),
+        }
+    }
+
+    pub fn inbox_item(item_id: &str, project_id: &str) -> Self {
+        Self {
+            task_id: None,
+            inbox_item_id: Some(item_id.to_string()),
+            project_id: project_id.to_string(),
+        }
+    }
+}
+
+/// A finished run, as recorded on its task (or inbox item)
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct AgentRun {
+    pub id: String,
+    #[serde(default)]
+    pub task_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub inbox_item_id: Option<String>,
+    pub project_id: String,
+    pub started_at: String,
+    pub finished_at: String,
+    pub duration_ms: u64,
+    /// None when the CLI was killed
+    pub exit_code: Option<i32>,
+    pub status: RunStatus,
+    pub log_file: String,
+}
+
+/// Run the agent CLI for `target` and wait for it, reporting each line of
+/// output to `sink`. Blocks for up to the command's timeout, or until
+/// `cancel` is set.
+pub fn run(
+    id: &str,
+    target: &RunTarget,
+    command: &AgentCommand,
+    log_dir: &Path,
+    cancel: &AtomicBool,
+    sink: &dyn Fn(AgentOutput),
+) -> Result<AgentRun, String> {
+    fs::create_dir_all(log_dir).map_err(|e| format!("Failed to create {}: {}", RUNS_DIR, e))?;
+    let log_path = log_dir.join(format!("{}.log", id));
+    let mut log =
+        File::create(&log_path).map_err(|e| format!("Failed to create run log: {}", e))?;
+
+    let started = Instant::now();
+    let started_at = chrono::Utc::now().to_rfc3339();
+    let mut child = Command::new(resolve_program(&command.program))
+        .args(&command.args)
+        .current_dir(&command.cwd)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("Failed to start {}: {}", command.program, e))?;
+
+    // Written from a thread so a CLI that prints before reading all of its
+    // input cannot deadlock against us; dropping stdin sends EOF
+    if let Some(mut stdin) = child.stdin.take() {
+        let prompt = command.prompt.clone();
+        std::thread::spawn(move || {
+            let _ = stdin.write_all(prompt.as_bytes());
+        });
+    }
+
+    let (tx, rx) = mpsc::channel();
+    if let Some(stdout) = child.stdout.take() {
+        forward(stdout, OutputStream::Stdout, tx.clone());
+    }
+    if let Some(stderr) = child.stderr.take() {
+        forward(stderr, OutputStream::Stderr, tx);
+    }
+
+    let mut handle = |(stream, line): (OutputStream, String)| {
+        let _ = match stream {
+            OutputStream::Stdout => writeln!(log, "{}", line),
+            OutputStream::Stderr => writeln!(log, "[stderr] {}", line),
+        };
+        sink(AgentOutput {
+            run_id: id.to_string(),
+            task_id: target.task_id.clone(),
+            inbox_item_id: target.inbox_item_id.clone(),
+            stream,
+            line,
+        });
+    };
+
+    let mut killed = None;
+    let exit = loop {
+        match rx.recv_timeout(POLL_INTERVAL) {
+            Ok(output) => handle(output),
+            Err(RecvTimeoutError::Timeout) => {}
+            // Both pipes closed before the process exited
+            Err(RecvTimeoutError::Disconnected) => std::thread::sleep(POLL_INTERVAL),
+        }
+
+        if let Some(status) = child
+            .try_wait()
+            .map_err(|e| format!("Failed to check agent process: {}", e))?
+        {
+            break Some(status);
+        }
+        if cancel.load(Ordering::SeqCst) {
+            killed = Some(RunStatus::Cancelled);
+        } else if started.elapsed() >= command.timeout {
+            killed = Some(RunStatus::TimedOut);
+        }
+        if killed.is_some() {
+            let _ = child.kill();
+            break child.wait().ok();
+        }
+    };
+
+    let drain_until = Instant::now() + DRAIN_GRACE;
+    while let Some(left) = drain_until.checked_duration_since(Instant::now()) {
+        match rx.recv_timeout(left) {
+            Ok(output) => handle(output),
+            Err(_) => break,
+        }
+    }
+    match killed {
+        Some(RunStatus::TimedOut) => {
+            let _ = writeln!(
+                log,
+                "[timeout] killed after {} ms",
+                command.timeout.as_millis()
+            );
+        }
+        Some(_) => {
+            let _ = writeln!(log, "[cancelled]");
+        }
+        None => {}
+    }
+
+    let exit_code = exit.and_then(|status| status.code());
+    let status = match (killed, exit_code) {
+        (Some(status), _) => status,
+        (None, Some(0)) => RunStatus::Succeeded,
+        (None, _) => RunStatus::Failed,
+    };
+
+    Ok(AgentRun {
+        id: id.to_string(),
+        task_id: target.task_id.clone(),
+        inbox_item_id: target.inbox_item_id.clone(),
+        project_id: target.project_id.clone(),
+        started_at,
+        finished_at: chrono::Utc::now().to_rfc3339(),
+        duration_ms: started.elapsed().as_millis() as u64,
+        exit_code: if killed.is_some() { None } else { exit_code },
+        status,
+        log_file: log_path.to_string_lossy().to_string(),
+    })
+}
+
+/// Send each line read from `pipe` down `tx` until it closes
+fn forward<R: Read + Send + 'static>(
+    pipe: R,
+    stream: OutputStream,
+    tx: Sender<(OutputStream, String)>,
+) {
+    std::thread::spawn(move || {
+        let mut reader = BufReader::new(pipe);
+        let mut buf = Vec::new();
+        loop {
+            buf.clear();
+            match reader.read_until(b'\n', &mut buf) {
+                Ok(0) | Err(_) => break,
+                Ok(_) => {
+                    let line = String::from_utf8_lossy(&buf).trim_end().to_string();
+                    if tx.send((stream, line)).is_err() {
+                        break;
+                    }
+                }
+            }
+        }
+    });
+}
+
+/// npm installs CLIs on Windows as `.cmd` shims, which `Command` does not
+/// find by bare name; look those up on PATH with PATHEXT
+fn resolve_program(program: &str) -> PathBuf {
+    let path = Path::new(program);
+    if !cfg!(windows) || path.extension().is_some() || path.components().count() > 1 {
+        return path.to_path_buf();
+    }
+
+    let extensions = std::env::var("PATHEXT").unwrap_or_else(|_| ".COM;.EXE;.BAT;.CMD".into());
+    std::env::var_os("PATH")
+        .iter()
+        .flat_map(std::env::split_paths)
+        .flat_map(|dir| {
+            extensions
+                .split(';')
+                .map(move |ext| dir.join(format!("{}{}", program, ext)))
+        })
+        .find(|candidate| candidate.is_file())
+        .unwrap_or_else(|| path.to_path_buf())
+}
+
+/// Add a finished task run to its task's `agentRuns`, newest last
+pub fn record_run(data_dir: &Path, writes: &WriteRegistry, run: &AgentRun) -> Result<(), String> {
+    let Some(task_id) = &run.task_id else {
+        return Ok(());
+    };
+    let mut tasks = data::load_tasks(data_dir)?;
+    let task = tasks
+        .tasks
+        .iter_mut()
+        .find(|t| &t.id == task_id)
+        .ok_or_else(|| format!("Task not found: {}", task_id))?;
+
+    let entry =
+        serde_json::to_value(run).map_err(|e| format!("Failed to serialize agent run: {}", e))?;
+    let runs = task
+        .extra
+        .entry("agentRuns")
+        .or_insert_with(|| Value::Array(vec![]));
+    if !runs.is_array() {
+        *runs = Value::Array(vec![]);
+    }
+    if let Value::Array(runs) = runs {
+        runs.push(entry);
+        let excess = runs.len().saturating_sub(MAX_RUNS_PER_TASK);
+        runs.drain(..excess);
+    }
+
+    data::save_tasks(data_dir, writes, &mut tasks, WriteOrigin::Backend)
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::data::Project;
+    use std::os::unix::fs::PermissionsExt;
+    use std::sync::Mutex;
+
+    /// A shell script standing in for the agent CLI
+    fn stub(dir: &Path, body: &str) -> String {
+        let path = dir.join("agent-stub");
+        fs::write(&path, format!("#!/bin/sh\n{}\n", body)).unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+        path.to_string_lossy().to_string()
+    }
+
+    fn task() -> Task {
+        let project: Project = serde_json::from_value(serde_json::json!({
+            "id": "anycalc",
+            "name": "AnyCalc"
+        }))
+        .unwrap();
+        Task::new(
+            "t-1".into(),
+            &project,
+            "Add tip calculator".into(),
+            "P1".into(),
+        )
+    }
+
+    fn command(program: String, cwd: &Path, timeout: Duration) -> AgentCommand {
+        AgentCommand {
+            program,
+            args: vec!["-p".into()],
+            cwd: cwd.to_path_buf(),
+            prompt: "Build the tip calculator".into(),
+            timeout,
+        }
+    }
+
+    #[test]
+    fn streams_output_and_records_the_exit_code() {
+        let dir = tempfile::tempdir().unwrap();
+        let program = stub(
+            dir.path(),
+            "echo \"args: $*\"\necho \"in: $(cat)\"\npwd\necho oops >&2\nexit 3",
+        );
+        let lines = Mutex::new(Vec::new());
+
+        let run = run(
+            "agent-run-1",
+            &RunTarget::task(&task()),
+            &command(program, dir.path(), Duration::from_secs(10)),
+            &dir.path().join(RUNS_DIR),
+            &AtomicBool::new(false),
+            &|output| lines.lock().unwrap().push((output.stream, output.line)),
+        )
+        .unwrap();
+
+        assert_eq!(run.status, RunStatus::Failed);
+        assert_eq!(run.exit_code, Some(3));
+        assert_eq!(
+            (run.task_id.as_deref(), run.project_id.as_str()),
+            (Some("t-1"), "anycalc")
+        );
+
+        let lines = lines.into_inner().unwrap();
+        let cwd = dir.path().canonicalize().unwrap();
+        assert!(lines.contains(&(OutputStream::Stdout, "args: -p".into())));
+        assert!(lines.contains(&(OutputStream::Stdout, "in: Build the tip calculator".into())));
+        assert!(lines.contains(&(OutputStream::Stdout, cwd.to_string_lossy().to_string())));
+        assert!(lines.contains(&(OutputStream::Stderr, "oops".into())));
+
+        let log = fs::read_to_string(&run.log_file).unwrap();
+        assert!(log.contains("[stderr] oops"));
+    }
+
+    #[test]
+    fn kills_a_run_that_outlives_the_timeout() {
+        let dir = tempfile::tempdir().unwrap();
+        let program = stub(dir.path(), "echo started\nexec sleep 30");
+
+        let started = Instant::now();
+        let run = run(
+            "agent-run-2",
+            &RunTarget::task(&task()),
+            &command(program, dir.path(), Duration::from_millis(500)),
+            &dir.path().join(RUNS_DIR),
+            &AtomicBool::new(false),
+            &|_| {},
+        )
+        .unwrap();
+
+        assert!(started.elapsed() < Duration::from_secs(5));
+        assert_eq!(run.status, RunStatus::TimedOut);
+        assert_eq!(run.exit_code, None);
+        let log = fs::read_to_string(&run.log_file).unwrap();
+        assert!(log.starts_with("started\n") && log.contains("[timeout]"));
+    }
+
+    #[test]
+    fn runs_are_recorded_on_the_task() {
+        let dir = tempfile::tempdir().unwrap();
+        let writes = WriteRegistry::default();
+        let mut tasks = data::load_tasks(dir.path()).unwrap();
+        tasks.tasks.push(task());
+        data::save_tasks(dir.path(), &writes, &mut tasks, WriteOrigin::App).unwrap();
+
+        let program = stub(dir.path(), "echo done");
+        let finished = run(
+            "agent-run-3",
+            &RunTarget::task(&task()),
+            &command(program, dir.path(), Duration::from_secs(10)),
+            &dir.path().join(RUNS_DIR),
+            &AtomicBool::new(false),
+            &|_| {},
+        )
+        .unwrap();
+        record_run(dir.path(), &writes, &finished).unwrap();
+
+        let tasks = data::load_tasks(dir.path()).unwrap();
+        let runs = tasks.tasks[0].extra["agentRuns"].as_array().unwrap();
+        assert_eq!(runs.len(), 1);
+        assert_eq!(runs[0]["status"], "succeeded");
+        assert_eq!(runs[0]["exitCode"], 0);
+    }
+}
@@ -0,0 +1,673 @@
+//! Agent session records
+//!
+//! Each agent session is kept as `sessions/<id>.json` in the data directory:
+//! the prompt, everything the agent printed, the files it touched in the
+//! repository (from git, against the state when the session started) and
+//! the changes it made to tasks and projects. Sessions come from runs the
+//! backend launches, or from agent CLI hooks dropping their payloads into
+//! `sessions/hooks/`.
+//!
+//! Board changes made outside the app while a session is open are credited
+//! to that session's agent. A session with no activity for longer than the
+//! run timeout is taken to be abandoned (a hook session that never got its
+//! Stop) and gets none; runner sessions a crash left open are closed on
+//! the next start.
+//!
+//! Hook sessions starting and finishing, sessions closed as interrupted
+//! and the board changes credited to a session go to the activity log;
+//! runner sessions are logged with their run.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::atomic::AtomicBool;
+use std::sync::Mutex;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+
+use super::runner::{self, AgentCommand, AgentOutput, AgentRun, OutputStream, RunTarget};
+use super::RUNS_DIR;
+use crate::audit::{self, Action, Actor};
+use crate::config;
+use crate::data::{self, Project, Task};
+
+/// Folder in the data directory holding session records
+pub const SESSIONS_DIR: &str = "sessions";
+
+/// Folder inside `sessions/` where agent CLI hooks drop their payloads
+pub const HOOKS_DIR: &str = "hooks";
+
+/// Serializes read-modify-write of session files between the run threads
+/// and the watcher
+static RECORDS: Mutex<()> = Mutex::new(());
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum SessionSource {
+    /// A run the backend launched
+    Runner,
+    /// An agent started elsewhere, reporting through a CLI hook
+    Hook,
+}
+
+/// A file the agent added, changed or deleted
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct FileTouched {
+    pub path: String,
+    /// "added", "modified", "deleted" or "renamed"
+    pub change: String,
+}
+
+/// A change to a task or project made while a session was open
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct BoardMutation {
+    pub at: String,
+    /// "task" or "project"
+    pub entity: String,
+    pub id: String,
+    pub project_id: Option<String>,
+    pub title: Option<String>,
+    /// "added", "updated" or "removed"
+    pub change: String,
+    /// Top-level fields that changed, for updates
+    #[serde(default)]
+    pub fields: Vec<String>,
+}
+
+/// The repository as it was when a session started
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct RepoBaseline {
+    pub head: Option<String>,
+    /// Files already changed at the start, with a SHA-256 of their content
+    pub dirty: HashMap<String, String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct AgentSession {
+    pub id: String,
+    pub source: SessionSource,
+    /// The task's `assignedAgent`, or "claude"
+    pub agent: String,
+    pub task_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub inbox_item_id: Option<String>,
+    pub project_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub run_id: Option<String>,
+    pub repo: Option<String>,
+    pub started_at: String,
+    /// None while the session is open
+    pub finished_at: Option<String>,
+    /// Last sign of life: the start, or the latest hook event
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub active_at: Option<String>,
+    pub status: Option<String>,
+    pub prompt: String,
+    /// Stdout as printed, stderr lines prefixed with `[stderr]`
+    pub output: String,
+    pub files: Vec<FileTouched>,
+    pub board: Vec<BoardMutation>,
+    #[serde(default)]
+    pub baseline: RepoBaseline,
+}
+
+impl AgentSession {
+    fn open(id: String, source: SessionSource, agent: &str, repo: Option<&Path>) -> Self {
+        let now = Utc::now().to_rfc3339();
+        Self {
+            id,
+            source,
+            agent: agent.to_string(),
+            task_id: None,
+            inbox_item_id: None,
+            project_id: None,
+            run_id: None,
+            repo: repo.map(|r| r.to_string_lossy().to_string()),
+            started_at: now.clone(),
+            finished_at: None,
+            active_at: Some(now),
+            status: None,
+            prompt: String::new(),
+            output: String::new(),
+            files: vec![],
+            board: vec![],
+            baseline: repo.map(baseline).unwrap_or_default(),
+        }
+    }
+
+    /// Close the session, listing the files touched since it started
+    fn close(&mut self, status: &str) {
+        if let Some(repo) = &self.repo {
+            self.files = files_touched(Path::new(repo), &self.baseline);
+        }
+        self.finished_at = Some(chrono::Utc::now().to_rfc3339());
+        self.status = Some(status.to_string());
+    }
+
+    /// Open, and active within `timeout` of `now`
+    fn is_live(&self, timeout: chrono::Duration, now: DateTime<Utc>) -> bool {
+        let active_at = self.active_at.as_deref().unwrap_or(&self.started_at);
+        self.finished_at.is_none()
+            && DateTime::parse_from_rfc3339(active_at)
+                .is_ok_and(|at| now - at.with_timezone(&Utc) < timeout)
+    }
+}
+
+fn sessions_dir(data_dir: &Path) -> PathBuf {
+    data_dir.join(SESSIONS_DIR)
+}
+
+fn load(data_dir: &Path, id: &str) -> Option<AgentSession> {
+    let content = fs::read_to_string(sessions_dir(data_dir).join(format!("{}.json", id))).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+fn save(data_dir: &Path, session: &AgentSession) -> Result<(), String> {
+    let dir = sessions_dir(data_dir);
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create {}: {}", SESSIONS_DIR, e))?;
+    let content = serde_json::to_string_pretty(session)
+        .map_err(|e| format!("Failed to serialize agent session: {}", e))?;
+    fs::write(dir.join(format!("{}.json", session.id)), content)
+        .map_err(|e| format!("Failed to save agent session: {}", e))
+}
+
+/// Recorded sessions, newest first, optionally only those for one task
+pub fn list(data_dir: &Path, task_id: Option<&str>) -> Vec<AgentSession> {
+    let entries = fs::read_dir(sessions_dir(data_dir))
+        .into_iter()
+        .flatten()
+        .flatten();
+    let mut sessions: Vec<AgentSession> = entries
+        .filter_map(|entry| {
+            let content = fs::read_to_string(entry.path()).ok()?;
+            serde_json::from_str::<AgentSession>(&content).ok()
+        })
+        .filter(|s| task_id.is_none() || s.task_id.as_deref() == task_id)
+        .collect();
+    sessions.sort_by(|a, b| b.started_at.cmp(&a.started_at));
+    sessions
+}
+
+/// Add a session starting or finishing to the activity log
+fn log_session(data_dir: &Path, session: &AgentSession, command: &str, result: Result<(), String>) {
+    let project_id = session.project_id.as_deref();
+    let mut action = Action::new(Actor::Agent, command).entity("session", &session.id, project_id);
+    if let Some(task_id) = &session.task_id {
+        action = action.entity("task", task_id, project_id);
+    }
+    let mut detail = session.agent.clone();
+    if session.finished_at.is_some() {
+        detail.push_str(&format!(" ({} files)", session.files.len()));
+    }
+    let _ = audit::record(data_dir, action.detail(detail), result);
+}
+
+/// Run `command` for `target` with a session recording it. The session is
+/// open (and so collects board changes) for as long as the run lasts.
+pub fn run_recorded(
+    data_dir: &Path,
+    run_id: &str,
+    target: &RunTarget,
+    agent: &str,
+    command: &AgentCommand,
+    cancel: &AtomicBool,
+    sink: &dyn Fn(AgentOutput),
+) -> Result<AgentRun, String> {
+    let mut session = AgentSession::open(
+        data::generate_id("session"),
+        SessionSource::Runner,
+        agent,
+        Some(&command.cwd),
+    );
+    session.task_id = target.task_id.clone();
+    session.inbox_item_id = target.inbox_item_id.clone();
+    session.project_id = Some(target.project_id.clone());
+    session.run_id = Some(run_id.to_string());
+    session.prompt = command.prompt.clone();
+    {
+        let _records = RECORDS.lock().unwrap();
+        save(data_dir, &session)?;
+    }
+
+    let output = Mutex::new(String::new());
+    let run = runner::run(
+        run_id,
+        target,
+        command,
+        &data_dir.join(RUNS_DIR),
+        cancel,
+        &|line| {
+            let mut output = output.lock().unwrap();
+            if line.stream == OutputStream::Stderr {
+                output.push_str("[stderr] ");
+            }
+            output.push_str(&line.line);
+            output.push('\n');
+            drop(output);
+            sink(line);
+        },
+    );
+
+    let status = match &run {
+        Ok(run) => serde_json::to_value(run.status)
+            .ok()
+            .and_then(|v| v.as_str().map(str::to_string))
+            .unwrap_or_default(),
+        Err(_) => "failed".to_string(),
+    };
+    {
+        let _records = RECORDS.lock().unwrap();
+        // Board changes were added to the file while the agent ran
+        let mut finished = load(data_dir, &session.id).unwrap_or(session);
+        finished.output = output.into_inner().unwrap();
+        if let Err(e) = &run {
+            finished.output.push_str(&format!("[error] {}\n", e));
+        }
+        finished.close(&status);
+        save(data_dir, &finished)?;
+    }
+
+    run
+}
+
+/// Close the runner sessions left open when the app last stopped; their
+/// runs died with it
+pub fn close_interrupted(data_dir: &Path) {
+    let _records = RECORDS.lock().unwrap();
+    let open = list(data_dir, None)
+        .into_iter()
+        .filter(|s| s.source == SessionSource::Runner && s.finished_at.is_none());
+    for mut session in open {
+        session.close("interrupted");
+        if save(data_dir, &session).is_ok() {
+            let interrupted = Err("Interrupted".to_string());
+            log_session(data_dir, &session, "session_finished", interrupted);
+        }
+    }
+}
+
+/// Credit board changes to the live sessions they belong to: one working on
+/// the same task, or else the newest one in the same project. Returns the
+/// sessions that changed.
+pub fn record_mutations(data_dir: &Path, mutations: &[BoardMutation]) -> Vec<AgentSession> {
+    let timeout = config::load_config(data_dir).agent_execution.timeout;
+    let timeout = chrono::Duration::milliseconds(timeout.try_into().unwrap_or(i64::MAX));
+    let now = Utc::now();
+
+    let _records = RECORDS.lock().unwrap();
+    let mut open: Vec<AgentSession> = list(data_dir, None)
+        .into_iter()
+        .filter(|s| s.is_live(timeout, now))
+        .collect();
+    if open.is_empty() {
+        return vec![];
+    }
+
+    let mut changed = vec![false; open.len()];
+    for mutation in mutations {
+        let same_task = open.iter().position(|s| {
+            mutation.entity == "task" && s.task_id.as_deref() == Some(mutation.id.as_str())
+        });
+        let same_project = || {
+            open.iter()
+                .position(|s| s.project_id.is_some() && s.project_id == mutation.project_id)
+        };
+        let Some(index) = same_task.or_else(same_project) else {
+            continue;
+        };
+
+        let session = &mut open[index];
+        session.board.push(mutation.clone());
+        changed[index] = true;
+
+        let mut action = format!(
+            "{} {} {}",
+            capitalize(&mutation.change),
+            mutation.entity,
+            mutation.id
+        );
+        if let Some(title) = &mutation.title {
+            action.push_str(&format!(": {}", title));
+        }
+        if !mutation.fields.is_empty() {
+            action.push_str(&format!(" ({})", mutation.fields.join(", ")));
+        }
+        let entry = Action::new(Actor::Agent, "board_change")
+            .entity(
+                &mutation.entity,
+                &mutation.id,
+                mutation.project_id.as_deref(),
+            )
+            .entity("session", &session.id, None)
+            .detail(action);
+        let _ = audit::record(data_dir, entry, Ok(()));
+    }
+
+    open.into_iter()
+        .zip(changed)
+        .filter(|(_, changed)| *changed)
+        .map(|(session, _)| session)
+        .filter(|session| save(data_dir, session).is_ok())
+        .collect()
+}
+
+fn capitalize(s: &str) -> String {
+    let mut chars = s.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+/// Apply one agent CLI hook payload (Claude Code's hook input, optionally
+/// with `taskId` and `agent` added) to its session
+pub fn apply_hook(
+    data_dir: &Path,
+    payload: &Value,
+    projects: &[Project],
+    tasks: &[Task],
+) -> Result<AgentSession, String> {
+    let text = |key: &str| {
+        payload
+            .get(key)
+            .and_then(Value::as_str)
+            .filter(|s| !s.is_empty())
+    };
+    let hook_session = text("session_id").ok_or("Hook payload has no session_id")?;
+    let id = format!(
+        "hook-{}",
+        hook_session
+            .chars()
+            .filter(|c| c.is_ascii_alphanumeric() || *c == '-')
+            .collect::<String>()
+    );
+
+    let _records = RECORDS.lock().unwrap();
+    let mut session = match load(data_dir, &id) {
+        Some(session) => session,
+        None => {
+            let cwd = text("cwd").map(PathBuf::from);
+            let project = cwd.as_deref().and_then(|cwd| {
+                projects.iter().find(|p| {
+                    p.extra
+                        .get("repoPath")
+                        .and_then(Value::as_str)
+                        .filter(|repo| !repo.is_empty())
+                        .is_some_and(|repo| cwd.starts_with(repo))
+                })
+            });
+            let task = text("taskId").and_then(|id| tasks.iter().find(|t| t.id == id));
+            let agent = text("agent")
+                .or_else(|| task?.extra.get("assignedAgent")?.as_str())
+                .unwrap_or("claude");
+
+            let mut session = AgentSession::open(id, SessionSource::Hook, agent, cwd.as_deref());
+            session.task_id = text("taskId").map(str::to_string);
+            session.project_id = project
+                .map(|p| p.id.clone())
+                .or_else(|| task.map(|t| t.project_id.clone()));
+            log_session(data_dir, &session, "session_started", Ok(()));
+            session
+        }
+    };
+
+    session.active_at = Some(Utc::now().to_rfc3339());
+    match text("hook_event_name").unwrap_or("") {
+        "UserPromptSubmit" => {
+            if let Some(prompt) = text("prompt") {
+                if !session.prompt.is_empty() {
+                    session.prompt.push_str("\n\n---\n\n");
+                }
+                session.prompt.push_str(prompt);
+            }
+            // A new turn reopens a session closed by the last Stop
+            session.finished_at = None;
+            session.status = None;
+        }
+        "PostToolUse" => {
+            let input = payload.get("tool_input");
+            let detail = ["file_path", "command", "pattern"]
+                .iter()
+                .find_map(|key| input?.get(key)?.as_str())
+                .unwrap_or("");
+            let tool = text("tool_name").unwrap_or("tool");
+            session.output.push_str(&format!("[{}] {}\n", tool, detail));
+        }
+        "Stop" | "SessionEnd" => {
+            session.close("finished");
+            log_session(data_dir, &session, "session_finished", Ok(()));
+        }
+        _ => {}
+    }
+
+    save(data_dir, &session)?;
+    Ok(session)
+}
+
+/// Where the repository stands now
+pub fn baseline(repo: &Path) -> RepoBaseline {
+    RepoBaseline {
+        head: git(repo, &["rev-parse", "HEAD"]).map(|head| head.trim().to_string()),
+        dirty: changed_paths(repo, None)
+            .into_iter()
+            .map(|file| {
+                let hash = content_hash(&repo.join(&file.path));
+                (file.path, hash)
+            })
+            .collect(),
+    }
+}
+
+/// Files changed since `baseline`, leaving out those that were already
+/// changed then and have not been touched since
+pub fn files_touched(repo: &Path, baseline: &RepoBaseline) -> Vec<FileTouched> {
+    changed_paths(repo, baseline.head.as_deref())
+        .into_iter()
+        .filter(|file| {
+            baseline.dirty.get(&file.path) != Some(&content_hash(&repo.join(&file.path)))
+        })
+        .collect()
+}
+
+/// Changes against `base` (HEAD when None), plus untracked files
+fn changed_paths(repo: &Path, base: Option<&str>) -> Vec<FileTouched> {
+    let Some(diff) = git(repo, &["diff", "--name-status", base.unwrap_or("HEAD")]) else {
+        return vec![];
+    };
+    let mut files: Vec<FileTouched> = diff
+        .lines()
+        .filter_map(|line| {
+            let mut parts = line.split('\t');
+            let status = parts.next()?;
+            let path = parts.next_back()?;
+            let change = match status.chars().next()? {
+                'A' => "added",
+                'D' => "deleted",
+                'R' => "renamed",
+                _ => "modified",
+            };
+            Some(FileTouched {
+                path: path.to_string(),
+                change: change.to_string(),
+            })
+        })
+        .collect();
+
+    let untracked = git(repo, &["ls-files", "--others", "--exclude-standard"]).unwrap_or_default();
+    files.extend(untracked.lines().map(|path| FileTouched {
+        path: path.to_string(),
+        change: "added".to_string(),
+    }));
+    files
+}
+
+fn git(repo: &Path, args: &[&str]) -> Option<String> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(repo)
+        .args(args)
+        .output()
+        .ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+/// SHA-256 of a file's content, that of no content when it does not exist
+fn content_hash(path: &Path) -> String {
+    format!("{:x}", Sha256::digest(fs::read(path).unwrap_or_default()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn run_git(repo: &Path, args: &[&str]) {
+        let status = Command::new("git")
+            .arg("-C")
+            .arg(repo)
+            .args(["-c", "user.name=Test", "-c", "user.email=test@example.com"])
+            .args(args)
+            .status()
+            .unwrap();
+        assert!(status.success());
+    }
+
+    #[test]
+    fn lists_files_changed_since_the_session_started() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = dir.path();
+        run_git(repo, &["init", "-q"]);
+        fs::write(repo.join("app.rs"), "fn main() {}\n").unwrap();
+        fs::write(repo.join("notes.md"), "todo\n").unwrap();
+        run_git(repo, &["add", "."]);
+        run_git(repo, &["commit", "-qm", "init"]);
+        // Already being edited before the agent starts
+        fs::write(repo.join("notes.md"), "todo: tip rounding\n").unwrap();
+
+        let start = baseline(repo);
+        fs::write(repo.join("app.rs"), "fn main() { tip(); }\n").unwrap();
+        fs::write(repo.join("tip.rs"), "fn tip() {}\n").unwrap();
+        run_git(repo, &["add", "tip.rs"]);
+        run_git(repo, &["commit", "-qm", "tip"]);
+
+        let mut files = files_touched(repo, &start);
+        files.sort_by(|a, b| a.path.cmp(&b.path));
+        let files: Vec<(&str, &str)> = files
+            .iter()
+            .map(|f| (f.path.as_str(), f.change.as_str()))
+            .collect();
+        assert_eq!(files, vec![("app.rs", "modified"), ("tip.rs", "added")]);
+    }
+
+    #[test]
+    fn board_changes_go_to_the_open_session_and_the_activity_log() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut session =
+            AgentSession::open("session-1".into(), SessionSource::Runner, "dev-agent", None);
+        session.task_id = Some("t-1".into());
+        session.project_id = Some("anycalc".into());
+        save(dir.path(), &session).unwrap();
+
+        let mutation = |id: &str, project: &str| BoardMutation {
+            at: String::new(),
+            entity: "task".into(),
+            id: id.into(),
+            project_id: Some(project.into()),
+            title: Some("Add tip calculator".into()),
+            change: "updated".into(),
+            fields: vec!["status".into()],
+        };
+        let changed = record_mutations(
+            dir.path(),
+            &[mutation("t-1", "anycalc"), mutation("t-9", "taskboard")],
+        );
+
+        assert_eq!(changed.len(), 1);
+        let recorded = &list(dir.path(), Some("t-1"))[0];
+        assert_eq!(recorded.board, vec![mutation("t-1", "anycalc")]);
+
+        let log = fs::read_to_string(dir.path().join(audit::LOG_FILE)).unwrap();
+        let entries: Vec<audit::ActivityEntry> = log
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].command, "board_change");
+        assert_eq!(
+            entries[0].detail.as_deref(),
+            Some("Updated task t-1: Add tip calculator (status)")
+        );
+    }
+
+    #[test]
+    fn abandoned_sessions_stop_collecting_board_changes() {
+        let dir = tempfile::tempdir().unwrap();
+        let long_ago = (Utc::now() - chrono::Duration::hours(3)).to_rfc3339();
+        let open = |id: &str, source: SessionSource| {
+            let mut session = AgentSession::open(id.into(), source, "dev-agent", None);
+            session.project_id = Some("anycalc".into());
+            session.started_at = long_ago.clone();
+            session.active_at = Some(long_ago.clone());
+            save(dir.path(), &session).unwrap();
+        };
+        // A crashed run, and a hook session that never got its Stop
+        open("session-1", SessionSource::Runner);
+        open("hook-abc", SessionSource::Hook);
+
+        let mutation = BoardMutation {
+            at: String::new(),
+            entity: "task".into(),
+            id: "t-1".into(),
+            project_id: Some("anycalc".into()),
+            title: None,
+            change: "added".into(),
+            fields: vec![],
+        };
+        assert!(record_mutations(dir.path(), &[mutation]).is_empty());
+
+        close_interrupted(dir.path());
+        let status = |id: &str| load(dir.path(), id).unwrap().status;
+        assert_eq!(status("session-1").as_deref(), Some("interrupted"));
+        assert_eq!(status("hook-abc"), None);
+    }
+
+    #[test]
+    fn hook_payloads_build_up_a_session() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = dir.path().join("anycalc");
+        fs::create_dir(&repo).unwrap();
+        let projects: Vec<Project> = serde_json::from_value(json!([
+            { "id": "anycalc", "name": "AnyCalc", "repoPath": repo }
+        ]))
+        .unwrap();
+        let hook = |event: Value| apply_hook(dir.path(), &event, &projects, &[]).unwrap();
+
+        hook(
+            json!({ "session_id": "abc", "cwd": repo, "hook_event_name": "UserPromptSubmit",
+                     "prompt": "Fix the tip rounding" }),
+        );
+        hook(
+            json!({ "session_id": "abc", "cwd": repo, "hook_event_name": "PostToolUse",
+                     "tool_name": "Edit", "tool_input": { "file_path": "src/tip.rs" } }),
+        );
+        let session = hook(json!({ "session_id": "abc", "cwd": repo, "hook_event_name": "Stop" }));
+
+        assert_eq!(session.id, "hook-abc");
+        assert_eq!(session.source, SessionSource::Hook);
+        assert_eq!(session.project_id.as_deref(), Some("anycalc"));
+        assert_eq!(session.prompt, "Fix the tip rounding");
+        assert_eq!(session.output, "[Edit] src/tip.rs\n");
+        assert_eq!(session.status.as_deref(), Some("finished"));
+    }
+}
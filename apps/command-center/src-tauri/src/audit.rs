@@ -0,0 +1,481 @@
+//! Action audit log
+//!
+//! When `advanced.logActions` is on, data-layer commands and backend
+//! subsystems append one JSON line per action to ~/.taskboard/activity.jsonl:
+//! when it happened, who did it (the user, Claude answering the inbox, an
+//! agent run or sync), the command, the entities it touched and whether it
+//! worked. The log is append-only; once it passes `MAX_LOG_BYTES` it is
+//! rotated to activity.1.jsonl, activity.2.jsonl and so on, keeping
+//! `KEEP_ROTATED` old files.
+//!
+//! `get_activity_feed` reads the log back newest first, filtered for a
+//! project timeline or a single entity. An entry that cannot be written
+//! never fails the action it describes; it is reported as an
+//! `activity-log-error` event instead.
+
+use std::collections::HashMap;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tauri::{AppHandle, Emitter, State};
+
+use crate::config;
+use crate::data;
+use crate::AppState;
+
+/// The current log, in the data directory
+pub const LOG_FILE: &str = "activity.jsonl";
+
+/// The log is rotated once it grows past this
+const MAX_LOG_BYTES: u64 = 2 * 1024 * 1024;
+
+/// Rotated logs kept besides the current one
+const KEEP_ROTATED: usize = 4;
+
+/// Entries returned by the feed when the filter sets no limit
+const DEFAULT_FEED_LIMIT: usize = 200;
+
+/// Serializes appends and rotation
+static LOG: Mutex<()> = Mutex::new(());
+
+/// Where log write failures are reported, once the app is up
+static REPORTER: OnceLock<Box<dyn Fn(String) + Send + Sync>> = OnceLock::new();
+
+/// Payload of `activity-log-error`
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+struct ActivityLogErrorEvent {
+    message: String,
+}
+
+/// Who performed an action
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum Actor {
+    User,
+    /// Claude answering `@claude` inbox items
+    Claude,
+    /// Agent runs and the board changes made during agent sessions
+    Agent,
+    Sync,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum Outcome {
+    Ok,
+    Error,
+}
+
+/// An entity an action touched
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct EntityRef {
+    /// "project", "task", "inboxItem", "job", "session" or "document"
+    pub kind: String,
+    pub id: String,
+    /// The project the entity belongs to, when known
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub project_id: Option<String>,
+}
+
+/// One line of the log
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ActivityEntry {
+    pub at: String,
+    pub actor: Actor,
+    pub command: String,
+    #[serde(default)]
+    pub entities: Vec<EntityRef>,
+    pub outcome: Outcome,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Short free-form note, such as the status an item was moved to
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+}
+
+impl ActivityEntry {
+    /// Whether the entry touched `project_id` or one of its entities
+    fn in_project(&self, project_id: &str) -> bool {
+        self.entities.iter().any(|e| {
+            e.project_id.as_deref() == Some(project_id)
+                || (e.kind == "project" && e.id == project_id)
+        })
+    }
+}
+
+/// An action about to be recorded
+#[derive(Debug, Clone)]
+pub struct Action {
+    actor: Actor,
+    command: String,
+    entities: Vec<EntityRef>,
+    detail: Option<String>,
+}
+
+impl Action {
+    pub fn new(actor: Actor, command: &str) -> Self {
+        Action {
+            actor,
+            command: command.to_string(),
+            entities: Vec::new(),
+            detail: None,
+        }
+    }
+
+    pub fn entity(mut self, kind: &str, id: &str, project_id: Option<&str>) -> Self {
+        self.entities.push(EntityRef {
+            kind: kind.to_string(),
+            id: id.to_string(),
+            project_id: project_id.map(str::to_string),
+        });
+        self
+    }
+
+    pub fn entities(mut self, entities: Vec<EntityRef>) -> Self {
+        self.entities.extend(entities);
+        self
+    }
+
+    pub fn detail(mut self, detail: impl Into<String>) -> Self {
+        self.detail = Some(detail.into());
+        self
+    }
+}
+
+/// Whether `advanced.logActions` is on
+pub fn enabled(data_dir: &Path) -> bool {
+    config::load_config(data_dir).advanced.log_actions
+}
+
+/// Report entries that cannot be written to the frontend
+pub fn report_failures(app: AppHandle) {
+    let _ = REPORTER.set(Box::new(move |message| {
+        let _ = app.emit("activity-log-error", ActivityLogErrorEvent { message });
+    }));
+}
+
+/// Record `action` with the outcome of `result`, and pass the result on
+pub fn record<T>(data_dir: &Path, action: Action, result: Result<T, String>) -> Result<T, String> {
+    if !enabled(data_dir) {
+        return result;
+    }
+    let entry = ActivityEntry {
+        at: Utc::now().to_rfc3339(),
+        actor: action.actor,
+        command: action.command,
+        entities: action.entities,
+        outcome: if result.is_ok() {
+            Outcome::Ok
+        } else {
+            Outcome::Error
+        },
+        error: result.as_ref().err().cloned(),
+        detail: action.detail,
+    };
+    if let Err(message) = append(data_dir, &entry) {
+        if let Some(report) = REPORTER.get() {
+            report(message);
+        }
+    }
+    result
+}
+
+fn rotated_path(data_dir: &Path, n: usize) -> PathBuf {
+    data_dir.join(format!("activity.{}.jsonl", n))
+}
+
+fn append(data_dir: &Path, entry: &ActivityEntry) -> Result<(), String> {
+    let line = serde_json::to_string(entry)
+        .map_err(|e| format!("Failed to serialize activity entry: {}", e))?;
+    let _guard = LOG.lock().unwrap();
+
+    let path = data_dir.join(LOG_FILE);
+    let size = fs::metadata(&path).map(|m| m.len()).unwrap_or(0);
+    if size > 0 && size + line.len() as u64 >= MAX_LOG_BYTES {
+        rotate(data_dir).map_err(|e| format!("Failed to rotate activity log: {}", e))?;
+    }
+
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .map_err(|e| format!("Failed to open activity log: {}", e))?;
+    writeln!(file, "{}", line).map_err(|e| format!("Failed to write activity log: {}", e))
+}
+
+/// activity.jsonl → activity.1.jsonl → … dropping the oldest
+fn rotate(data_dir: &Path) -> std::io::Result<()> {
+    let oldest = rotated_path(data_dir, KEEP_ROTATED);
+    if oldest.exists() {
+        fs::remove_file(oldest)?;
+    }
+    for n in (1..KEEP_ROTATED).rev() {
+        let from = rotated_path(data_dir, n);
+        if from.exists() {
+            fs::rename(from, rotated_path(data_dir, n + 1))?;
+        }
+    }
+    fs::rename(data_dir.join(LOG_FILE), rotated_path(data_dir, 1))
+}
+
+/// Entities of `kind` added, changed or removed between two versions of a
+/// data file ("projects", "tasks" or "items" array)
+pub fn changed_entities(before: Option<&Value>, after: &Value, kind: &str) -> Vec<EntityRef> {
+    let collection = match kind {
+        "project" => "projects",
+        "task" => "tasks",
+        _ => "items",
+    };
+    let by_id = |file: Option<&Value>| -> HashMap<String, Value> {
+        file.and_then(|f| f.get(collection))
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+            .filter_map(|item| Some((item.get("id")?.as_str()?.to_string(), item.clone())))
+            .collect()
+    };
+    let old = by_id(before);
+    let new = by_id(Some(after));
+
+    let project_of = |id: &str, item: &Value| {
+        if kind == "project" {
+            return Some(id.to_string());
+        }
+        ["projectId", "project"]
+            .iter()
+            .find_map(|key| item.get(*key).and_then(Value::as_str))
+            .map(str::to_string)
+    };
+    let mut changed: Vec<EntityRef> = new
+        .iter()
+        .filter(|(id, item)| old.get(*id) != Some(item))
+        .chain(old.iter().filter(|(id, _)| !new.contains_key(*id)))
+        .map(|(id, item)| EntityRef {
+            kind: kind.to_string(),
+            id: id.clone(),
+            project_id: project_of(id, item),
+        })
+        .collect();
+    changed.sort_by(|a, b| a.id.cmp(&b.id));
+    changed
+}
+
+/// `changed_entities` between two in-memory versions of a data file
+pub fn changed_between<T: Serialize>(before: &T, after: &T, kind: &str) -> Vec<EntityRef> {
+    let before = serde_json::to_value(before).ok();
+    match serde_json::to_value(after) {
+        Ok(after) => changed_entities(before.as_ref(), &after, kind),
+        Err(_) => Vec::new(),
+    }
+}
+
+/// Which entries the feed returns
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ActivityFilter {
+    /// Entries touching this project or any of its entities
+    pub project_id: Option<String>,
+    /// Entries touching this task, inbox item, job or document
+    pub entity_id: Option<String>,
+    pub actor: Option<Actor>,
+    pub command: Option<String>,
+    pub outcome: Option<Outcome>,
+    /// RFC 3339 bounds, inclusive
+    pub since: Option<String>,
+    pub until: Option<String>,
+    pub limit: Option<usize>,
+}
+
+impl ActivityFilter {
+    fn matches(&self, entry: &ActivityEntry) -> bool {
+        let at = DateTime::parse_from_rfc3339(&entry.at).ok();
+        let bound = |b: &Option<String>| {
+            b.as_deref()
+                .and_then(|b| DateTime::parse_from_rfc3339(b).ok())
+        };
+        self.project_id
+            .as_deref()
+            .is_none_or(|p| entry.in_project(p))
+            && self
+                .entity_id
+                .as_deref()
+                .is_none_or(|id| entry.entities.iter().any(|e| e.id == id))
+            && self.actor.is_none_or(|a| a == entry.actor)
+            && self.command.as_deref().is_none_or(|c| c == entry.command)
+            && self.outcome.is_none_or(|o| o == entry.outcome)
+            && bound(&self.since).is_none_or(|since| at.is_some_and(|at| at >= since))
+            && bound(&self.until).is_none_or(|until| at.is_some_and(|at| at <= until))
+    }
+}
+
+/// Entries matching `filter`, newest first, across the current and rotated logs
+pub fn feed(data_dir: &Path, filter: &ActivityFilter) -> Vec<ActivityEntry> {
+    let limit = filter.limit.unwrap_or(DEFAULT_FEED_LIMIT);
+    let files = std::iter::once(data_dir.join(LOG_FILE))
+        .chain((1..=KEEP_ROTATED).map(|n| rotated_path(data_dir, n)));
+
+    let mut entries = Vec::new();
+    for path in files {
+        let Ok(text) = fs::read_to_string(&path) else {
+            continue;
+        };
+        let matching = text
+            .lines()
+            .rev()
+            .filter_map(|line| serde_json::from_str::<ActivityEntry>(line).ok())
+            .filter(|entry| filter.matches(entry));
+        for entry in matching {
+            if entries.len() == limit {
+                return entries;
+            }
+            entries.push(entry);
+        }
+    }
+    entries
+}
+
+/// Read the activity log, newest first
+#[tauri::command]
+pub fn get_activity_feed(
+    filters: Option<ActivityFilter>,
+    state: State<'_, Mutex<AppState>>,
+) -> Vec<ActivityEntry> {
+    feed(&data::get_data_dir(&state), &filters.unwrap_or_default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn log_actions(dir: &Path, on: bool) {
+        let config = serde_json::json!({ "advanced": { "logActions": on } });
+        fs::write(dir.join("config.json"), config.to_string()).unwrap();
+    }
+
+    #[test]
+    fn records_outcomes_and_filters_a_project_timeline() {
+        let dir = tempfile::tempdir().unwrap();
+        log_actions(dir.path(), true);
+
+        let before = serde_json::json!({ "tasks": [
+            { "id": "t-1", "projectId": "anycalc", "title": "Tip" },
+            { "id": "t-3", "projectId": "anycalc", "title": "Same" }
+        ]});
+        let after = serde_json::json!({ "tasks": [
+            { "id": "t-1", "projectId": "anycalc", "title": "Tips" },
+            { "id": "t-2", "projectId": "other", "title": "New" },
+            { "id": "t-3", "projectId": "anycalc", "title": "Same" }
+        ]});
+        let changed = changed_entities(Some(&before), &after, "task");
+        assert_eq!(changed.len(), 2);
+        assert_eq!(changed[0].project_id.as_deref(), Some("anycalc"));
+
+        let ok: Result<(), String> = Ok(());
+        record(
+            dir.path(),
+            Action::new(Actor::User, "write_tasks").entities(changed),
+            ok,
+        )
+        .unwrap();
+        let failed: Result<(), String> = Err("Inbox item not found: i-9".to_string());
+        let action = Action::new(Actor::Claude, "answer_inbox_item").entity(
+            "inboxItem",
+            "i-9",
+            Some("anycalc"),
+        );
+        assert!(record(dir.path(), action, failed).is_err());
+        let ok: Result<(), String> = Ok(());
+        let action = Action::new(Actor::Sync, "push").entity("project", "other", Some("other"));
+        record(dir.path(), action, ok).unwrap();
+
+        let timeline = feed(
+            dir.path(),
+            &ActivityFilter {
+                project_id: Some("anycalc".into()),
+                ..Default::default()
+            },
+        );
+        let commands: Vec<&str> = timeline.iter().map(|e| e.command.as_str()).collect();
+        assert_eq!(commands, ["answer_inbox_item", "write_tasks"]);
+        assert_eq!(timeline[0].outcome, Outcome::Error);
+        assert_eq!(
+            timeline[0].error.as_deref(),
+            Some("Inbox item not found: i-9")
+        );
+
+        let by_actor = ActivityFilter {
+            actor: Some(Actor::Sync),
+            ..Default::default()
+        };
+        assert_eq!(feed(dir.path(), &by_actor).len(), 1);
+
+        log_actions(dir.path(), false);
+        let ok: Result<(), String> = Ok(());
+        record(dir.path(), Action::new(Actor::User, "write_projects"), ok).unwrap();
+        assert_eq!(feed(dir.path(), &ActivityFilter::default()).len(), 3);
+    }
+
+    #[test]
+    fn rotates_by_size_and_reads_across_rotated_files() {
+        let dir = tempfile::tempdir().unwrap();
+        log_actions(dir.path(), true);
+        fs::write(
+            dir.path().join(LOG_FILE),
+            "x".repeat(MAX_LOG_BYTES as usize),
+        )
+        .unwrap();
+        for n in 1..=KEEP_ROTATED {
+            let old = Action::new(Actor::User, &format!("old-{}", n));
+            let line = serde_json::to_string(&ActivityEntry {
+                at: Utc::now().to_rfc3339(),
+                actor: old.actor,
+                command: old.command,
+                entities: Vec::new(),
+                outcome: Outcome::Ok,
+                error: None,
+                detail: None,
+            })
+            .unwrap();
+            fs::write(rotated_path(dir.path(), n), line + "\n").unwrap();
+        }
+
+        let ok: Result<(), String> = Ok(());
+        record(dir.path(), Action::new(Actor::Agent, "run_agent"), ok).unwrap();
+
+        let log = fs::read_to_string(dir.path().join(LOG_FILE)).unwrap();
+        assert_eq!(log.lines().count(), 1);
+        assert!(rotated_path(dir.path(), 1).metadata().unwrap().len() >= MAX_LOG_BYTES);
+        assert!(!rotated_path(dir.path(), KEEP_ROTATED + 1).exists());
+
+        // The oversized line in activity.1.jsonl is skipped, the oldest file dropped
+        let commands: Vec<String> = feed(dir.path(), &ActivityFilter::default())
+            .into_iter()
+            .map(|e| e.command)
+            .collect();
+        assert_eq!(commands, ["run_agent", "old-1", "old-2", "old-3"]);
+    }
+
+    #[test]
+    fn a_log_it_cannot_write_leaves_the_outcome_alone() {
+        let dir = tempfile::tempdir().unwrap();
+        log_actions(dir.path(), true);
+        fs::create_dir(dir.path().join(LOG_FILE)).unwrap();
+
+        let ok: Result<u32, String> = Ok(7);
+        assert_eq!(
+            record(dir.path(), Action::new(Actor::User, "write_tasks"), ok),
+            Ok(7)
+        );
+        let failed: Result<(), String> = Err("Task not found".into());
+        let error = record(dir.path(), Action::new(Actor::User, "write_tasks"), failed);
+        assert_eq!(error, Err("Task not found".to_string()));
+    }
+}
@@ -0,0 +1,477 @@
+//! Typed view of config.json
+//!
+//! Only the sections the backend acts on are modelled here. Missing values
+//! fall back to their defaults. Each section is read on its own, so a
+//! malformed value resets only the section it is in (to the same defaults
+//! as config.example.json) and a partial config never stops a background
+//! subsystem.
+
+use chrono_tz::Tz;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::fs;
+use std::path::Path;
+
+use crate::watcher::{WriteOrigin, WriteRegistry};
+
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(default, rename_all = "camelCase")]
+pub struct AppConfig {
+    pub user: UserConfig,
+    pub notifications: NotificationsConfig,
+    pub integrations: IntegrationsConfig,
+    pub voice: VoiceSettings,
+    pub agents: AgentsConfig,
+    pub agent_execution: AgentExecutionConfig,
+    pub advanced: AdvancedConfig,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default, rename_all = "camelCase")]
+pub struct UserConfig {
+    pub timezone: String,
+}
+
+impl Default for UserConfig {
+    fn default() -> Self {
+        Self {
+            timezone: "UTC".to_string(),
+        }
+    }
+}
+
+impl UserConfig {
+    /// Parse the configured IANA timezone, falling back to UTC
+    pub fn tz(&self) -> Tz {
+        self.timezone.parse().unwrap_or(Tz::UTC)
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default, rename_all = "camelCase")]
+pub struct NotificationsConfig {
+    pub enabled: bool,
+    pub desktop: bool,
+    pub quiet_hours: QuietHoursConfig,
+    pub sources: SourceRules,
+    pub batching: BatchingConfig,
+}
+
+impl Default for NotificationsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            desktop: true,
+            quiet_hours: QuietHoursConfig::default(),
+            sources: SourceRules::default(),
+            batching: BatchingConfig::default(),
+        }
+    }
+}
+
+/// Window during which non-urgent notifications are held back.
+/// `start`/`end` are "HH:MM" in the user's timezone; the window may wrap midnight.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default, rename_all = "camelCase")]
+pub struct QuietHoursConfig {
+    pub enabled: bool,
+    pub start: String,
+    pub end: String,
+}
+
+impl Default for QuietHoursConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            start: "22:00".to_string(),
+            end: "07:00".to_string(),
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(default, rename_all = "camelCase")]
+pub struct SourceRules {
+    pub inbox: SourceRule,
+    pub replies: SourceRule,
+    pub reminders: SourceRule,
+    pub agents: SourceRule,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default, rename_all = "camelCase")]
+pub struct SourceRule {
+    /// Show notifications from this source at all
+    pub enabled: bool,
+    /// Deliver immediately even during quiet hours
+    pub bypass_quiet_hours: bool,
+}
+
+impl Default for SourceRule {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            bypass_quiet_hours: false,
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default, rename_all = "camelCase")]
+pub struct BatchingConfig {
+    /// How long to collect events before deciding how to show them
+    pub window_ms: u64,
+    /// Collapse into a single digest once this many events are pending
+    pub threshold: usize,
+}
+
+impl Default for BatchingConfig {
+    fn default() -> Self {
+        Self {
+            window_ms: 3000,
+            threshold: 3,
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(default, rename_all = "camelCase")]
+pub struct IntegrationsConfig {
+    pub groq: GroqConfig,
+    pub openai_compatible: OpenAiCompatibleConfig,
+}
+
+/// `useFor` entry that routes voice capture to a hosted API
+pub const USE_FOR_VOICE: &str = "voiceTranscription";
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default, rename_all = "camelCase")]
+pub struct GroqConfig {
+    pub enabled: bool,
+    pub api_key: Option<String>,
+    pub model: String,
+    pub use_for: Vec<String>,
+}
+
+impl Default for GroqConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            api_key: None,
+            model: "whisper-large-v3-turbo".to_string(),
+            use_for: vec![USE_FOR_VOICE.to_string()],
+        }
+    }
+}
+
+impl GroqConfig {
+    /// Enabled for voice with a real key (not the example placeholder)
+    pub fn transcribes_voice(&self) -> bool {
+        self.enabled
+            && self.use_for.iter().any(|u| u == USE_FOR_VOICE)
+            && is_real_key(self.api_key.as_deref())
+    }
+}
+
+/// Any server exposing OpenAI's `/audio/transcriptions` (OpenAI itself,
+/// a self-hosted faster-whisper, ...)
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default, rename_all = "camelCase")]
+pub struct OpenAiCompatibleConfig {
+    pub enabled: bool,
+    /// API root, e.g. "https://api.openai.com/v1"
+    pub base_url: String,
+    pub api_key: Option<String>,
+    pub model: String,
+    pub use_for: Vec<String>,
+}
+
+impl Default for OpenAiCompatibleConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            base_url: "https://api.openai.com/v1".to_string(),
+            api_key: None,
+            model: "whisper-1".to_string(),
+            use_for: vec![USE_FOR_VOICE.to_string()],
+        }
+    }
+}
+
+impl OpenAiCompatibleConfig {
+    /// Enabled for voice; self-hosted servers often need no key
+    pub fn transcribes_voice(&self) -> bool {
+        self.enabled && self.use_for.iter().any(|u| u == USE_FOR_VOICE) && !self.base_url.is_empty()
+    }
+}
+
+fn is_real_key(key: Option<&str>) -> bool {
+    key.is_some_and(|k| !k.trim().is_empty() && !k.starts_with("YOUR_"))
+}
+
+/// Local Whisper model and spoken language
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default, rename_all = "camelCase")]
+pub struct VoiceSettings {
+    /// Model id, e.g. "base" or "small.en-q5_1"
+    pub model: String,
+    /// Whisper language code, or "auto" to detect
+    pub language: String,
+    /// Keep each capture's audio and transcript in the voice archive
+    pub keep_recordings: bool,
+}
+
+impl Default for VoiceSettings {
+    fn default() -> Self {
+        Self {
+            model: "base".to_string(),
+            language: "en".to_string(),
+            keep_recordings: false,
+        }
+    }
+}
+
+/// The shared agent definitions
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default, rename_all = "camelCase")]
+pub struct AgentsConfig {
+    pub enabled: bool,
+    /// Folder of `<agent>.md` definitions; `~` is the home directory
+    pub unified_agents_path: String,
+    pub available: AvailableAgents,
+}
+
+impl Default for AgentsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            unified_agents_path: "~/Documents/Projects/_claude-shared/agents".to_string(),
+            available: AvailableAgents::default(),
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default, rename_all = "camelCase")]
+pub struct AvailableAgents {
+    pub agents: Vec<String>,
+}
+
+impl Default for AvailableAgents {
+    fn default() -> Self {
+        Self {
+            agents: ["designer", "architect", "qa", "dev", "walkthrough"]
+                .map(String::from)
+                .to_vec(),
+        }
+    }
+}
+
+/// How agent runs are launched
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default, rename_all = "camelCase")]
+pub struct AgentExecutionConfig {
+    /// "auto", "manual" or "hybrid"
+    pub mode: String,
+    /// "inbox" starts the agent on inbox items addressed to Claude
+    pub auto_trigger: String,
+    /// Kill switch for the inbox trigger, off until switched on so a first
+    /// launch never runs the agent on old inbox items
+    pub auto_trigger_enabled: bool,
+    /// Reply with what would have run instead of running it
+    pub dry_run: bool,
+    /// Agent CLI, a bare name looked up on PATH or a full path
+    pub claude_code_path: String,
+    /// Arguments before the prompt, which is written to the CLI's stdin
+    pub args: Vec<String>,
+    /// Longest a run may take (ms) before it is killed
+    pub timeout: u64,
+    /// Queued jobs running at once, across all projects
+    pub max_concurrent: usize,
+    /// Queued jobs running at once within one project
+    pub max_concurrent_per_project: usize,
+    /// Runs a job gets before it is marked failed (1 = no retry)
+    pub max_attempts: u32,
+    /// Wait (ms) before a failed or timed-out job is run again
+    pub retry_delay: u64,
+    pub notifications: AgentNotifications,
+}
+
+impl Default for AgentExecutionConfig {
+    fn default() -> Self {
+        Self {
+            mode: "hybrid".to_string(),
+            auto_trigger: "inbox".to_string(),
+            auto_trigger_enabled: false,
+            dry_run: false,
+            claude_code_path: "claude".to_string(),
+            args: vec!["-p".to_string()],
+            timeout: 300_000,
+            max_concurrent: 2,
+            max_concurrent_per_project: 1,
+            max_attempts: 2,
+            retry_delay: 60_000,
+            notifications: AgentNotifications::default(),
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default, rename_all = "camelCase")]
+pub struct AgentNotifications {
+    pub on_start: bool,
+    pub on_complete: bool,
+    pub on_error: bool,
+}
+
+impl Default for AgentNotifications {
+    fn default() -> Self {
+        Self {
+            on_start: true,
+            on_complete: true,
+            on_error: true,
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default, rename_all = "camelCase")]
+pub struct AdvancedConfig {
+    /// Keep the activity log, agent activity included
+    pub log_actions: bool,
+    /// Quiet period (ms) after the last file event before reacting to it
+    pub file_watch_debounce: u64,
+    pub file_watch_mode: FileWatchMode,
+}
+
+impl Default for AdvancedConfig {
+    fn default() -> Self {
+        Self {
+            log_actions: true,
+            file_watch_debounce: 500,
+            file_watch_mode: FileWatchMode::Auto,
+        }
+    }
+}
+
+/// How the data directory is watched
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "camelCase")]
+pub enum FileWatchMode {
+    /// Native OS events, falling back to polling if they are unavailable
+    #[default]
+    Auto,
+    /// Native OS events only
+    Native,
+    /// Always poll (network mounts where native events never arrive)
+    Poll,
+}
+
+/// Load config.json from the data directory, using defaults when absent or
+/// invalid, section by section
+pub fn load_config(data_dir: &Path) -> AppConfig {
+    let root: Value = fs::read_to_string(data_dir.join("config.json"))
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default();
+
+    AppConfig {
+        user: section(&root, "user"),
+        notifications: section(&root, "notifications"),
+        integrations: section(&root, "integrations"),
+        voice: section(&root, "voice"),
+        agents: section(&root, "agents"),
+        agent_execution: section(&root, "agentExecution"),
+        advanced: section(&root, "advanced"),
+    }
+}
+
+/// One top-level section of config.json, or its defaults if it is missing
+/// or malformed
+fn section<T: DeserializeOwned + Default>(root: &Value, name: &str) -> T {
+    root.get(name)
+        .and_then(|section| T::deserialize(section).ok())
+        .unwrap_or_default()
+}
+
+/// Set the voice model and language in config.json, keeping everything else
+/// as written. The frontend is told to reload via `config-changed`.
+pub fn save_voice_settings(
+    data_dir: &Path,
+    writes: &WriteRegistry,
+    settings: &VoiceSettings,
+) -> Result<(), String> {
+    update_section(data_dir, writes, "voice", |voice| {
+        voice["model"] = json!(settings.model);
+        voice["language"] = json!(settings.language);
+    })
+}
+
+/// Switch the inbox trigger on or off, and optionally its dry-run mode
+pub fn save_inbox_trigger(
+    data_dir: &Path,
+    writes: &WriteRegistry,
+    enabled: bool,
+    dry_run: Option<bool>,
+) -> Result<(), String> {
+    update_section(data_dir, writes, "agentExecution", |settings| {
+        settings["autoTriggerEnabled"] = json!(enabled);
+        if let Some(dry_run) = dry_run {
+            settings["dryRun"] = json!(dry_run);
+        }
+    })
+}
+
+/// Change one top-level section of config.json in place
+fn update_section(
+    data_dir: &Path,
+    writes: &WriteRegistry,
+    name: &str,
+    update: impl FnOnce(&mut Value),
+) -> Result<(), String> {
+    let path = data_dir.join("config.json");
+    let mut config: Value = match fs::read_to_string(&path) {
+        Ok(content) => serde_json::from_str(&content)
+            .map_err(|e| format!("Failed to parse config.json: {}", e))?,
+        Err(_) => json!({}),
+    };
+
+    let root = config.as_object_mut().ok_or("config.json is not an object")?;
+    let section = root.entry(name).or_insert_with(|| json!({}));
+    if !section.is_object() {
+        *section = json!({});
+    }
+    update(section);
+
+    let content = serde_json::to_string_pretty(&config)
+        .map_err(|e| format!("Failed to serialize config: {}", e))?;
+    writes
+        .write(&path, &content, WriteOrigin::Backend)
+        .map_err(|e| format!("Failed to write config.json: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_malformed_value_only_resets_its_own_section() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = json!({
+            "user": { "timezone": "Europe/Lisbon" },
+            "voice": { "model": 42 },
+            "agentExecution": { "autoTriggerEnabled": true, "timeout": "soon" },
+            "advanced": { "logActions": false }
+        });
+        fs::write(dir.path().join("config.json"), config.to_string()).unwrap();
+
+        let config = load_config(dir.path());
+        assert_eq!(config.user.timezone, "Europe/Lisbon");
+        assert!(!config.advanced.log_actions);
+        assert_eq!(config.voice.model, VoiceSettings::default().model);
+        // The kill switch stays off when its section cannot be read
+        assert!(!config.agent_execution.auto_trigger_enabled);
+        assert!(!AppConfig::default().agent_execution.auto_trigger_enabled);
+    }
+}
@@ -1,19 +1,109 @@
 //! Data layer for reading/writing JSON files
 //!
 //! Handles all file operations for projects, tasks, and inbox.
+//!
+//! Every write is registered with the file watcher first, so the watcher
+//! can tell the app's own changes apart from external edits.
+
+mod board;
+mod inbox;
+mod inbox_md;
 
-use serde::{Deserialize, Serialize};
 use std::fs;
-use std::path::PathBuf;
-use tauri::State;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use tauri::{AppHandle, State};
 use std::sync::Mutex;
+use crate::agents::{self, JobQueue};
+use crate::audit::{self, Action, Actor};
+use crate::config;
+use crate::notifications::Notifier;
+use crate::watcher::{self, WriteOrigin, WriteRegistry};
 use crate::AppState;
 
+pub use board::{
+    load_projects, load_tasks, phase_for_stage, save_projects, save_tasks, Project, ProjectsFile,
+    Task, TaskComment, TasksFile, STAGES, TASK_STATUSES,
+};
+pub use inbox::{load_inbox, save_inbox, InboxFile, InboxItem, InboxReply};
+pub use inbox_md::SyncSummary;
+
+/// Last timestamp handed out by `generate_id`
+static LAST_ID_MS: AtomicU64 = AtomicU64::new(0);
+
+/// Generate an id in the frontend's `{prefix}-{millis}` format.
+/// Timestamps are bumped when needed so ids stay unique within a process.
+pub fn generate_id(prefix: &str) -> String {
+    let now = chrono::Utc::now().timestamp_millis().max(0) as u64;
+    let mut last = LAST_ID_MS.load(Ordering::Relaxed);
+    loop {
+        let next = now.max(last + 1);
+        match LAST_ID_MS.compare_exchange(last, next, Ordering::Relaxed, Ordering::Relaxed) {
+            Ok(_) => return format!("{}-{}", prefix, next),
+            Err(actual) => last = actual,
+        }
+    }
+}
+
+/// Write `content` to a temporary file next to `path` and rename it into
+/// place, so a failed write never leaves a truncated file behind
+pub fn write_atomic(path: &Path, content: &str) -> std::io::Result<()> {
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".tmp");
+    fs::write(&tmp, content)?;
+    fs::rename(&tmp, path)
+}
+
 /// Get the data directory path
-fn get_data_dir(state: &State<'_, Mutex<AppState>>) -> PathBuf {
+pub(crate) fn get_data_dir(state: &State<'_, Mutex<AppState>>) -> PathBuf {
     state.lock().unwrap().data_dir.clone()
 }
 
+/// Get the shared write registry
+pub(crate) fn get_writes(state: &State<'_, Mutex<AppState>>) -> WriteRegistry {
+    state.lock().unwrap().writes.clone()
+}
+
+/// Write a file in the data directory as an app-originated change
+fn write_data_file(
+    filename: &str,
+    data: &str,
+    state: &State<'_, Mutex<AppState>>,
+) -> Result<(), String> {
+    let path = get_data_dir(state).join(filename);
+
+    get_writes(state)
+        .write(&path, data, WriteOrigin::App)
+        .map_err(|e| format!("Failed to write {}: {}", filename, e))
+}
+
+/// Write a whole data file from the frontend, logging the `kind` entities
+/// it adds, changes or removes. Saves that change nothing are not logged.
+fn write_collection(
+    command: &str,
+    filename: &str,
+    kind: &str,
+    data: &str,
+    state: &State<'_, Mutex<AppState>>,
+) -> Result<(), String> {
+    let data_dir = get_data_dir(state);
+    if !audit::enabled(&data_dir) {
+        return write_data_file(filename, data, state);
+    }
+
+    let parse = |text: &str| serde_json::from_str::<serde_json::Value>(text).ok();
+    let before = fs::read_to_string(data_dir.join(filename)).ok().and_then(|t| parse(&t));
+    let changed = match parse(data) {
+        Some(after) => audit::changed_entities(before.as_ref(), &after, kind),
+        None => Vec::new(),
+    };
+    let result = write_data_file(filename, data, state);
+    if changed.is_empty() && result.is_ok() {
+        return result;
+    }
+    audit::record(&data_dir, Action::new(Actor::User, command).entities(changed), result)
+}
+
 /// Get the path to a data file
 #[tauri::command]
 pub fn get_data_path(filename: &str, state: State<'_, Mutex<AppState>>) -> Result<String, String> {
@@ -21,6 +111,32 @@ pub fn get_data_path(filename: &str, state: State<'_, Mutex<AppState>>) -> Resul
     Ok(path.to_string_lossy().to_string())
 }
 
+/// Switch to a different data directory (workspace), restarting the watcher
+/// on it and loading its agent job queue
+#[tauri::command]
+pub fn switch_workspace(
+    data_dir: String,
+    app_handle: AppHandle,
+    state: State<'_, Mutex<AppState>>,
+    notifier: State<'_, Notifier>,
+    agent_jobs: State<'_, JobQueue>,
+) -> Result<(), String> {
+    let data_dir = PathBuf::from(data_dir);
+
+    if !data_dir.exists() {
+        fs::create_dir_all(&data_dir)
+            .map_err(|e| format!("Failed to create data directory: {}", e))?;
+    }
+
+    state.lock().unwrap().data_dir = data_dir.clone();
+    notifier.set_data_dir(data_dir.clone());
+    let jobs = agent_jobs.open(&data_dir);
+    agents::reload_policy(&app_handle);
+    watcher::start_watcher(app_handle);
+
+    jobs.map(|_| ())
+}
+
 /// Read projects.json
 #[tauri::command]
 pub fn read_projects(state: State<'_, Mutex<AppState>>) -> Result<String, String> {
@@ -38,10 +154,7 @@ pub fn read_projects(state: State<'_, Mutex<AppState>>) -> Result<String, String
 /// Write projects.json
 #[tauri::command]
 pub fn write_projects(data: String, state: State<'_, Mutex<AppState>>) -> Result<(), String> {
-    let path = get_data_dir(&state).join("projects.json");
-
-    fs::write(&path, data)
-        .map_err(|e| format!("Failed to write projects.json: {}", e))
+    write_collection("write_projects", "projects.json", "project", &data, &state)
 }
 
 /// Read tasks.json
@@ -60,10 +173,7 @@ pub fn read_tasks(state: State<'_, Mutex<AppState>>) -> Result<String, String> {
 /// Write tasks.json
 #[tauri::command]
 pub fn write_tasks(data: String, state: State<'_, Mutex<AppState>>) -> Result<(), String> {
-    let path = get_data_dir(&state).join("tasks.json");
-
-    fs::write(&path, data)
-        .map_err(|e| format!("Failed to write tasks.json: {}", e))
+    write_collection("write_tasks", "tasks.json", "task", &data, &state)
 }
 
 /// Read inbox.md (for Claude readability)
@@ -82,10 +192,8 @@ pub fn read_inbox(state: State<'_, Mutex<AppState>>) -> Result<String, String> {
 /// Write inbox.md (for Claude readability)
 #[tauri::command]
 pub fn write_inbox(data: String, state: State<'_, Mutex<AppState>>) -> Result<(), String> {
-    let path = get_data_dir(&state).join("inbox.md");
-
-    fs::write(&path, data)
-        .map_err(|e| format!("Failed to write inbox.md: {}", e))
+    let result = write_data_file("inbox.md", &data, &state);
+    audit::record(&get_data_dir(&state), Action::new(Actor::User, "write_inbox"), result)
 }
 
 /// Read inbox.json (structured data)
@@ -101,13 +209,266 @@ pub fn read_inbox_json(state: State<'_, Mutex<AppState>>) -> Result<String, Stri
         .map_err(|e| format!("Failed to read inbox.json: {}", e))
 }
 
-/// Write inbox.json (structured data)
+/// Write inbox.json (structured data) and re-render inbox.md from it
 #[tauri::command]
 pub fn write_inbox_json(data: String, state: State<'_, Mutex<AppState>>) -> Result<(), String> {
-    let path = get_data_dir(&state).join("inbox.json");
+    let parsed: InboxFile = serde_json::from_str(&data)
+        .map_err(|e| format!("Failed to parse inbox.json: {}", e))?;
+    write_collection("write_inbox_json", "inbox.json", "inboxItem", &data, &state)?;
 
-    fs::write(&path, data)
-        .map_err(|e| format!("Failed to write inbox.json: {}", e))
+    inbox::write_inbox_markdown(&get_data_dir(&state), &get_writes(&state), &parsed.items)
+}
+
+/// Reconcile inbox.md and inbox.json.
+///
+/// New checkbox lines and replies written in inbox.md are added to
+/// inbox.json. When inbox.md is the more recently edited file, its text,
+/// priority, project and checkbox state win for existing items. Both files
+/// are then rewritten so every item in inbox.md carries its id.
+#[tauri::command]
+pub fn sync_inbox(state: State<'_, Mutex<AppState>>) -> Result<SyncSummary, String> {
+    let data_dir = get_data_dir(&state);
+    let writes = get_writes(&state);
+
+    let md_path = data_dir.join("inbox.md");
+    let json_path = data_dir.join("inbox.json");
+    let markdown = fs::read_to_string(&md_path).unwrap_or_default();
+
+    let modified = |path: &PathBuf| fs::metadata(path).and_then(|m| m.modified()).ok();
+    let markdown_wins = match (modified(&md_path), modified(&json_path)) {
+        (Some(md), Some(json)) => md > json,
+        (Some(_), None) => true,
+        _ => false,
+    };
+
+    let mut inbox = load_inbox(&data_dir)?;
+    let document = inbox_md::parse(&markdown);
+    let tz = config::load_config(&data_dir).user.tz();
+    let summary = inbox_md::reconcile(&mut inbox, &document, markdown_wins, tz);
+
+    let result = save_inbox(&data_dir, &writes, &mut inbox, WriteOrigin::Backend);
+    let detail = format!(
+        "{} added, {} updated, {} replies",
+        summary.items_added, summary.items_updated, summary.replies_added
+    );
+    audit::record(&data_dir, Action::new(Actor::User, "sync_inbox").detail(detail), result)?;
+
+    Ok(summary)
+}
+
+/// Load inbox.json, apply `update` and save it as a backend change, so the
+/// watcher refreshes the frontend
+fn update_inbox<T>(
+    state: &State<'_, Mutex<AppState>>,
+    action: Action,
+    update: impl FnOnce(&mut InboxFile) -> Result<T, String>,
+) -> Result<T, String> {
+    let data_dir = get_data_dir(state);
+    let result = load_inbox(&data_dir).and_then(|mut inbox| {
+        let result = update(&mut inbox)?;
+        save_inbox(&data_dir, &get_writes(state), &mut inbox, WriteOrigin::Backend)?;
+        Ok(result)
+    });
+    audit::record(&data_dir, action, result)
+}
+
+/// An action on a single inbox item, by the user
+fn inbox_action(command: &str, item_id: &str) -> Action {
+    Action::new(Actor::User, command).entity("inboxItem", item_id, None)
+}
+
+/// Mark an inbox item done or skipped (or back to pending)
+#[tauri::command]
+pub fn mark_inbox_processed(
+    item_id: String,
+    status: Option<String>,
+    state: State<'_, Mutex<AppState>>,
+) -> Result<InboxItem, String> {
+    let status = status.unwrap_or_else(|| "done".to_string());
+    if !matches!(status.as_str(), "done" | "skipped" | "pending") {
+        return Err(format!("Invalid inbox status: {}", status));
+    }
+
+    let action = inbox_action("mark_inbox_processed", &item_id).detail(status.clone());
+    update_inbox(&state, action, |inbox| {
+        let item = inbox.item_mut(&item_id)?;
+        item.set_status(&status);
+        Ok(item.clone())
+    })
+}
+
+/// Result of archiving inbox items
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InboxArchiveResult {
+    pub archived: usize,
+    /// Archive file the items were appended to, if any were archived
+    pub path: Option<String>,
+}
+
+/// Move inbox items into today's archive file. Without ids, every
+/// processed (done or skipped) item is archived.
+#[tauri::command]
+pub fn archive_inbox_items(
+    item_ids: Option<Vec<String>>,
+    state: State<'_, Mutex<AppState>>,
+) -> Result<InboxArchiveResult, String> {
+    let data_dir = get_data_dir(&state);
+    let writes = get_writes(&state);
+
+    let mut action = Action::new(Actor::User, "archive_inbox_items");
+    for id in item_ids.iter().flatten() {
+        action = action.entity("inboxItem", id, None);
+    }
+    update_inbox(&state, action, |inbox| {
+        let ids: Vec<String> = match item_ids {
+            Some(ids) => ids,
+            None => inbox
+                .items
+                .iter()
+                .filter(|item| item.status != "pending")
+                .map(|item| item.id.clone())
+                .collect(),
+        };
+
+        let archived = inbox.items.iter().filter(|item| ids.contains(&item.id)).count();
+        if archived == 0 {
+            return Ok(InboxArchiveResult { archived, path: None });
+        }
+
+        let path = inbox::archive_items(&data_dir, &writes, inbox, &ids, WriteOrigin::Backend)?;
+        Ok(InboxArchiveResult {
+            archived,
+            path: Some(path.to_string_lossy().to_string()),
+        })
+    })
+}
+
+/// Create a task from an inbox item. The task links back via `sourceInbox`
+/// and the item is marked done with the new task's id; if the item cannot
+/// be saved, the task is removed again.
+#[tauri::command]
+pub fn convert_inbox_to_task(
+    item_id: String,
+    project_id: Option<String>,
+    state: State<'_, Mutex<AppState>>,
+) -> Result<Task, String> {
+    let data_dir = get_data_dir(&state);
+    let result = convert_item(&data_dir, &get_writes(&state), &item_id, project_id);
+
+    let mut action = inbox_action("convert_inbox_to_task", &item_id);
+    if let Ok(task) = &result {
+        action = action.entity("task", &task.id, Some(&task.project_id));
+    }
+    audit::record(&data_dir, action, result)
+}
+
+fn convert_item(
+    data_dir: &Path,
+    writes: &WriteRegistry,
+    item_id: &str,
+    project_id: Option<String>,
+) -> Result<Task, String> {
+    let mut inbox = load_inbox(data_dir)?;
+    let item = inbox.item_mut(item_id)?;
+
+    if let Some(task_id) = &item.task_id {
+        return Err(format!("Inbox item {} was already converted to task {}", item_id, task_id));
+    }
+
+    let project_id = project_id
+        .or_else(|| item.project.clone())
+        .ok_or_else(|| format!("Inbox item {} has no project; choose one first", item_id))?;
+    let projects = load_projects(data_dir)?;
+    let project = projects
+        .projects
+        .iter()
+        .find(|p| p.id == project_id)
+        .ok_or_else(|| format!("Project not found: {}", project_id))?;
+
+    // First line becomes the title; longer notes are kept as the description
+    let title = item.text.lines().next().unwrap_or("").trim().to_string();
+    if title.is_empty() {
+        return Err(format!("Inbox item {} has no text", item_id));
+    }
+    let priority = item.priority.clone().unwrap_or_else(|| "P2".to_string());
+
+    let mut task = Task::new(generate_id("t"), project, title, priority);
+    task.source_inbox = Some(item_id.to_string());
+    if item.text.trim().contains('\n') {
+        task.extra.insert(
+            "description".into(),
+            serde_json::Value::String(item.text.trim().to_string()),
+        );
+    }
+
+    let mut tasks = load_tasks(data_dir)?;
+    tasks.tasks.push(task.clone());
+    save_tasks(data_dir, writes, &mut tasks, WriteOrigin::Backend)?;
+
+    item.task_id = Some(task.id.clone());
+    item.project = Some(project_id);
+    item.set_status("done");
+    if let Err(e) = save_inbox(data_dir, writes, &mut inbox, WriteOrigin::Backend) {
+        // Take the task out again so the item can still be converted
+        tasks.tasks.retain(|t| t.id != task.id);
+        return Err(match save_tasks(data_dir, writes, &mut tasks, WriteOrigin::Backend) {
+            Ok(()) => e,
+            Err(undo) => format!("{}; task {} was left behind: {}", e, task.id, undo),
+        });
+    }
+
+    Ok(task)
+}
+
+/// Assign an inbox item to a project, or clear its project with `None`
+#[tauri::command]
+pub fn assign_inbox_item(
+    item_id: String,
+    project_id: Option<String>,
+    state: State<'_, Mutex<AppState>>,
+) -> Result<InboxItem, String> {
+    if let Some(project_id) = &project_id {
+        let projects = load_projects(&get_data_dir(&state))?;
+        if !projects.projects.iter().any(|p| &p.id == project_id) {
+            return Err(format!("Project not found: {}", project_id));
+        }
+    }
+
+    let mut action = inbox_action("assign_inbox_item", &item_id);
+    if let Some(project_id) = &project_id {
+        action = action.entity("project", project_id, Some(project_id));
+    }
+    update_inbox(&state, action, |inbox| {
+        let item = inbox.item_mut(&item_id)?;
+        item.project = project_id;
+        Ok(item.clone())
+    })
+}
+
+/// Add a reply from the user to an inbox item
+#[tauri::command]
+pub fn reply_to_inbox_item(
+    item_id: String,
+    text: String,
+    state: State<'_, Mutex<AppState>>,
+) -> Result<InboxReply, String> {
+    let text = text.trim().to_string();
+    if text.is_empty() {
+        return Err("Reply text is empty".to_string());
+    }
+
+    update_inbox(&state, inbox_action("reply_to_inbox_item", &item_id), |inbox| {
+        let reply = InboxReply {
+            id: generate_id("reply"),
+            author: "user".to_string(),
+            text,
+            created_at: chrono::Utc::now().to_rfc3339(),
+            extra: serde_json::Map::new(),
+        };
+        inbox.item_mut(&item_id)?.replies.push(reply.clone());
+        Ok(reply)
+    })
 }
 
 /// Read sync-config.json (gist sync settings)
@@ -126,10 +487,8 @@ pub fn read_sync_config(state: State<'_, Mutex<AppState>>) -> Result<String, Str
 /// Write sync-config.json (gist sync settings)
 #[tauri::command]
 pub fn write_sync_config(data: String, state: State<'_, Mutex<AppState>>) -> Result<(), String> {
-    let path = get_data_dir(&state).join("sync-config.json");
-
-    fs::write(&path, data)
-        .map_err(|e| format!("Failed to write sync-config.json: {}", e))
+    let result = write_data_file("sync-config.json", &data, &state);
+    audit::record(&get_data_dir(&state), Action::new(Actor::User, "write_sync_config"), result)
 }
 
 /// Read any markdown document
@@ -147,17 +506,27 @@ pub fn read_document(path: String) -> Result<String, String> {
 
 /// Write any markdown document
 #[tauri::command]
-pub fn write_document(path: String, content: String) -> Result<(), String> {
+pub fn write_document(
+    path: String,
+    content: String,
+    state: State<'_, Mutex<AppState>>,
+) -> Result<(), String> {
     let path = PathBuf::from(path);
-
-    // Ensure parent directory exists
-    if let Some(parent) = path.parent() {
-        if !parent.exists() {
-            fs::create_dir_all(parent)
-                .map_err(|e| format!("Failed to create directory: {}", e))?;
+    let action = Action::new(Actor::User, "write_document")
+        .entity("document", &path.to_string_lossy(), None);
+
+    let result = (|| {
+        // Ensure parent directory exists
+        if let Some(parent) = path.parent() {
+            if !parent.exists() {
+                fs::create_dir_all(parent)
+                    .map_err(|e| format!("Failed to create directory: {}", e))?;
+            }
         }
-    }
 
-    fs::write(&path, content)
-        .map_err(|e| format!("Failed to write document: {}", e))
+        get_writes(&state)
+            .write(&path, &content, WriteOrigin::App)
+            .map_err(|e| format!("Failed to write document: {}", e))
+    })();
+    audit::record(&get_data_dir(&state), action, result)
 }
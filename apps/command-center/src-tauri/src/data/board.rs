@@ -0,0 +1,273 @@
+//! Typed tasks.json / projects.json model
+//!
+//! Only the fields backend commands read or change are typed; everything
+//! else is kept in `extra` so the frontend's data survives a round trip.
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::fs;
+use std::path::Path;
+
+use crate::watcher::{WriteOrigin, WriteRegistry};
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct TasksFile {
+    #[serde(default = "default_version")]
+    pub version: String,
+    #[serde(default)]
+    pub last_updated: String,
+    #[serde(default)]
+    pub tasks: Vec<Task>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Task {
+    pub id: String,
+    pub project_id: String,
+    pub title: String,
+    #[serde(default)]
+    pub stage: String,
+    #[serde(default)]
+    pub phase: String,
+    #[serde(default = "default_task_status")]
+    pub status: String,
+    #[serde(default = "default_priority")]
+    pub priority: String,
+    #[serde(default)]
+    pub started_at: Option<String>,
+    #[serde(default)]
+    pub updated_at: String,
+    #[serde(default)]
+    pub completed_at: Option<String>,
+    #[serde(default)]
+    pub comments: Vec<TaskComment>,
+    /// Inbox item the task was created from
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source_inbox: Option<String>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct TaskComment {
+    pub id: String,
+    #[serde(rename = "type")]
+    pub comment_type: String,
+    pub author: String,
+    pub content: String,
+    pub created_at: String,
+    #[serde(default)]
+    pub for_claude: bool,
+    #[serde(default)]
+    pub resolved: bool,
+    #[serde(default = "default_comment_source")]
+    pub source: String,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ProjectsFile {
+    #[serde(default = "default_version")]
+    pub version: String,
+    #[serde(default)]
+    pub last_updated: String,
+    #[serde(default)]
+    pub projects: Vec<Project>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Project {
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub stage: String,
+    #[serde(default)]
+    pub current_phase: String,
+    #[serde(default)]
+    pub last_updated: String,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+fn default_version() -> String {
+    "1.0.0".to_string()
+}
+
+fn default_task_status() -> String {
+    "todo".to_string()
+}
+
+fn default_priority() -> String {
+    "P2".to_string()
+}
+
+fn default_comment_source() -> String {
+    "text".to_string()
+}
+
+/// Project stages in board order, with the phase each belongs to
+pub const STAGES: [(&str, &str); 15] = [
+    ("conception", "design"),
+    ("discovery", "design"),
+    ("requirements", "design"),
+    ("architecture", "engineering"),
+    ("qa-planning", "engineering"),
+    ("review", "engineering"),
+    ("development", "build"),
+    ("testing", "build"),
+    ("staging", "build"),
+    ("ship", "launch"),
+    ("announce", "launch"),
+    ("walkthrough", "launch"),
+    ("documentation", "closure"),
+    ("portfolio", "closure"),
+    ("retrospective", "closure"),
+];
+
+/// Task statuses the board shows as columns
+pub const TASK_STATUSES: [&str; 5] = ["todo", "in-progress", "review", "completed", "blocked"];
+
+/// Phase a stage belongs to, defaulting to build like the task modal does
+pub fn phase_for_stage(stage: &str) -> &'static str {
+    STAGES
+        .iter()
+        .find(|(s, _)| *s == stage)
+        .map(|(_, phase)| *phase)
+        .unwrap_or("build")
+}
+
+impl Task {
+    /// A new todo task with the same defaults the task modal uses
+    pub fn new(id: String, project: &Project, title: String, priority: String) -> Self {
+        let now = chrono::Utc::now().to_rfc3339();
+        let stage = if project.stage.is_empty() {
+            "development".to_string()
+        } else {
+            project.stage.clone()
+        };
+
+        let mut extra = Map::new();
+        extra.insert("description".into(), Value::String(String::new()));
+        extra.insert("complexity".into(), Value::String("M".into()));
+        extra.insert("assignee".into(), Value::String("claude".into()));
+        extra.insert("assignedAgent".into(), Value::String("dev-agent".into()));
+        extra.insert("dueDate".into(), Value::Null);
+        extra.insert("createdAt".into(), Value::String(now.clone()));
+        extra.insert("dependencies".into(), Value::Array(vec![]));
+        extra.insert("linkedDocs".into(), Value::Array(vec![]));
+        extra.insert("subtasks".into(), Value::Array(vec![]));
+        extra.insert("tags".into(), Value::Array(vec![]));
+        extra.insert("createdBy".into(), Value::String("user".into()));
+        extra.insert("sourceDoc".into(), Value::Null);
+
+        Self {
+            id,
+            project_id: project.id.clone(),
+            title,
+            phase: phase_for_stage(&stage).to_string(),
+            stage,
+            status: "todo".to_string(),
+            priority,
+            started_at: None,
+            updated_at: now,
+            completed_at: None,
+            comments: vec![],
+            source_inbox: None,
+            extra,
+        }
+    }
+
+    /// Change status, keeping the started/completed timestamps in step
+    pub fn set_status(&mut self, status: &str) {
+        let now = chrono::Utc::now().to_rfc3339();
+
+        if status == "in-progress" && self.started_at.is_none() {
+            self.started_at = Some(now.clone());
+        }
+        self.completed_at = if status == "completed" {
+            Some(now.clone())
+        } else {
+            None
+        };
+        self.status = status.to_string();
+        self.updated_at = now;
+    }
+}
+
+/// Load tasks.json, treating a missing file as an empty board
+pub fn load_tasks(data_dir: &Path) -> Result<TasksFile, String> {
+    let path = data_dir.join("tasks.json");
+
+    if !path.exists() {
+        return Ok(TasksFile {
+            version: default_version(),
+            last_updated: String::new(),
+            tasks: vec![],
+            extra: Map::new(),
+        });
+    }
+
+    let content =
+        fs::read_to_string(&path).map_err(|e| format!("Failed to read tasks.json: {}", e))?;
+    serde_json::from_str(&content).map_err(|e| format!("Failed to parse tasks.json: {}", e))
+}
+
+/// Save tasks.json
+pub fn save_tasks(
+    data_dir: &Path,
+    writes: &WriteRegistry,
+    tasks: &mut TasksFile,
+    origin: WriteOrigin,
+) -> Result<(), String> {
+    tasks.last_updated = chrono::Utc::now().to_rfc3339();
+
+    let content = serde_json::to_string_pretty(tasks)
+        .map_err(|e| format!("Failed to serialize tasks: {}", e))?;
+    writes
+        .write(&data_dir.join("tasks.json"), &content, origin)
+        .map_err(|e| format!("Failed to write tasks.json: {}", e))
+}
+
+/// Load projects.json, treating a missing file as no projects
+pub fn load_projects(data_dir: &Path) -> Result<ProjectsFile, String> {
+    let path = data_dir.join("projects.json");
+
+    if !path.exists() {
+        return Ok(ProjectsFile {
+            version: default_version(),
+            last_updated: String::new(),
+            projects: vec![],
+            extra: Map::new(),
+        });
+    }
+
+    let content =
+        fs::read_to_string(&path).map_err(|e| format!("Failed to read projects.json: {}", e))?;
+    serde_json::from_str(&content).map_err(|e| format!("Failed to parse projects.json: {}", e))
+}
+
+/// Save projects.json
+pub fn save_projects(
+    data_dir: &Path,
+    writes: &WriteRegistry,
+    projects: &mut ProjectsFile,
+    origin: WriteOrigin,
+) -> Result<(), String> {
+    projects.last_updated = chrono::Utc::now().to_rfc3339();
+
+    let content = serde_json::to_string_pretty(projects)
+        .map_err(|e| format!("Failed to serialize projects: {}", e))?;
+    writes
+        .write(&data_dir.join("projects.json"), &content, origin)
+        .map_err(|e| format!("Failed to write projects.json: {}", e))
+}
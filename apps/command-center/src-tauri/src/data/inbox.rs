@@ -0,0 +1,283 @@
+//! Typed inbox.json model
+//!
+//! Mirrors the frontend's `InboxItem` / `InboxReply` types. Fields the
+//! backend does not know about are kept in `extra`, so a round trip through
+//! Rust never drops data the frontend (or Claude) added.
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::inbox_md;
+use crate::config;
+use crate::watcher::{WriteOrigin, WriteRegistry};
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct InboxFile {
+    #[serde(default = "default_version")]
+    pub version: String,
+    #[serde(default)]
+    pub last_updated: String,
+    #[serde(default)]
+    pub items: Vec<InboxItem>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+impl Default for InboxFile {
+    fn default() -> Self {
+        Self {
+            version: default_version(),
+            last_updated: String::new(),
+            items: vec![],
+            extra: Map::new(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct InboxItem {
+    pub id: String,
+    pub text: String,
+    #[serde(rename = "type", default = "default_item_type")]
+    pub item_type: String,
+    #[serde(default)]
+    pub project: Option<String>,
+    #[serde(default)]
+    pub priority: Option<String>,
+    #[serde(default = "default_status")]
+    pub status: String,
+    #[serde(default)]
+    pub created_at: String,
+    /// When the item left `pending`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub processed_at: Option<String>,
+    /// Task created from this item
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub task_id: Option<String>,
+    #[serde(default)]
+    pub read: bool,
+    #[serde(default = "default_author")]
+    pub author: String,
+    #[serde(default)]
+    pub parent_id: Option<String>,
+    #[serde(default)]
+    pub replies: Vec<InboxReply>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct InboxReply {
+    pub id: String,
+    pub author: String,
+    pub text: String,
+    #[serde(default)]
+    pub created_at: String,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+impl InboxFile {
+    pub fn item_mut(&mut self, id: &str) -> Result<&mut InboxItem, String> {
+        self.items
+            .iter_mut()
+            .find(|item| item.id == id)
+            .ok_or_else(|| format!("Inbox item not found: {}", id))
+    }
+}
+
+impl InboxItem {
+    /// Change status, stamping `processedAt` when the item leaves `pending`
+    pub fn set_status(&mut self, status: &str) {
+        if status == "pending" {
+            self.processed_at = None;
+        } else if self.status == "pending" || self.processed_at.is_none() {
+            self.processed_at = Some(chrono::Utc::now().to_rfc3339());
+        }
+        self.status = status.to_string();
+    }
+}
+
+fn default_version() -> String {
+    "1.0.0".to_string()
+}
+
+fn default_item_type() -> String {
+    "task".to_string()
+}
+
+fn default_status() -> String {
+    "pending".to_string()
+}
+
+fn default_author() -> String {
+    "user".to_string()
+}
+
+/// Load inbox.json, treating a missing file as an empty inbox
+pub fn load_inbox(data_dir: &Path) -> Result<InboxFile, String> {
+    let path = data_dir.join("inbox.json");
+
+    if !path.exists() {
+        return Ok(InboxFile::default());
+    }
+
+    let content =
+        fs::read_to_string(&path).map_err(|e| format!("Failed to read inbox.json: {}", e))?;
+    serde_json::from_str(&content).map_err(|e| format!("Failed to parse inbox.json: {}", e))
+}
+
+/// Save inbox.json and re-render inbox.md from it, so both files always
+/// describe the same items
+pub fn save_inbox(
+    data_dir: &Path,
+    writes: &WriteRegistry,
+    inbox: &mut InboxFile,
+    origin: WriteOrigin,
+) -> Result<(), String> {
+    inbox.last_updated = chrono::Utc::now().to_rfc3339();
+
+    let content = serde_json::to_string_pretty(inbox)
+        .map_err(|e| format!("Failed to serialize inbox: {}", e))?;
+    writes
+        .write(&data_dir.join("inbox.json"), &content, origin)
+        .map_err(|e| format!("Failed to write inbox.json: {}", e))?;
+
+    write_inbox_markdown(data_dir, writes, &inbox.items)
+}
+
+/// Render items into inbox.md, keeping the layout and any unknown content
+/// of the existing file
+pub fn write_inbox_markdown(
+    data_dir: &Path,
+    writes: &WriteRegistry,
+    items: &[InboxItem],
+) -> Result<(), String> {
+    let md_path = data_dir.join("inbox.md");
+    let existing = fs::read_to_string(&md_path).unwrap_or_default();
+
+    let document = inbox_md::parse(&existing);
+    let tz = config::load_config(data_dir).user.tz();
+    let markdown = inbox_md::render(&document, items, tz);
+
+    writes
+        .write(&md_path, &markdown, WriteOrigin::App)
+        .map_err(|e| format!("Failed to write inbox.md: {}", e))
+}
+
+/// Move items out of inbox.json into `archive/inbox-YYYY-MM-DD.json`
+/// (today's date in the user's timezone), appending to that day's file.
+/// Returns the archive path.
+pub fn archive_items(
+    data_dir: &Path,
+    writes: &WriteRegistry,
+    inbox: &mut InboxFile,
+    ids: &[String],
+    origin: WriteOrigin,
+) -> Result<PathBuf, String> {
+    let tz = config::load_config(data_dir).user.tz();
+    let today = chrono::Utc::now().with_timezone(&tz).date_naive();
+    let path = data_dir
+        .join("archive")
+        .join(format!("inbox-{}.json", today.format("%Y-%m-%d")));
+
+    let mut archive: InboxFile = match fs::read_to_string(&path) {
+        Ok(content) => serde_json::from_str(&content)
+            .map_err(|e| format!("Failed to parse {}: {}", path.display(), e))?,
+        Err(_) => InboxFile::default(),
+    };
+
+    let (archived, kept): (Vec<InboxItem>, Vec<InboxItem>) = std::mem::take(&mut inbox.items)
+        .into_iter()
+        .partition(|item| ids.contains(&item.id));
+    inbox.items = kept;
+    archive.items.extend(archived);
+    archive.last_updated = chrono::Utc::now().to_rfc3339();
+
+    fs::create_dir_all(data_dir.join("archive"))
+        .map_err(|e| format!("Failed to create archive directory: {}", e))?;
+    let content = serde_json::to_string_pretty(&archive)
+        .map_err(|e| format!("Failed to serialize archive: {}", e))?;
+    writes
+        .write(&path, &content, origin)
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(id: &str, status: &str) -> InboxItem {
+        serde_json::from_value(serde_json::json!({
+            "id": id,
+            "text": format!("item {}", id),
+            "status": status,
+            "customField": 42
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn status_changes_stamp_processed_at() {
+        let mut item = item("inbox-1", "pending");
+
+        item.set_status("done");
+        let stamped = item.processed_at.clone();
+        assert!(stamped.is_some());
+
+        // Switching between processed states keeps the original timestamp
+        item.set_status("skipped");
+        assert_eq!(item.processed_at, stamped);
+
+        item.set_status("pending");
+        assert_eq!(item.processed_at, None);
+    }
+
+    #[test]
+    fn archiving_appends_to_the_days_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let writes = WriteRegistry::default();
+        let mut inbox = InboxFile {
+            items: vec![
+                item("inbox-1", "done"),
+                item("inbox-2", "pending"),
+                item("inbox-3", "skipped"),
+            ],
+            ..InboxFile::default()
+        };
+
+        let first = archive_items(
+            dir.path(),
+            &writes,
+            &mut inbox,
+            &["inbox-1".to_string()],
+            WriteOrigin::Backend,
+        )
+        .unwrap();
+        let second = archive_items(
+            dir.path(),
+            &writes,
+            &mut inbox,
+            &["inbox-3".to_string()],
+            WriteOrigin::Backend,
+        )
+        .unwrap();
+        assert_eq!(first, second);
+
+        let ids: Vec<&str> = inbox.items.iter().map(|i| i.id.as_str()).collect();
+        assert_eq!(ids, vec!["inbox-2"]);
+
+        let archive: InboxFile =
+            serde_json::from_str(&fs::read_to_string(&first).unwrap()).unwrap();
+        let ids: Vec<&str> = archive.items.iter().map(|i| i.id.as_str()).collect();
+        assert_eq!(ids, vec!["inbox-1", "inbox-3"]);
+        assert_eq!(archive.items[0].extra["customField"], 42);
+    }
+}
@@ -0,0 +1,850 @@
+//! inbox.md ↔ inbox.json conversion
+//!
+//! inbox.md is the Claude-readable view of the inbox. It has three
+//! sections the converter owns:
+//!
+//! - `## Active Instructions` — pending items, as `- [ ]` checkboxes
+//! - `## Pending Feedback` — pending notes waiting for Claude
+//! - `## Processed Archive` — done or skipped items, as `- [x]`
+//!
+//! Items are grouped under `### YYYY-MM-DD` headings and carry their id in
+//! a trailing `<!-- id:... -->` comment; replies are indented quotes below
+//! their item. A checkbox line without an id is a new item written by hand.
+//! Item text escapes anything that would read back as a priority, project
+//! or status marker (`Ping \@arun`), so it survives the round trip.
+//! Any other section (e.g. Quick Commands) and free text inside the owned
+//! sections is preserved verbatim.
+
+use chrono::{DateTime, NaiveDate, TimeZone, Utc};
+use chrono_tz::Tz;
+use serde::Serialize;
+use serde_json::Map;
+
+use super::generate_id;
+use super::inbox::{InboxFile, InboxItem, InboxReply};
+
+/// What follows a space in item text and would read back as a marker
+const MARKER_STARTS: [&str; 3] = ["@", "(P", "_("];
+
+/// Layout used when inbox.md does not exist yet
+const DEFAULT_TEMPLATE: &str = r#"# Inbox
+
+Quick instructions and feedback for Claude. Write here anytime - Claude will check this on every session.
+
+---
+
+## Active Instructions
+
+<!-- CLAUDE: Read and process all items in this section -->
+
+---
+
+## Pending Feedback
+
+<!-- Items waiting for Claude to acknowledge/process -->
+
+---
+
+## Quick Commands
+
+Use these patterns to communicate with Claude:
+
+```
+- Move {PROJECT} {TASK} to {STATUS}
+- Create task for {PROJECT}: {TITLE} (P1)
+- P0/P1/P2 the {TASK}
+- Add comment to {TASK}: {TEXT}
+- {PROJECT} is now in {STAGE}
+- @claude: {any instruction}
+```
+
+---
+
+## Processed Archive
+
+<!-- Claude moves processed items here with timestamp -->
+"#;
+
+/// The sections whose content is generated from inbox.json
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SectionKind {
+    Active,
+    Feedback,
+    Archive,
+}
+
+impl SectionKind {
+    const ALL: [SectionKind; 3] = [
+        SectionKind::Active,
+        SectionKind::Feedback,
+        SectionKind::Archive,
+    ];
+
+    fn from_heading(heading: &str) -> Option<Self> {
+        match heading.trim().to_lowercase().as_str() {
+            // "Active Items" is what older app versions wrote
+            "active instructions" | "active items" => Some(SectionKind::Active),
+            "pending feedback" => Some(SectionKind::Feedback),
+            "processed archive" => Some(SectionKind::Archive),
+            _ => None,
+        }
+    }
+
+    fn heading(&self) -> &'static str {
+        match self {
+            SectionKind::Active => "Active Instructions",
+            SectionKind::Feedback => "Pending Feedback",
+            SectionKind::Archive => "Processed Archive",
+        }
+    }
+
+    fn placeholder(&self) -> &'static str {
+        match self {
+            SectionKind::Archive => "_Archive empty_",
+            _ => "_None_",
+        }
+    }
+
+    /// Which section an item belongs in
+    fn for_item(item: &InboxItem) -> Self {
+        if item.status != "pending" {
+            SectionKind::Archive
+        } else if item.item_type == "note" || item.item_type == "claude-response" {
+            SectionKind::Feedback
+        } else {
+            SectionKind::Active
+        }
+    }
+}
+
+/// A checkbox line parsed from inbox.md
+#[derive(Debug, Clone, PartialEq)]
+pub struct MdEntry {
+    pub id: Option<String>,
+    pub checked: bool,
+    /// Explicit status marker such as `_(skipped)_`
+    pub status: Option<String>,
+    pub text: String,
+    pub priority: Option<String>,
+    pub project: Option<String>,
+    /// Date of the `###` group the entry appeared under
+    pub date: Option<NaiveDate>,
+    pub replies: Vec<MdReply>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MdReply {
+    pub id: Option<String>,
+    pub author: String,
+    pub text: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Section {
+    Known {
+        kind: SectionKind,
+        /// Free text inside the section (comments, notes), kept as-is
+        notes: Vec<String>,
+        entries: Vec<MdEntry>,
+    },
+    Other {
+        heading: String,
+        body: Vec<String>,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct InboxDocument {
+    /// Everything before the first `##` heading
+    pub preamble: Vec<String>,
+    pub sections: Vec<Section>,
+}
+
+/// What a reconcile pass changed in inbox.json
+#[derive(Serialize, Debug, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncSummary {
+    pub items_added: usize,
+    pub items_updated: usize,
+    pub replies_added: usize,
+}
+
+/// Parse inbox.md into its sections and entries
+pub fn parse(markdown: &str) -> InboxDocument {
+    let mut document = InboxDocument::default();
+    let mut lines = markdown.lines().peekable();
+
+    while let Some(line) = lines.peek() {
+        if line.starts_with("## ") {
+            break;
+        }
+        document.preamble.push(line.to_string());
+        lines.next();
+    }
+    trim_block(&mut document.preamble);
+
+    let mut current: Option<Section> = None;
+    let mut date: Option<NaiveDate> = None;
+    let mut legacy = false;
+
+    for line in lines {
+        if let Some(heading) = line.strip_prefix("## ") {
+            if let Some(section) = current.take() {
+                document.sections.push(finish_section(section));
+            }
+            date = None;
+            legacy = false;
+            current = Some(match SectionKind::from_heading(heading) {
+                Some(kind) => Section::Known {
+                    kind,
+                    notes: vec![],
+                    entries: vec![],
+                },
+                None => Section::Other {
+                    heading: heading.trim().to_string(),
+                    body: vec![],
+                },
+            });
+            continue;
+        }
+
+        match current.as_mut() {
+            Some(Section::Other { body, .. }) => body.push(line.to_string()),
+            Some(Section::Known { notes, entries, .. }) => {
+                parse_known_line(line, &mut date, &mut legacy, notes, entries)
+            }
+            None => {}
+        }
+    }
+
+    if let Some(section) = current.take() {
+        document.sections.push(finish_section(section));
+    }
+
+    document
+}
+
+fn parse_known_line(
+    line: &str,
+    date: &mut Option<NaiveDate>,
+    legacy: &mut bool,
+    notes: &mut Vec<String>,
+    entries: &mut Vec<MdEntry>,
+) {
+    let trimmed = line.trim();
+
+    // Older app versions wrote one "### [TASK] ..." block per item, ending
+    // in `---`. Those items are re-rendered from inbox.json, so drop them.
+    if *legacy {
+        *legacy = trimmed != "---";
+        return;
+    }
+    if trimmed.starts_with("### [") {
+        *legacy = true;
+        return;
+    }
+    if trimmed.starts_with("*(") && trimmed.ends_with("older items omitted)*") {
+        return;
+    }
+
+    if trimmed.is_empty() || trimmed == "---" || trimmed == "_None_" || trimmed == "_Archive empty_"
+    {
+        return;
+    }
+
+    if let Some(heading) = trimmed.strip_prefix("### ") {
+        if let Ok(d) = NaiveDate::parse_from_str(heading.trim(), "%Y-%m-%d") {
+            *date = Some(d);
+            return;
+        }
+    }
+
+    if let Some(entry) = parse_entry(trimmed, *date) {
+        entries.push(entry);
+        return;
+    }
+
+    // Indented quotes under an entry are its replies
+    if line.starts_with(char::is_whitespace) {
+        if let (Some(quote), Some(entry)) = (trimmed.strip_prefix('>'), entries.last_mut()) {
+            if let Some(reply) = parse_reply(quote.trim()) {
+                entry.replies.push(reply);
+                return;
+            }
+        }
+    }
+
+    notes.push(line.to_string());
+}
+
+fn parse_entry(line: &str, date: Option<NaiveDate>) -> Option<MdEntry> {
+    let (checked, rest) = if let Some(rest) = line.strip_prefix("- [ ] ") {
+        (false, rest)
+    } else if let Some(rest) = line
+        .strip_prefix("- [x] ")
+        .or_else(|| line.strip_prefix("- [X] "))
+    {
+        (true, rest)
+    } else {
+        return None;
+    };
+
+    // Rendered lines separate markers by exactly one space, so whitespace
+    // around the text is the item's own; hand-written lines get trimmed
+    let (rest, id) = split_id(rest);
+    let mut rest = if id.is_some() { rest } else { rest.trim_end() };
+
+    let mut status = None;
+    if let Some((before, marker)) = rest.strip_suffix(")_").and_then(|r| r.rsplit_once(" _(")) {
+        status = marker.split_whitespace().next().map(|s| s.to_string());
+        rest = before;
+    }
+
+    let mut project = None;
+    if let Some((before, last)) = rest.rsplit_once(' ') {
+        if let Some(name) = last.strip_prefix('@') {
+            if !name.is_empty()
+                && name
+                    .chars()
+                    .all(|c| c.is_alphanumeric() || c == '-' || c == '_')
+            {
+                project = Some(name.to_string());
+                rest = before;
+            }
+        }
+    }
+
+    let mut priority = None;
+    if let Some(before) = rest.strip_suffix(')') {
+        if let Some((text, p)) = before.rsplit_once(" (") {
+            if matches!(p, "P0" | "P1" | "P2" | "P3") {
+                priority = Some(p.to_string());
+                rest = text;
+            }
+        }
+    }
+
+    if id.is_none() {
+        rest = rest.trim_end();
+    }
+
+    Some(MdEntry {
+        id,
+        checked,
+        status,
+        text: from_single_line(&escape_markers(rest, false)),
+        priority,
+        project,
+        date,
+        replies: vec![],
+    })
+}
+
+fn parse_reply(quote: &str) -> Option<MdReply> {
+    let (rest, id) = split_id(quote);
+    let (label, text) = rest.split_once(':')?;
+
+    // Older files wrote "Claude (Jan 18, 10:00 AM): ..."; drop the timestamp
+    let author = label.split(" (").next().unwrap_or(label).trim();
+    if author.is_empty() || author.contains(' ') {
+        return None;
+    }
+
+    Some(MdReply {
+        id,
+        author: author.to_lowercase(),
+        text: from_single_line(text.trim()),
+    })
+}
+
+/// Split a trailing `<!-- id:... -->` marker off a line
+fn split_id(line: &str) -> (&str, Option<String>) {
+    let trimmed = line.trim_end();
+    if let Some(before) = trimmed.strip_suffix("-->") {
+        if let Some((text, id)) = before.rsplit_once("<!-- id:") {
+            let id = id.trim();
+            if !id.is_empty() {
+                return (text.strip_suffix(' ').unwrap_or(text), Some(id.to_string()));
+            }
+        }
+    }
+    (line, None)
+}
+
+fn finish_section(section: Section) -> Section {
+    match section {
+        Section::Other { heading, mut body } => {
+            trim_block(&mut body);
+            Section::Other { heading, body }
+        }
+        known => known,
+    }
+}
+
+/// Drop leading/trailing blank lines and trailing `---` separators
+fn trim_block(lines: &mut Vec<String>) {
+    while lines
+        .last()
+        .map(|l| l.trim().is_empty() || l.trim() == "---")
+        .unwrap_or(false)
+    {
+        lines.pop();
+    }
+    while lines.first().map(|l| l.trim().is_empty()).unwrap_or(false) {
+        lines.remove(0);
+    }
+}
+
+fn to_single_line(text: &str) -> String {
+    text.replace("\r\n", "\n").replace('\n', "<br>")
+}
+
+fn from_single_line(text: &str) -> String {
+    text.replace("<br>", "\n")
+}
+
+/// Add (or, unescaping, remove) a backslash before every marker start that
+/// follows a space. Backslashes already there count towards the escape, so
+/// text that contains `\@` itself comes back unchanged.
+fn escape_markers(text: &str, escape: bool) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(space) = rest.find(' ') {
+        out.push_str(&rest[..=space]);
+        rest = &rest[space + 1..];
+
+        let unslashed = rest.trim_start_matches('\\');
+        if MARKER_STARTS.iter().any(|m| unslashed.starts_with(m)) {
+            if escape {
+                out.push('\\');
+            } else if unslashed.len() < rest.len() {
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Render inbox items into the layout of `document`
+pub fn render(document: &InboxDocument, items: &[InboxItem], tz: Tz) -> String {
+    let template;
+    let document = if document.preamble.is_empty() && document.sections.is_empty() {
+        template = parse(DEFAULT_TEMPLATE);
+        &template
+    } else {
+        document
+    };
+
+    let mut sections = document.sections.clone();
+
+    // Restore owned sections someone deleted, archive last
+    for kind in SectionKind::ALL {
+        let present = sections
+            .iter()
+            .any(|s| matches!(s, Section::Known { kind: k, .. } if *k == kind));
+        if !present {
+            let section = Section::Known {
+                kind,
+                notes: vec![],
+                entries: vec![],
+            };
+            match kind {
+                SectionKind::Archive => sections.push(section),
+                _ => {
+                    let index = sections
+                        .iter()
+                        .position(|s| !matches!(s, Section::Known { .. }))
+                        .unwrap_or(sections.len());
+                    sections.insert(index, section);
+                }
+            }
+        }
+    }
+
+    let mut blocks = Vec::new();
+    if !document.preamble.is_empty() {
+        blocks.push(document.preamble.join("\n"));
+    }
+
+    let mut rendered_kinds = Vec::new();
+    for section in &sections {
+        match section {
+            Section::Other { heading, body } => {
+                let mut block = format!("## {}", heading);
+                if !body.is_empty() {
+                    block.push_str("\n\n");
+                    block.push_str(&body.join("\n"));
+                }
+                blocks.push(block);
+            }
+            Section::Known { kind, notes, .. } => {
+                // A duplicated owned section would list its items twice
+                if rendered_kinds.contains(kind) {
+                    continue;
+                }
+                rendered_kinds.push(*kind);
+
+                let section_items: Vec<&InboxItem> = items
+                    .iter()
+                    .filter(|item| SectionKind::for_item(item) == *kind)
+                    .collect();
+                blocks.push(render_section(*kind, notes, &section_items, tz));
+            }
+        }
+    }
+
+    let mut markdown = blocks.join("\n\n---\n\n");
+    markdown.push('\n');
+    markdown
+}
+
+fn render_section(kind: SectionKind, notes: &[String], items: &[&InboxItem], tz: Tz) -> String {
+    let mut parts = vec![format!("## {}", kind.heading())];
+
+    if !notes.is_empty() {
+        parts.push(notes.join("\n"));
+    }
+
+    if items.is_empty() {
+        parts.push(kind.placeholder().to_string());
+        return parts.join("\n\n");
+    }
+
+    // Group by local creation date, keeping file order within a day
+    let mut groups: Vec<(Option<NaiveDate>, Vec<&InboxItem>)> = Vec::new();
+    for item in items {
+        let date = local_date(&item.created_at, tz);
+        match groups.iter_mut().find(|(d, _)| *d == date) {
+            Some((_, group)) => group.push(item),
+            None => groups.push((date, vec![item])),
+        }
+    }
+    groups.sort_by_key(|(date, _)| *date);
+
+    for (date, group) in groups {
+        let lines: Vec<String> = group.iter().map(|item| render_item(item)).collect();
+        match date {
+            Some(date) => parts.push(format!("### {}\n\n{}", date, lines.join("\n"))),
+            None => parts.push(lines.join("\n")),
+        }
+    }
+
+    parts.join("\n\n")
+}
+
+fn render_item(item: &InboxItem) -> String {
+    let checkbox = if item.status == "pending" {
+        "[ ]"
+    } else {
+        "[x]"
+    };
+    let text = escape_markers(&to_single_line(&item.text), true);
+    let mut line = format!("- {} {}", checkbox, text);
+
+    if let Some(priority) = &item.priority {
+        line.push_str(&format!(" ({})", priority));
+    }
+    if let Some(project) = &item.project {
+        line.push_str(&format!(" @{}", project));
+    }
+    if item.status != "pending" && item.status != "done" {
+        line.push_str(&format!(" _({})_", item.status));
+    }
+    line.push_str(&format!(" <!-- id:{} -->", item.id));
+
+    for reply in &item.replies {
+        line.push_str(&format!(
+            "\n  > {}: {} <!-- id:{} -->",
+            display_author(&reply.author),
+            to_single_line(&reply.text),
+            reply.id
+        ));
+    }
+
+    line
+}
+
+fn local_date(timestamp: &str, tz: Tz) -> Option<NaiveDate> {
+    DateTime::parse_from_rfc3339(timestamp)
+        .ok()
+        .map(|dt| dt.with_timezone(&tz).date_naive())
+}
+
+fn display_author(author: &str) -> String {
+    let mut chars = author.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::from("User"),
+    }
+}
+
+/// Merge entries from inbox.md into inbox.json.
+///
+/// Entries without an id are always added. For entries that already exist
+/// in inbox.json, the markdown side only wins when `markdown_wins` is set
+/// (inbox.md was edited more recently). Items missing from inbox.md are
+/// never deleted; they reappear when the markdown is re-rendered.
+pub fn reconcile(
+    inbox: &mut InboxFile,
+    document: &InboxDocument,
+    markdown_wins: bool,
+    tz: Tz,
+) -> SyncSummary {
+    let mut summary = SyncSummary::default();
+    let now = Utc::now();
+
+    for section in &document.sections {
+        let (kind, entries) = match section {
+            Section::Known { kind, entries, .. } => (*kind, entries),
+            Section::Other { .. } => continue,
+        };
+
+        for entry in entries {
+            let existing = entry
+                .id
+                .as_ref()
+                .and_then(|id| inbox.items.iter_mut().find(|item| &item.id == id));
+
+            match existing {
+                Some(item) => {
+                    if markdown_wins && apply_entry(item, entry) {
+                        summary.items_updated += 1;
+                    }
+                    summary.replies_added += merge_replies(item, entry, markdown_wins, now);
+                }
+                None => {
+                    let mut item = new_item(entry, kind, tz, now);
+                    summary.replies_added += merge_replies(&mut item, entry, true, now);
+                    inbox.items.push(item);
+                    summary.items_added += 1;
+                }
+            }
+        }
+    }
+
+    summary
+}
+
+fn entry_status(entry: &MdEntry) -> String {
+    if entry.checked {
+        entry.status.clone().unwrap_or_else(|| "done".to_string())
+    } else {
+        "pending".to_string()
+    }
+}
+
+/// Copy the fields inbox.md carries onto an item. Returns whether anything changed.
+fn apply_entry(item: &mut InboxItem, entry: &MdEntry) -> bool {
+    let status = entry_status(entry);
+    let changed = item.text != entry.text
+        || item.priority != entry.priority
+        || item.project != entry.project
+        || item.status != status;
+
+    item.text = entry.text.clone();
+    item.priority = entry.priority.clone();
+    item.project = entry.project.clone();
+    item.set_status(&status);
+
+    changed
+}
+
+fn new_item(entry: &MdEntry, kind: SectionKind, tz: Tz, now: DateTime<Utc>) -> InboxItem {
+    let created_at = entry
+        .date
+        .and_then(|d| d.and_hms_opt(0, 0, 0))
+        .and_then(|naive| tz.from_local_datetime(&naive).earliest())
+        .map(|dt| dt.with_timezone(&Utc))
+        .unwrap_or(now);
+
+    InboxItem {
+        id: entry.id.clone().unwrap_or_else(|| generate_id("inbox")),
+        text: entry.text.clone(),
+        item_type: match kind {
+            SectionKind::Feedback => "note".to_string(),
+            _ => "task".to_string(),
+        },
+        project: entry.project.clone(),
+        priority: entry.priority.clone(),
+        status: entry_status(entry),
+        created_at: created_at.to_rfc3339(),
+        processed_at: entry.checked.then(|| now.to_rfc3339()),
+        task_id: None,
+        read: true,
+        author: "user".to_string(),
+        parent_id: None,
+        replies: vec![],
+        extra: Map::new(),
+    }
+}
+
+/// Add replies that only exist in inbox.md; returns how many were added
+fn merge_replies(
+    item: &mut InboxItem,
+    entry: &MdEntry,
+    markdown_wins: bool,
+    now: DateTime<Utc>,
+) -> usize {
+    let mut added = 0;
+
+    for reply in &entry.replies {
+        let existing = reply
+            .id
+            .as_ref()
+            .and_then(|id| item.replies.iter_mut().find(|r| &r.id == id));
+
+        match existing {
+            Some(existing) => {
+                if markdown_wins {
+                    existing.text = reply.text.clone();
+                }
+            }
+            None => {
+                item.replies.push(InboxReply {
+                    id: reply.id.clone().unwrap_or_else(|| generate_id("reply")),
+                    author: reply.author.clone(),
+                    text: reply.text.clone(),
+                    created_at: now.to_rfc3339(),
+                    extra: Map::new(),
+                });
+                added += 1;
+            }
+        }
+    }
+
+    added
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &str = include_str!("../../tests/fixtures/inbox/inbox.md");
+
+    #[test]
+    fn parses_checkboxes_and_keeps_unknown_sections() {
+        let document = parse(SAMPLE);
+
+        let entries: Vec<&MdEntry> = document
+            .sections
+            .iter()
+            .filter_map(|s| match s {
+                Section::Known {
+                    kind: SectionKind::Active,
+                    entries,
+                    ..
+                } => Some(entries),
+                _ => None,
+            })
+            .flatten()
+            .collect();
+        assert_eq!(entries.len(), 3);
+        assert_eq!(
+            entries[0].text,
+            "**UI Review**: Progress bar looks good. Add subtle animation when percentage changes."
+        );
+        assert_eq!(entries[0].date, NaiveDate::from_ymd_opt(2026, 1, 18));
+        assert!(entries.iter().all(|e| e.id.is_none() && !e.checked));
+
+        assert!(document
+            .sections
+            .iter()
+            .any(|s| matches!(s, Section::Other { heading, .. } if heading == "Quick Commands")));
+    }
+
+    #[test]
+    fn sync_round_trips_through_markdown() {
+        let mut inbox = InboxFile::default();
+        let summary = reconcile(&mut inbox, &parse(SAMPLE), true, Tz::UTC);
+        assert_eq!(summary.items_added, 3);
+
+        let markdown = render(&parse(SAMPLE), &inbox.items, Tz::UTC);
+        assert!(markdown.contains("## Quick Commands"));
+        assert!(markdown.contains("- Move {PROJECT} {TASK} to {STATUS}"));
+        assert!(markdown.contains("### 2026-01-18"));
+        assert!(markdown.contains(&format!("<!-- id:{} -->", inbox.items[0].id)));
+
+        // Ticking a box in the markdown archives the item
+        let ticked = markdown.replacen("- [ ] **UI Review**", "- [x] **UI Review**", 1);
+        let summary = reconcile(&mut inbox, &parse(&ticked), true, Tz::UTC);
+        assert_eq!(summary.items_added, 0);
+        assert_eq!(summary.items_updated, 1);
+        assert_eq!(inbox.items[0].status, "done");
+
+        // Re-rendering is stable once every item carries its id
+        let rendered = render(&parse(&ticked), &inbox.items, Tz::UTC);
+        assert_eq!(render(&parse(&rendered), &inbox.items, Tz::UTC), rendered);
+        assert!(rendered.contains("## Processed Archive"));
+    }
+
+    #[test]
+    fn parses_entry_metadata_and_replies() {
+        let document = parse(
+            "## Active Instructions\n\n\
+             - [ ] Ship the release (P1) @taskboard <!-- id:inbox-7 -->\n  \
+             > Claude: Tagged v0.2.0 <!-- id:reply-9 -->\n  \
+             > User: thanks\n",
+        );
+
+        let entry = match &document.sections[0] {
+            Section::Known { entries, .. } => entries[0].clone(),
+            _ => panic!("expected an owned section"),
+        };
+        assert_eq!(entry.id.as_deref(), Some("inbox-7"));
+        assert_eq!(entry.text, "Ship the release");
+        assert_eq!(entry.priority.as_deref(), Some("P1"));
+        assert_eq!(entry.project.as_deref(), Some("taskboard"));
+        assert_eq!(entry.replies.len(), 2);
+        assert_eq!(entry.replies[0].author, "claude");
+        assert_eq!(entry.replies[1].id, None);
+    }
+
+    #[test]
+    fn item_text_reads_back_unchanged() {
+        let item = |id: &str, text: &str, priority: Option<&str>, project: Option<&str>| {
+            serde_json::from_value::<InboxItem>(serde_json::json!({
+                "id": id, "text": text, "priority": priority, "project": project,
+                "createdAt": "2026-05-01T10:00:00Z"
+            }))
+            .unwrap()
+        };
+        let mut inbox = InboxFile {
+            items: vec![
+                item("inbox-1", "Ping @arun", None, None),
+                item("inbox-2", "Fix (P1)", None, None),
+                item("inbox-3", "Fix (P1) @web", Some("P2"), Some("taskboard")),
+                item("inbox-4", "  Mind the gap  ", Some("P0"), None),
+                item("inbox-5", "Keep \\@this and _(that)_", None, None),
+            ],
+            ..InboxFile::default()
+        };
+        let saved = inbox.items.clone();
+
+        let markdown = render(&InboxDocument::default(), &inbox.items, Tz::UTC);
+        assert!(markdown.contains("- [ ] Ping \\@arun <!-- id:inbox-1 -->"));
+        assert!(markdown.contains("- [ ] Fix \\(P1) <!-- id:inbox-2 -->"));
+
+        let summary = reconcile(&mut inbox, &parse(&markdown), true, Tz::UTC);
+        assert_eq!(summary, SyncSummary::default());
+        assert_eq!(inbox.items, saved);
+    }
+
+    #[test]
+    fn drops_blocks_written_by_older_versions() {
+        let document = parse(
+            "# Inbox\n\n## Active Items\n\n\
+             ### [TASK] Fix login [P1]\n**ID:** inbox-1\n**Status:** pending (read)\n\n\
+             > User: Fix login\n\n---\n",
+        );
+
+        assert_eq!(
+            document.sections,
+            vec![Section::Known {
+                kind: SectionKind::Active,
+                notes: vec![],
+                entries: vec![],
+            }]
+        );
+    }
+}
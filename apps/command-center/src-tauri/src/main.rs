@@ -1,9 +1,15 @@
 // Prevents additional console window on Windows in release
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
+mod agents;
+mod audit;
 mod voice;
 mod data;
 mod watcher;
+mod config;
+mod notifications;
+mod quick_commands;
+mod sync;
 
 use std::sync::Mutex;
 use tauri::Manager;
@@ -11,6 +17,12 @@ use tauri::Manager;
 // Application state
 pub struct AppState {
     pub data_dir: std::path::PathBuf,
+    /// Writes the app makes itself, so the watcher can ignore their echo
+    pub writes: watcher::WriteRegistry,
+    /// Whisper model kept loaded between voice captures
+    pub voice: voice::EngineCache,
+    /// Streaming voice captures in progress
+    pub voice_sessions: voice::SessionRegistry,
 }
 
 fn main() {
@@ -28,17 +40,66 @@ fn main() {
         .plugin(tauri_plugin_shell::init())
         .plugin(tauri_plugin_clipboard_manager::init())
         .plugin(tauri_plugin_notification::init())
-        .manage(Mutex::new(AppState { data_dir }))
+        .manage(notifications::Notifier::new(data_dir.clone()))
+        .manage(watcher::WatcherSupervisor::default())
+        .manage(voice::DropQueue::default())
+        .manage(agents::JobQueue::default())
+        .manage(agents::InboxTrigger::default())
+        .manage(sync::SyncScheduler::default())
+        .manage(Mutex::new(AppState {
+            data_dir,
+            writes: watcher::WriteRegistry::default(),
+            voice: voice::EngineCache::default(),
+            voice_sessions: voice::SessionRegistry::default(),
+        }))
         .setup(|app| {
-            // Start watching inbox.json for new items
-            watcher::start_inbox_watcher(app.handle().clone());
+            // Report activity log entries that cannot be written
+            audit::report_failures(app.handle().clone());
+
+            // Deliver queued notifications according to the user's policy
+            notifications::start_notifier(app.handle().clone());
+
+            // Watch data files and project docs for external changes
+            watcher::start_watcher(app.handle().clone());
+
+            // Resume queued agent jobs
+            agents::start_queue(app.handle().clone());
+
+            // Pick up inbox instructions for Claude, including interrupted ones
+            agents::scan_inbox(app.handle(), true);
+
+            // Sync with the gist on start, on schedule and after local changes
+            sync::start_sync(app.handle().clone());
             Ok(())
         })
         .invoke_handler(tauri::generate_handler![
             // Voice commands
             voice::check_voice_available,
             voice::voice_capture,
+            voice::start_voice_capture,
+            voice::stop_voice_capture,
+            voice::cancel_voice_capture,
             voice::setup_voice,
+            voice::list_voice_models,
+            voice::download_voice_model,
+            voice::delete_voice_model,
+            voice::set_voice_model,
+            voice::file_voice_transcript,
+            voice::transcribe_file,
+            voice::list_voice_sessions,
+            voice::get_voice_session_audio,
+            voice::retranscribe_voice_session,
+            voice::delete_voice_session,
+            // Agent commands
+            agents::run_agent,
+            agents::read_agent_log,
+            agents::enqueue_agent_job,
+            agents::list_agent_jobs,
+            agents::cancel_agent_job,
+            agents::set_inbox_trigger,
+            agents::suggest_agent,
+            agents::build_agent_prompt,
+            agents::list_agent_sessions,
             // Data commands
             data::read_projects,
             data::write_projects,
@@ -48,12 +109,42 @@ fn main() {
             data::write_inbox,
             data::read_inbox_json,
             data::write_inbox_json,
+            data::sync_inbox,
+            data::mark_inbox_processed,
+            data::archive_inbox_items,
+            data::convert_inbox_to_task,
+            data::assign_inbox_item,
+            data::reply_to_inbox_item,
             data::read_sync_config,
             data::write_sync_config,
             data::read_document,
             data::write_document,
             data::get_data_path,
+            data::switch_workspace,
+            // Quick command commands
+            quick_commands::preview_quick_commands,
+            quick_commands::apply_quick_commands,
+            // Watcher commands
+            watcher::restart_watcher,
+            watcher::stop_watcher,
+            // Notification commands
+            notifications::snooze_notifications,
+            notifications::resume_notifications,
+            notifications::list_notifications,
+            notifications::mark_notification_read,
+            notifications::clear_notifications,
+            // Sync commands
+            sync::sync_now,
+            sync::get_sync_status,
+            // Activity log commands
+            audit::get_activity_feed,
         ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|app_handle, event| {
+            // Stop the watcher thread cleanly on exit
+            if let tauri::RunEvent::Exit = event {
+                app_handle.state::<watcher::WatcherSupervisor>().stop();
+            }
+        });
 }
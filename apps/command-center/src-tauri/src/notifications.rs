@@ -0,0 +1,751 @@
+//! Notification policy and delivery
+//!
+//! Backend subsystems never call the notification plugin directly. They
+//! hand events to the `Notifier`, which collects them for a short batching
+//! window, applies the per-source rules from config.json, holds them back
+//! during quiet hours or while snoozed, and collapses bursts into a single
+//! digest toast. Deferred notifications are released on the first tick
+//! after quiet hours (or the snooze) end.
+//!
+//! Every notification is also recorded in ~/.taskboard/notifications.json
+//! (whether or not a toast was shown) so the notification center can list
+//! what happened while the user was away. A history file that cannot be
+//! read is moved aside to notifications.unreadable.json rather than
+//! written over.
+
+use chrono::{DateTime, Duration, NaiveTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use tauri::{AppHandle, Emitter, Manager, State};
+use tauri_plugin_notification::NotificationExt;
+
+use crate::config::{self, AppConfig, QuietHoursConfig, SourceRule};
+
+/// How often the delivery loop wakes up to flush batches and deferrals
+const TICK_INTERVAL: std::time::Duration = std::time::Duration::from_millis(500);
+
+/// Oldest records are dropped once the history grows past this
+const MAX_HISTORY: usize = 500;
+
+/// Where an unreadable history file is moved
+const UNREADABLE_HISTORY: &str = "notifications.unreadable.json";
+
+/// Where a notification originated, used to pick its rule
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "camelCase")]
+pub enum NotificationSource {
+    Inbox,
+    Reply,
+    Reminder,
+    Agent,
+}
+
+impl NotificationSource {
+    fn rule<'a>(&self, config: &'a AppConfig) -> &'a SourceRule {
+        let sources = &config.notifications.sources;
+        match self {
+            NotificationSource::Inbox => &sources.inbox,
+            NotificationSource::Reply => &sources.replies,
+            NotificationSource::Reminder => &sources.reminders,
+            NotificationSource::Agent => &sources.agents,
+        }
+    }
+}
+
+/// The board entity a notification refers to, so the UI can jump to it
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "kind", content = "id", rename_all = "camelCase")]
+pub enum EntityLink {
+    Task(String),
+    InboxItem(String),
+    Project(String),
+}
+
+/// A notification as stored in notifications.json
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct NotificationRecord {
+    pub id: String,
+    pub source: NotificationSource,
+    #[serde(default)]
+    pub entity: Option<EntityLink>,
+    pub title: String,
+    pub body: String,
+    #[serde(default)]
+    pub read: bool,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct NotificationsFile {
+    version: String,
+    last_updated: String,
+    notifications: Vec<NotificationRecord>,
+}
+
+impl Default for NotificationsFile {
+    fn default() -> Self {
+        Self {
+            version: "1.0.0".to_string(),
+            last_updated: String::new(),
+            notifications: vec![],
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Notification {
+    pub source: NotificationSource,
+    pub title: String,
+    pub body: String,
+    pub created_at: DateTime<Utc>,
+}
+
+/// What to do with a notification right now
+#[derive(Debug, PartialEq)]
+enum Decision {
+    Deliver,
+    Defer,
+    Drop,
+}
+
+#[derive(Default)]
+struct NotifierState {
+    /// Collected during the current batching window
+    pending: Vec<Notification>,
+    /// Held back by quiet hours or snooze
+    deferred: Vec<Notification>,
+    snoozed_until: Option<DateTime<Utc>>,
+    source_snoozes: HashMap<NotificationSource, DateTime<Utc>>,
+}
+
+/// Managed state owning the notification queue and history file
+pub struct Notifier {
+    data_dir: Mutex<PathBuf>,
+    /// config.json as of the last change, read by every tick
+    config: Mutex<AppConfig>,
+    state: Mutex<NotifierState>,
+    /// Serializes read-modify-write cycles on notifications.json
+    history_lock: Mutex<()>,
+    next_id: AtomicU64,
+}
+
+impl Notifier {
+    pub fn new(data_dir: PathBuf) -> Self {
+        Self {
+            config: Mutex::new(config::load_config(&data_dir)),
+            data_dir: Mutex::new(data_dir),
+            state: Mutex::new(NotifierState::default()),
+            history_lock: Mutex::new(()),
+            next_id: AtomicU64::new(0),
+        }
+    }
+
+    fn data_dir(&self) -> PathBuf {
+        self.data_dir.lock().unwrap().clone()
+    }
+
+    /// Point config and history at a different data directory
+    pub fn set_data_dir(&self, data_dir: PathBuf) {
+        *self.data_dir.lock().unwrap() = data_dir;
+        self.reload_config();
+    }
+
+    /// Read the policy from config.json again, after it changed
+    pub fn reload_config(&self) {
+        *self.config.lock().unwrap() = config::load_config(&self.data_dir());
+    }
+
+    fn history_path(&self) -> PathBuf {
+        self.data_dir().join("notifications.json")
+    }
+
+    /// The history; a missing file is empty. An unreadable one is moved
+    /// aside, so the next read starts afresh, and the error returned.
+    fn read_history(&self) -> Result<NotificationsFile, String> {
+        let path = self.history_path();
+        let Ok(content) = fs::read_to_string(&path) else {
+            return Ok(NotificationsFile::default());
+        };
+        serde_json::from_str(&content).or_else(|e| {
+            let aside = self.data_dir().join(UNREADABLE_HISTORY);
+            fs::rename(&path, aside).map_err(|r| {
+                format!(
+                    "Failed to parse notifications.json: {}; failed to move it aside: {}",
+                    e, r
+                )
+            })?;
+            Err(format!(
+                "Failed to parse notifications.json: {}; moved it to {}",
+                e, UNREADABLE_HISTORY
+            ))
+        })
+    }
+
+    fn write_history(&self, mut file: NotificationsFile) -> Result<(), String> {
+        file.last_updated = Utc::now().to_rfc3339();
+        let content = serde_json::to_string_pretty(&file)
+            .map_err(|e| format!("Failed to serialize notifications: {}", e))?;
+
+        crate::data::write_atomic(&self.history_path(), &content)
+            .map_err(|e| format!("Failed to write notifications.json: {}", e))
+    }
+
+    /// Append a notification to the history and return the stored record
+    pub fn record(
+        &self,
+        notification: &Notification,
+        entity: Option<EntityLink>,
+    ) -> Result<NotificationRecord, String> {
+        let seq = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let record = NotificationRecord {
+            id: format!(
+                "notif-{}-{}",
+                notification.created_at.timestamp_millis(),
+                seq
+            ),
+            source: notification.source,
+            entity,
+            title: notification.title.clone(),
+            body: notification.body.clone(),
+            read: false,
+            created_at: notification.created_at,
+        };
+
+        let _guard = self.history_lock.lock();
+        let mut file = self.read_history()?;
+        file.notifications.push(record.clone());
+        if file.notifications.len() > MAX_HISTORY {
+            let excess = file.notifications.len() - MAX_HISTORY;
+            file.notifications.drain(..excess);
+        }
+        self.write_history(file)?;
+
+        Ok(record)
+    }
+
+    /// History, newest first
+    pub fn list(&self, unread_only: bool) -> Result<Vec<NotificationRecord>, String> {
+        let _guard = self.history_lock.lock();
+        Ok(self
+            .read_history()?
+            .notifications
+            .into_iter()
+            .rev()
+            .filter(|n| !unread_only || !n.read)
+            .collect())
+    }
+
+    /// Mark one record as read
+    pub fn mark_read(&self, id: &str) -> Result<(), String> {
+        let _guard = self.history_lock.lock();
+        let mut file = self.read_history()?;
+
+        let record = file
+            .notifications
+            .iter_mut()
+            .find(|n| n.id == id)
+            .ok_or_else(|| format!("Notification not found: {}", id))?;
+        record.read = true;
+
+        self.write_history(file)
+    }
+
+    /// Remove all records, or only those already read
+    pub fn clear(&self, read_only: bool) -> Result<(), String> {
+        let _guard = self.history_lock.lock();
+        let mut file = self.read_history()?;
+
+        if read_only {
+            file.notifications.retain(|n| !n.read);
+        } else {
+            file.notifications.clear();
+        }
+
+        self.write_history(file)
+    }
+
+    /// Queue a notification for the next flush
+    pub fn push(&self, notification: Notification) {
+        if let Ok(mut state) = self.state.lock() {
+            state.pending.push(notification);
+        }
+    }
+
+    /// Snooze all sources, or just one, for the given duration
+    pub fn snooze(&self, source: Option<NotificationSource>, duration: Duration) {
+        let until = Utc::now() + duration;
+        if let Ok(mut state) = self.state.lock() {
+            match source {
+                Some(source) => {
+                    state.source_snoozes.insert(source, until);
+                }
+                None => state.snoozed_until = Some(until),
+            }
+        }
+    }
+
+    /// Lift every snooze; deferred notifications go out on the next tick
+    pub fn resume(&self) {
+        if let Ok(mut state) = self.state.lock() {
+            state.snoozed_until = None;
+            state.source_snoozes.clear();
+        }
+    }
+
+    /// Advance the queue and return the (title, body) toasts to show now
+    fn tick(&self, now: DateTime<Utc>) -> Vec<(String, String)> {
+        let config = self.config.lock().unwrap().clone();
+
+        let mut state = match self.state.lock() {
+            Ok(s) => s,
+            Err(_) => return vec![],
+        };
+
+        let mut ready = Vec::new();
+
+        // Flush the batch once its oldest entry has waited out the window
+        let window = Duration::milliseconds(config.notifications.batching.window_ms as i64);
+        let batch_due = state
+            .pending
+            .first()
+            .map(|n| now - n.created_at >= window)
+            .unwrap_or(false);
+
+        if batch_due {
+            let pending = std::mem::take(&mut state.pending);
+            for notification in pending {
+                match decide(&config, &state, notification.source, now) {
+                    Decision::Deliver => ready.push(notification),
+                    Decision::Defer => state.deferred.push(notification),
+                    Decision::Drop => {}
+                }
+            }
+        }
+
+        // Release anything whose quiet hours or snooze has ended
+        if !state.deferred.is_empty() {
+            let deferred = std::mem::take(&mut state.deferred);
+            for notification in deferred {
+                match decide(&config, &state, notification.source, now) {
+                    Decision::Deliver => ready.push(notification),
+                    Decision::Defer => state.deferred.push(notification),
+                    Decision::Drop => {}
+                }
+            }
+        }
+
+        // Expired snoozes no longer matter
+        if state.snoozed_until.map(|t| t <= now).unwrap_or(false) {
+            state.snoozed_until = None;
+        }
+        state.source_snoozes.retain(|_, until| *until > now);
+
+        group_for_display(ready, config.notifications.batching.threshold)
+    }
+}
+
+/// Apply enablement, snooze and quiet-hours rules to a single source
+fn decide(
+    config: &AppConfig,
+    state: &NotifierState,
+    source: NotificationSource,
+    now: DateTime<Utc>,
+) -> Decision {
+    let rule = source.rule(config);
+
+    if !config.notifications.enabled || !config.notifications.desktop || !rule.enabled {
+        return Decision::Drop;
+    }
+
+    let globally_snoozed = state.snoozed_until.map(|t| now < t).unwrap_or(false);
+    let source_snoozed = state
+        .source_snoozes
+        .get(&source)
+        .map(|t| now < *t)
+        .unwrap_or(false);
+    if globally_snoozed || source_snoozed {
+        return Decision::Defer;
+    }
+
+    if !rule.bypass_quiet_hours
+        && in_quiet_hours(&config.notifications.quiet_hours, config.user.tz(), now)
+    {
+        return Decision::Defer;
+    }
+
+    Decision::Deliver
+}
+
+/// Check whether `now` falls inside the quiet-hours window in the user's timezone
+fn in_quiet_hours(quiet: &QuietHoursConfig, tz: chrono_tz::Tz, now: DateTime<Utc>) -> bool {
+    if !quiet.enabled {
+        return false;
+    }
+
+    let (start, end) = match (
+        NaiveTime::parse_from_str(&quiet.start, "%H:%M"),
+        NaiveTime::parse_from_str(&quiet.end, "%H:%M"),
+    ) {
+        (Ok(s), Ok(e)) => (s, e),
+        _ => return false,
+    };
+
+    let local = now.with_timezone(&tz).time();
+
+    if start <= end {
+        local >= start && local < end
+    } else {
+        // Window wraps midnight, e.g. 22:00 → 07:00
+        local >= start || local < end
+    }
+}
+
+/// Turn ready notifications into toasts, collapsing bursts into one digest
+fn group_for_display(ready: Vec<Notification>, threshold: usize) -> Vec<(String, String)> {
+    if ready.is_empty() {
+        return vec![];
+    }
+
+    if ready.len() < threshold.max(2) {
+        return ready.into_iter().map(|n| (n.title, n.body)).collect();
+    }
+
+    let mut lines: Vec<String> = ready
+        .iter()
+        .take(3)
+        .map(|n| format!("• {}", n.title))
+        .collect();
+    if ready.len() > 3 {
+        lines.push(format!("+{} more", ready.len() - 3));
+    }
+
+    vec![(
+        format!("{} new notifications", ready.len()),
+        lines.join("\n"),
+    )]
+}
+
+/// Start the background delivery loop
+pub fn start_notifier(app_handle: AppHandle) {
+    std::thread::spawn(move || loop {
+        std::thread::sleep(TICK_INTERVAL);
+
+        let toasts = app_handle.state::<Notifier>().tick(Utc::now());
+        for (title, body) in toasts {
+            send_notification(&app_handle, &title, &body);
+        }
+    });
+}
+
+/// Payload of `notification-history-error`
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct NotificationHistoryError {
+    pub message: String,
+}
+
+/// Record and queue a notification from any backend subsystem.
+///
+/// The record is emitted to the frontend immediately as `notification-added`;
+/// whether a toast is shown is up to the policy. If it cannot be stored, a
+/// `notification-history-error` is emitted instead and the toast still goes
+/// out.
+pub fn notify(
+    app_handle: &AppHandle,
+    source: NotificationSource,
+    entity: Option<EntityLink>,
+    title: &str,
+    body: &str,
+) {
+    let notifier = app_handle.state::<Notifier>();
+    let notification = Notification {
+        source,
+        title: title.to_string(),
+        body: body.to_string(),
+        created_at: Utc::now(),
+    };
+
+    match notifier.record(&notification, entity) {
+        Ok(record) => {
+            let _ = app_handle.emit("notification-added", &record);
+        }
+        Err(message) => {
+            let _ = app_handle.emit(
+                "notification-history-error",
+                NotificationHistoryError { message },
+            );
+        }
+    }
+
+    notifier.push(notification);
+}
+
+/// Send a desktop toast notification
+fn send_notification(app_handle: &AppHandle, title: &str, body: &str) {
+    let _ = app_handle
+        .notification()
+        .builder()
+        .title(title)
+        .body(body)
+        .show();
+}
+
+// Tauri commands
+
+/// Snooze notifications for `minutes`, optionally for a single source
+#[tauri::command]
+pub fn snooze_notifications(
+    minutes: i64,
+    source: Option<NotificationSource>,
+    notifier: State<'_, Notifier>,
+) -> Result<(), String> {
+    if minutes <= 0 {
+        return Err("Snooze duration must be positive".to_string());
+    }
+
+    notifier.snooze(source, Duration::minutes(minutes));
+    Ok(())
+}
+
+/// Cancel any active snooze
+#[tauri::command]
+pub fn resume_notifications(notifier: State<'_, Notifier>) {
+    notifier.resume();
+}
+
+/// List notification history, newest first
+#[tauri::command]
+pub fn list_notifications(
+    unread_only: Option<bool>,
+    limit: Option<usize>,
+    notifier: State<'_, Notifier>,
+) -> Result<Vec<NotificationRecord>, String> {
+    let mut records = notifier.list(unread_only.unwrap_or(false))?;
+    if let Some(limit) = limit {
+        records.truncate(limit);
+    }
+    Ok(records)
+}
+
+/// Mark a notification as read
+#[tauri::command]
+pub fn mark_notification_read(id: String, notifier: State<'_, Notifier>) -> Result<(), String> {
+    notifier.mark_read(&id)
+}
+
+/// Clear notification history (only read entries when `read_only` is set)
+#[tauri::command]
+pub fn clear_notifications(
+    read_only: Option<bool>,
+    notifier: State<'_, Notifier>,
+) -> Result<(), String> {
+    notifier.clear(read_only.unwrap_or(false))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(time: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(time)
+            .unwrap()
+            .with_timezone(&Utc)
+    }
+
+    fn notification(
+        source: NotificationSource,
+        title: &str,
+        created_at: DateTime<Utc>,
+    ) -> Notification {
+        Notification {
+            source,
+            title: title.to_string(),
+            body: String::new(),
+            created_at,
+        }
+    }
+
+    /// Quiet 22:00 to 07:00 in New York (UTC-4 in May)
+    fn quiet_config() -> AppConfig {
+        let mut config = AppConfig::default();
+        config.user.timezone = "America/New_York".to_string();
+        config.notifications.quiet_hours.enabled = true;
+        config
+    }
+
+    #[test]
+    fn quiet_hours_wrap_midnight_in_the_users_timezone() {
+        let config = quiet_config();
+        let quiet = &config.notifications.quiet_hours;
+        let tz = config.user.tz();
+
+        // 21:59 local, though inside the window in UTC
+        assert!(!in_quiet_hours(quiet, tz, at("2026-05-02T01:59:00Z")));
+        assert!(in_quiet_hours(quiet, tz, at("2026-05-02T02:00:00Z")));
+        assert!(in_quiet_hours(quiet, tz, at("2026-05-02T04:30:00Z")));
+        // 06:59 local, though outside the window in UTC
+        assert!(in_quiet_hours(quiet, tz, at("2026-05-02T10:59:00Z")));
+        assert!(!in_quiet_hours(quiet, tz, at("2026-05-02T11:00:00Z")));
+
+        let disabled = QuietHoursConfig {
+            enabled: false,
+            ..quiet.clone()
+        };
+        assert!(!in_quiet_hours(&disabled, tz, at("2026-05-02T04:30:00Z")));
+    }
+
+    #[test]
+    fn source_rules_drop_or_bypass_quiet_hours() {
+        let mut config = quiet_config();
+        config.notifications.sources.inbox.enabled = false;
+        config.notifications.sources.reminders.bypass_quiet_hours = true;
+        let state = NotifierState::default();
+        let night = at("2026-05-02T04:30:00Z");
+        let day = at("2026-05-02T16:00:00Z");
+
+        let cases = [
+            (NotificationSource::Inbox, day, Decision::Drop),
+            (NotificationSource::Reminder, night, Decision::Deliver),
+            (NotificationSource::Agent, night, Decision::Defer),
+            (NotificationSource::Agent, day, Decision::Deliver),
+        ];
+        for (source, now, expected) in cases {
+            assert_eq!(
+                decide(&config, &state, source, now),
+                expected,
+                "{:?}",
+                source
+            );
+        }
+
+        config.notifications.desktop = false;
+        let reminder = decide(&config, &state, NotificationSource::Reminder, day);
+        assert_eq!(reminder, Decision::Drop);
+    }
+
+    #[test]
+    fn snoozes_defer_until_they_expire() {
+        let config = AppConfig::default();
+        let now = at("2026-05-02T16:00:00Z");
+        let mut state = NotifierState {
+            snoozed_until: Some(now + Duration::minutes(10)),
+            ..NotifierState::default()
+        };
+        assert_eq!(
+            decide(&config, &state, NotificationSource::Inbox, now),
+            Decision::Defer
+        );
+        let later = now + Duration::minutes(10);
+        assert_eq!(
+            decide(&config, &state, NotificationSource::Inbox, later),
+            Decision::Deliver
+        );
+
+        state.snoozed_until = None;
+        state
+            .source_snoozes
+            .insert(NotificationSource::Reply, later);
+        assert_eq!(
+            decide(&config, &state, NotificationSource::Reply, now),
+            Decision::Defer
+        );
+        assert_eq!(
+            decide(&config, &state, NotificationSource::Inbox, now),
+            Decision::Deliver
+        );
+
+        // Deferred notifications go out on the first tick after the snooze
+        let dir = tempfile::tempdir().unwrap();
+        let notifier = Notifier::new(dir.path().to_path_buf());
+        notifier.snooze(None, Duration::minutes(10));
+        let created = Utc::now();
+        notifier.push(notification(NotificationSource::Inbox, "Tip", created));
+        assert!(notifier.tick(created + Duration::seconds(4)).is_empty());
+        let released = notifier.tick(created + Duration::minutes(11));
+        assert_eq!(released, [("Tip".to_string(), String::new())]);
+        assert_eq!(notifier.state.lock().unwrap().snoozed_until, None);
+    }
+
+    #[test]
+    fn history_persists_and_keeps_the_newest_records() {
+        let dir = tempfile::tempdir().unwrap();
+        let notifier = Notifier::new(dir.path().to_path_buf());
+        let now = at("2026-05-02T16:00:00Z");
+        for i in 0..MAX_HISTORY + 5 {
+            let title = format!("Item {}", i);
+            let created = now + Duration::seconds(i as i64);
+            notifier
+                .record(
+                    &notification(NotificationSource::Inbox, &title, created),
+                    None,
+                )
+                .unwrap();
+        }
+
+        // A fresh notifier reads the same history back
+        let reopened = Notifier::new(dir.path().to_path_buf());
+        let history = reopened.list(false).unwrap();
+        assert_eq!(history.len(), MAX_HISTORY);
+        assert_eq!(history[0].title, format!("Item {}", MAX_HISTORY + 4));
+        assert_eq!(history[MAX_HISTORY - 1].title, "Item 5");
+
+        reopened.mark_read(&history[0].id).unwrap();
+        assert!(reopened.mark_read("notif-missing").is_err());
+        assert_eq!(notifier.list(true).unwrap().len(), MAX_HISTORY - 1);
+
+        notifier.clear(true).unwrap();
+        assert!(reopened.list(false).unwrap().iter().all(|n| !n.read));
+        assert_eq!(reopened.list(false).unwrap().len(), MAX_HISTORY - 1);
+        notifier.clear(false).unwrap();
+        assert!(reopened.list(false).unwrap().is_empty());
+    }
+
+    #[test]
+    fn an_unreadable_history_is_moved_aside_not_overwritten() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("notifications.json"), "{ not json").unwrap();
+        let notifier = Notifier::new(dir.path().to_path_buf());
+        let tip = notification(NotificationSource::Inbox, "Tip", Utc::now());
+
+        let err = notifier.record(&tip, None).unwrap_err();
+        assert!(err.contains(UNREADABLE_HISTORY));
+        let kept = fs::read_to_string(dir.path().join(UNREADABLE_HISTORY)).unwrap();
+        assert_eq!(kept, "{ not json");
+
+        // Later records start a fresh history
+        notifier.record(&tip, None).unwrap();
+        assert_eq!(notifier.list(false).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn bursts_collapse_into_a_digest_at_the_threshold() {
+        let now = at("2026-05-02T16:00:00Z");
+        let burst = |count: usize| {
+            (1..=count)
+                .map(|i| notification(NotificationSource::Inbox, &format!("Item {}", i), now))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(group_for_display(burst(2), 3).len(), 2);
+        assert_eq!(
+            group_for_display(burst(3), 3),
+            [(
+                "3 new notifications".to_string(),
+                "• Item 1\n• Item 2\n• Item 3".to_string()
+            )]
+        );
+        let digest = group_for_display(burst(5), 3);
+        assert_eq!(digest[0].0, "5 new notifications");
+        assert!(digest[0].1.ends_with("• Item 3\n+2 more"));
+
+        // A single notification is never a digest
+        assert_eq!(group_for_display(burst(1), 1).len(), 1);
+        assert!(group_for_display(Vec::new(), 3).is_empty());
+    }
+}
@@ -0,0 +1,639 @@
+//! Quick Commands: board operations written as inbox text
+//!
+//! An inbox item such as "Move AnyCalc dark mode to done" is parsed by the
+//! grammar, its names are resolved against projects.json and tasks.json,
+//! and the resulting operations are returned as a preview. Operations the
+//! user confirms are applied through the data layer and the outcome is
+//! posted as a reply on the inbox item.
+
+mod grammar;
+pub(crate) mod matcher;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::sync::Mutex;
+use tauri::State;
+
+use crate::audit::{self, Action, Actor};
+use crate::data::{
+    self, InboxReply, Project, ProjectsFile, Task, TaskComment, TasksFile, STAGES, TASK_STATUSES,
+};
+use crate::watcher::WriteOrigin;
+use crate::AppState;
+use grammar::Command;
+use matcher::Match;
+
+const PRIORITIES: [&str; 4] = ["P0", "P1", "P2", "P3"];
+
+/// A resolved board operation, referring to entities by id
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "op", rename_all = "camelCase", rename_all_fields = "camelCase")]
+pub enum Operation {
+    MoveTask {
+        task_id: String,
+        status: String,
+    },
+    CreateTask {
+        project_id: String,
+        title: String,
+        priority: String,
+    },
+    AddComment {
+        task_id: String,
+        text: String,
+    },
+    SetPriority {
+        task_id: String,
+        priority: String,
+    },
+    SetStage {
+        project_id: String,
+        stage: String,
+    },
+}
+
+/// An operation together with the line it came from and a readable summary
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct PlannedOperation {
+    pub line: String,
+    pub summary: String,
+    pub operation: Operation,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct LineError {
+    pub line: String,
+    pub message: String,
+}
+
+/// What applying an inbox item would do
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct QuickCommandPreview {
+    pub item_id: String,
+    pub operations: Vec<PlannedOperation>,
+    /// Lines that look like commands but could not be resolved
+    pub errors: Vec<LineError>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct OperationOutcome {
+    pub summary: String,
+    pub error: Option<String>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct QuickCommandResult {
+    pub item_id: String,
+    pub outcomes: Vec<OperationOutcome>,
+    /// Id of the reply recording the outcome on the inbox item
+    pub reply_id: String,
+}
+
+/// Resolves names written in commands against the current board
+struct Board<'a> {
+    projects: &'a ProjectsFile,
+    tasks: &'a TasksFile,
+}
+
+impl<'a> Board<'a> {
+    fn project(&self, query: &str) -> Result<&'a Project, String> {
+        match matcher::find(query, &self.projects.projects, |p| {
+            vec![p.id.as_str(), p.name.as_str()]
+        }) {
+            Match::Found(project, _) => Ok(project),
+            Match::Ambiguous(candidates) => Err(format!(
+                "\"{}\" could be any of: {}",
+                query,
+                candidates
+                    .iter()
+                    .map(|p| p.name.as_str())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )),
+            Match::NotFound => Err(format!("No project matches \"{}\"", query)),
+        }
+    }
+
+    /// Find a task, looking in `project_id` first when given
+    fn task(&self, query: &str, project_id: Option<&str>) -> Result<(&'a Task, f64), String> {
+        if let Some(project_id) = project_id {
+            let in_project = self
+                .tasks
+                .tasks
+                .iter()
+                .filter(|t| t.project_id == project_id);
+            match matcher::find(query, in_project, |t| vec![t.title.as_str(), t.id.as_str()]) {
+                Match::NotFound => {}
+                found => return task_result(query, found),
+            }
+        }
+
+        task_result(
+            query,
+            matcher::find(query, &self.tasks.tasks, |t| {
+                vec![t.title.as_str(), t.id.as_str()]
+            }),
+        )
+    }
+
+    /// "Move {PROJECT} {TASK}": try every split of the words into a project
+    /// prefix and a task, falling back to treating it all as the task
+    fn move_target(&self, subject: &str, hint: Option<&str>) -> Result<&'a Task, String> {
+        let words: Vec<&str> = subject.split_whitespace().collect();
+        let mut best: Option<(f64, &'a Task)> = None;
+
+        for split in 1..words.len() {
+            let project = match self.project(&words[..split].join(" ")) {
+                Ok(p) => p,
+                Err(_) => continue,
+            };
+            let project_score = matcher::score(&words[..split].join(" "), &project.name)
+                .max(matcher::score(&words[..split].join(" "), &project.id));
+
+            let in_project = self
+                .tasks
+                .tasks
+                .iter()
+                .filter(|t| t.project_id == project.id);
+            if let Match::Found(task, task_score) =
+                matcher::find(&words[split..].join(" "), in_project, |t| {
+                    vec![t.title.as_str(), t.id.as_str()]
+                })
+            {
+                let combined = (project_score + task_score) / 2.0;
+                if best.map(|(s, _)| combined > s).unwrap_or(true) {
+                    best = Some((combined, task));
+                }
+            }
+        }
+
+        match best {
+            Some((_, task)) => Ok(task),
+            None => self.task(subject, hint).map(|(task, _)| task),
+        }
+    }
+}
+
+fn task_result<'a>(query: &str, found: Match<'a, Task>) -> Result<(&'a Task, f64), String> {
+    match found {
+        Match::Found(task, score) => Ok((task, score)),
+        Match::Ambiguous(candidates) => Err(format!(
+            "\"{}\" could be any of: {}",
+            query,
+            candidates
+                .iter()
+                .map(|t| format!("\"{}\"", t.title))
+                .collect::<Vec<_>>()
+                .join(", ")
+        )),
+        Match::NotFound => Err(format!("No task matches \"{}\"", query)),
+    }
+}
+
+/// Parse every line of an inbox item and resolve it against the board.
+/// `project_hint` is the item's own project, searched first for tasks.
+pub fn plan(
+    text: &str,
+    projects: &ProjectsFile,
+    tasks: &TasksFile,
+    project_hint: Option<&str>,
+) -> (Vec<PlannedOperation>, Vec<LineError>) {
+    let board = Board { projects, tasks };
+    let mut operations = Vec::new();
+    let mut errors = Vec::new();
+
+    for line in text.lines() {
+        let command = match grammar::parse_line(line) {
+            Some(c) => c,
+            None => continue,
+        };
+
+        match command.and_then(|c| resolve(&board, c, project_hint)) {
+            Ok((summary, operation)) => operations.push(PlannedOperation {
+                line: line.trim().to_string(),
+                summary,
+                operation,
+            }),
+            Err(message) => errors.push(LineError {
+                line: line.trim().to_string(),
+                message,
+            }),
+        }
+    }
+
+    (operations, errors)
+}
+
+fn resolve(
+    board: &Board,
+    command: Command,
+    hint: Option<&str>,
+) -> Result<(String, Operation), String> {
+    match command {
+        Command::MoveTask { subject, status } => {
+            let task = board.move_target(&subject, hint)?;
+            Ok((
+                format!("Move \"{}\" from {} to {}", task.title, task.status, status),
+                Operation::MoveTask {
+                    task_id: task.id.clone(),
+                    status,
+                },
+            ))
+        }
+        Command::CreateTask {
+            project,
+            title,
+            priority,
+        } => {
+            let project = board.project(&project)?;
+            let priority = priority.unwrap_or_else(|| "P2".to_string());
+            Ok((
+                format!("Create \"{}\" in {} ({})", title, project.name, priority),
+                Operation::CreateTask {
+                    project_id: project.id.clone(),
+                    title,
+                    priority,
+                },
+            ))
+        }
+        Command::AddComment { task, text } => {
+            let (task, _) = board.task(&task, hint)?;
+            Ok((
+                format!("Comment on \"{}\": {}", task.title, text),
+                Operation::AddComment {
+                    task_id: task.id.clone(),
+                    text,
+                },
+            ))
+        }
+        Command::SetPriority { task, priority } => {
+            let (task, _) = board.task(&task, hint)?;
+            Ok((
+                format!(
+                    "Set \"{}\" priority from {} to {}",
+                    task.title, task.priority, priority
+                ),
+                Operation::SetPriority {
+                    task_id: task.id.clone(),
+                    priority,
+                },
+            ))
+        }
+        Command::SetStage { project, stage } => {
+            let project = board.project(&project)?;
+            Ok((
+                format!(
+                    "Move {} from {} to {} stage",
+                    project.name, project.stage, stage
+                ),
+                Operation::SetStage {
+                    project_id: project.id.clone(),
+                    stage,
+                },
+            ))
+        }
+    }
+}
+
+/// Apply confirmed operations in order. Returns one outcome per operation;
+/// a failed operation does not stop the ones after it.
+pub fn apply(
+    operations: &[PlannedOperation],
+    projects: &mut ProjectsFile,
+    tasks: &mut TasksFile,
+) -> Vec<OperationOutcome> {
+    operations
+        .iter()
+        .map(|planned| OperationOutcome {
+            summary: planned.summary.clone(),
+            error: apply_one(&planned.operation, projects, tasks).err(),
+        })
+        .collect()
+}
+
+fn apply_one(
+    operation: &Operation,
+    projects: &mut ProjectsFile,
+    tasks: &mut TasksFile,
+) -> Result<(), String> {
+    let now = chrono::Utc::now().to_rfc3339();
+
+    let find_task = |tasks: &mut TasksFile, id: &str| -> Result<usize, String> {
+        tasks
+            .tasks
+            .iter()
+            .position(|t| t.id == id)
+            .ok_or_else(|| format!("Task {} no longer exists", id))
+    };
+
+    // Operations come back from the frontend, so check what the planner
+    // would have checked
+    let check_priority = |priority: &str| {
+        if PRIORITIES.contains(&priority) {
+            Ok(())
+        } else {
+            Err(format!("Unknown priority \"{}\"", priority))
+        }
+    };
+
+    match operation {
+        Operation::MoveTask { task_id, status } => {
+            if !TASK_STATUSES.contains(&status.as_str()) {
+                return Err(format!("Unknown status \"{}\"", status));
+            }
+            let index = find_task(tasks, task_id)?;
+            tasks.tasks[index].set_status(status);
+        }
+        Operation::CreateTask {
+            project_id,
+            title,
+            priority,
+        } => {
+            check_priority(priority)?;
+            let project = projects
+                .projects
+                .iter()
+                .find(|p| &p.id == project_id)
+                .ok_or_else(|| format!("Project {} no longer exists", project_id))?;
+            let task = Task::new(
+                data::generate_id("t"),
+                project,
+                title.clone(),
+                priority.clone(),
+            );
+            tasks.tasks.push(task);
+        }
+        Operation::AddComment { task_id, text } => {
+            let index = find_task(tasks, task_id)?;
+            let task = &mut tasks.tasks[index];
+            task.comments.push(TaskComment {
+                id: data::generate_id("c"),
+                comment_type: "note".to_string(),
+                author: "user".to_string(),
+                content: text.clone(),
+                created_at: now.clone(),
+                for_claude: false,
+                resolved: false,
+                source: "text".to_string(),
+                extra: Map::new(),
+            });
+            task.updated_at = now;
+        }
+        Operation::SetPriority { task_id, priority } => {
+            check_priority(priority)?;
+            let index = find_task(tasks, task_id)?;
+            let task = &mut tasks.tasks[index];
+            task.priority = priority.clone();
+            task.updated_at = now;
+        }
+        Operation::SetStage { project_id, stage } => {
+            if !STAGES.iter().any(|(s, _)| s == stage) {
+                return Err(format!("Unknown stage \"{}\"", stage));
+            }
+            let project = projects
+                .projects
+                .iter_mut()
+                .find(|p| &p.id == project_id)
+                .ok_or_else(|| format!("Project {} no longer exists", project_id))?;
+            project.stage = stage.clone();
+            project.current_phase = data::phase_for_stage(stage).to_string();
+            project
+                .extra
+                .insert("stageStatus".into(), Value::String("in-progress".into()));
+            project.last_updated = now;
+        }
+    }
+
+    Ok(())
+}
+
+/// Reply text recording what was applied
+fn outcome_reply(outcomes: &[OperationOutcome]) -> String {
+    let applied = outcomes.iter().filter(|o| o.error.is_none()).count();
+    let mut text = format!(
+        "Applied {} of {} quick command{}:",
+        applied,
+        outcomes.len(),
+        if outcomes.len() == 1 { "" } else { "s" }
+    );
+
+    for outcome in outcomes {
+        match &outcome.error {
+            None => text.push_str(&format!("\n✓ {}", outcome.summary)),
+            Some(e) => text.push_str(&format!("\n✗ {} — {}", outcome.summary, e)),
+        }
+    }
+
+    text
+}
+
+// Tauri commands
+
+/// Parse an inbox item and preview the board operations it describes
+#[tauri::command]
+pub fn preview_quick_commands(
+    item_id: String,
+    state: State<'_, Mutex<AppState>>,
+) -> Result<QuickCommandPreview, String> {
+    let data_dir = data::get_data_dir(&state);
+
+    let inbox = data::load_inbox(&data_dir)?;
+    let item = inbox
+        .items
+        .iter()
+        .find(|i| i.id == item_id)
+        .ok_or_else(|| format!("Inbox item not found: {}", item_id))?;
+
+    let projects = data::load_projects(&data_dir)?;
+    let tasks = data::load_tasks(&data_dir)?;
+    let (operations, errors) = plan(&item.text, &projects, &tasks, item.project.as_deref());
+
+    Ok(QuickCommandPreview {
+        item_id,
+        operations,
+        errors,
+    })
+}
+
+/// Apply the operations the user confirmed from a preview and record the
+/// outcome as a reply on the inbox item
+#[tauri::command]
+pub fn apply_quick_commands(
+    item_id: String,
+    operations: Vec<PlannedOperation>,
+    state: State<'_, Mutex<AppState>>,
+) -> Result<QuickCommandResult, String> {
+    let data_dir = data::get_data_dir(&state);
+    let writes = data::get_writes(&state);
+
+    let mut inbox = data::load_inbox(&data_dir)?;
+    if !inbox.items.iter().any(|i| i.id == item_id) {
+        return Err(format!("Inbox item not found: {}", item_id));
+    }
+
+    let mut projects = data::load_projects(&data_dir)?;
+    let mut tasks = data::load_tasks(&data_dir)?;
+    let projects_before = projects.clone();
+    let tasks_before = tasks.clone();
+
+    let outcomes = apply(&operations, &mut projects, &mut tasks);
+
+    let entities = audit::changed_between(&projects_before, &projects, "project")
+        .into_iter()
+        .chain(audit::changed_between(&tasks_before, &tasks, "task"))
+        .collect();
+    let action = Action::new(Actor::User, "apply_quick_commands")
+        .entity("inboxItem", &item_id, None)
+        .entities(entities);
+    let saved = (|| {
+        if tasks != tasks_before {
+            data::save_tasks(&data_dir, &writes, &mut tasks, WriteOrigin::Backend)?;
+        }
+        if projects != projects_before {
+            data::save_projects(&data_dir, &writes, &mut projects, WriteOrigin::Backend)?;
+        }
+        Ok(())
+    })();
+    audit::record(&data_dir, action, saved)?;
+
+    let reply = InboxReply {
+        id: data::generate_id("reply"),
+        // The user applied these, so the outcome is theirs too
+        author: "user".to_string(),
+        text: outcome_reply(&outcomes),
+        created_at: chrono::Utc::now().to_rfc3339(),
+        extra: Map::new(),
+    };
+    let reply_id = reply.id.clone();
+
+    if let Some(item) = inbox.items.iter_mut().find(|i| i.id == item_id) {
+        item.replies.push(reply);
+    }
+    data::save_inbox(&data_dir, &writes, &mut inbox, WriteOrigin::Backend)?;
+
+    Ok(QuickCommandResult {
+        item_id,
+        outcomes,
+        reply_id,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn board() -> (ProjectsFile, TasksFile) {
+        let projects = serde_json::from_value(serde_json::json!({
+            "projects": [
+                { "id": "anycalc", "name": "AnyCalc", "stage": "development" },
+                { "id": "orbit", "name": "Orbit", "stage": "requirements" }
+            ]
+        }))
+        .unwrap();
+        let tasks = serde_json::from_value(serde_json::json!({
+            "tasks": [
+                { "id": "t-1", "projectId": "anycalc", "title": "Add dark mode toggle", "status": "todo", "priority": "P2" },
+                { "id": "t-2", "projectId": "orbit", "title": "Add dark mode toggle", "status": "todo", "priority": "P2" },
+                { "id": "t-3", "projectId": "orbit", "title": "Voice capture screen", "status": "in-progress", "priority": "P1" }
+            ]
+        }))
+        .unwrap();
+        (projects, tasks)
+    }
+
+    #[test]
+    fn plans_commands_against_the_board() {
+        let (projects, tasks) = board();
+        let text = "Move orbit dark mode to done\n\
+                    Create task for anycalc: Export history (P1)\n\
+                    Add comment to voice capture: needs a waveform\n\
+                    Nice work on the progress bar\n\
+                    Move dark mode to review\n\
+                    Orbit is now in architecture";
+
+        let (operations, errors) = plan(text, &projects, &tasks, None);
+
+        let ops: Vec<&Operation> = operations.iter().map(|p| &p.operation).collect();
+        assert_eq!(
+            ops,
+            vec![
+                &Operation::MoveTask {
+                    task_id: "t-2".into(),
+                    status: "completed".into()
+                },
+                &Operation::CreateTask {
+                    project_id: "anycalc".into(),
+                    title: "Export history".into(),
+                    priority: "P1".into()
+                },
+                &Operation::AddComment {
+                    task_id: "t-3".into(),
+                    text: "needs a waveform".into()
+                },
+                &Operation::SetStage {
+                    project_id: "orbit".into(),
+                    stage: "architecture".into()
+                },
+            ]
+        );
+
+        // Two projects have a "dark mode" task, so the bare form is ambiguous
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].line, "Move dark mode to review");
+    }
+
+    #[test]
+    fn applies_operations_and_reports_failures() {
+        let (mut projects, mut tasks) = board();
+        let (mut operations, _) = plan(
+            "Move anycalc dark mode to in progress\nP0 the voice capture\nOrbit is now in testing",
+            &projects,
+            &tasks,
+            None,
+        );
+        operations.push(PlannedOperation {
+            line: "stale".into(),
+            summary: "Move a deleted task".into(),
+            operation: Operation::MoveTask {
+                task_id: "t-gone".into(),
+                status: "completed".into(),
+            },
+        });
+        operations.push(PlannedOperation {
+            line: "forged".into(),
+            summary: "Move to a status the board does not have".into(),
+            operation: Operation::MoveTask {
+                task_id: "t-1".into(),
+                status: "shipped".into(),
+            },
+        });
+        operations.push(PlannedOperation {
+            line: "forged".into(),
+            summary: "Set a priority the board does not have".into(),
+            operation: Operation::SetPriority {
+                task_id: "t-1".into(),
+                priority: "urgent".into(),
+            },
+        });
+
+        let outcomes = apply(&operations, &mut projects, &mut tasks);
+
+        assert_eq!(tasks.tasks[0].status, "in-progress");
+        assert!(tasks.tasks[0].started_at.is_some());
+        assert_eq!(tasks.tasks[2].priority, "P0");
+        assert_eq!(projects.projects[1].stage, "testing");
+        assert_eq!(projects.projects[1].current_phase, "build");
+
+        assert!(outcomes[..3].iter().all(|o| o.error.is_none()));
+        assert!(outcomes[3].error.is_some());
+        assert_eq!(outcomes[4].error.as_deref(), Some("Unknown status \"shipped\""));
+        assert_eq!(outcomes[5].error.as_deref(), Some("Unknown priority \"urgent\""));
+        assert_eq!(tasks.tasks[0].priority, "P2");
+        assert!(outcome_reply(&outcomes).starts_with("Applied 3 of 6 quick commands:"));
+    }
+}
@@ -0,0 +1,241 @@
+//! Quick Command grammar
+//!
+//! Recognises the patterns listed under "Quick Commands" in inbox.md, one
+//! command per line. Names are returned as written; resolving them against
+//! the board is the planner's job.
+
+use regex::Regex;
+use std::sync::OnceLock;
+
+use crate::data::{STAGES, TASK_STATUSES};
+
+/// A recognised command with its raw arguments
+#[derive(Debug, Clone, PartialEq)]
+pub enum Command {
+    /// "Move {PROJECT} {TASK} to {STATUS}" — the project is optional, and
+    /// where it ends and the task begins is left to the matcher
+    MoveTask { subject: String, status: String },
+    /// "Create task for {PROJECT}: {TITLE} (P1)"
+    CreateTask {
+        project: String,
+        title: String,
+        priority: Option<String>,
+    },
+    /// "Add comment to {TASK}: {TEXT}"
+    AddComment { task: String, text: String },
+    /// "P0/P1/P2 the {TASK}"
+    SetPriority { task: String, priority: String },
+    /// "{PROJECT} is now in {STAGE}"
+    SetStage { project: String, stage: String },
+}
+
+struct Patterns {
+    create: Regex,
+    comment: Regex,
+    priority: Regex,
+    move_task: Regex,
+    stage: Regex,
+}
+
+fn patterns() -> &'static Patterns {
+    static PATTERNS: OnceLock<Patterns> = OnceLock::new();
+    PATTERNS.get_or_init(|| Patterns {
+        create: Regex::new(
+            r"(?i)^create (?:a )?(?:new )?task (?:for|in) (?P<project>.+?)\s*:\s*(?P<title>.+?)(?:\s*\((?P<priority>p[0-3])\))?$",
+        )
+        .unwrap(),
+        comment: Regex::new(r"(?i)^add (?:a )?comment (?:to|on) (?P<task>.+?)\s*:\s*(?P<text>.+)$")
+            .unwrap(),
+        priority: Regex::new(r"(?i)^(?P<priority>p[0-3]) the (?P<task>.+)$").unwrap(),
+        move_task: Regex::new(r"(?i)^move (?P<subject>.+) (?:to|into) (?P<status>.+)$").unwrap(),
+        stage: Regex::new(
+            r"(?i)^(?P<project>.+?) is now in (?:the )?(?P<stage>.+?)(?: stage| phase)?$",
+        )
+        .unwrap(),
+    })
+}
+
+/// Parse one line of an inbox item.
+///
+/// Returns `None` for lines that are not commands (plain notes), and an
+/// error when a line matches a pattern but an argument is invalid.
+pub fn parse_line(line: &str) -> Option<Result<Command, String>> {
+    let line = clean_line(line);
+    if line.is_empty() {
+        return None;
+    }
+
+    let p = patterns();
+
+    if let Some(caps) = p.create.captures(&line) {
+        return Some(Ok(Command::CreateTask {
+            project: caps["project"].trim().to_string(),
+            title: caps["title"].trim().to_string(),
+            priority: caps.name("priority").map(|m| m.as_str().to_uppercase()),
+        }));
+    }
+
+    if let Some(caps) = p.comment.captures(&line) {
+        return Some(Ok(Command::AddComment {
+            task: caps["task"].trim().to_string(),
+            text: caps["text"].trim().to_string(),
+        }));
+    }
+
+    if let Some(caps) = p.priority.captures(&line) {
+        return Some(Ok(Command::SetPriority {
+            task: caps["task"].trim().to_string(),
+            priority: caps["priority"].to_uppercase(),
+        }));
+    }
+
+    if let Some(caps) = p.move_task.captures(&line) {
+        return Some(
+            normalize_status(&caps["status"]).map(|status| Command::MoveTask {
+                subject: caps["subject"].trim().to_string(),
+                status,
+            }),
+        );
+    }
+
+    if let Some(caps) = p.stage.captures(&line) {
+        return Some(
+            normalize_stage(&caps["stage"]).map(|stage| Command::SetStage {
+                project: caps["project"].trim().to_string(),
+                stage,
+            }),
+        );
+    }
+
+    None
+}
+
+/// Strip list markers, checkboxes and trailing punctuation
+fn clean_line(line: &str) -> String {
+    let mut line = line.trim();
+
+    for marker in ["- [ ] ", "- [x] ", "- [X] ", "- ", "* "] {
+        if let Some(rest) = line.strip_prefix(marker) {
+            line = rest.trim_start();
+            break;
+        }
+    }
+
+    line.trim_end_matches(['.', '!']).trim().to_string()
+}
+
+/// Map the ways people write a status onto the board's status ids
+fn normalize_status(raw: &str) -> Result<String, String> {
+    let key = normalize_key(raw);
+
+    let status = match key.as_str() {
+        "todo" | "to-do" | "backlog" | "not-started" | "open" => "todo",
+        "in-progress" | "progress" | "doing" | "wip" | "started" | "active" => "in-progress",
+        "review" | "in-review" | "needs-review" => "review",
+        "completed" | "complete" | "done" | "finished" | "closed" => "completed",
+        "blocked" | "on-hold" | "stuck" => "blocked",
+        _ => {
+            return Err(format!(
+                "Unknown status \"{}\" (expected one of: {})",
+                raw.trim(),
+                TASK_STATUSES.join(", ")
+            ))
+        }
+    };
+
+    Ok(status.to_string())
+}
+
+/// Map a written stage onto a stage id, tolerating small typos
+fn normalize_stage(raw: &str) -> Result<String, String> {
+    let key = normalize_key(raw);
+
+    let alias = match key.as_str() {
+        "qa" | "qa-plan" | "test-planning" => Some("qa-planning"),
+        "dev" | "build" | "building" => Some("development"),
+        "test" => Some("testing"),
+        "shipping" | "launch" | "release" => Some("ship"),
+        "docs" => Some("documentation"),
+        "retro" => Some("retrospective"),
+        _ => None,
+    };
+    if let Some(stage) = alias {
+        return Ok(stage.to_string());
+    }
+
+    STAGES
+        .iter()
+        .map(|(stage, _)| (strsim::jaro_winkler(&key, stage), *stage))
+        .filter(|(score, _)| *score >= 0.9)
+        .max_by(|a, b| a.0.total_cmp(&b.0))
+        .map(|(_, stage)| stage.to_string())
+        .ok_or_else(|| format!("Unknown stage \"{}\"", raw.trim()))
+}
+
+/// Lowercase and hyphenate ("In Progress" → "in-progress")
+fn normalize_key(raw: &str) -> String {
+    raw.trim()
+        .to_lowercase()
+        .split(|c: char| c.is_whitespace() || c == '_' || c == '-')
+        .filter(|s| !s.is_empty())
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn recognises_documented_patterns() {
+        assert_eq!(
+            parse_line("- [ ] Move AnyCalc dark mode toggle to done."),
+            Some(Ok(Command::MoveTask {
+                subject: "AnyCalc dark mode toggle".into(),
+                status: "completed".into(),
+            }))
+        );
+        assert_eq!(
+            parse_line("Create task for taskboard: Add export button (p1)"),
+            Some(Ok(Command::CreateTask {
+                project: "taskboard".into(),
+                title: "Add export button".into(),
+                priority: Some("P1".into()),
+            }))
+        );
+        assert_eq!(
+            parse_line("Add comment to login flow: check the redirect"),
+            Some(Ok(Command::AddComment {
+                task: "login flow".into(),
+                text: "check the redirect".into(),
+            }))
+        );
+        assert_eq!(
+            parse_line("P0 the payment bug"),
+            Some(Ok(Command::SetPriority {
+                task: "payment bug".into(),
+                priority: "P0".into(),
+            }))
+        );
+        assert_eq!(
+            parse_line("Orbit is now in QA Planning"),
+            Some(Ok(Command::SetStage {
+                project: "Orbit".into(),
+                stage: "qa-planning".into(),
+            }))
+        );
+    }
+
+    #[test]
+    fn plain_text_and_bad_arguments() {
+        assert_eq!(parse_line("Progress bar looks good"), None);
+        assert!(matches!(
+            parse_line("Move login to someday"),
+            Some(Err(e)) if e.contains("Unknown status")
+        ));
+        assert!(matches!(
+            parse_line("Orbit is now in limbo"),
+            Some(Err(e)) if e.contains("Unknown stage")
+        ));
+    }
+}
@@ -0,0 +1,132 @@
+//! Fuzzy name matching for projects and tasks
+//!
+//! People write "anycalc dark mode" for a task titled "Add dark mode toggle"
+//! in project "AnyCalc". A candidate scores highest when every word of the
+//! query appears in it (allowing small typos); otherwise whole-string
+//! similarity decides.
+
+/// Minimum score for a candidate to count as a match
+const THRESHOLD: f64 = 0.8;
+
+/// Scores closer than this are too close to call
+const AMBIGUITY_MARGIN: f64 = 0.02;
+
+/// Score how well `query` describes `candidate`, from 0.0 to 1.0
+pub fn score(query: &str, candidate: &str) -> f64 {
+    let query = tokens(query);
+    let candidate = tokens(candidate);
+
+    if query.is_empty() || candidate.is_empty() {
+        return 0.0;
+    }
+    if query == candidate {
+        return 1.0;
+    }
+
+    let covered = query
+        .iter()
+        .filter(|q| {
+            candidate
+                .iter()
+                .any(|c| c == *q || strsim::jaro_winkler(q, c) >= 0.9)
+        })
+        .count();
+
+    if covered == query.len() {
+        // Every word present: prefer candidates the query describes more fully
+        return 0.9 + 0.09 * (query.len() as f64 / candidate.len().max(query.len()) as f64);
+    }
+
+    let coverage = covered as f64 / query.len() as f64;
+    let whole = strsim::jaro_winkler(&query.join(" "), &candidate.join(" "));
+    whole.max(coverage * 0.8)
+}
+
+/// Outcome of looking a name up
+#[derive(Debug)]
+pub enum Match<'a, T> {
+    Found(&'a T, f64),
+    Ambiguous(Vec<&'a T>),
+    NotFound,
+}
+
+/// Find the candidate best described by `query`. `names` lists the strings
+/// a candidate can be referred to by (e.g. a project's id and name).
+pub fn find<'a, T, F>(
+    query: &str,
+    candidates: impl IntoIterator<Item = &'a T>,
+    names: F,
+) -> Match<'a, T>
+where
+    F: Fn(&T) -> Vec<&str>,
+{
+    let mut ranked: Vec<(f64, &'a T)> = candidates
+        .into_iter()
+        .map(|candidate| {
+            let best = names(candidate)
+                .into_iter()
+                .map(|name| score(query, name))
+                .fold(0.0, f64::max);
+            (best, candidate)
+        })
+        .filter(|(s, _)| *s >= THRESHOLD)
+        .collect();
+    ranked.sort_by(|a, b| b.0.total_cmp(&a.0));
+
+    match ranked.as_slice() {
+        [] => Match::NotFound,
+        [(top, best), rest @ ..] => {
+            let close: Vec<&'a T> = rest
+                .iter()
+                .take_while(|(s, _)| top - s < AMBIGUITY_MARGIN)
+                .map(|(_, c)| *c)
+                .collect();
+
+            if close.is_empty() || *top >= 1.0 {
+                Match::Found(best, *top)
+            } else {
+                Match::Ambiguous(std::iter::once(*best).chain(close).collect())
+            }
+        }
+    }
+}
+
+/// Lowercase alphanumeric words
+fn tokens(s: &str) -> Vec<String> {
+    s.to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|t| !t.is_empty() && *t != "the")
+        .map(|t| t.to_string())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scores_partial_and_misspelled_names() {
+        assert_eq!(score("AnyCalc", "anycalc"), 1.0);
+        assert!(score("dark mode", "Add dark mode toggle") > 0.9);
+        assert!(score("dark mdoe", "Add dark mode toggle") >= THRESHOLD);
+        assert!(score("payments", "Add dark mode toggle") < THRESHOLD);
+    }
+
+    #[test]
+    fn reports_ambiguous_matches() {
+        let titles = ["Fix login redirect", "Fix login timeout", "Write docs"];
+
+        assert!(matches!(
+            find("login", &titles, |t| vec![*t]),
+            Match::Ambiguous(c) if c.len() == 2
+        ));
+        assert!(matches!(
+            find("login timeout", &titles, |t| vec![*t]),
+            Match::Found(t, _) if *t == "Fix login timeout"
+        ));
+        assert!(matches!(
+            find("billing", &titles, |t| vec![*t]),
+            Match::NotFound
+        ));
+    }
+}
@@ -0,0 +1,946 @@
+//! GitHub Gist sync
+//!
+//! Keeps projects.json, tasks.json and inbox.json in step with the gist
+//! configured in sync-config.json (`gistToken`, `gistId`). Each sync reads
+//! the gist, merges it with the local files entity by entity (see `merge`),
+//! writes back whatever changed on either side, and records the gist
+//! revision it ended on in sync-state.json. That revision is the base of
+//! the next merge: when the gist has moved on since, it is fetched from the
+//! gist's revision history, so changes made on both sides are told apart
+//! from changes made on one.
+//!
+//! Orbit's `klarity-inbox.json` is merged into the inbox and kept up to
+//! date alongside it.
+//!
+//! Syncs run on start, every `pollIntervalMs`, and shortly after local
+//! data changes. Each one is reported as `sync-status` events. Local
+//! changes wait in an outbox (see `outbox`) until a sync gets them to the
+//! gist; while syncs fail, e.g. offline, they are retried with backoff.
+
+mod gist;
+mod merge;
+mod outbox;
+#[cfg(test)]
+mod stand_in;
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Condvar, Mutex};
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use tauri::{AppHandle, Emitter, Manager, State};
+
+use crate::audit::{self, Action, Actor, EntityRef};
+use crate::data::{self, InboxFile, ProjectsFile, TasksFile};
+use crate::watcher::{WriteOrigin, WriteRegistry};
+use crate::AppState;
+use gist::{Gist, GistClient, GITHUB_API_URL};
+use outbox::{Outbox, PendingChange};
+
+/// Synced files: file name, the array holding its entities, entity kind
+const SYNCED: [(&str, &str, &str); 3] = [
+    ("projects.json", "projects", "project"),
+    ("tasks.json", "tasks", "task"),
+    ("inbox.json", "items", "inboxItem"),
+];
+
+/// The inbox file Orbit reads and writes
+const ORBIT_INBOX: &str = "klarity-inbox.json";
+
+const CONFIG_FILE: &str = "sync-config.json";
+const STATE_FILE: &str = "sync-state.json";
+
+const DEFAULT_POLL_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Wait after a local change before syncing, so a burst of saves goes out
+/// as one revision
+const LOCAL_CHANGE_DELAY: Duration = Duration::from_secs(2);
+
+/// Syncs never overlap
+static RUNNING: Mutex<()> = Mutex::new(());
+
+/// Gist settings from sync-config.json
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(default, rename_all = "camelCase")]
+pub struct SyncSettings {
+    pub gist_token: String,
+    pub gist_id: String,
+    pub poll_interval_ms: Option<u64>,
+    /// GitHub Enterprise (or test) API root
+    pub gist_api_url: Option<String>,
+}
+
+impl SyncSettings {
+    pub fn load(data_dir: &Path) -> Self {
+        fs::read_to_string(data_dir.join(CONFIG_FILE))
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn enabled(&self) -> bool {
+        !self.gist_token.trim().is_empty() && !self.gist_id.trim().is_empty()
+    }
+
+    fn poll_interval(&self) -> Duration {
+        self.poll_interval_ms
+            .map(|ms| Duration::from_millis(ms.max(5_000)))
+            .unwrap_or(DEFAULT_POLL_INTERVAL)
+    }
+
+    fn client(&self) -> GistClient {
+        let api_url = self.gist_api_url.as_deref().unwrap_or(GITHUB_API_URL);
+        GistClient::new(api_url, &self.gist_token)
+    }
+}
+
+/// Where the last sync left off, kept in sync-state.json
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(default, rename_all = "camelCase")]
+pub struct SyncState {
+    pub gist_id: String,
+    /// Gist revision the local files matched after the last sync
+    pub revision: Option<String>,
+    pub last_synced_at: Option<String>,
+    /// Hash of the entities in each synced file as the last sync left it
+    pub hashes: HashMap<String, String>,
+    /// Hash of each synced entity as the last sync left it, by "kind:id"
+    pub entities: HashMap<String, String>,
+}
+
+impl SyncState {
+    pub fn load(data_dir: &Path) -> Self {
+        fs::read_to_string(data_dir.join(STATE_FILE))
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    fn save(&self, data_dir: &Path) -> Result<(), String> {
+        let content = serde_json::to_string_pretty(self)
+            .map_err(|e| format!("Failed to serialize sync state: {}", e))?;
+        fs::write(data_dir.join(STATE_FILE), content)
+            .map_err(|e| format!("Failed to write {}: {}", STATE_FILE, e))
+    }
+
+    /// Whether a synced file changed since the last sync
+    pub fn local_changed(&self, data_dir: &Path) -> bool {
+        read_locals(data_dir).map_or(true, |locals| content_hashes(&locals) != self.hashes)
+    }
+}
+
+/// Hash of the entities in each of `files`, given in `SYNCED` order. The
+/// file's own lastUpdated is left out, as every save moves it.
+fn content_hashes(files: &[Value]) -> HashMap<String, String> {
+    SYNCED
+        .iter()
+        .zip(files)
+        .map(|((name, collection, _), file)| {
+            let entities = file.get(collection).cloned().unwrap_or(json!([]));
+            let hash = format!("{:x}", Sha256::digest(entities.to_string()));
+            (name.to_string(), hash)
+        })
+        .collect()
+}
+
+/// What a sync did
+#[derive(Serialize, Debug, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncReport {
+    /// Gist revision the local files now match
+    pub revision: Option<String>,
+    /// Entities changed locally by the gist
+    pub pulled: Vec<EntityRef>,
+    /// Entities changed on the gist
+    pub pushed: Vec<EntityRef>,
+    /// Ids changed on both sides, resolved by the merge
+    pub conflicts: Vec<String>,
+    /// Whether both sides had changed since the last sync
+    pub diverged: bool,
+}
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum SyncPhase {
+    Syncing,
+    Synced,
+    Failed,
+}
+
+/// Payload of `sync-status`
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncEvent {
+    pub state: SyncPhase,
+    pub at: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub report: Option<SyncReport>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// The gist's inbox, with Orbit's items merged in. Orbit's file has no base
+/// of its own, so an item it still lists comes back even if it was deleted
+/// here; every push rewrites the file, so that only happens when Orbit wrote
+/// it from an older copy.
+fn remote_inbox(gist: &Gist) -> Result<Option<Value>, String> {
+    let orbit = gist
+        .json(ORBIT_INBOX)?
+        .map(|orbit| json!({ "items": orbit.get("items").cloned().unwrap_or(json!([])) }));
+    Ok(match (gist.json("inbox.json")?, orbit) {
+        (inbox, None) => inbox,
+        (None, orbit) => orbit,
+        (Some(inbox), Some(orbit)) => {
+            Some(merge::merge_file(None, &inbox, Some(&orbit), "items").file)
+        }
+    })
+}
+
+fn remote_file(gist: &Gist, name: &str) -> Result<Option<Value>, String> {
+    let file = match name {
+        "inbox.json" => remote_inbox(gist)?,
+        _ => gist.json(name)?,
+    };
+    file.map(|file| normalize(name, file)).transpose()
+}
+
+/// Every synced file, in `SYNCED` order
+fn read_locals(data_dir: &Path) -> Result<Vec<Value>, String> {
+    SYNCED
+        .iter()
+        .map(|(name, collection, _)| read_local(data_dir, name, collection))
+        .collect()
+}
+
+fn read_local(data_dir: &Path, name: &str, collection: &str) -> Result<Value, String> {
+    let file = match fs::read_to_string(data_dir.join(name)) {
+        Ok(content) => serde_json::from_str(&content)
+            .map_err(|e| format!("Failed to parse {}: {}", name, e))?,
+        Err(_) => json!({ collection: [] }),
+    };
+    normalize(name, file)
+}
+
+/// `file` as the data layer reads and writes it, with defaults filled in,
+/// so files from either side compare equal when their entities are
+fn normalize(name: &str, file: Value) -> Result<Value, String> {
+    let invalid = |e: serde_json::Error| format!("Failed to read synced {}: {}", name, e);
+    let normalized = match name {
+        "projects.json" => {
+            serde_json::from_value::<ProjectsFile>(file).and_then(serde_json::to_value)
+        }
+        "tasks.json" => serde_json::from_value::<TasksFile>(file).and_then(serde_json::to_value),
+        _ => serde_json::from_value::<InboxFile>(file).and_then(serde_json::to_value),
+    };
+    normalized.map_err(invalid)
+}
+
+fn save_local(
+    data_dir: &Path,
+    writes: &WriteRegistry,
+    name: &str,
+    file: Value,
+) -> Result<(), String> {
+    let invalid = |e: serde_json::Error| format!("Failed to apply synced {}: {}", name, e);
+    let origin = WriteOrigin::Backend;
+    match name {
+        "projects.json" => {
+            let mut projects = serde_json::from_value(file).map_err(invalid)?;
+            data::save_projects(data_dir, writes, &mut projects, origin)
+        }
+        "tasks.json" => {
+            let mut tasks = serde_json::from_value(file).map_err(invalid)?;
+            data::save_tasks(data_dir, writes, &mut tasks, origin)
+        }
+        _ => {
+            let mut inbox = serde_json::from_value(file).map_err(invalid)?;
+            data::save_inbox(data_dir, writes, &mut inbox, origin)
+        }
+    }
+}
+
+/// Merge `remote` into `locals` against `base`, and write back the local
+/// files that changed
+fn merge_round(
+    data_dir: &Path,
+    writes: &WriteRegistry,
+    base: Option<&Gist>,
+    locals: &[Value],
+    remote: &Gist,
+    report: &mut SyncReport,
+) -> Result<Vec<Value>, String> {
+    let mut merges = Vec::new();
+    for ((name, collection, kind), local) in SYNCED.into_iter().zip(locals) {
+        let theirs = remote_file(remote, name)?;
+        let original = match base {
+            Some(base) => remote_file(base, name)?,
+            None => None,
+        };
+        let merged = merge::merge_file(original.as_ref(), local, theirs.as_ref(), collection);
+        report.conflicts.extend(merged.conflicts);
+
+        let pulled = audit::changed_entities(Some(local), &merged.file, kind);
+        if !pulled.is_empty() {
+            save_local(data_dir, writes, name, merged.file.clone())?;
+            report.pulled.extend(pulled);
+        }
+        merges.push(merged.file);
+    }
+    Ok(merges)
+}
+
+/// The files the gist needs for it to match `merged`
+fn uploads(
+    merged: &[Value],
+    gist: &Gist,
+    report: &mut SyncReport,
+) -> Result<Vec<(&'static str, String)>, String> {
+    let serialize = |name: &str, file: &Value| {
+        serde_json::to_string_pretty(file)
+            .map_err(|e| format!("Failed to serialize {}: {}", name, e))
+    };
+
+    let mut uploads = Vec::new();
+    for ((name, _, kind), file) in SYNCED.into_iter().zip(merged) {
+        let theirs = gist.json(name)?.map(|f| normalize(name, f)).transpose()?;
+        let pushed = audit::changed_entities(theirs.as_ref(), file, kind);
+        if !pushed.is_empty() || theirs.is_none() {
+            uploads.push((name, serialize(name, file)?));
+            report.pushed.extend(pushed);
+        }
+
+        let orbit = match name {
+            "inbox.json" => gist.json(ORBIT_INBOX)?,
+            _ => None,
+        };
+        let items = file.get("items").cloned().unwrap_or(json!([]));
+        if orbit.is_some_and(|orbit| orbit.get("items") != Some(&items)) {
+            let payload = json!({
+                "version": "1.0.0",
+                "lastUpdated": chrono::Utc::now().to_rfc3339(),
+                "source": "command-center",
+                "items": items,
+            });
+            uploads.push((ORBIT_INBOX, serialize(ORBIT_INBOX, &payload)?));
+        }
+    }
+    Ok(uploads)
+}
+
+/// Sync the local files with the configured gist once. Local changes are
+/// queued in the outbox first; they leave it once the gist has them, and
+/// a failed sync schedules a retry.
+pub fn sync_once(
+    data_dir: &Path,
+    writes: &WriteRegistry,
+    settings: &SyncSettings,
+) -> Result<SyncReport, String> {
+    let _running = RUNNING.lock().unwrap();
+    let mut outbox = Outbox::load(data_dir);
+    let queue = |outbox: &mut Outbox| outbox.queue(data_dir, &SyncState::load(data_dir).entities);
+
+    let result = queue(&mut outbox)
+        .and_then(|()| sync_gist(data_dir, writes, settings))
+        .and_then(|report| {
+            // Everything queued so far is in the gist now
+            outbox.changes.clear();
+            queue(&mut outbox).map(|()| report)
+        });
+    match &result {
+        Ok(_) => outbox.succeeded(),
+        Err(e) => outbox.failed(e),
+    }
+    outbox.save(data_dir)?;
+    result
+}
+
+/// Queue local changes made since the last sync in the outbox
+pub fn queue_changes(data_dir: &Path) -> Result<Outbox, String> {
+    let _running = RUNNING.lock().unwrap();
+    let mut outbox = Outbox::load(data_dir);
+    outbox.queue(data_dir, &SyncState::load(data_dir).entities)?;
+    outbox.save(data_dir)?;
+    Ok(outbox)
+}
+
+fn sync_gist(
+    data_dir: &Path,
+    writes: &WriteRegistry,
+    settings: &SyncSettings,
+) -> Result<SyncReport, String> {
+    let client = settings.client();
+    let gist_id = settings.gist_id.trim();
+
+    let mut state = SyncState::load(data_dir);
+    if state.gist_id != gist_id {
+        state = SyncState {
+            gist_id: gist_id.to_string(),
+            ..SyncState::default()
+        };
+    }
+
+    let remote = client.get(gist_id)?;
+    let head = remote.head();
+    let base = match &state.revision {
+        Some(revision) if Some(revision) == head.as_ref() => Some(remote.clone()),
+        // Gone from the history (e.g. the gist was recreated): merge without a base
+        Some(revision) => client.revision(gist_id, revision)?,
+        None => None,
+    };
+
+    let mut report = SyncReport {
+        diverged: state.revision.is_some()
+            && state.revision != head
+            && state.local_changed(data_dir),
+        ..SyncReport::default()
+    };
+    let locals = read_locals(data_dir)?;
+    let mut merged = merge_round(
+        data_dir,
+        writes,
+        base.as_ref(),
+        &locals,
+        &remote,
+        &mut report,
+    )?;
+
+    report.revision = head.clone();
+    let changes = uploads(&merged, &remote, &mut report)?;
+    if !changes.is_empty() {
+        let pushed = client.update(gist_id, &changes)?;
+        report.revision = pushed.head();
+
+        // Another device pushed between our read and our write: its revision
+        // is now only in the history, so merge it into ours and push again
+        let intervening = pushed
+            .history
+            .get(1)
+            .filter(|r| Some(&r.version) != head.as_ref());
+        if let Some(theirs) = intervening {
+            let theirs = client
+                .revision(gist_id, &theirs.version)?
+                .ok_or_else(|| format!("Gist revision {} disappeared", theirs.version))?;
+            // Read the files again rather than reusing `merged`: they may
+            // have been edited during the round trips, and what this merge
+            // writes back would undo those edits
+            let locals = read_locals(data_dir)?;
+            merged = merge_round(
+                data_dir,
+                writes,
+                Some(&remote),
+                &locals,
+                &theirs,
+                &mut report,
+            )?;
+            let changes = uploads(&merged, &pushed, &mut report)?;
+            if !changes.is_empty() {
+                report.revision = client.update(gist_id, &changes)?.head();
+            }
+        }
+    }
+
+    state.revision = report.revision.clone();
+    state.last_synced_at = Some(chrono::Utc::now().to_rfc3339());
+    // What was pushed, not the files as they are now: an edit made while the
+    // push was in flight stays pending for the next sync
+    state.hashes = content_hashes(&merged);
+    state.entities = outbox::hash_entities(&merged)
+        .into_iter()
+        .map(|(entity, hash)| (outbox::entity_key(&entity.kind, &entity.id), hash))
+        .collect();
+    state.save(data_dir)?;
+    Ok(report)
+}
+
+/// Wakes the sync loop early when local data changes
+#[derive(Default)]
+pub struct SyncScheduler {
+    requested: Mutex<bool>,
+    wake: Condvar,
+}
+
+impl SyncScheduler {
+    /// Ask for a sync soon
+    pub fn request(&self) {
+        *self.requested.lock().unwrap() = true;
+        self.wake.notify_one();
+    }
+
+    /// Wait for a request, or until `timeout` passes. True when asked.
+    fn wait(&self, timeout: Duration) -> bool {
+        let requested = self.requested.lock().unwrap();
+        let (mut requested, _) = self
+            .wake
+            .wait_timeout_while(requested, timeout, |requested| !*requested)
+            .unwrap();
+        std::mem::take(&mut *requested)
+    }
+}
+
+fn data_context(app: &AppHandle) -> (PathBuf, WriteRegistry) {
+    let state = app.state::<Mutex<AppState>>();
+    let state = state.lock().unwrap();
+    (state.data_dir.clone(), state.writes.clone())
+}
+
+fn announce(app: &AppHandle, state: SyncPhase, report: Option<SyncReport>, error: Option<String>) {
+    let event = SyncEvent {
+        state,
+        at: chrono::Utc::now().to_rfc3339(),
+        report,
+        error,
+    };
+    let _ = app.emit("sync-status", &event);
+}
+
+/// Sync now, reporting progress as `sync-status` events and what changed
+/// in the activity log
+fn run(app: &AppHandle) -> Result<SyncReport, String> {
+    let (data_dir, writes) = data_context(app);
+    let settings = SyncSettings::load(&data_dir);
+    if !settings.enabled() {
+        return Err("Gist sync is not set up: add gistToken and gistId to sync-config.json".into());
+    }
+
+    announce(app, SyncPhase::Syncing, None, None);
+    let result = sync_once(&data_dir, &writes, &settings);
+    match &result {
+        Ok(report) => {
+            for (command, entities) in
+                [("sync_pull", &report.pulled), ("sync_push", &report.pushed)]
+            {
+                if !entities.is_empty() {
+                    let action = Action::new(Actor::Sync, command).entities(entities.clone());
+                    let _ = audit::record(&data_dir, action, Ok(()));
+                }
+            }
+            announce(app, SyncPhase::Synced, Some(report.clone()), None);
+        }
+        Err(e) => {
+            let _ =
+                audit::record::<()>(&data_dir, Action::new(Actor::Sync, "sync"), Err(e.clone()));
+            announce(app, SyncPhase::Failed, None, Some(e.clone()));
+        }
+    }
+    result
+}
+
+/// Start the loop that syncs on start, every poll interval, and after
+/// local changes
+pub fn start_sync(app: AppHandle) {
+    std::thread::spawn(move || {
+        let scheduler = app.state::<SyncScheduler>();
+        let mut requested = false;
+        loop {
+            let (data_dir, _) = data_context(&app);
+            let settings = SyncSettings::load(&data_dir);
+            let mut wait = settings.poll_interval();
+            if settings.enabled() {
+                // A file that cannot be read fails the sync below as well,
+                // which reports it
+                let outbox = queue_changes(&data_dir).unwrap_or_else(|_| Outbox::load(&data_dir));
+                // A change request for files the last sync wrote itself is an
+                // echo, and while backing off only the retry itself syncs
+                let due = !requested || !outbox.changes.is_empty();
+                if due && outbox.retry_in().is_none() {
+                    // Failures reach the UI as sync-status events and the outbox
+                    let _ = run(&app);
+                }
+                if let Some(delay) = Outbox::load(&data_dir).retry_in() {
+                    wait = delay;
+                }
+            }
+
+            requested = scheduler.wait(wait);
+            if requested {
+                std::thread::sleep(LOCAL_CHANGE_DELAY);
+            }
+        }
+    });
+}
+
+/// Ask for a sync because a synced file changed
+pub fn local_change(app: &AppHandle) {
+    app.state::<SyncScheduler>().request();
+}
+
+/// Sync health, as shown in the UI
+#[derive(Serialize, Debug, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncStatus {
+    pub enabled: bool,
+    /// Gist revision of the last successful sync
+    pub revision: Option<String>,
+    pub last_success_at: Option<String>,
+    pub last_attempt_at: Option<String>,
+    /// Error of the last sync, when it failed
+    pub last_error: Option<String>,
+    /// Failed syncs in a row
+    pub attempts: u32,
+    /// When the next automatic sync runs after a failure
+    pub retry_at: Option<String>,
+    pub pending_count: usize,
+    /// Local changes the gist does not have yet
+    pub pending: Vec<PendingChange>,
+}
+
+pub fn status(data_dir: &Path) -> SyncStatus {
+    let enabled = SyncSettings::load(data_dir).enabled();
+    let state = SyncState::load(data_dir);
+    let mut outbox = Outbox::load(data_dir);
+    if enabled {
+        // Include edits the sync loop has not queued yet
+        let _ = outbox.queue(data_dir, &state.entities);
+    }
+    let pending = if enabled { outbox.changes } else { Vec::new() };
+    SyncStatus {
+        enabled,
+        revision: state.revision,
+        last_success_at: state.last_synced_at,
+        last_attempt_at: outbox.last_attempt_at,
+        last_error: outbox.last_error,
+        attempts: outbox.attempts,
+        retry_at: outbox.retry_at,
+        pending_count: pending.len(),
+        pending,
+    }
+}
+
+// Tauri commands
+
+/// Sync health: last success, pending local changes and the last error
+#[tauri::command]
+pub fn get_sync_status(state: State<'_, Mutex<AppState>>) -> SyncStatus {
+    let data_dir = state.lock().unwrap().data_dir.clone();
+    status(&data_dir)
+}
+
+/// Sync with the gist now
+#[tauri::command]
+pub async fn sync_now(app: AppHandle) -> Result<SyncReport, String> {
+    tokio::task::spawn_blocking(move || run(&app))
+        .await
+        .map_err(|e| format!("Sync failed: {}", e))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use outbox::ChangeKind;
+    use stand_in::{GistStandIn, GIST_ID, TOKEN};
+
+    fn settings(gist: &GistStandIn) -> SyncSettings {
+        SyncSettings {
+            gist_token: TOKEN.to_string(),
+            gist_id: GIST_ID.to_string(),
+            poll_interval_ms: None,
+            gist_api_url: Some(gist.api_url()),
+        }
+    }
+
+    fn task(id: &str, title: &str, at: &str) -> Value {
+        json!({
+            "id": id, "projectId": "anycalc", "title": title,
+            "status": "todo", "priority": "P2", "updatedAt": at
+        })
+    }
+
+    fn write(dir: &Path, name: &str, file: Value) {
+        fs::write(dir.join(name), serde_json::to_string_pretty(&file).unwrap()).unwrap();
+    }
+
+    fn titles(file: &Value) -> Vec<String> {
+        file["tasks"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|t| t["title"].as_str().unwrap().to_string())
+            .collect()
+    }
+
+    fn local(dir: &Path, name: &str) -> Value {
+        serde_json::from_str(&fs::read_to_string(dir.join(name)).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn pushes_then_merges_changes_made_on_both_sides() {
+        let dir = tempfile::tempdir().unwrap();
+        let writes = WriteRegistry::default();
+        write(
+            dir.path(),
+            "projects.json",
+            json!({ "projects": [{ "id": "anycalc", "name": "AnyCalc" }] }),
+        );
+        write(
+            dir.path(),
+            "tasks.json",
+            json!({ "tasks": [
+                task("t-1", "Tip", "2026-05-01T10:00:00Z"),
+                task("t-2", "Split", "2026-05-01T10:00:00Z")
+            ]}),
+        );
+        let gist = GistStandIn::start(&[]);
+        let settings = settings(&gist);
+
+        let first = sync_once(dir.path(), &writes, &settings).unwrap();
+        assert_eq!(first.pushed.len(), 3);
+        assert_eq!(first.revision.as_deref(), Some("rev-2"));
+        assert_eq!(titles(&gist.file("tasks.json").unwrap()), ["Tip", "Split"]);
+        assert!(gist.file("inbox.json").is_some());
+
+        // The phone renames t-1 and adds t-3 while t-2 is renamed here
+        let mut theirs = gist.file("tasks.json").unwrap();
+        theirs["tasks"][0] = task("t-1", "Tips", "2026-05-02T10:00:00Z");
+        theirs["tasks"]
+            .as_array_mut()
+            .unwrap()
+            .push(task("t-3", "Round", "2026-05-02T10:00:00Z"));
+        gist.push(&[("tasks.json", theirs)]);
+        let mut ours = local(dir.path(), "tasks.json");
+        ours["tasks"][1] = task("t-2", "Split bill", "2026-05-02T11:00:00Z");
+        write(dir.path(), "tasks.json", ours);
+
+        let second = sync_once(dir.path(), &writes, &settings).unwrap();
+        assert!(second.diverged);
+        assert!(second.conflicts.is_empty());
+        assert!(gist
+            .requests()
+            .contains(&format!("GET /gists/{}/rev-2", GIST_ID)));
+        let pulled: Vec<&str> = second.pulled.iter().map(|e| e.id.as_str()).collect();
+        assert_eq!(pulled, ["t-1", "t-3"]);
+        let pushed: Vec<&str> = second.pushed.iter().map(|e| e.id.as_str()).collect();
+        assert_eq!(pushed, ["t-2"]);
+        assert_eq!(
+            titles(&local(dir.path(), "tasks.json")),
+            ["Tips", "Split bill", "Round"]
+        );
+        assert_eq!(
+            titles(&gist.file("tasks.json").unwrap()),
+            ["Tips", "Split bill", "Round"]
+        );
+
+        // Nothing changed since: nothing to push
+        let revisions = gist.revisions();
+        let third = sync_once(dir.path(), &writes, &settings).unwrap();
+        assert!(third.pulled.is_empty() && third.pushed.is_empty());
+        assert_eq!(gist.revisions(), revisions);
+        assert!(!SyncState::load(dir.path()).local_changed(dir.path()));
+    }
+
+    #[test]
+    fn brings_in_orbit_items_and_keeps_orbits_file_current() {
+        let dir = tempfile::tempdir().unwrap();
+        let writes = WriteRegistry::default();
+        let item = |id: &str, text: &str| {
+            json!({ "id": id, "text": text, "status": "pending",
+                    "createdAt": "2026-05-01T10:00:00Z", "replies": [] })
+        };
+        write(
+            dir.path(),
+            "inbox.json",
+            json!({ "items": [item("i-1", "From the desk")] }),
+        );
+        let orbit = json!({
+            "version": "1.0.0",
+            "source": "orbit",
+            "items": [item("i-2", "From the phone")]
+        });
+        let gist = GistStandIn::start(&[(ORBIT_INBOX, orbit)]);
+
+        let report = sync_once(dir.path(), &writes, &settings(&gist)).unwrap();
+        assert_eq!(report.pulled.len(), 1);
+
+        let ids = |file: &Value| -> Vec<String> {
+            file["items"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|i| i["id"].as_str().unwrap().to_string())
+                .collect()
+        };
+        assert_eq!(ids(&local(dir.path(), "inbox.json")), ["i-2", "i-1"]);
+        assert_eq!(ids(&gist.file("inbox.json").unwrap()), ["i-2", "i-1"]);
+        let orbit = gist.file(ORBIT_INBOX).unwrap();
+        assert_eq!(ids(&orbit), ["i-2", "i-1"]);
+        assert_eq!(orbit["source"], "command-center");
+        assert!(dir.path().join("inbox.md").exists());
+
+        let wrong_token = SyncSettings {
+            gist_token: "nope".into(),
+            ..settings(&gist)
+        };
+        let error = sync_once(dir.path(), &writes, &wrong_token).unwrap_err();
+        assert!(error.contains("401"), "{}", error);
+    }
+
+    #[test]
+    fn queues_offline_edits_and_pushes_them_once_back_online() {
+        let dir = tempfile::tempdir().unwrap();
+        let writes = WriteRegistry::default();
+        write(
+            dir.path(),
+            "projects.json",
+            json!({ "projects": [{ "id": "anycalc", "name": "AnyCalc" }] }),
+        );
+        write(
+            dir.path(),
+            "tasks.json",
+            json!({ "tasks": [
+                task("t-1", "Tip", "2026-05-01T10:00:00Z"),
+                task("t-2", "Split", "2026-05-01T10:00:00Z")
+            ]}),
+        );
+        let gist = GistStandIn::start(&[]);
+        let settings = settings(&gist);
+        write(
+            dir.path(),
+            CONFIG_FILE,
+            json!({ "gistToken": TOKEN, "gistId": GIST_ID, "gistApiUrl": gist.api_url() }),
+        );
+        sync_once(dir.path(), &writes, &settings).unwrap();
+        assert_eq!(status(dir.path()).pending_count, 0);
+
+        // Offline: t-1 edited twice, t-2 deleted, t-3 added
+        gist.set_down(true);
+        let edit = |tasks: Vec<Value>| write(dir.path(), "tasks.json", json!({ "tasks": tasks }));
+        edit(vec![
+            task("t-1", "Tips", "2026-05-02T10:00:00Z"),
+            task("t-2", "Split", "2026-05-01T10:00:00Z"),
+        ]);
+        queue_changes(dir.path()).unwrap();
+        edit(vec![
+            task("t-1", "Tipping", "2026-05-02T11:00:00Z"),
+            task("t-3", "Round", "2026-05-02T11:00:00Z"),
+        ]);
+        let error = sync_once(dir.path(), &writes, &settings).unwrap_err();
+        assert!(error.contains("503"), "{}", error);
+
+        let offline = status(dir.path());
+        assert_eq!(offline.last_error.as_deref(), Some(error.as_str()));
+        assert_eq!(offline.attempts, 1);
+        assert!(offline.retry_at.is_some());
+        let pending: Vec<(&str, ChangeKind, u32)> = offline
+            .pending
+            .iter()
+            .map(|c| (c.entity.id.as_str(), c.change, c.edits))
+            .collect();
+        assert_eq!(
+            pending,
+            [
+                ("t-1", ChangeKind::Updated, 2),
+                ("t-3", ChangeKind::Created, 1),
+                ("t-2", ChangeKind::Deleted, 1)
+            ]
+        );
+        assert_eq!(offline.pending_count, 3);
+
+        sync_once(dir.path(), &writes, &settings).unwrap_err();
+        let outbox = Outbox::load(dir.path());
+        assert_eq!(outbox.attempts, 2);
+        assert!(outbox.retry_in().unwrap() > outbox::backoff(1));
+
+        gist.set_down(false);
+        let report = sync_once(dir.path(), &writes, &settings).unwrap();
+        assert_eq!(report.pushed.len(), 3);
+        assert_eq!(
+            titles(&gist.file("tasks.json").unwrap()),
+            ["Tipping", "Round"]
+        );
+        let online = status(dir.path());
+        assert_eq!(online.pending_count, 0);
+        assert_eq!((online.attempts, online.last_error), (0, None));
+        assert!(online.last_success_at.is_some());
+    }
+
+    #[test]
+    fn keeps_an_edit_made_during_the_push_pending() {
+        let dir = tempfile::tempdir().unwrap();
+        let writes = WriteRegistry::default();
+        write(
+            dir.path(),
+            "tasks.json",
+            json!({ "tasks": [task("t-1", "Tip", "2026-05-01T10:00:00Z")] }),
+        );
+        let gist = GistStandIn::start(&[]);
+        let settings = settings(&gist);
+        write(
+            dir.path(),
+            CONFIG_FILE,
+            json!({ "gistToken": TOKEN, "gistId": GIST_ID, "gistApiUrl": gist.api_url() }),
+        );
+        let data_dir = dir.path().to_path_buf();
+        gist.on_patch(move || {
+            let tasks = json!({ "tasks": [task("t-1", "Tips", "2026-05-01T10:05:00Z")] });
+            write(&data_dir, "tasks.json", tasks);
+            Vec::new()
+        });
+
+        let report = sync_once(dir.path(), &writes, &settings).unwrap();
+        assert_eq!(report.pushed.len(), 1);
+        assert_eq!(titles(&gist.file("tasks.json").unwrap()), ["Tip"]);
+        assert_eq!(titles(&local(dir.path(), "tasks.json")), ["Tips"]);
+
+        let pending = status(dir.path()).pending;
+        assert_eq!(pending.len(), 1);
+        assert_eq!(
+            (pending[0].entity.id.as_str(), pending[0].change),
+            ("t-1", ChangeKind::Updated)
+        );
+        assert!(SyncState::load(dir.path()).local_changed(dir.path()));
+
+        gist.on_patch(Vec::new);
+        sync_once(dir.path(), &writes, &settings).unwrap();
+        assert_eq!(titles(&gist.file("tasks.json").unwrap()), ["Tips"]);
+        assert_eq!(status(dir.path()).pending_count, 0);
+    }
+
+    #[test]
+    fn keeps_local_edits_made_while_merging_an_intervening_push() {
+        let dir = tempfile::tempdir().unwrap();
+        let writes = WriteRegistry::default();
+        let tip = task("t-1", "Tip", "2026-05-01T10:00:00Z");
+        write(dir.path(), "tasks.json", json!({ "tasks": [tip.clone()] }));
+        let gist = GistStandIn::start(&[]);
+        let settings = settings(&gist);
+        sync_once(dir.path(), &writes, &settings).unwrap();
+
+        let tips = task("t-1", "Tips", "2026-05-01T10:05:00Z");
+        write(dir.path(), "tasks.json", json!({ "tasks": [tips.clone()] }));
+        // While the push is in flight another device adds t-2, and t-3 is
+        // added here
+        let (data_dir, mut first) = (dir.path().to_path_buf(), true);
+        gist.on_patch(move || {
+            if !std::mem::take(&mut first) {
+                return Vec::new();
+            }
+            let round = task("t-3", "Round", "2026-05-01T10:06:00Z");
+            write(
+                &data_dir,
+                "tasks.json",
+                json!({ "tasks": [tips.clone(), round] }),
+            );
+            let split = task("t-2", "Split", "2026-05-01T10:06:00Z");
+            vec![("tasks.json", json!({ "tasks": [tip.clone(), split] }))]
+        });
+
+        sync_once(dir.path(), &writes, &settings).unwrap();
+        let sorted = |file: &Value| {
+            let mut titles = titles(file);
+            titles.sort();
+            titles
+        };
+        assert_eq!(
+            sorted(&local(dir.path(), "tasks.json")),
+            ["Round", "Split", "Tips"]
+        );
+        assert_eq!(
+            sorted(&gist.file("tasks.json").unwrap()),
+            ["Round", "Split", "Tips"]
+        );
+        assert_eq!(status(dir.path()).pending_count, 0);
+    }
+}
@@ -0,0 +1,164 @@
+//! Minimal GitHub Gist API client: read a gist (or one of its revisions)
+//! and update its files
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use reqwest::blocking::{Client, RequestBuilder, Response};
+use reqwest::StatusCode;
+use serde::Deserialize;
+use serde_json::{json, Map, Value};
+
+pub const GITHUB_API_URL: &str = "https://api.github.com";
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// One entry of a gist's revision history
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct Revision {
+    /// Commit sha of the revision
+    pub version: String,
+    #[serde(default)]
+    pub committed_at: String,
+}
+
+/// A gist's files as of one revision, with the history up to it (newest
+/// first)
+#[derive(Debug, Clone, Default)]
+pub struct Gist {
+    pub files: HashMap<String, String>,
+    pub history: Vec<Revision>,
+}
+
+impl Gist {
+    /// The revision these files belong to
+    pub fn head(&self) -> Option<String> {
+        self.history.first().map(|r| r.version.clone())
+    }
+
+    /// A file parsed as JSON, or None if the gist does not have it
+    pub fn json(&self, name: &str) -> Result<Option<Value>, String> {
+        self.files
+            .get(name)
+            .map(|content| serde_json::from_str(content))
+            .transpose()
+            .map_err(|e| format!("Failed to parse {} from the gist: {}", name, e))
+    }
+}
+
+#[derive(Deserialize)]
+struct RawGist {
+    #[serde(default)]
+    files: HashMap<String, Option<RawFile>>,
+    #[serde(default)]
+    history: Vec<Revision>,
+}
+
+#[derive(Deserialize)]
+struct RawFile {
+    content: Option<String>,
+    #[serde(default)]
+    truncated: bool,
+    raw_url: Option<String>,
+}
+
+pub struct GistClient {
+    client: Client,
+    api_url: String,
+    token: String,
+}
+
+impl GistClient {
+    pub fn new(api_url: &str, token: &str) -> Self {
+        Self {
+            client: Client::builder()
+                .timeout(REQUEST_TIMEOUT)
+                .user_agent("taskboard-command-center")
+                .build()
+                .unwrap_or_default(),
+            api_url: api_url.trim_end_matches('/').to_string(),
+            token: token.to_string(),
+        }
+    }
+
+    fn send(&self, request: RequestBuilder) -> Result<Response, String> {
+        request
+            .bearer_auth(&self.token)
+            .header("Accept", "application/vnd.github+json")
+            .send()
+            .map_err(|e| format!("Failed to reach the Gist API: {}", e))
+    }
+
+    /// The gist's current files and history
+    pub fn get(&self, gist_id: &str) -> Result<Gist, String> {
+        let url = format!("{}/gists/{}", self.api_url, gist_id);
+        let response = self.send(self.client.get(url))?;
+        self.read(response, gist_id)
+    }
+
+    /// The gist as of `revision`, or None when GitHub no longer has it
+    pub fn revision(&self, gist_id: &str, revision: &str) -> Result<Option<Gist>, String> {
+        let url = format!("{}/gists/{}/{}", self.api_url, gist_id, revision);
+        let response = self.send(self.client.get(url))?;
+        if response.status() == StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        self.read(response, gist_id).map(Some)
+    }
+
+    /// Replace the content of `files`, returning the gist as of the new revision
+    pub fn update(&self, gist_id: &str, files: &[(&str, String)]) -> Result<Gist, String> {
+        let files: Map<String, Value> = files
+            .iter()
+            .map(|(name, content)| (name.to_string(), json!({ "content": content })))
+            .collect();
+        let url = format!("{}/gists/{}", self.api_url, gist_id);
+        let response = self.send(self.client.patch(url).json(&json!({ "files": files })))?;
+        self.read(response, gist_id)
+    }
+
+    fn read(&self, response: Response, gist_id: &str) -> Result<Gist, String> {
+        let status = response.status();
+        if !status.is_success() {
+            let reason = match status {
+                StatusCode::UNAUTHORIZED => " (check gistToken)".to_string(),
+                StatusCode::NOT_FOUND => format!(" (no gist {})", gist_id),
+                _ => String::new(),
+            };
+            return Err(format!("Gist API returned {}{}", status.as_u16(), reason));
+        }
+
+        let raw: RawGist = response
+            .json()
+            .map_err(|e| format!("Failed to parse the Gist API response: {}", e))?;
+        let mut files = HashMap::new();
+        for (name, file) in raw.files {
+            let Some(file) = file else {
+                continue;
+            };
+            // Files over 1 MB come without content and must be fetched whole
+            let content = match (file.content, file.raw_url) {
+                (Some(content), _) if !file.truncated => content,
+                (_, Some(raw_url)) => {
+                    let response = self.send(self.client.get(raw_url))?;
+                    if !response.status().is_success() {
+                        return Err(format!(
+                            "Failed to download {} from the gist: HTTP {}",
+                            name,
+                            response.status().as_u16()
+                        ));
+                    }
+                    response
+                        .text()
+                        .map_err(|e| format!("Failed to download {} from the gist: {}", name, e))?
+                }
+                (content, None) => content.unwrap_or_default(),
+            };
+            files.insert(name, content);
+        }
+        Ok(Gist {
+            files,
+            history: raw.history,
+        })
+    }
+}
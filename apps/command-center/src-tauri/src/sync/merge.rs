@@ -0,0 +1,279 @@
+//! Per-entity three-way merge of a synced data file
+//!
+//! Entities are matched by id against the base (the file as of the last
+//! synced gist revision). A side that left an entity as it was in the base
+//! takes the other side's version, including its deletion. When both sides
+//! changed the same entity, the more recently updated version wins, and
+//! replies and comments from both are kept. Inbox items that either side
+//! marked done (or skipped) stay that way. Without a base (the first sync)
+//! the two files are simply unioned, so an entity one side deleted before
+//! then comes back from the other.
+
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+use chrono::DateTime;
+use serde_json::Value;
+
+/// Child lists merged by id when both sides changed an entity
+const CHILD_LISTS: [&str; 2] = ["replies", "comments"];
+
+/// The merged file, and the ids both sides had changed
+#[derive(Debug, Clone, PartialEq)]
+pub struct Merged {
+    pub file: Value,
+    pub conflicts: Vec<String>,
+}
+
+fn id_of(entity: &Value) -> Option<&str> {
+    entity.get("id").and_then(Value::as_str)
+}
+
+fn entities<'a>(file: Option<&'a Value>, collection: &str) -> &'a [Value] {
+    file.and_then(|f| f.get(collection))
+        .and_then(Value::as_array)
+        .map(Vec::as_slice)
+        .unwrap_or_default()
+}
+
+fn by_id(entities: &[Value]) -> HashMap<&str, &Value> {
+    entities
+        .iter()
+        .filter_map(|e| Some((id_of(e)?, e)))
+        .collect()
+}
+
+/// Merge the `collection` array of `local` and `remote`. Everything else
+/// in the file is kept as it is locally.
+pub fn merge_file(
+    base: Option<&Value>,
+    local: &Value,
+    remote: Option<&Value>,
+    collection: &str,
+) -> Merged {
+    let mut file = local.clone();
+    let Some(remote) = remote else {
+        return Merged {
+            file,
+            conflicts: Vec::new(),
+        };
+    };
+
+    let (items, conflicts) = merge_entities(
+        entities(base, collection),
+        entities(Some(local), collection),
+        entities(Some(remote), collection),
+        collection == "items",
+    );
+    match file.as_object_mut() {
+        Some(object) => {
+            object.insert(collection.to_string(), Value::Array(items));
+        }
+        None => file = serde_json::json!({ collection: items }),
+    }
+    Merged { file, conflicts }
+}
+
+fn merge_entities(
+    base: &[Value],
+    local: &[Value],
+    remote: &[Value],
+    inbox: bool,
+) -> (Vec<Value>, Vec<String>) {
+    let base = by_id(base);
+    let local_ids = by_id(local);
+    let remote_ids = by_id(remote);
+    let mut merged = Vec::new();
+    let mut conflicts = Vec::new();
+
+    for entity in local {
+        let Some(id) = id_of(entity) else {
+            merged.push(entity.clone());
+            continue;
+        };
+        let original = base.get(id).copied();
+        match remote_ids.get(id).copied() {
+            Some(theirs) if theirs == entity || original == Some(theirs) => {
+                merged.push(entity.clone())
+            }
+            Some(theirs) if original == Some(entity) => merged.push(theirs.clone()),
+            Some(theirs) => {
+                conflicts.push(id.to_string());
+                merged.push(resolve(entity, theirs, inbox));
+            }
+            // Deleted remotely: gone, unless it was changed here since
+            None if original.is_some_and(|o| o != entity) => merged.push(entity.clone()),
+            None if original.is_some() => {}
+            None => merged.push(entity.clone()),
+        }
+    }
+
+    // Entities only the remote has go after the entity they follow there
+    let mut previous: Option<&str> = None;
+    for entity in remote {
+        let Some(id) = id_of(entity) else {
+            continue;
+        };
+        let deleted_here = base.get(id).is_some_and(|original| *original == entity);
+        if !local_ids.contains_key(id) && !deleted_here {
+            let at = previous
+                .and_then(|p| merged.iter().position(|e| id_of(e) == Some(p)))
+                .map_or(0, |i| i + 1);
+            merged.insert(at, entity.clone());
+        }
+        previous = Some(id);
+    }
+
+    (merged, conflicts)
+}
+
+/// When the entity was last changed, by whichever timestamp it carries
+fn changed_at(entity: &Value) -> Option<DateTime<chrono::FixedOffset>> {
+    ["updatedAt", "lastUpdated", "processedAt", "createdAt"]
+        .iter()
+        .find_map(|key| entity.get(*key).and_then(Value::as_str))
+        .and_then(|at| DateTime::parse_from_rfc3339(at).ok())
+}
+
+/// Resolve an entity both sides changed
+fn resolve(local: &Value, remote: &Value, inbox: bool) -> Value {
+    let (mut winner, other) = match changed_at(remote).cmp(&changed_at(local)) {
+        Ordering::Greater => (remote.clone(), local),
+        _ => (local.clone(), remote),
+    };
+
+    for list in CHILD_LISTS {
+        let (Some(theirs), Some(ours)) = (
+            other.get(list).and_then(Value::as_array),
+            winner.get_mut(list).and_then(Value::as_array_mut),
+        ) else {
+            continue;
+        };
+        for child in theirs {
+            if !ours.iter().any(|c| c.get("id") == child.get("id")) {
+                ours.push(child.clone());
+            }
+        }
+        ours.sort_by(|a, b| {
+            let at = |c: &Value| {
+                c.get("createdAt")
+                    .and_then(Value::as_str)
+                    .map(str::to_string)
+            };
+            at(a).cmp(&at(b))
+        });
+    }
+
+    if inbox {
+        let rank = |e: &Value| match e.get("status").and_then(Value::as_str) {
+            Some("done") => 2,
+            Some("skipped") => 1,
+            _ => 0,
+        };
+        if rank(other) > rank(&winner) {
+            for key in ["status", "processedAt"] {
+                if let Some(value) = other.get(key) {
+                    winner[key] = value.clone();
+                }
+            }
+        }
+    }
+    winner
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn ids(merged: &Merged, collection: &str) -> Vec<String> {
+        entities(Some(&merged.file), collection)
+            .iter()
+            .filter_map(|e| id_of(e).map(str::to_string))
+            .collect()
+    }
+
+    #[test]
+    fn takes_each_sides_changes_and_deletions() {
+        let task =
+            |id: &str, title: &str, at: &str| json!({ "id": id, "title": title, "updatedAt": at });
+        let base = json!({ "tasks": [
+            task("t-1", "Tip", "2026-05-01T10:00:00Z"),
+            task("t-2", "Split", "2026-05-01T10:00:00Z"),
+            task("t-3", "Round", "2026-05-01T10:00:00Z"),
+            task("t-4", "Share", "2026-05-01T10:00:00Z")
+        ]});
+        // Here: renamed t-1, deleted t-3, added t-5
+        let local = json!({ "lastUpdated": "local", "tasks": [
+            task("t-1", "Tips", "2026-05-02T10:00:00Z"),
+            task("t-2", "Split", "2026-05-01T10:00:00Z"),
+            task("t-4", "Share it", "2026-05-02T09:00:00Z"),
+            task("t-5", "History", "2026-05-02T10:00:00Z")
+        ]});
+        // There: renamed t-2, also deleted t-3, edited t-4 later, added t-6
+        let remote = json!({ "lastUpdated": "remote", "tasks": [
+            task("t-6", "Currency", "2026-05-02T08:00:00Z"),
+            task("t-1", "Tip", "2026-05-01T10:00:00Z"),
+            task("t-2", "Split bill", "2026-05-02T10:00:00Z"),
+            task("t-4", "Share via link", "2026-05-02T11:00:00Z")
+        ]});
+
+        let merged = merge_file(Some(&base), &local, Some(&remote), "tasks");
+        assert_eq!(ids(&merged, "tasks"), ["t-6", "t-1", "t-2", "t-4", "t-5"]);
+        let titles: Vec<&str> = entities(Some(&merged.file), "tasks")
+            .iter()
+            .filter_map(|t| t["title"].as_str())
+            .collect();
+        assert_eq!(
+            titles,
+            [
+                "Currency",
+                "Tips",
+                "Split bill",
+                "Share via link",
+                "History"
+            ]
+        );
+        assert_eq!(merged.conflicts, ["t-4"]);
+        assert_eq!(merged.file["lastUpdated"], "local");
+
+        // A remote deletion of something changed here keeps the local version
+        let gone = json!({ "tasks": [] });
+        let merged = merge_file(Some(&base), &local, Some(&gone), "tasks");
+        assert_eq!(ids(&merged, "tasks"), ["t-1", "t-4", "t-5"]);
+    }
+
+    #[test]
+    fn unions_inbox_items_and_their_replies_without_a_base() {
+        let reply = |id: &str, at: &str| json!({ "id": id, "text": id, "createdAt": at });
+        let local = json!({ "items": [{
+            "id": "i-1", "text": "Ship it", "status": "pending",
+            "createdAt": "2026-05-01T10:00:00Z",
+            "replies": [reply("r-1", "2026-05-01T11:00:00Z"), reply("r-3", "2026-05-01T13:00:00Z")]
+        }]});
+        let remote = json!({ "items": [
+            { "id": "i-2", "text": "From the phone", "status": "pending",
+              "createdAt": "2026-05-02T10:00:00Z", "replies": [] },
+            { "id": "i-1", "text": "Ship it", "status": "done",
+              "createdAt": "2026-05-01T10:00:00Z",
+              "replies": [
+                  reply("r-1", "2026-05-01T11:00:00Z"),
+                  reply("r-2", "2026-05-01T12:00:00Z")
+              ] }
+        ]});
+
+        let merged = merge_file(None, &local, Some(&remote), "items");
+        assert_eq!(ids(&merged, "items"), ["i-2", "i-1"]);
+        let item = &merged.file["items"][1];
+        assert_eq!(item["status"], "done");
+        let replies: Vec<&str> = item["replies"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .filter_map(|r| r["id"].as_str())
+            .collect();
+        assert_eq!(replies, ["r-1", "r-2", "r-3"]);
+
+        assert_eq!(merge_file(None, &local, None, "items").file, local);
+    }
+}
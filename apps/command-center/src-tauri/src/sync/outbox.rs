@@ -0,0 +1,259 @@
+//! Outbox of local changes the gist has not seen yet
+//!
+//! Kept in sync-outbox.json. Each synced entity is compared with the hash
+//! the last sync left for it in sync-state.json, so changes made while
+//! offline, or while the app was closed, are found again on the next
+//! start. Several edits to one entity coalesce into a single entry. When a
+//! sync fails the outbox keeps its entries and the next attempt waits
+//! twice as long as the last, up to `RETRY_MAX`.
+
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+
+use super::{read_local, SYNCED};
+use crate::audit::EntityRef;
+
+const OUTBOX_FILE: &str = "sync-outbox.json";
+
+/// Wait before the first retry of a failed sync
+const RETRY_BASE: Duration = Duration::from_secs(5);
+const RETRY_MAX: Duration = Duration::from_secs(10 * 60);
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum ChangeKind {
+    Created,
+    Updated,
+    Deleted,
+}
+
+/// A local entity that differs from the gist
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct PendingChange {
+    #[serde(flatten)]
+    pub entity: EntityRef,
+    pub change: ChangeKind,
+    /// Edits coalesced into this entry
+    pub edits: u32,
+    pub first_changed_at: String,
+    pub last_changed_at: String,
+    /// Hash of the entity when last queued, to tell a new edit from the same
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hash: Option<String>,
+}
+
+/// Pending changes and retry state, kept in sync-outbox.json
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(default, rename_all = "camelCase")]
+pub struct Outbox {
+    pub changes: Vec<PendingChange>,
+    /// Failed syncs in a row
+    pub attempts: u32,
+    pub last_attempt_at: Option<String>,
+    /// Error of the last sync, when it failed
+    pub last_error: Option<String>,
+    /// When the next automatic sync may run after a failure
+    pub retry_at: Option<String>,
+}
+
+impl Outbox {
+    pub fn load(data_dir: &Path) -> Self {
+        fs::read_to_string(data_dir.join(OUTBOX_FILE))
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self, data_dir: &Path) -> Result<(), String> {
+        let content = serde_json::to_string_pretty(self)
+            .map_err(|e| format!("Failed to serialize sync outbox: {}", e))?;
+        fs::write(data_dir.join(OUTBOX_FILE), content)
+            .map_err(|e| format!("Failed to write {}: {}", OUTBOX_FILE, e))
+    }
+
+    /// Bring the outbox up to date with the local files, given the entity
+    /// hashes of the last sync
+    pub fn queue(
+        &mut self,
+        data_dir: &Path,
+        synced: &HashMap<String, String>,
+    ) -> Result<(), String> {
+        let now = Utc::now().to_rfc3339();
+        let mut seen = HashSet::new();
+        let mut changes = Vec::new();
+
+        for (entity, hash) in entity_hashes(data_dir)? {
+            let key = entity_key(&entity.kind, &entity.id);
+            let change = match synced.get(&key) {
+                Some(synced) if *synced == hash => None,
+                Some(_) => Some(ChangeKind::Updated),
+                None => Some(ChangeKind::Created),
+            };
+            seen.insert(key);
+            if let Some(change) = change {
+                changes.push(self.coalesce(entity, change, Some(hash), &now));
+            }
+        }
+
+        let mut deleted: Vec<&String> = synced.keys().filter(|k| !seen.contains(*k)).collect();
+        deleted.sort();
+        for key in deleted {
+            let Some((kind, id)) = key.split_once(':') else {
+                continue;
+            };
+            let entity = EntityRef {
+                kind: kind.to_string(),
+                id: id.to_string(),
+                project_id: None,
+            };
+            changes.push(self.coalesce(entity, ChangeKind::Deleted, None, &now));
+        }
+
+        self.changes = changes;
+        Ok(())
+    }
+
+    /// The entry for `entity`, folding it into the one already queued
+    fn coalesce(
+        &self,
+        entity: EntityRef,
+        change: ChangeKind,
+        hash: Option<String>,
+        now: &str,
+    ) -> PendingChange {
+        let queued = self
+            .changes
+            .iter()
+            .find(|c| c.entity.kind == entity.kind && c.entity.id == entity.id);
+        match queued {
+            Some(queued) if queued.hash == hash && queued.change == change => queued.clone(),
+            Some(queued) => PendingChange {
+                entity: EntityRef {
+                    project_id: entity.project_id.or(queued.entity.project_id.clone()),
+                    ..entity
+                },
+                // Created then edited is still a creation as far as the gist knows
+                change: match (queued.change, change) {
+                    (ChangeKind::Created, ChangeKind::Updated) => ChangeKind::Created,
+                    _ => change,
+                },
+                edits: queued.edits + 1,
+                first_changed_at: queued.first_changed_at.clone(),
+                last_changed_at: now.to_string(),
+                hash,
+            },
+            None => PendingChange {
+                entity,
+                change,
+                edits: 1,
+                first_changed_at: now.to_string(),
+                last_changed_at: now.to_string(),
+                hash,
+            },
+        }
+    }
+
+    /// Note a failed sync and schedule the next attempt
+    pub fn failed(&mut self, error: &str) {
+        let now = Utc::now();
+        self.attempts += 1;
+        self.last_attempt_at = Some(now.to_rfc3339());
+        self.last_error = Some(error.to_string());
+        let delay = chrono::Duration::from_std(backoff(self.attempts)).unwrap_or_default();
+        self.retry_at = Some((now + delay).to_rfc3339());
+    }
+
+    /// Note a successful sync
+    pub fn succeeded(&mut self) {
+        self.attempts = 0;
+        self.last_attempt_at = Some(Utc::now().to_rfc3339());
+        self.last_error = None;
+        self.retry_at = None;
+    }
+
+    /// How long until the next automatic sync may run, while backing off
+    pub fn retry_in(&self) -> Option<Duration> {
+        let retry_at = DateTime::parse_from_rfc3339(self.retry_at.as_deref()?).ok()?;
+        (retry_at.with_timezone(&Utc) - Utc::now())
+            .to_std()
+            .ok()
+            .filter(|delay| !delay.is_zero())
+    }
+}
+
+/// Wait after `attempts` failed syncs in a row
+pub fn backoff(attempts: u32) -> Duration {
+    let doublings = attempts.saturating_sub(1).min(16);
+    RETRY_BASE.saturating_mul(1 << doublings).min(RETRY_MAX)
+}
+
+pub fn entity_key(kind: &str, id: &str) -> String {
+    format!("{}:{}", kind, id)
+}
+
+/// Every synced entity in the local files, with a hash of its content
+pub fn entity_hashes(data_dir: &Path) -> Result<Vec<(EntityRef, String)>, String> {
+    let files = SYNCED
+        .iter()
+        .map(|(name, collection, _)| read_local(data_dir, name, collection))
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(hash_entities(&files))
+}
+
+/// Every entity in `files`, given in `SYNCED` order, with a hash of its content
+pub fn hash_entities(files: &[Value]) -> Vec<(EntityRef, String)> {
+    let mut hashes = Vec::new();
+    for ((_, collection, kind), file) in SYNCED.into_iter().zip(files) {
+        let entities = file.get(collection).and_then(Value::as_array);
+        for entity in entities.into_iter().flatten() {
+            let Some(id) = entity.get("id").and_then(Value::as_str) else {
+                continue;
+            };
+            let project_id = match kind {
+                "project" => Some(id.to_string()),
+                _ => ["projectId", "project"]
+                    .iter()
+                    .find_map(|key| entity.get(*key).and_then(Value::as_str))
+                    .map(str::to_string),
+            };
+            let entity_ref = EntityRef {
+                kind: kind.to_string(),
+                id: id.to_string(),
+                project_id,
+            };
+            let hash = format!("{:x}", Sha256::digest(entity.to_string()));
+            hashes.push((entity_ref, hash));
+        }
+    }
+    hashes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backs_off_exponentially_up_to_the_cap() {
+        assert_eq!(backoff(1), Duration::from_secs(5));
+        assert_eq!(backoff(2), Duration::from_secs(10));
+        assert_eq!(backoff(4), Duration::from_secs(40));
+        assert_eq!(backoff(8), RETRY_MAX);
+        assert_eq!(backoff(u32::MAX), RETRY_MAX);
+
+        let mut outbox = Outbox::default();
+        outbox.failed("offline");
+        let wait = outbox.retry_in().unwrap();
+        assert!(wait <= backoff(1) && wait > Duration::from_secs(3));
+        outbox.succeeded();
+        assert_eq!(outbox.retry_in(), None);
+        assert_eq!(outbox.last_error, None);
+    }
+}
@@ -0,0 +1,175 @@
+//! Local HTTP stand-in for the Gist API, for tests
+//!
+//! Serves one gist with a revision history: `GET /gists/:id`,
+//! `GET /gists/:id/:sha` and `PATCH /gists/:id`. Every request must carry
+//! the token. Other devices are simulated with `push`, an outage with
+//! `set_down`, and work done while a push is in flight with `on_patch`.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use serde_json::{json, Value};
+
+pub const GIST_ID: &str = "gist-1";
+pub const TOKEN: &str = "test-token";
+
+/// Run on every `PATCH`, before it is committed; returns files another
+/// device pushes first
+type PatchHook = Box<dyn FnMut() -> Vec<(&'static str, Value)> + Send>;
+
+#[derive(Default)]
+struct Store {
+    /// Newest first
+    revisions: Vec<(String, HashMap<String, String>)>,
+    requests: Vec<String>,
+    /// Answer every request with 503
+    down: bool,
+    on_patch: Option<PatchHook>,
+}
+
+impl Store {
+    /// Commit `files` on top of the current revision
+    fn push(&mut self, files: &[(&str, Value)]) {
+        let mut current = self
+            .revisions
+            .first()
+            .map(|(_, f)| f.clone())
+            .unwrap_or_default();
+        for (name, content) in files {
+            current.insert(name.to_string(), content.to_string());
+        }
+        self.commit(current);
+    }
+
+    fn commit(&mut self, files: HashMap<String, String>) {
+        let sha = format!("rev-{}", self.revisions.len() + 1);
+        self.revisions.insert(0, (sha, files));
+    }
+
+    fn render(&self, index: usize) -> Value {
+        let files: serde_json::Map<String, Value> = self.revisions[index]
+            .1
+            .iter()
+            .map(|(name, content)| {
+                (
+                    name.clone(),
+                    json!({ "filename": name, "content": content, "truncated": false }),
+                )
+            })
+            .collect();
+        let history: Vec<Value> = self.revisions[index..]
+            .iter()
+            .map(|(sha, _)| json!({ "version": sha, "committed_at": "2026-05-01T10:00:00Z" }))
+            .collect();
+        json!({ "id": GIST_ID, "files": files, "history": history })
+    }
+}
+
+pub struct GistStandIn {
+    server: Arc<tiny_http::Server>,
+    store: Arc<Mutex<Store>>,
+}
+
+impl GistStandIn {
+    /// A gist whose first revision holds `files`
+    pub fn start(files: &[(&str, Value)]) -> Self {
+        let server = Arc::new(tiny_http::Server::http("127.0.0.1:0").unwrap());
+        let store = Arc::new(Mutex::new(Store::default()));
+        let stand_in = Self { server, store };
+        stand_in.push(files);
+
+        let (srv, store) = (stand_in.server.clone(), stand_in.store.clone());
+        std::thread::spawn(move || {
+            for mut request in srv.incoming_requests() {
+                let mut body = String::new();
+                let _ = request.as_reader().read_to_string(&mut body);
+                let authorized = request.headers().iter().any(|h| {
+                    h.field.equiv("Authorization") && h.value == *format!("Bearer {}", TOKEN)
+                });
+
+                let mut store = store.lock().unwrap();
+                let method = request.method().to_string();
+                store.requests.push(format!("{} {}", method, request.url()));
+                let path: Vec<&str> = request.url().trim_matches('/').split('/').collect();
+
+                let (status, response) = match (method.as_str(), path.as_slice()) {
+                    _ if store.down => (503, json!({ "message": "Service Unavailable" })),
+                    _ if !authorized => (401, json!({ "message": "Bad credentials" })),
+                    ("GET", ["gists", GIST_ID]) => (200, store.render(0)),
+                    ("GET", ["gists", GIST_ID, sha]) => {
+                        match store.revisions.iter().position(|(s, _)| s == sha) {
+                            Some(index) => (200, store.render(index)),
+                            None => (404, json!({ "message": "Not Found" })),
+                        }
+                    }
+                    ("PATCH", ["gists", GIST_ID]) => {
+                        if let Some(hook) = store.on_patch.as_mut() {
+                            let pushed = hook();
+                            if !pushed.is_empty() {
+                                store.push(&pushed);
+                            }
+                        }
+                        let update: Value = serde_json::from_str(&body).unwrap_or_default();
+                        let mut files = store.revisions[0].1.clone();
+                        for (name, file) in update["files"].as_object().into_iter().flatten() {
+                            match file["content"].as_str() {
+                                Some(content) => files.insert(name.clone(), content.to_string()),
+                                None => files.remove(name),
+                            };
+                        }
+                        store.commit(files);
+                        (200, store.render(0))
+                    }
+                    _ => (404, json!({ "message": "Not Found" })),
+                };
+
+                let header = "Content-Type: application/json".parse::<tiny_http::Header>();
+                let response = tiny_http::Response::from_string(response.to_string())
+                    .with_status_code(status)
+                    .with_header(header.unwrap());
+                let _ = request.respond(response);
+            }
+        });
+
+        stand_in
+    }
+
+    pub fn api_url(&self) -> String {
+        format!("http://{}", self.server.server_addr())
+    }
+
+    /// Commit a new revision, as another device would
+    pub fn push(&self, files: &[(&str, Value)]) {
+        self.store.lock().unwrap().push(files);
+    }
+
+    /// A file of the current revision
+    pub fn file(&self, name: &str) -> Option<Value> {
+        let store = self.store.lock().unwrap();
+        let content = store.revisions.first()?.1.get(name)?;
+        serde_json::from_str(content).ok()
+    }
+
+    pub fn revisions(&self) -> usize {
+        self.store.lock().unwrap().revisions.len()
+    }
+
+    pub fn set_down(&self, down: bool) {
+        self.store.lock().unwrap().down = down;
+    }
+
+    pub fn on_patch(&self, hook: impl FnMut() -> Vec<(&'static str, Value)> + Send + 'static) {
+        self.store.lock().unwrap().on_patch = Some(Box::new(hook));
+    }
+
+    /// "METHOD /path" of every request so far
+    pub fn requests(&self) -> Vec<String> {
+        self.store.lock().unwrap().requests.clone()
+    }
+}
+
+impl Drop for GistStandIn {
+    fn drop(&mut self) {
+        self.server.unblock();
+    }
+}
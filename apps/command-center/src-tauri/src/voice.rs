@@ -1,225 +1,554 @@
 //! Voice capture using Whisper.cpp
 //!
-//! Provides local speech-to-text for Quick Capture feature.
-//! Uses whisper.cpp for accurate, offline transcription.
-
-use std::path::PathBuf;
-use std::process::Command;
-use tauri::AppHandle;
-
-/// Whisper model sizes
-#[derive(Debug, Clone, Copy)]
-pub enum WhisperModel {
-    Tiny,   // ~75MB, fastest, less accurate
-    Base,   // ~142MB, good balance
-    Small,  // ~466MB, better accuracy
-    Medium, // ~1.5GB, high accuracy
-}
-
-impl WhisperModel {
-    pub fn filename(&self) -> &str {
-        match self {
-            WhisperModel::Tiny => "ggml-tiny.bin",
-            WhisperModel::Base => "ggml-base.bin",
-            WhisperModel::Small => "ggml-small.bin",
-            WhisperModel::Medium => "ggml-medium.bin",
-        }
-    }
-
-    pub fn download_url(&self) -> &str {
-        match self {
-            WhisperModel::Tiny => "https://huggingface.co/ggerganov/whisper.cpp/resolve/main/ggml-tiny.bin",
-            WhisperModel::Base => "https://huggingface.co/ggerganov/whisper.cpp/resolve/main/ggml-base.bin",
-            WhisperModel::Small => "https://huggingface.co/ggerganov/whisper.cpp/resolve/main/ggml-small.bin",
-            WhisperModel::Medium => "https://huggingface.co/ggerganov/whisper.cpp/resolve/main/ggml-medium.bin",
-        }
-    }
+//! Provides speech-to-text for Quick Capture feature. Audio is recorded
+//! natively (cpal) and ends on silence or on push-to-talk release.
+//! Uses whisper.cpp for accurate, offline transcription: in-process via
+//! whisper-rs when built with the `whisper` feature, otherwise (or if that
+//! fails) by running the whisper CLI. Groq or another OpenAI-compatible
+//! API can be configured instead, with local Whisper as the offline
+//! fallback.
+
+mod archive;
+mod audio;
+mod capture;
+mod decode;
+mod drop_folder;
+mod engine;
+mod intent;
+mod local;
+mod models;
+mod openai;
+mod transcriber;
+mod stream;
+mod transcript;
+mod vad;
+
+pub use archive::{Archive, SessionRecord};
+pub use capture::Capture;
+pub use decode::is_audio_file;
+pub use drop_folder::{DropQueue, DROP_DIR};
+pub use engine::EngineCache;
+pub use intent::{IntentExtractor, RuleExtractor};
+pub use models::{ModelEntry, ModelManager};
+pub use stream::{SessionRegistry, VoiceSession};
+pub use transcriber::TranscriberChain;
+pub use transcript::Transcript;
+
+use archive::TranscriptInfo;
+use capture::{CaptureOptions, DeviceSource, InputSource, Recording, WavSource};
+use intent::IntentContext;
+use stream::VoiceEvent;
+
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager, State};
+
+use crate::audit::{self, Action, Actor};
+use crate::config::{self, AppConfig, VoiceSettings};
+use crate::data::{self, InboxItem};
+use crate::watcher::{WriteOrigin, WriteRegistry};
+use crate::AppState;
+
+/// Audio per incremental transcription while streaming
+const STREAM_CHUNK: Duration = Duration::from_secs(4);
+
+/// Payload of `voice-error`: a failure after the capture itself succeeded
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+struct VoiceErrorEvent {
+    session_id: String,
+    message: String,
 }
 
 /// Voice configuration
+#[derive(Clone)]
 pub struct VoiceConfig {
-    pub model: WhisperModel,
+    /// Model id from the catalog, e.g. "base.en"
+    pub model: String,
     pub models_dir: PathBuf,
     pub whisper_path: PathBuf,
+    /// Spoken language code passed to Whisper
+    pub language: String,
 }
 
 impl Default for VoiceConfig {
     fn default() -> Self {
+        Self::from_settings(&VoiceSettings::default())
+    }
+}
+
+impl VoiceConfig {
+    /// The model and language chosen in config.json
+    pub fn from_settings(settings: &VoiceSettings) -> Self {
         let home = dirs::home_dir().unwrap_or_default();
         Self {
-            model: WhisperModel::Base,
+            model: settings.model.clone(),
             models_dir: home.join(".taskboard").join("models"),
             whisper_path: home.join(".taskboard").join("bin").join("whisper"),
+            language: settings.language.clone(),
         }
     }
-}
 
-/// Check if Whisper is installed and model exists
-pub fn is_voice_available(config: &VoiceConfig) -> bool {
-    let model_path = config.models_dir.join(config.model.filename());
-    config.whisper_path.exists() && model_path.exists()
-}
-
-/// Record audio from microphone (uses system audio tools)
-/// Returns path to temporary WAV file
-pub async fn record_audio(duration_secs: u32) -> Result<PathBuf, String> {
-    let temp_path = std::env::temp_dir().join("taskboard_voice.wav");
-
-    #[cfg(target_os = "windows")]
-    {
-        // Use PowerShell to record audio on Windows
-        let script = format!(
-            r#"
-            Add-Type -AssemblyName System.Speech
-            $recognizer = New-Object System.Speech.Recognition.SpeechRecognitionEngine
-            $recognizer.SetInputToDefaultAudioDevice()
-            # Record for {} seconds
-            Start-Sleep -Seconds {}
-            "#,
-            duration_secs, duration_secs
-        );
+    pub fn model_path(&self) -> PathBuf {
+        self.models_dir.join(models::file_name(&self.model))
+    }
 
-        // For Windows, we'll use a different approach with NAudio or similar
-        // This is a placeholder - actual implementation would use proper audio recording
-        return Err("Windows audio recording requires additional setup".to_string());
+    pub fn models(&self) -> ModelManager {
+        ModelManager::new(self.models_dir.clone())
     }
+}
 
-    #[cfg(target_os = "macos")]
-    {
-        // Use sox on macOS
-        let output = Command::new("sox")
-            .args([
-                "-d",  // default audio device
-                "-r", "16000",  // 16kHz sample rate (Whisper requirement)
-                "-c", "1",  // mono
-                "-b", "16",  // 16-bit
-                temp_path.to_str().unwrap(),
-                "trim", "0", &duration_secs.to_string(),
-            ])
-            .output()
-            .map_err(|e| format!("Failed to record: {}", e))?;
-
-        if !output.status.success() {
-            return Err(String::from_utf8_lossy(&output.stderr).to_string());
-        }
+/// The microphone, or a WAV file named by TASKBOARD_VOICE_INPUT (for
+/// exercising voice capture on machines without one)
+fn input_source() -> Box<dyn InputSource> {
+    match std::env::var_os("TASKBOARD_VOICE_INPUT") {
+        Some(path) => Box::new(WavSource {
+            path: path.into(),
+            realtime: true,
+        }),
+        None => Box::new(DeviceSource),
     }
+}
 
-    #[cfg(target_os = "linux")]
-    {
-        // Use arecord on Linux
-        let output = Command::new("arecord")
-            .args([
-                "-f", "S16_LE",
-                "-r", "16000",
-                "-c", "1",
-                "-d", &duration_secs.to_string(),
-                temp_path.to_str().unwrap(),
-            ])
-            .output()
-            .map_err(|e| format!("Failed to record: {}", e))?;
-
-        if !output.status.success() {
-            return Err(String::from_utf8_lossy(&output.stderr).to_string());
+/// Record from the microphone on a background thread, then write the
+/// 16 kHz mono result to a temporary WAV, named for the session, for the
+/// transcriber
+async fn finish_capture(
+    session_id: &str,
+    capture: Capture,
+    stop: bool,
+) -> Result<(PathBuf, Recording), String> {
+    let recording = tokio::task::spawn_blocking(move || {
+        if stop {
+            capture.stop()
+        } else {
+            capture.wait()
         }
-    }
+    })
+    .await
+    .map_err(|e| format!("Audio capture task failed: {}", e))??;
 
-    Ok(temp_path)
+    let temp_path = std::env::temp_dir().join(format!("taskboard-{}.wav", session_id));
+    audio::write_wav_16k_mono(&temp_path, &recording.samples)?;
+    Ok((temp_path, recording))
 }
 
-/// Transcribe audio file using Whisper.cpp
-pub async fn transcribe(audio_path: &PathBuf, config: &VoiceConfig) -> Result<String, String> {
-    let model_path = config.models_dir.join(config.model.filename());
+/// Transcribe with the configured backend, off the async runtime
+pub async fn transcribe(
+    audio_path: &Path,
+    config: &AppConfig,
+    voice: &VoiceConfig,
+    engines: &EngineCache,
+) -> Result<Transcript, String> {
+    let chain = TranscriberChain::from_config(config, voice, engines);
+    let audio_path = audio_path.to_path_buf();
+
+    tokio::task::spawn_blocking(move || chain.transcribe(&audio_path))
+        .await
+        .map_err(|e| format!("Transcription task failed: {}", e))?
+}
 
-    if !model_path.exists() {
-        return Err(format!(
-            "Whisper model not found. Download from: {}",
-            config.model.download_url()
-        ));
-    }
+/// Decode an audio file (WAV, MP3, Ogg, M4A, ...) and transcribe it with
+/// the configured backend. Blocks.
+fn transcribe_audio_file(
+    path: &Path,
+    config: &AppConfig,
+    voice: &VoiceConfig,
+    engines: &EngineCache,
+) -> Result<Transcript, String> {
+    let samples = decode::decode_16k_mono(path)?;
+    let wav = std::env::temp_dir().join(format!("{}.wav", data::generate_id("taskboard_import")));
+    audio::write_wav_16k_mono(&wav, &samples)?;
+
+    let result = TranscriberChain::from_config(config, voice, engines).transcribe(&wav);
+    let _ = std::fs::remove_file(&wav);
+    result
+}
 
-    let output = Command::new(&config.whisper_path)
-        .args([
-            "-m", model_path.to_str().unwrap(),
-            "-f", audio_path.to_str().unwrap(),
-            "-otxt",  // output as text
-            "--no-timestamps",
-            "-l", "en",  // English
-        ])
-        .output()
-        .map_err(|e| format!("Failed to run Whisper: {}", e))?;
-
-    if !output.status.success() {
-        return Err(String::from_utf8_lossy(&output.stderr).to_string());
-    }
+/// Download a model on a blocking thread, reporting `voice-model-progress`
+async fn download_model(app: AppHandle, voice: &VoiceConfig, model: String) -> Result<(), String> {
+    let manager = voice.models();
+
+    tokio::task::spawn_blocking(move || {
+        manager.download(&model, &|progress| {
+            let _ = app.emit("voice-model-progress", &progress);
+        })
+    })
+    .await
+    .map_err(|e| format!("Model download task failed: {}", e))?
+    .map(|_| ())
+}
 
-    let transcript = String::from_utf8_lossy(&output.stdout)
-        .trim()
-        .to_string();
+/// The model behind a transcript, going by the backend that made it
+fn transcript_model(transcript: &Transcript, config: &AppConfig, voice: &VoiceConfig) -> String {
+    match transcript.backend.as_str() {
+        "groq" => config.integrations.groq.model.clone(),
+        "openai-compatible" => config.integrations.openai_compatible.model.clone(),
+        _ => voice.model.clone(),
+    }
+}
 
-    Ok(transcript)
+/// Keep a capture in the voice archive if `voice.keepRecordings` is on,
+/// reporting a failure as a `voice-error` event
+fn keep_recording(
+    app: &AppHandle,
+    data_dir: &Path,
+    session_id: &str,
+    recording: &Recording,
+    transcript: &Transcript,
+    config: &AppConfig,
+    voice: &VoiceConfig,
+) {
+    if !config.voice.keep_recordings {
+        return;
+    }
+    let info = TranscriptInfo {
+        model: &transcript_model(transcript, config, voice),
+        language: &voice.language,
+    };
+    let saved = Archive::new(data_dir).save(
+        session_id,
+        &recording.samples,
+        Some(recording.stopped_by),
+        transcript,
+        info,
+    );
+    if let Err(e) = saved {
+        let event = VoiceErrorEvent {
+            session_id: session_id.to_string(),
+            message: format!("Failed to keep the recording: {}", e),
+        };
+        let _ = app.emit("voice-error", event);
+    }
 }
 
-/// Download Whisper model if not present
-pub async fn download_model(config: &VoiceConfig) -> Result<(), String> {
-    let model_path = config.models_dir.join(config.model.filename());
+/// The extractor used for transcripts filed from voice capture
+fn intent_extractor() -> Box<dyn IntentExtractor> {
+    Box::new(RuleExtractor)
+}
 
-    if model_path.exists() {
-        return Ok(());
+/// Work out what a transcript asks for and add it to the inbox as a
+/// pending item, with the transcript (and the audio it came from) attached
+fn file_transcript(
+    data_dir: &Path,
+    writes: &WriteRegistry,
+    transcript: &Transcript,
+    audio: Option<&Path>,
+) -> Result<InboxItem, String> {
+    let projects = data::load_projects(data_dir)?;
+    let tasks = data::load_tasks(data_dir)?;
+    let tz = config::load_config(data_dir).user.tz();
+    let context = IntentContext {
+        projects: &projects,
+        tasks: &tasks,
+        today: chrono::Utc::now().with_timezone(&tz).date_naive(),
+    };
+    let intent = intent_extractor().extract(&transcript.text, &context)?;
+
+    let mut item = intent::to_inbox_item(&intent, transcript);
+    if let Some(audio) = audio {
+        item.extra.insert(
+            "audioFile".into(),
+            serde_json::Value::String(audio.to_string_lossy().to_string()),
+        );
     }
 
-    // Create models directory
-    std::fs::create_dir_all(&config.models_dir)
-        .map_err(|e| format!("Failed to create models dir: {}", e))?;
+    let action = Action::new(Actor::User, "file_voice_transcript")
+        .entity("inboxItem", &item.id, item.project.as_deref());
+    let saved = data::load_inbox(data_dir).and_then(|mut inbox| {
+        inbox.items.insert(0, item.clone());
+        data::save_inbox(data_dir, writes, &mut inbox, WriteOrigin::Backend)
+    });
+    audit::record(data_dir, action, saved)?;
 
-    // Download using curl or reqwest
-    println!("Downloading Whisper model from: {}", config.model.download_url());
+    Ok(item)
+}
 
-    let output = Command::new("curl")
-        .args([
-            "-L",
-            "-o", model_path.to_str().unwrap(),
-            config.model.download_url(),
-        ])
-        .output()
-        .map_err(|e| format!("Failed to download model: {}", e))?;
+/// Queue a file that appeared in the voice-drop folder. Each is transcribed
+/// and filed in turn, reporting `voice-drop-processed`.
+pub fn queue_dropped_file(app: &AppHandle, path: PathBuf) {
+    let handle = app.clone();
+
+    app.state::<DropQueue>().push(path, move |path| {
+        let (data_dir, writes, engines) = {
+            let state = handle.state::<Mutex<AppState>>();
+            let state = state.lock().unwrap();
+            (state.data_dir.clone(), state.writes.clone(), state.voice.clone())
+        };
+        let config = config::load_config(&data_dir);
+        let voice = VoiceConfig::from_settings(&config.voice);
+
+        let result = drop_folder::process(
+            path,
+            &|audio| transcribe_audio_file(audio, &config, &voice, &engines),
+            &|transcript, audio| file_transcript(&data_dir, &writes, transcript, Some(audio)),
+        );
+        let _ = handle.emit("voice-drop-processed", &result);
+    });
+}
 
-    if !output.status.success() {
-        return Err(String::from_utf8_lossy(&output.stderr).to_string());
-    }
+// Tauri commands
 
-    Ok(())
+/// The app config, its voice settings and the warm model cache
+fn voice_context(state: &State<'_, Mutex<AppState>>) -> (AppConfig, VoiceConfig, EngineCache) {
+    let state = state.lock().unwrap();
+    let config = config::load_config(&state.data_dir);
+    let voice = VoiceConfig::from_settings(&config.voice);
+    (config, voice, state.voice.clone())
 }
 
-// Tauri commands
+fn voice_archive(state: &State<'_, Mutex<AppState>>) -> Archive {
+    Archive::new(&state.lock().unwrap().data_dir)
+}
 
 #[tauri::command]
-pub async fn check_voice_available() -> bool {
-    let config = VoiceConfig::default();
-    is_voice_available(&config)
+pub async fn check_voice_available(state: State<'_, Mutex<AppState>>) -> Result<bool, String> {
+    let (config, voice, engines) = voice_context(&state);
+    let chain = TranscriberChain::from_config(&config, &voice, &engines);
+    Ok(chain.is_available())
 }
 
+/// Record until the speaker goes quiet (at most `duration_secs`), then transcribe
 #[tauri::command]
-pub async fn voice_capture(duration_secs: u32) -> Result<String, String> {
-    let config = VoiceConfig::default();
-
-    // Record audio
-    let audio_path = record_audio(duration_secs).await?;
+pub async fn voice_capture(
+    duration_secs: u32,
+    app: AppHandle,
+    state: State<'_, Mutex<AppState>>,
+) -> Result<Transcript, String> {
+    let (config, voice, engines) = voice_context(&state);
+    let data_dir = state.lock().unwrap().data_dir.clone();
+    let session_id = data::generate_id("voice");
+
+    let options = CaptureOptions {
+        max_duration: Duration::from_secs(duration_secs.into()),
+        ..CaptureOptions::default()
+    };
+    let capture = Capture::start(input_source(), options);
+    let (audio_path, recording) = finish_capture(&session_id, capture, false).await?;
 
     // Transcribe
-    let transcript = transcribe(&audio_path, &config).await?;
+    let result = transcribe(&audio_path, &config, &voice, &engines).await;
+    if let Ok(transcript) = &result {
+        let (config, voice) = (&config, &voice);
+        keep_recording(&app, &data_dir, &session_id, &recording, transcript, config, voice);
+    }
 
     // Cleanup temp file
     let _ = std::fs::remove_file(&audio_path);
 
-    Ok(transcript)
+    result
+}
+
+/// Push-to-talk: start a streaming capture and return its session id.
+/// Text arrives as `voice-partial` events while recording and the whole
+/// transcript as `voice-final`. With `auto_stop` the capture also ends on
+/// trailing silence. Several captures may run at once.
+#[tauri::command]
+pub fn start_voice_capture(
+    auto_stop: bool,
+    app: AppHandle,
+    state: State<'_, Mutex<AppState>>,
+) -> Result<String, String> {
+    let (config, voice, engines) = voice_context(&state);
+    let mut state = state.lock().unwrap();
+    let id = data::generate_id("voice");
+
+    let options = CaptureOptions {
+        vad: auto_stop.then(Default::default),
+        ..CaptureOptions::default()
+    };
+    let capture = Capture::start(input_source(), options);
+    let chain = TranscriberChain::from_config(&config, &voice, &engines);
+    let keep = config.voice.keep_recordings.then(|| {
+        let (app, data_dir, id) = (app.clone(), state.data_dir.clone(), id.clone());
+        Box::new(move |recording: &Recording, transcript: &Transcript| {
+            keep_recording(&app, &data_dir, &id, recording, transcript, &config, &voice)
+        }) as stream::KeepRecording
+    });
+
+    let session = VoiceSession::start(
+        id.clone(),
+        capture,
+        chain,
+        STREAM_CHUNK,
+        Box::new(move |event| {
+            let _ = match event {
+                VoiceEvent::Partial(partial) => app.emit("voice-partial", &partial),
+                VoiceEvent::Final(done) => app.emit("voice-final", &done),
+            };
+        }),
+        keep,
+    );
+    state.voice_sessions.insert(session);
+    Ok(id)
+}
+
+/// Push-to-talk: stop recording and wait for the final transcript. Stops
+/// the latest capture when no session id is given.
+#[tauri::command]
+pub async fn stop_voice_capture(
+    session_id: Option<String>,
+    state: State<'_, Mutex<AppState>>,
+) -> Result<Transcript, String> {
+    let session = state
+        .lock()
+        .unwrap()
+        .voice_sessions
+        .take(session_id.as_deref())
+        .ok_or("Not recording")?;
+
+    tokio::task::spawn_blocking(move || session.finish())
+        .await
+        .map_err(|e| format!("Voice session failed: {}", e))?
 }
 
+/// Stop recording and discard it; `voice-final` reports it as cancelled
 #[tauri::command]
-pub async fn setup_voice() -> Result<String, String> {
-    let config = VoiceConfig::default();
+pub fn cancel_voice_capture(
+    session_id: String,
+    state: State<'_, Mutex<AppState>>,
+) -> Result<(), String> {
+    let mut state = state.lock().unwrap();
+
+    match state.voice_sessions.take(Some(&session_id)) {
+        Some(session) => {
+            session.cancel();
+            Ok(())
+        }
+        None => Err(format!("No voice session {}", session_id)),
+    }
+}
 
-    // Download model if needed
-    download_model(&config).await?;
+/// Download the selected model if it is missing
+#[tauri::command]
+pub async fn setup_voice(
+    app: AppHandle,
+    state: State<'_, Mutex<AppState>>,
+) -> Result<String, String> {
+    let (_, voice, _) = voice_context(&state);
+    download_model(app, &voice, voice.model.clone()).await?;
 
     Ok("Voice setup complete".to_string())
 }
+
+/// Installed and downloadable Whisper models
+#[tauri::command]
+pub fn list_voice_models(state: State<'_, Mutex<AppState>>) -> Result<Vec<ModelEntry>, String> {
+    let (_, voice, _) = voice_context(&state);
+    Ok(voice.models().list(&voice.model))
+}
+
+/// Download (or resume downloading) a model; progress arrives as
+/// `voice-model-progress` events
+#[tauri::command]
+pub async fn download_voice_model(
+    model_id: String,
+    app: AppHandle,
+    state: State<'_, Mutex<AppState>>,
+) -> Result<(), String> {
+    let (_, voice, _) = voice_context(&state);
+    download_model(app, &voice, model_id).await
+}
+
+/// Remove a downloaded model, unloading it if it is the one in use
+#[tauri::command]
+pub fn delete_voice_model(
+    model_id: String,
+    state: State<'_, Mutex<AppState>>,
+) -> Result<(), String> {
+    let (_, voice, engines) = voice_context(&state);
+    if model_id == voice.model {
+        engines.clear();
+    }
+    voice.models().delete(&model_id)
+}
+
+/// Choose the model and language used for local transcription
+#[tauri::command]
+pub fn set_voice_model(
+    model_id: String,
+    language: String,
+    state: State<'_, Mutex<AppState>>,
+) -> Result<(), String> {
+    let state = state.lock().unwrap();
+    let settings = VoiceSettings {
+        model: model_id,
+        language,
+        ..config::load_config(&state.data_dir).voice
+    };
+    config::save_voice_settings(&state.data_dir, &state.writes, &settings)
+}
+
+/// File a transcript as a pending inbox item. Its kind, project, task,
+/// priority and due date are worked out from what was said, and the
+/// transcript itself is attached to the item.
+#[tauri::command]
+pub fn file_voice_transcript(
+    transcript: Transcript,
+    state: State<'_, Mutex<AppState>>,
+) -> Result<InboxItem, String> {
+    let (data_dir, writes) = {
+        let state = state.lock().unwrap();
+        (state.data_dir.clone(), state.writes.clone())
+    };
+    file_transcript(&data_dir, &writes, &transcript, None)
+}
+
+/// Transcribe an audio file (WAV, MP3, Ogg, M4A) with the configured backend
+#[tauri::command]
+pub async fn transcribe_file(
+    path: String,
+    state: State<'_, Mutex<AppState>>,
+) -> Result<Transcript, String> {
+    let (config, voice, engines) = voice_context(&state);
+    let path = PathBuf::from(path);
+
+    tokio::task::spawn_blocking(move || transcribe_audio_file(&path, &config, &voice, &engines))
+        .await
+        .map_err(|e| format!("Transcription task failed: {}", e))?
+}
+
+/// Kept voice sessions, newest first
+#[tauri::command]
+pub fn list_voice_sessions(state: State<'_, Mutex<AppState>>) -> Vec<SessionRecord> {
+    voice_archive(&state).list()
+}
+
+/// Path of a kept session's audio, for playback
+#[tauri::command]
+pub fn get_voice_session_audio(
+    session_id: String,
+    state: State<'_, Mutex<AppState>>,
+) -> Result<String, String> {
+    let path = voice_archive(&state).audio(&session_id)?;
+    Ok(path.to_string_lossy().to_string())
+}
+
+/// Transcribe a kept session again with the current backend and model
+#[tauri::command]
+pub async fn retranscribe_voice_session(
+    session_id: String,
+    state: State<'_, Mutex<AppState>>,
+) -> Result<SessionRecord, String> {
+    let (config, voice, engines) = voice_context(&state);
+    let archive = voice_archive(&state);
+    let audio_path = archive.audio(&session_id)?;
+
+    let transcript = transcribe(&audio_path, &config, &voice, &engines).await?;
+    let info = TranscriptInfo {
+        model: &transcript_model(&transcript, &config, &voice),
+        language: &voice.language,
+    };
+    archive.update_transcript(&session_id, &transcript, info)
+}
+
+/// Delete a kept session's audio and transcript
+#[tauri::command]
+pub fn delete_voice_session(
+    session_id: String,
+    state: State<'_, Mutex<AppState>>,
+) -> Result<(), String> {
+    voice_archive(&state).delete(&session_id)
+}
@@ -0,0 +1,249 @@
+//! Archive of past voice sessions
+//!
+//! With `voice.keepRecordings` on, each capture's audio is kept in the
+//! data directory's `voice/` folder as `<session id>.wav`, next to
+//! `<session id>.json` holding the transcript and how it was made. Sessions
+//! can be listed, replayed, transcribed again (with a different model, say)
+//! and deleted.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use super::audio::{self, WHISPER_SAMPLE_RATE};
+use super::capture::StopReason;
+use super::Transcript;
+
+/// Folder in the data directory holding kept sessions
+pub const ARCHIVE_DIR: &str = "voice";
+
+/// A kept voice session
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionRecord {
+    pub id: String,
+    pub created_at: String,
+    pub duration_ms: u64,
+    /// Whisper model or API model that produced the transcript
+    pub model: String,
+    pub language: String,
+    pub stopped_by: Option<StopReason>,
+    pub transcript: Transcript,
+    /// Set when the session was transcribed again
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retranscribed_at: Option<String>,
+}
+
+/// How a transcript was produced
+pub struct TranscriptInfo<'a> {
+    pub model: &'a str,
+    pub language: &'a str,
+}
+
+pub struct Archive {
+    dir: PathBuf,
+}
+
+impl Archive {
+    pub fn new(data_dir: &Path) -> Self {
+        Self {
+            dir: data_dir.join(ARCHIVE_DIR),
+        }
+    }
+
+    /// Keep a session's 16 kHz mono audio and transcript
+    pub fn save(
+        &self,
+        id: &str,
+        samples: &[f32],
+        stopped_by: Option<StopReason>,
+        transcript: &Transcript,
+        info: TranscriptInfo,
+    ) -> Result<SessionRecord, String> {
+        fs::create_dir_all(&self.dir)
+            .map_err(|e| format!("Failed to create voice archive: {}", e))?;
+        audio::write_wav_16k_mono(&self.audio_path(id), samples)?;
+
+        let record = SessionRecord {
+            id: id.to_string(),
+            created_at: chrono::Utc::now().to_rfc3339(),
+            duration_ms: samples.len() as u64 * 1000 / WHISPER_SAMPLE_RATE as u64,
+            model: info.model.to_string(),
+            language: info.language.to_string(),
+            stopped_by,
+            transcript: transcript.clone(),
+            retranscribed_at: None,
+        };
+        self.write_record(&record)?;
+        Ok(record)
+    }
+
+    /// Kept sessions, newest first
+    pub fn list(&self) -> Vec<SessionRecord> {
+        let mut records: Vec<SessionRecord> = fs::read_dir(&self.dir)
+            .into_iter()
+            .flatten()
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().is_some_and(|e| e == "json"))
+            .filter_map(|path| fs::read_to_string(path).ok())
+            .filter_map(|content| serde_json::from_str(&content).ok())
+            .collect();
+        records.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+        records
+    }
+
+    pub fn get(&self, id: &str) -> Result<SessionRecord, String> {
+        let content = fs::read_to_string(self.record_path(id)?)
+            .map_err(|_| format!("Voice session not found: {}", id))?;
+        serde_json::from_str(&content)
+            .map_err(|e| format!("Failed to parse voice session {}: {}", id, e))
+    }
+
+    /// The session's WAV file, for playback or transcribing again
+    pub fn audio(&self, id: &str) -> Result<PathBuf, String> {
+        self.get(id)?;
+        let path = self.audio_path(id);
+        if !path.is_file() {
+            return Err(format!("Audio for voice session {} is missing", id));
+        }
+        Ok(path)
+    }
+
+    /// Replace the session's transcript with a new one
+    pub fn update_transcript(
+        &self,
+        id: &str,
+        transcript: &Transcript,
+        info: TranscriptInfo,
+    ) -> Result<SessionRecord, String> {
+        let mut record = self.get(id)?;
+        record.transcript = transcript.clone();
+        record.model = info.model.to_string();
+        record.language = info.language.to_string();
+        record.retranscribed_at = Some(chrono::Utc::now().to_rfc3339());
+        self.write_record(&record)?;
+        Ok(record)
+    }
+
+    pub fn delete(&self, id: &str) -> Result<(), String> {
+        let record = self.record_path(id)?;
+        if !record.exists() {
+            return Err(format!("Voice session not found: {}", id));
+        }
+
+        for path in [record, self.audio_path(id)] {
+            if path.exists() {
+                fs::remove_file(&path)
+                    .map_err(|e| format!("Failed to delete voice session: {}", e))?;
+            }
+        }
+        Ok(())
+    }
+
+    fn write_record(&self, record: &SessionRecord) -> Result<(), String> {
+        let content = serde_json::to_string_pretty(record)
+            .map_err(|e| format!("Failed to serialize voice session: {}", e))?;
+        fs::write(self.record_path(&record.id)?, content)
+            .map_err(|e| format!("Failed to write voice session: {}", e))
+    }
+
+    /// Ids come from the frontend; keep them inside the archive folder
+    fn record_path(&self, id: &str) -> Result<PathBuf, String> {
+        if id.is_empty()
+            || !id
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        {
+            return Err(format!("Invalid voice session id: {}", id));
+        }
+        Ok(self.dir.join(format!("{}.json", id)))
+    }
+
+    fn audio_path(&self, id: &str) -> PathBuf {
+        self.dir.join(format!("{}.wav", id))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn transcript(text: &str) -> Transcript {
+        Transcript {
+            text: text.to_string(),
+            segments: vec![],
+            backend: "whisper-cli".to_string(),
+            fallback: None,
+        }
+    }
+
+    fn info(model: &str) -> TranscriptInfo<'_> {
+        TranscriptInfo {
+            model,
+            language: "en",
+        }
+    }
+
+    #[test]
+    fn keeps_lists_updates_and_deletes_sessions() {
+        let dir = tempfile::tempdir().unwrap();
+        let archive = Archive::new(dir.path());
+        let samples = vec![0.0; WHISPER_SAMPLE_RATE as usize * 3 / 2];
+
+        let first = archive
+            .save(
+                "voice-1",
+                &samples,
+                Some(StopReason::Silence),
+                &transcript("first"),
+                info("base"),
+            )
+            .unwrap();
+        assert_eq!(first.duration_ms, 1500);
+        std::thread::sleep(std::time::Duration::from_millis(5));
+        archive
+            .save(
+                "voice-2",
+                &samples,
+                None,
+                &transcript("second"),
+                info("base"),
+            )
+            .unwrap();
+
+        let ids: Vec<String> = archive.list().into_iter().map(|r| r.id).collect();
+        assert_eq!(ids, ["voice-2", "voice-1"]);
+        assert_eq!(
+            audio::read_wav(&archive.audio("voice-1").unwrap())
+                .unwrap()
+                .samples
+                .len(),
+            samples.len()
+        );
+
+        let updated = archive
+            .update_transcript("voice-1", &transcript("first, again"), info("small.en"))
+            .unwrap();
+        assert_eq!(updated.model, "small.en");
+        assert!(updated.retranscribed_at.is_some());
+        assert_eq!(
+            archive.get("voice-1").unwrap().transcript.text,
+            "first, again"
+        );
+
+        archive.delete("voice-1").unwrap();
+        assert!(archive.get("voice-1").is_err());
+        assert!(!dir.path().join("voice/voice-1.wav").exists());
+    }
+
+    #[test]
+    fn rejects_ids_that_escape_the_archive() {
+        let dir = tempfile::tempdir().unwrap();
+        let archive = Archive::new(dir.path());
+
+        assert!(archive.delete("../config").is_err());
+        assert!(archive.audio("a/b").is_err());
+    }
+}
@@ -0,0 +1,206 @@
+//! WAV reading/writing and conversion to the 16 kHz mono Whisper expects
+
+use std::path::Path;
+
+use rubato::{FftFixedInOut, Resampler};
+
+/// Sample rate whisper.cpp expects
+pub const WHISPER_SAMPLE_RATE: u32 = 16_000;
+
+/// Decoded WAV file, samples interleaved
+pub struct WavData {
+    pub samples: Vec<f32>,
+    pub sample_rate: u32,
+    pub channels: u16,
+}
+
+/// Read a WAV file as interleaved f32 samples
+pub fn read_wav(path: &Path) -> Result<WavData, String> {
+    let mut reader =
+        hound::WavReader::open(path).map_err(|e| format!("Failed to open WAV file: {}", e))?;
+    let spec = reader.spec();
+
+    let samples: Vec<f32> = match spec.sample_format {
+        hound::SampleFormat::Float => reader
+            .samples::<f32>()
+            .collect::<Result<_, _>>()
+            .map_err(|e| format!("Failed to read WAV samples: {}", e))?,
+        hound::SampleFormat::Int => {
+            let scale = (1i64 << (spec.bits_per_sample - 1)) as f32;
+            reader
+                .samples::<i32>()
+                .map(|s| s.map(|s| s as f32 / scale))
+                .collect::<Result<_, _>>()
+                .map_err(|e| format!("Failed to read WAV samples: {}", e))?
+        }
+    };
+
+    Ok(WavData {
+        samples,
+        sample_rate: spec.sample_rate,
+        channels: spec.channels.max(1),
+    })
+}
+
+/// Read any WAV file as 16 kHz mono
+#[cfg(feature = "whisper")]
+pub fn read_wav_16k_mono(path: &Path) -> Result<Vec<f32>, String> {
+    let wav = read_wav(path)?;
+    let mut resampler = StreamResampler::new(wav.sample_rate)?;
+
+    let mut out = resampler.push(&downmix(&wav.samples, wav.channels))?;
+    out.extend(resampler.finish()?);
+    Ok(out)
+}
+
+/// Write 16 kHz mono samples as a 16-bit WAV file
+pub fn write_wav_16k_mono(path: &Path, samples: &[f32]) -> Result<(), String> {
+    let spec = hound::WavSpec {
+        channels: 1,
+        sample_rate: WHISPER_SAMPLE_RATE,
+        bits_per_sample: 16,
+        sample_format: hound::SampleFormat::Int,
+    };
+    let mut writer =
+        hound::WavWriter::create(path, spec).map_err(|e| format!("Failed to create WAV: {}", e))?;
+
+    for sample in samples {
+        let value = (sample.clamp(-1.0, 1.0) * i16::MAX as f32) as i16;
+        writer
+            .write_sample(value)
+            .map_err(|e| format!("Failed to write WAV: {}", e))?;
+    }
+    writer
+        .finalize()
+        .map_err(|e| format!("Failed to write WAV: {}", e))
+}
+
+/// Average interleaved frames down to one channel
+pub fn downmix(samples: &[f32], channels: u16) -> Vec<f32> {
+    if channels <= 1 {
+        return samples.to_vec();
+    }
+    samples
+        .chunks(channels as usize)
+        .map(|frame| frame.iter().sum::<f32>() / frame.len() as f32)
+        .collect()
+}
+
+/// Incremental mono resampler to 16 kHz. Audio arrives in whatever chunk
+/// sizes the device delivers; the FFT resampler wants fixed-size chunks.
+pub struct StreamResampler {
+    /// None when the input is already 16 kHz
+    inner: Option<FftFixedInOut<f32>>,
+    pending: Vec<f32>,
+    /// Leading output frames that are resampler latency, not audio
+    delay: usize,
+    in_frames: usize,
+    out_frames: usize,
+    input_rate: u32,
+}
+
+impl StreamResampler {
+    pub fn new(input_rate: u32) -> Result<Self, String> {
+        let inner = if input_rate == WHISPER_SAMPLE_RATE {
+            None
+        } else {
+            Some(
+                FftFixedInOut::new(input_rate as usize, WHISPER_SAMPLE_RATE as usize, 1024, 1)
+                    .map_err(|e| format!("Failed to create resampler: {}", e))?,
+            )
+        };
+
+        Ok(Self {
+            delay: inner.as_ref().map(|r| r.output_delay()).unwrap_or(0),
+            inner,
+            pending: Vec::new(),
+            in_frames: 0,
+            out_frames: 0,
+            input_rate,
+        })
+    }
+
+    /// Feed mono samples, returning whatever 16 kHz output is ready
+    pub fn push(&mut self, samples: &[f32]) -> Result<Vec<f32>, String> {
+        self.in_frames += samples.len();
+
+        let Some(resampler) = self.inner.as_mut() else {
+            self.out_frames += samples.len();
+            return Ok(samples.to_vec());
+        };
+
+        self.pending.extend_from_slice(samples);
+        let mut out = Vec::new();
+
+        while self.pending.len() >= resampler.input_frames_next() {
+            let chunk: Vec<f32> = self
+                .pending
+                .drain(..resampler.input_frames_next())
+                .collect();
+            let resampled = resampler
+                .process(&[chunk], None)
+                .map_err(|e| format!("Failed to resample: {}", e))?;
+            out.extend_from_slice(&resampled[0]);
+        }
+
+        Ok(self.trim(out))
+    }
+
+    /// Flush the remaining input, padding with silence
+    pub fn finish(&mut self) -> Result<Vec<f32>, String> {
+        let Some(resampler) = self.inner.as_mut() else {
+            return Ok(Vec::new());
+        };
+
+        let expected =
+            (self.in_frames as u64 * WHISPER_SAMPLE_RATE as u64 / self.input_rate as u64) as usize;
+        let mut out = Vec::new();
+
+        // Keep pushing until the delayed tail has come out
+        let mut input = Some(std::mem::take(&mut self.pending));
+        while self.out_frames + out.len().saturating_sub(self.delay) < expected {
+            let resampled = resampler
+                .process_partial(input.take().as_ref().map(std::slice::from_ref), None)
+                .map_err(|e| format!("Failed to resample: {}", e))?;
+            out.extend_from_slice(&resampled[0]);
+        }
+
+        let mut out = self.trim(out);
+        out.truncate(expected.saturating_sub(self.out_frames - out.len()));
+        Ok(out)
+    }
+
+    /// Drop the resampler's leading latency
+    fn trim(&mut self, mut out: Vec<f32>) -> Vec<f32> {
+        let skip = self.delay.min(out.len());
+        self.delay -= skip;
+        out.drain(..skip);
+        self.out_frames += out.len();
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resamples_to_the_expected_length() {
+        let input: Vec<f32> = (0..44_100)
+            .map(|i| (i as f32 * 440.0 * std::f32::consts::TAU / 44_100.0).sin())
+            .collect();
+        let mut resampler = StreamResampler::new(44_100).unwrap();
+
+        // Uneven chunks, like a device callback delivers
+        let mut out = Vec::new();
+        for chunk in input.chunks(997) {
+            out.extend(resampler.push(chunk).unwrap());
+        }
+        out.extend(resampler.finish().unwrap());
+
+        assert_eq!(out.len(), 16_000);
+        // The tone survives: RMS of a full-scale sine is ~0.707
+        let rms = (out[1000..15_000].iter().map(|s| s * s).sum::<f32>() / 14_000.0).sqrt();
+        assert!((rms - 0.707).abs() < 0.05, "rms {}", rms);
+    }
+}
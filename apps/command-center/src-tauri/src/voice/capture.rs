@@ -0,0 +1,374 @@
+//! Native audio capture
+//!
+//! A capture runs on its own thread: it starts an `InputSource` (the default
+//! microphone via cpal, or a WAV file standing in for one), downmixes and
+//! resamples what arrives to 16 kHz mono, and stops when asked to
+//! (push-to-talk), when the VAD hears the speaker finish, or at the length
+//! limit.
+
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use cpal::{FromSample, Sample, SampleFormat, SizedSample};
+use serde::{Deserialize, Serialize};
+
+use super::audio::{self, StreamResampler, WHISPER_SAMPLE_RATE};
+use super::vad::{Vad, VadConfig, VadEnd};
+
+/// What an input source delivers to the capture thread
+pub enum Chunk {
+    /// Interleaved samples
+    Samples(Vec<f32>),
+    /// The source has nothing more (end of file, device unplugged)
+    End,
+    Error(String),
+}
+
+/// Format of the samples a source delivers
+#[derive(Debug, Clone, Copy)]
+pub struct SourceFormat {
+    pub sample_rate: u32,
+    pub channels: u16,
+}
+
+/// Somewhere to record from
+pub trait InputSource: Send {
+    /// Start sending chunks. Capture stops when the returned guard drops.
+    fn start(
+        self: Box<Self>,
+        tx: Sender<Chunk>,
+    ) -> Result<(SourceFormat, Box<dyn std::any::Any>), String>;
+}
+
+/// The system's default input device
+pub struct DeviceSource;
+
+impl InputSource for DeviceSource {
+    fn start(
+        self: Box<Self>,
+        tx: Sender<Chunk>,
+    ) -> Result<(SourceFormat, Box<dyn std::any::Any>), String> {
+        let host = cpal::default_host();
+        let device = host.default_input_device().ok_or("No microphone found")?;
+        let supported = device
+            .default_input_config()
+            .map_err(|e| format!("Failed to read microphone config: {}", e))?;
+
+        let format = SourceFormat {
+            sample_rate: supported.sample_rate().0,
+            channels: supported.channels(),
+        };
+        let config = supported.config();
+
+        let stream = match supported.sample_format() {
+            SampleFormat::F32 => build_stream::<f32>(&device, &config, tx),
+            SampleFormat::I16 => build_stream::<i16>(&device, &config, tx),
+            SampleFormat::U16 => build_stream::<u16>(&device, &config, tx),
+            SampleFormat::I32 => build_stream::<i32>(&device, &config, tx),
+            other => return Err(format!("Unsupported microphone format: {:?}", other)),
+        }?;
+        stream
+            .play()
+            .map_err(|e| format!("Failed to start microphone: {}", e))?;
+
+        Ok((format, Box::new(stream)))
+    }
+}
+
+fn build_stream<T>(
+    device: &cpal::Device,
+    config: &cpal::StreamConfig,
+    tx: Sender<Chunk>,
+) -> Result<cpal::Stream, String>
+where
+    T: SizedSample,
+    f32: FromSample<T>,
+{
+    let err_tx = tx.clone();
+    device
+        .build_input_stream(
+            config,
+            move |data: &[T], _: &cpal::InputCallbackInfo| {
+                let samples = data.iter().map(|s| f32::from_sample(*s)).collect();
+                let _ = tx.send(Chunk::Samples(samples));
+            },
+            move |e| {
+                let _ = err_tx.send(Chunk::Error(e.to_string()));
+            },
+            None,
+        )
+        .map_err(|e| format!("Failed to open microphone: {}", e))
+}
+
+/// A WAV file played into the capture as if it were a microphone
+pub struct WavSource {
+    pub path: PathBuf,
+    /// Deliver at real-time speed rather than as fast as possible
+    pub realtime: bool,
+}
+
+impl InputSource for WavSource {
+    fn start(
+        self: Box<Self>,
+        tx: Sender<Chunk>,
+    ) -> Result<(SourceFormat, Box<dyn std::any::Any>), String> {
+        let wav = audio::read_wav(&self.path)?;
+        let format = SourceFormat {
+            sample_rate: wav.sample_rate,
+            channels: wav.channels,
+        };
+
+        // 10 ms per chunk, like a device callback
+        let chunk_len = (wav.sample_rate / 100) as usize * wav.channels as usize;
+        let realtime = self.realtime;
+        std::thread::spawn(move || {
+            for chunk in wav.samples.chunks(chunk_len.max(1)) {
+                if tx.send(Chunk::Samples(chunk.to_vec())).is_err() {
+                    return;
+                }
+                if realtime {
+                    std::thread::sleep(Duration::from_millis(10));
+                }
+            }
+            let _ = tx.send(Chunk::End);
+        });
+
+        Ok((format, Box::new(())))
+    }
+}
+
+/// How a capture ends
+#[derive(Debug, Clone)]
+pub struct CaptureOptions {
+    /// Stop on trailing silence; None for push-to-talk
+    pub vad: Option<VadConfig>,
+    pub max_duration: Duration,
+}
+
+impl Default for CaptureOptions {
+    fn default() -> Self {
+        Self {
+            vad: Some(VadConfig::default()),
+            max_duration: Duration::from_secs(120),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum StopReason {
+    /// Stop was requested (push-to-talk released)
+    Requested,
+    Silence,
+    NoSpeech,
+    MaxDuration,
+    EndOfInput,
+}
+
+/// Captured audio, 16 kHz mono
+pub struct Recording {
+    pub samples: Vec<f32>,
+    pub stopped_by: StopReason,
+}
+
+impl Recording {
+    pub fn duration(&self) -> Duration {
+        Duration::from_secs_f64(self.samples.len() as f64 / WHISPER_SAMPLE_RATE as f64)
+    }
+}
+
+/// A capture in progress
+pub struct Capture {
+    stop: Arc<AtomicBool>,
+    /// 16 kHz mono audio so far, readable while recording
+    audio: Arc<Mutex<Vec<f32>>>,
+    handle: JoinHandle<Result<Recording, String>>,
+}
+
+impl Capture {
+    pub fn start(source: Box<dyn InputSource>, options: CaptureOptions) -> Self {
+        let stop = Arc::new(AtomicBool::new(false));
+        let audio = Arc::new(Mutex::new(Vec::new()));
+
+        let (flag, buffer) = (stop.clone(), audio.clone());
+        let handle = std::thread::spawn(move || record(source, options, &flag, &buffer));
+
+        Self {
+            stop,
+            audio,
+            handle,
+        }
+    }
+
+    /// Whether the capture already ended on its own
+    pub fn is_finished(&self) -> bool {
+        self.handle.is_finished()
+    }
+
+    /// Number of 16 kHz samples recorded so far
+    pub fn len(&self) -> usize {
+        self.audio.lock().unwrap().len()
+    }
+
+    /// Copy of the recorded audio in `range`, while still recording
+    pub fn audio(&self, range: std::ops::Range<usize>) -> Vec<f32> {
+        let audio = self.audio.lock().unwrap();
+        audio[range.start.min(audio.len())..range.end.min(audio.len())].to_vec()
+    }
+
+    /// Ask the capture to stop without waiting for it
+    pub fn request_stop(&self) {
+        self.stop.store(true, Ordering::SeqCst);
+    }
+
+    /// Stop now and return what was recorded
+    pub fn stop(self) -> Result<Recording, String> {
+        self.request_stop();
+        self.wait()
+    }
+
+    /// Wait for the capture to end by itself
+    pub fn wait(self) -> Result<Recording, String> {
+        self.handle
+            .join()
+            .map_err(|_| "Audio capture thread panicked".to_string())?
+    }
+}
+
+/// The capture thread: pull chunks until something says stop
+fn record(
+    source: Box<dyn InputSource>,
+    options: CaptureOptions,
+    stop: &AtomicBool,
+    audio: &Mutex<Vec<f32>>,
+) -> Result<Recording, String> {
+    let (tx, rx): (Sender<Chunk>, Receiver<Chunk>) = mpsc::channel();
+    let (format, guard) = source.start(tx)?;
+
+    let mut resampler = StreamResampler::new(format.sample_rate)?;
+    let mut vad = options.vad.map(Vad::new);
+    let max_samples = (options.max_duration.as_secs_f64() * WHISPER_SAMPLE_RATE as f64) as usize;
+    let mut recorded = 0;
+
+    let stopped_by = loop {
+        if stop.load(Ordering::SeqCst) {
+            break StopReason::Requested;
+        }
+
+        match rx.recv_timeout(Duration::from_millis(50)) {
+            Ok(Chunk::Samples(chunk)) => {
+                let mono = audio::downmix(&chunk, format.channels);
+                let resampled = resampler.push(&mono)?;
+                audio.lock().unwrap().extend_from_slice(&resampled);
+                recorded += resampled.len();
+
+                if let Some(end) = vad.as_mut().and_then(|v| v.push(&resampled)) {
+                    break match end {
+                        VadEnd::Silence => StopReason::Silence,
+                        VadEnd::NoSpeech => StopReason::NoSpeech,
+                    };
+                }
+                if recorded >= max_samples {
+                    break StopReason::MaxDuration;
+                }
+            }
+            Ok(Chunk::End) | Err(RecvTimeoutError::Disconnected) => break StopReason::EndOfInput,
+            Ok(Chunk::Error(e)) => return Err(format!("Audio capture failed: {}", e)),
+            Err(RecvTimeoutError::Timeout) => {}
+        }
+    };
+
+    // Stop the device before flushing
+    drop(guard);
+    let mut samples = std::mem::take(&mut *audio.lock().unwrap());
+    samples.extend(resampler.finish()?);
+    samples.truncate(max_samples);
+
+    Ok(Recording {
+        samples,
+        stopped_by,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    fn fixture() -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/voice/tone.wav")
+    }
+
+    fn wav(path: PathBuf, realtime: bool) -> Box<dyn InputSource> {
+        Box::new(WavSource { path, realtime })
+    }
+
+    #[test]
+    fn vad_stops_after_the_speaker_finishes() {
+        // tone.wav: 0.5 s silence, 1 s tone, 1.5 s silence
+        let vad = VadConfig {
+            silence_ms: 900,
+            ..VadConfig::default()
+        };
+        let options = CaptureOptions {
+            vad: Some(vad),
+            ..CaptureOptions::default()
+        };
+
+        let recording = Capture::start(wav(fixture(), false), options)
+            .wait()
+            .unwrap();
+
+        assert_eq!(recording.stopped_by, StopReason::Silence);
+        let secs = recording.duration().as_secs_f32();
+        assert!((2.3..2.6).contains(&secs), "stopped at {}s", secs);
+    }
+
+    #[test]
+    fn resamples_device_audio_to_16k_mono() {
+        // A 48 kHz stereo "device"
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("stereo.wav");
+        let spec = hound::WavSpec {
+            channels: 2,
+            sample_rate: 48_000,
+            bits_per_sample: 16,
+            sample_format: hound::SampleFormat::Int,
+        };
+        let mut writer = hound::WavWriter::create(&path, spec).unwrap();
+        for i in 0..48_000 {
+            let s = ((i as f32 * 440.0 * std::f32::consts::TAU / 48_000.0).sin() * 16_000.0) as i16;
+            writer.write_sample(s).unwrap();
+            writer.write_sample(s).unwrap();
+        }
+        writer.finalize().unwrap();
+
+        let options = CaptureOptions {
+            vad: None,
+            ..CaptureOptions::default()
+        };
+        let recording = Capture::start(wav(path, false), options).wait().unwrap();
+
+        assert_eq!(recording.stopped_by, StopReason::EndOfInput);
+        assert_eq!(recording.samples.len(), 16_000);
+    }
+
+    #[test]
+    fn push_to_talk_stops_on_request() {
+        let options = CaptureOptions {
+            vad: None,
+            ..CaptureOptions::default()
+        };
+        let capture = Capture::start(wav(fixture(), true), options);
+        std::thread::sleep(Duration::from_millis(300));
+
+        let recording = capture.stop().unwrap();
+        assert_eq!(recording.stopped_by, StopReason::Requested);
+        assert!(recording.duration() < Duration::from_secs(2));
+    }
+}
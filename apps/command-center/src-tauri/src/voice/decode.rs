@@ -0,0 +1,134 @@
+//! Decoding audio files (WAV, MP3, Ogg Vorbis, M4A/AAC, FLAC) to the
+//! 16 kHz mono Whisper expects, via symphonia
+
+use std::fs::File;
+use std::path::Path;
+
+use symphonia::core::audio::SampleBuffer;
+use symphonia::core::codecs::{DecoderOptions, CODEC_TYPE_NULL};
+use symphonia::core::errors::Error;
+use symphonia::core::formats::FormatOptions;
+use symphonia::core::io::MediaSourceStream;
+use symphonia::core::meta::MetadataOptions;
+use symphonia::core::probe::Hint;
+
+use super::audio::{self, StreamResampler};
+
+/// File extensions `decode_16k_mono` accepts
+pub const AUDIO_EXTENSIONS: [&str; 8] = ["wav", "mp3", "ogg", "oga", "m4a", "mp4", "aac", "flac"];
+
+/// Whether `path` looks like an audio file we can decode
+pub fn is_audio_file(path: &Path) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| AUDIO_EXTENSIONS.contains(&e.to_lowercase().as_str()))
+}
+
+/// Decode the first audio track of a file to 16 kHz mono
+pub fn decode_16k_mono(path: &Path) -> Result<Vec<f32>, String> {
+    let file = File::open(path).map_err(|e| format!("Failed to open audio file: {}", e))?;
+    let stream = MediaSourceStream::new(Box::new(file), Default::default());
+
+    let mut hint = Hint::new();
+    if let Some(ext) = path.extension().and_then(|e| e.to_str()) {
+        hint.with_extension(ext);
+    }
+    let probed = symphonia::default::get_probe()
+        .format(
+            &hint,
+            stream,
+            &FormatOptions::default(),
+            &MetadataOptions::default(),
+        )
+        .map_err(|e| format!("Unsupported audio file: {}", e))?;
+    let mut format = probed.format;
+
+    let track = format
+        .tracks()
+        .iter()
+        .find(|t| t.codec_params.codec != CODEC_TYPE_NULL)
+        .ok_or("The file has no audio track")?;
+    let track_id = track.id;
+    let mut decoder = symphonia::default::get_codecs()
+        .make(&track.codec_params, &DecoderOptions::default())
+        .map_err(|e| format!("Unsupported audio codec: {}", e))?;
+
+    let mut resampler: Option<StreamResampler> = None;
+    let mut samples = Vec::new();
+
+    loop {
+        let packet = match format.next_packet() {
+            Ok(packet) => packet,
+            Err(Error::IoError(e)) if e.kind() == std::io::ErrorKind::UnexpectedEof => break,
+            Err(e) => return Err(format!("Failed to read audio file: {}", e)),
+        };
+        if packet.track_id() != track_id {
+            continue;
+        }
+
+        let decoded = match decoder.decode(&packet) {
+            Ok(decoded) => decoded,
+            // A corrupt frame; skip it like players do
+            Err(Error::DecodeError(_)) => continue,
+            Err(e) => return Err(format!("Failed to decode audio: {}", e)),
+        };
+
+        let spec = *decoded.spec();
+        let mut buffer = SampleBuffer::<f32>::new(decoded.capacity() as u64, spec);
+        buffer.copy_interleaved_ref(decoded);
+
+        let resampler = match resampler.as_mut() {
+            Some(r) => r,
+            None => resampler.insert(StreamResampler::new(spec.rate)?),
+        };
+        let mono = audio::downmix(buffer.samples(), spec.channels.count() as u16);
+        samples.extend(resampler.push(&mono)?);
+    }
+
+    if let Some(resampler) = resampler.as_mut() {
+        samples.extend(resampler.finish()?);
+    }
+    if samples.is_empty() {
+        return Err("The file contains no audio".to_string());
+    }
+    Ok(samples)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::voice::audio::WHISPER_SAMPLE_RATE;
+
+    #[test]
+    fn decodes_and_resamples_a_stereo_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("memo.WAV");
+        let spec = hound::WavSpec {
+            channels: 2,
+            sample_rate: 44_100,
+            bits_per_sample: 16,
+            sample_format: hound::SampleFormat::Int,
+        };
+        let mut writer = hound::WavWriter::create(&path, spec).unwrap();
+        for i in 0..88_200 {
+            let s = ((i as f32 * 440.0 * std::f32::consts::TAU / 44_100.0).sin() * 16_000.0) as i16;
+            writer.write_sample(s).unwrap();
+            writer.write_sample(s).unwrap();
+        }
+        writer.finalize().unwrap();
+
+        assert!(is_audio_file(&path));
+        let samples = decode_16k_mono(&path).unwrap();
+        assert_eq!(samples.len(), 2 * WHISPER_SAMPLE_RATE as usize);
+    }
+
+    #[test]
+    fn rejects_files_that_are_not_audio() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("notes.mp3");
+        std::fs::write(&path, b"definitely not an mp3").unwrap();
+
+        assert!(decode_16k_mono(&path).is_err());
+        assert!(!is_audio_file(Path::new("notes.txt")));
+    }
+}
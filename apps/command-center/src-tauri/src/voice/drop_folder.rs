@@ -0,0 +1,259 @@
+//! The voice-drop folder
+//!
+//! Audio files put in `~/.taskboard/voice-drop` (voice memos synced from a
+//! phone, say) are transcribed and filed as inbox items. The file watcher
+//! queues them here and a single worker thread handles them in turn. A
+//! handled file moves to `voice-drop/processed/`, or to `voice-drop/failed/`
+//! next to a note of what went wrong, so nothing is transcribed twice.
+
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Sender};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+
+use super::Transcript;
+use crate::data::InboxItem;
+
+/// Folder in the data directory that is watched for audio files
+pub const DROP_DIR: &str = "voice-drop";
+
+/// How often a file's size is checked while it is still being copied in
+const SETTLE_INTERVAL: Duration = Duration::from_millis(300);
+
+/// Give up waiting for a file that keeps growing after this long
+const SETTLE_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// Payload of `voice-drop-processed`
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct DropResult {
+    /// Where the file ended up (processed/ or failed/)
+    pub file: String,
+    pub item_id: Option<String>,
+    pub error: Option<String>,
+}
+
+/// Files waiting for the worker, which starts with the first one
+#[derive(Clone, Default)]
+pub struct DropQueue {
+    inner: Arc<Mutex<QueueState>>,
+}
+
+#[derive(Default)]
+struct QueueState {
+    tx: Option<Sender<PathBuf>>,
+    /// Queued or in progress; further events for these are ignored
+    queued: HashSet<PathBuf>,
+}
+
+impl DropQueue {
+    /// Queue a dropped file. `handler` processes every file and is only
+    /// used when this push starts the worker.
+    pub fn push<F>(&self, path: PathBuf, handler: F)
+    where
+        F: Fn(&Path) + Send + 'static,
+    {
+        let mut state = self.inner.lock().unwrap();
+        if !path.is_file() || !state.queued.insert(path.clone()) {
+            return;
+        }
+
+        let inner = self.inner.clone();
+        let tx = state.tx.get_or_insert_with(|| {
+            let (tx, rx) = mpsc::channel::<PathBuf>();
+            std::thread::spawn(move || {
+                for path in rx {
+                    handler(&path);
+                    inner.lock().unwrap().queued.remove(&path);
+                }
+            });
+            tx
+        });
+        let _ = tx.send(path);
+    }
+}
+
+/// Transcribe a dropped file and file it, then move it out of the way.
+/// Blocks, including while the file is still being copied in.
+pub fn process(
+    path: &Path,
+    transcribe: &dyn Fn(&Path) -> Result<Transcript, String>,
+    file: &dyn Fn(&Transcript, &Path) -> Result<InboxItem, String>,
+) -> DropResult {
+    if !wait_until_settled(path) {
+        return DropResult {
+            file: path.to_string_lossy().to_string(),
+            item_id: None,
+            error: Some("The file disappeared or never finished copying".to_string()),
+        };
+    }
+
+    let drop_dir = path.parent().unwrap_or(Path::new("."));
+    let transcribed = transcribe(path);
+    let folder = if transcribed.is_ok() {
+        "processed"
+    } else {
+        "failed"
+    };
+    let mut moved = move_into(path, &drop_dir.join(folder));
+    // If the move failed, leave the item pointing at the original
+    let audio = moved.as_deref().unwrap_or(path).to_path_buf();
+
+    let outcome = transcribed.and_then(|transcript| file(&transcript, &audio));
+    // Transcribed but not filed: it still needs looking at
+    if outcome.is_err() && folder == "processed" {
+        moved = move_into(&audio, &drop_dir.join("failed"));
+    }
+    let audio = moved.as_deref().unwrap_or(&audio);
+
+    let error = outcome
+        .as_ref()
+        .err()
+        .cloned()
+        .or_else(|| moved.as_ref().err().cloned());
+    if let Some(error) = outcome.as_ref().err() {
+        let note = audio.with_file_name(format!(
+            "{}.error.txt",
+            audio.file_name().unwrap_or_default().to_string_lossy()
+        ));
+        let _ = fs::write(note, error);
+    }
+
+    DropResult {
+        file: audio.to_string_lossy().to_string(),
+        item_id: outcome.ok().map(|item| item.id),
+        error,
+    }
+}
+
+/// Wait until the file stops growing; false if it vanished or never settled
+fn wait_until_settled(path: &Path) -> bool {
+    let started = Instant::now();
+    let mut last = None;
+
+    while started.elapsed() < SETTLE_TIMEOUT {
+        let size = match fs::metadata(path) {
+            Ok(meta) => meta.len(),
+            Err(_) => return false,
+        };
+        if last == Some(size) && size > 0 {
+            return true;
+        }
+        last = Some(size);
+        std::thread::sleep(SETTLE_INTERVAL);
+    }
+    false
+}
+
+/// Move `path` into `dir`, never overwriting
+fn move_into(path: &Path, dir: &Path) -> Result<PathBuf, String> {
+    fs::create_dir_all(dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    let mut target = dir.join(name.as_ref());
+    if target.exists() {
+        target = dir.join(format!(
+            "{}-{}",
+            chrono::Utc::now().timestamp_millis(),
+            name
+        ));
+    }
+
+    fs::rename(path, &target).map_err(|e| format!("Failed to move {}: {}", name, e))?;
+    Ok(target)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::InboxFile;
+
+    fn item(id: &str) -> InboxItem {
+        let mut inbox: InboxFile =
+            serde_json::from_value(serde_json::json!({ "items": [{ "id": id, "text": "" }] }))
+                .unwrap();
+        inbox.items.remove(0)
+    }
+
+    fn transcript(text: &str) -> Transcript {
+        Transcript {
+            text: text.to_string(),
+            segments: vec![],
+            backend: "stub".to_string(),
+            fallback: None,
+        }
+    }
+
+    #[test]
+    fn files_the_transcript_and_moves_the_memo() {
+        let dir = tempfile::tempdir().unwrap();
+        let memo = dir.path().join("memo.m4a");
+        fs::write(&memo, b"audio").unwrap();
+
+        let filed = Mutex::new(None);
+        let result = process(
+            &memo,
+            &|_| Ok(transcript("Remind me to call the bank")),
+            &|t, audio| {
+                *filed.lock().unwrap() = Some((t.text.clone(), audio.to_path_buf()));
+                Ok(item("inbox-voice-1"))
+            },
+        );
+
+        let processed = dir.path().join("processed").join("memo.m4a");
+        assert_eq!(result.item_id.as_deref(), Some("inbox-voice-1"));
+        assert_eq!(result.error, None);
+        assert!(!memo.exists() && processed.exists());
+        assert_eq!(
+            filed.into_inner().unwrap(),
+            Some(("Remind me to call the bank".to_string(), processed))
+        );
+    }
+
+    #[test]
+    fn failed_files_are_set_aside_with_the_error() {
+        let dir = tempfile::tempdir().unwrap();
+        let memo = dir.path().join("memo.mp3");
+        fs::write(&memo, b"audio").unwrap();
+
+        let result = process(
+            &memo,
+            &|_| Err("Unsupported audio file".to_string()),
+            &|_, _| unreachable!(),
+        );
+
+        let failed = dir.path().join("failed");
+        assert_eq!(result.error.as_deref(), Some("Unsupported audio file"));
+        assert!(failed.join("memo.mp3").exists());
+        assert_eq!(
+            fs::read_to_string(failed.join("memo.mp3.error.txt")).unwrap(),
+            "Unsupported audio file"
+        );
+    }
+
+    #[test]
+    fn memos_that_cannot_be_filed_are_set_aside_too() {
+        let dir = tempfile::tempdir().unwrap();
+        let memo = dir.path().join("memo.m4a");
+        fs::write(&memo, b"audio").unwrap();
+
+        let result = process(
+            &memo,
+            &|_| Ok(transcript("Remind me to call the bank")),
+            &|_, _| Err("Failed to write inbox.md: disk full".to_string()),
+        );
+
+        let failed = dir.path().join("failed");
+        assert_eq!(result.item_id, None);
+        assert_eq!(result.file, failed.join("memo.m4a").to_string_lossy());
+        assert!(!dir.path().join("processed").join("memo.m4a").exists());
+        assert_eq!(
+            fs::read_to_string(failed.join("memo.m4a.error.txt")).unwrap(),
+            "Failed to write inbox.md: disk full"
+        );
+    }
+}
@@ -0,0 +1,213 @@
+//! In-process whisper.cpp transcription
+//!
+//! Built only with the `whisper` cargo feature (whisper-rs, which compiles
+//! whisper.cpp and needs cmake and a C++ toolchain). The model is loaded on
+//! first use and kept warm in `AppState`; without the feature every call
+//! fails and `voice::transcribe` falls back to the CLI.
+
+use std::path::Path;
+
+use super::transcript::Transcript;
+
+#[cfg(feature = "whisper")]
+use std::path::PathBuf;
+#[cfg(feature = "whisper")]
+use std::sync::{Arc, Mutex};
+#[cfg(feature = "whisper")]
+use whisper_rs::{FullParams, SamplingStrategy, WhisperContext, WhisperContextParameters};
+
+#[cfg(feature = "whisper")]
+use super::{audio, transcript::Segment};
+
+/// Shared handle to the loaded model, cheap to clone
+#[derive(Clone, Default)]
+pub struct EngineCache {
+    #[cfg(feature = "whisper")]
+    loaded: Arc<Mutex<Option<Arc<WhisperEngine>>>>,
+}
+
+impl EngineCache {
+    /// Whether this build can transcribe in-process
+    pub fn is_available(&self) -> bool {
+        cfg!(feature = "whisper")
+    }
+
+    /// Transcribe a 16 kHz WAV file, loading `model_path` if it is not the
+    /// model currently in memory
+    #[cfg(feature = "whisper")]
+    pub fn transcribe(
+        &self,
+        model_path: &Path,
+        audio_path: &Path,
+        language: &str,
+    ) -> Result<Transcript, String> {
+        let samples = audio::read_wav_16k_mono(audio_path)?;
+        self.engine(model_path)?.transcribe(&samples, language)
+    }
+
+    #[cfg(not(feature = "whisper"))]
+    pub fn transcribe(
+        &self,
+        _model_path: &Path,
+        _audio_path: &Path,
+        _language: &str,
+    ) -> Result<Transcript, String> {
+        Err("Built without in-process Whisper (enable the `whisper` feature)".to_string())
+    }
+
+    /// Unload the model, freeing its memory
+    pub fn clear(&self) {
+        #[cfg(feature = "whisper")]
+        self.loaded.lock().unwrap().take();
+    }
+
+    /// The loaded engine, (re)loading it when the selected model changed.
+    /// The lock is only held while loading, never while transcribing.
+    #[cfg(feature = "whisper")]
+    fn engine(&self, model_path: &Path) -> Result<Arc<WhisperEngine>, String> {
+        let mut loaded = self.loaded.lock().unwrap();
+
+        match loaded.as_ref() {
+            Some(engine) if engine.model_path == model_path => Ok(engine.clone()),
+            _ => {
+                let engine = Arc::new(WhisperEngine::load(model_path)?);
+                *loaded = Some(engine.clone());
+                Ok(engine)
+            }
+        }
+    }
+}
+
+/// A loaded ggml model
+#[cfg(feature = "whisper")]
+pub struct WhisperEngine {
+    model_path: PathBuf,
+    ctx: WhisperContext,
+}
+
+#[cfg(feature = "whisper")]
+impl WhisperEngine {
+    pub fn load(model_path: &Path) -> Result<Self, String> {
+        let path = model_path
+            .to_str()
+            .ok_or_else(|| format!("Invalid model path: {:?}", model_path))?;
+        let ctx = WhisperContext::new_with_params(path, WhisperContextParameters::default())
+            .map_err(|e| format!("Failed to load Whisper model: {}", e))?;
+
+        Ok(Self {
+            model_path: model_path.to_path_buf(),
+            ctx,
+        })
+    }
+
+    /// Transcribe 16 kHz mono samples
+    pub fn transcribe(&self, samples: &[f32], language: &str) -> Result<Transcript, String> {
+        let mut state = self
+            .ctx
+            .create_state()
+            .map_err(|e| format!("Failed to create Whisper state: {}", e))?;
+
+        let mut params = FullParams::new(SamplingStrategy::Greedy { best_of: 1 });
+        params.set_language(Some(language));
+        params.set_n_threads(num_threads());
+        params.set_print_special(false);
+        params.set_print_progress(false);
+        params.set_print_realtime(false);
+        params.set_print_timestamps(false);
+
+        state
+            .full(params, samples)
+            .map_err(|e| format!("Failed to transcribe: {}", e))?;
+
+        let n_segments = state
+            .full_n_segments()
+            .map_err(|e| format!("Failed to read segments: {}", e))?;
+        let eot = self.ctx.token_eot();
+
+        let mut segments = Vec::new();
+        for i in 0..n_segments {
+            let read = || -> Result<Segment, whisper_rs::WhisperError> {
+                // Segment times are in 10 ms units
+                let start = state.full_get_segment_t0(i)?;
+                let end = state.full_get_segment_t1(i)?;
+                let text = state.full_get_segment_text_lossy(i)?;
+
+                // Mean probability of the text tokens (special tokens sort after EOT)
+                let mut probs = Vec::new();
+                for t in 0..state.full_n_tokens(i)? {
+                    if state.full_get_token_id(i, t)? < eot {
+                        probs.push(state.full_get_token_prob(i, t)?);
+                    }
+                }
+                let confidence =
+                    (!probs.is_empty()).then(|| probs.iter().sum::<f32>() / probs.len() as f32);
+
+                Ok(Segment {
+                    start_ms: start.max(0) as u64 * 10,
+                    end_ms: end.max(0) as u64 * 10,
+                    text: text.trim().to_string(),
+                    confidence,
+                })
+            };
+            segments.push(read().map_err(|e| format!("Failed to read segment {}: {}", i, e))?);
+        }
+
+        Ok(Transcript::from_segments(segments, "whisper-rs"))
+    }
+}
+
+#[cfg(feature = "whisper")]
+fn num_threads() -> i32 {
+    std::thread::available_parallelism()
+        .map(|n| n.get().min(8) as i32)
+        .unwrap_or(4)
+}
+
+#[cfg(all(test, feature = "whisper"))]
+mod tests {
+    use super::*;
+
+    /// The tiny model from `WHISPER_TEST_MODEL`, or the one the app
+    /// downloaded, if there is one
+    fn tiny_model() -> Option<PathBuf> {
+        std::env::var_os("WHISPER_TEST_MODEL")
+            .map(PathBuf::from)
+            .or_else(|| Some(dirs::home_dir()?.join(".taskboard/models/ggml-tiny.bin")))
+            .filter(|path| path.exists())
+    }
+
+    /// Runs whenever the tiny model is present, against whisper.cpp's
+    /// samples/jfk.wav in tests/fixtures/voice/
+    #[test]
+    fn transcribes_speech_with_tiny_model() {
+        let Some(model) = tiny_model() else {
+            eprintln!("skipped: no ggml-tiny.bin; set WHISPER_TEST_MODEL to run it");
+            return;
+        };
+        let wav = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/voice/jfk.wav");
+        assert!(wav.exists(), "speech fixture missing: {}", wav.display());
+
+        let cache = EngineCache::default();
+        let transcript = cache.transcribe(&model, &wav, "en").unwrap();
+
+        assert_eq!(transcript.backend, "whisper-rs");
+        let text = transcript.text.to_lowercase();
+        assert!(
+            text.contains("ask not what your country can do for you"),
+            "{}",
+            transcript.text
+        );
+        assert!(!transcript.segments.is_empty());
+        for segment in &transcript.segments {
+            assert!(segment.start_ms <= segment.end_ms);
+            assert!(segment.end_ms <= 11_500);
+            if let Some(c) = segment.confidence {
+                assert!((0.0..=1.0).contains(&c));
+            }
+        }
+
+        // Second call reuses the warm model
+        let first = cache.engine(&model).unwrap();
+        assert!(Arc::ptr_eq(&first, &cache.engine(&model).unwrap()));
+    }
+}
@@ -0,0 +1,563 @@
+//! Voice intent extraction
+//!
+//! Turns a raw transcript into a structured inbox item: what kind of
+//! message it is (an instruction for Claude, a new task, a comment, a
+//! question), which project or task it is about, how urgent it is and when
+//! it is due. The rules here handle the phrasings people actually use in
+//! Quick Capture; a smarter extractor (an LLM, say) can implement
+//! `IntentExtractor` and be swapped in without touching the callers.
+
+use chrono::{Datelike, Days, Duration, NaiveDate, Weekday};
+use regex::{Captures, Regex};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map};
+use std::sync::OnceLock;
+
+use super::Transcript;
+use crate::data::{self, InboxItem, Project, ProjectsFile, Task, TasksFile};
+use crate::quick_commands::matcher::{self, Match};
+
+/// A spoken name must match a project or task title this closely
+const MENTION_THRESHOLD: f64 = 0.95;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum IntentKind {
+    /// Something for Claude to do
+    Instruction,
+    TaskCreation,
+    /// A remark, on a task if one is mentioned
+    Comment,
+    Question,
+}
+
+impl IntentKind {
+    /// The inbox item type the frontend shows this as
+    fn item_type(&self) -> &'static str {
+        match self {
+            IntentKind::Instruction | IntentKind::TaskCreation => "task",
+            IntentKind::Comment | IntentKind::Question => "note",
+        }
+    }
+}
+
+/// What a transcript asks for
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct VoiceIntent {
+    pub kind: IntentKind,
+    /// The item text: the task title for new tasks, otherwise what was said
+    /// minus any "Claude," address
+    pub text: String,
+    pub project_id: Option<String>,
+    pub task_id: Option<String>,
+    pub priority: Option<String>,
+    /// YYYY-MM-DD in the user's timezone
+    pub due_date: Option<String>,
+}
+
+/// The board and the date the transcript is read against
+pub struct IntentContext<'a> {
+    pub projects: &'a ProjectsFile,
+    pub tasks: &'a TasksFile,
+    /// Today in the user's timezone
+    pub today: NaiveDate,
+}
+
+/// Something that can work out what a transcript asks for
+pub trait IntentExtractor: Send + Sync {
+    fn extract(&self, transcript: &str, context: &IntentContext) -> Result<VoiceIntent, String>;
+}
+
+/// Keyword and pattern rules; no network, always available
+pub struct RuleExtractor;
+
+struct Patterns {
+    comment: Regex,
+    create: Regex,
+    address: Regex,
+    question: Regex,
+    instruction: Regex,
+    task_cue: Regex,
+    priorities: [(&'static str, Regex); 4],
+    due: Regex,
+}
+
+const MONTHS: &str = "jan(?:uary)?|feb(?:ruary)?|mar(?:ch)?|apr(?:il)?|may|june?|july?|aug(?:ust)?|sep(?:t(?:ember)?)?|oct(?:ober)?|nov(?:ember)?|dec(?:ember)?";
+const WEEKDAYS: &str = "monday|tuesday|wednesday|thursday|friday|saturday|sunday";
+
+fn patterns() -> &'static Patterns {
+    static PATTERNS: OnceLock<Patterns> = OnceLock::new();
+    PATTERNS.get_or_init(|| {
+        let due = format!(
+            r"(?i)\b(?:(?:by|before|on|due|until|for)\s+)?(?:(?P<relative>today|tonight|tomorrow|next week|end of (?:the )?(?:day|week|month))|(?:next\s+|this\s+)?(?P<weekday>{w})|in (?P<count>\d+|a|one|two|three|four) (?P<unit>day|week)s?|(?P<month>{m})\.? (?P<day>\d{{1,2}})(?:st|nd|rd|th)?|(?P<day2>\d{{1,2}})(?:st|nd|rd|th)? (?:of )?(?P<month2>{m})|(?P<iso>\d{{4}}-\d{{2}}-\d{{2}}))\b",
+            w = WEEKDAYS,
+            m = MONTHS
+        );
+
+        Patterns {
+            comment: Regex::new(
+                r"(?i)^(?:add (?:a )?)?(?:comment|note) (?:on|to|for) (?:the )?(?P<task>.+?)(?: task)?\s*[:,\-]\s*(?P<text>.+)$",
+            )
+            .unwrap(),
+            create: Regex::new(
+                r"(?i)^(?:please\s+)?(?:(?:add|create|make|open) (?:a )?(?:new )?task|new task|to ?do|remind me to|i need to|we need to|don'?t forget to)\b[\s:,]*(?P<rest>.+)$",
+            )
+            .unwrap(),
+            address: Regex::new(r"(?i)^(?:hey |ok |okay )?@?claude\b[\s,:]*").unwrap(),
+            question: Regex::new(
+                r"(?i)^(?:what|what's|whats|how|why|when|where|who|which|is|are|was|were|can|could|should|would|does|do|did|has|have|will)\b",
+            )
+            .unwrap(),
+            instruction: Regex::new(
+                r"(?i)^(?:please\s+)?(?:fix|refactor|implement|update|review|write|check|investigate|look into|add|remove|delete|rename|change|build|test|deploy|document|run|set up|setup|migrate|upgrade|clean up|draft|research)\b",
+            )
+            .unwrap(),
+            task_cue: Regex::new(r"(?i)\b(?:on|to|for|about|with) the (?P<task>[^,.;:?!]+?) task\b")
+                .unwrap(),
+            priorities: [
+                (
+                    "P0",
+                    Regex::new(
+                        r"(?i)\b(?:it'?s |this is )?(?:urgent(?:ly)?|asap|as soon as possible|critical|emergency|immediately|p ?0|p zero)\b",
+                    )
+                    .unwrap(),
+                ),
+                (
+                    "P1",
+                    Regex::new(r"(?i)\b(?:it'?s |this is )?(?:high[- ]priority|important|p ?1|p one)\b")
+                        .unwrap(),
+                ),
+                (
+                    "P3",
+                    Regex::new(
+                        r"(?i)\b(?:it'?s |this is )?(?:low[- ]priority|no rush|whenever|someday|p ?3|p three)\b",
+                    )
+                    .unwrap(),
+                ),
+                (
+                    "P2",
+                    Regex::new(
+                        r"(?i)\b(?:it'?s |this is )?(?:medium[- ]priority|normal priority|p ?2|p two)\b",
+                    )
+                    .unwrap(),
+                ),
+            ],
+            due: Regex::new(&due).unwrap(),
+        }
+    })
+}
+
+impl IntentExtractor for RuleExtractor {
+    fn extract(&self, transcript: &str, context: &IntentContext) -> Result<VoiceIntent, String> {
+        let p = patterns();
+        let said = transcript.split_whitespace().collect::<Vec<_>>().join(" ");
+        if said.is_empty() {
+            return Err("Nothing was said".to_string());
+        }
+        let board = Mentions { context };
+
+        let priority = p
+            .priorities
+            .iter()
+            .find(|(_, re)| re.is_match(&said))
+            .map(|(priority, _)| priority.to_string());
+        let due = p
+            .due
+            .captures_iter(&said)
+            .find_map(|c| resolve_due(&c, context.today));
+
+        let mut project = None;
+        let mut task = None;
+        let (kind, text) = if let Some(c) = p.comment.captures(&said) {
+            task = board.task(&c["task"], None);
+            (IntentKind::Comment, c["text"].to_string())
+        } else if let Some(c) = p.create.captures(&said) {
+            let (found, title) = board.project_prefix(&c["rest"]);
+            project = found;
+            (IntentKind::TaskCreation, task_title(&title))
+        } else {
+            let unaddressed = p.address.replace(&said, "").to_string();
+            let addressed = unaddressed.len() != said.len();
+
+            let kind = if said.ends_with('?') || p.question.is_match(&unaddressed) {
+                IntentKind::Question
+            } else if addressed || p.instruction.is_match(&unaddressed) {
+                IntentKind::Instruction
+            } else {
+                IntentKind::Comment
+            };
+            (kind, capitalize(&unaddressed))
+        };
+
+        let project = project.or_else(|| board.project(&said));
+        let task = task
+            .or_else(|| {
+                p.task_cue
+                    .captures(&said)
+                    .and_then(|c| board.task(&c["task"], project.map(|p| p.id.as_str())))
+            })
+            .or_else(|| board.task_title(&said, project.map(|p| p.id.as_str())));
+
+        Ok(VoiceIntent {
+            kind,
+            text,
+            project_id: project
+                .map(|p| p.id.clone())
+                .or_else(|| task.map(|t| t.project_id.clone())),
+            task_id: task.map(|t| t.id.clone()),
+            priority,
+            due_date: due.map(|d| d.format("%Y-%m-%d").to_string()),
+        })
+    }
+}
+
+/// Finds projects and tasks named in speech
+struct Mentions<'a> {
+    context: &'a IntentContext<'a>,
+}
+
+impl<'a> Mentions<'a> {
+    /// The project whose name or id is said somewhere in `said`
+    fn project(&self, said: &str) -> Option<&'a Project> {
+        self.context
+            .projects
+            .projects
+            .iter()
+            .map(|p| {
+                (
+                    mention_score(said, &p.name).max(mention_score(said, &p.id)),
+                    p,
+                )
+            })
+            .filter(|(score, _)| *score >= MENTION_THRESHOLD)
+            .max_by(|a, b| a.0.total_cmp(&b.0))
+            .map(|(_, p)| p)
+    }
+
+    /// "for AnyCalc: dark mode toggle" → the project and the rest. When the
+    /// words before the colon or comma are not a project, it is all title.
+    fn project_prefix(&self, rest: &str) -> (Option<&'a Project>, String) {
+        static PREFIX: OnceLock<Regex> = OnceLock::new();
+        let prefix = PREFIX.get_or_init(|| {
+            Regex::new(r"(?i)^(?:for|in|to|on) (?:the )?(?P<project>[^:,]+?)(?: project)?\s*[:,]\s*(?P<title>.+)$")
+                .unwrap()
+        });
+
+        if let Some(c) = prefix.captures(rest) {
+            if let Match::Found(project, _) =
+                matcher::find(&c["project"], &self.context.projects.projects, |p| {
+                    vec![p.name.as_str(), p.id.as_str()]
+                })
+            {
+                return (Some(project), c["title"].to_string());
+            }
+        }
+        (None, rest.to_string())
+    }
+
+    /// A task referred to by (part of) its title, preferring `project_id`
+    fn task(&self, query: &str, project_id: Option<&str>) -> Option<&'a Task> {
+        let tasks = &self.context.tasks.tasks;
+        fn names(t: &Task) -> Vec<&str> {
+            vec![t.title.as_str(), t.id.as_str()]
+        }
+
+        if let Some(project_id) = project_id {
+            let in_project = tasks.iter().filter(|t| t.project_id == project_id);
+            if let Match::Found(task, _) = matcher::find(query, in_project, names) {
+                return Some(task);
+            }
+        }
+        match matcher::find(query, tasks, names) {
+            Match::Found(task, _) => Some(task),
+            _ => None,
+        }
+    }
+
+    /// A task whose whole title is said somewhere in `said`
+    fn task_title(&self, said: &str, project_id: Option<&str>) -> Option<&'a Task> {
+        self.context
+            .tasks
+            .tasks
+            .iter()
+            .filter(|t| project_id.is_none_or(|id| t.project_id == id))
+            // One-word titles ("Deploy") are too easy to say by accident
+            .filter(|t| t.title.split_whitespace().count() > 1)
+            .map(|t| (mention_score(said, &t.title), t))
+            .filter(|(score, _)| *score >= MENTION_THRESHOLD)
+            .max_by(|a, b| a.0.total_cmp(&b.0))
+            .map(|(_, t)| t)
+    }
+}
+
+/// How well any run of words in `said` matches `name`. Runs are also tried
+/// run together, since speech-to-text splits "AnyCalc" into "Any Calc".
+fn mention_score(said: &str, name: &str) -> f64 {
+    let words: Vec<&str> = said
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .collect();
+    let name_len = name.split_whitespace().count().max(1);
+
+    let mut best: f64 = 0.0;
+    for len in 1..=(name_len + 1).min(words.len()) {
+        for window in words.windows(len) {
+            best = best.max(matcher::score(&window.join(" "), name));
+            if len > 1 {
+                best = best.max(matcher::score(&window.concat(), name));
+            }
+        }
+    }
+    best
+}
+
+/// Strip the priority and due-date phrases from a new task's title
+fn task_title(raw: &str) -> String {
+    let p = patterns();
+    let mut title = raw.to_string();
+    for (_, re) in &p.priorities {
+        title = re.replace_all(&title, "").to_string();
+    }
+    title = p.due.replace_all(&title, "").to_string();
+    capitalize(&tidy(&title))
+}
+
+/// Close up the punctuation left behind after removing phrases
+fn tidy(text: &str) -> String {
+    static SPACE_BEFORE: OnceLock<Regex> = OnceLock::new();
+    static DANGLING: OnceLock<Regex> = OnceLock::new();
+    let space_before = SPACE_BEFORE.get_or_init(|| Regex::new(r"\s+([,.;:!?])").unwrap());
+    let dangling = DANGLING.get_or_init(|| Regex::new(r"[,;:]+\s*([,.;:!?]|$)").unwrap());
+
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    let text = space_before.replace_all(&text, "$1");
+    let text = dangling.replace_all(&text, "$1");
+    text.trim_end_matches(|c: char| c == '.' || c.is_whitespace())
+        .trim_start_matches(|c: char| c == ',' || c.is_whitespace())
+        .to_string()
+}
+
+fn capitalize(text: &str) -> String {
+    let mut chars = text.trim().chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+/// The date a due-date phrase means, counting from `today`
+fn resolve_due(c: &Captures, today: NaiveDate) -> Option<NaiveDate> {
+    if let Some(relative) = c.name("relative") {
+        let relative = relative.as_str().to_lowercase();
+        return Some(match relative.as_str() {
+            "today" | "tonight" | "end of day" | "end of the day" => today,
+            "tomorrow" => today + Duration::days(1),
+            "next week" => next_weekday(today, Weekday::Mon),
+            "end of week" | "end of the week" => {
+                let ahead = (Weekday::Fri.num_days_from_monday() + 7
+                    - today.weekday().num_days_from_monday())
+                    % 7;
+                today + Duration::days(ahead.into())
+            }
+            _ => {
+                // End of the month
+                let (year, month) = match today.month() {
+                    12 => (today.year() + 1, 1),
+                    m => (today.year(), m + 1),
+                };
+                NaiveDate::from_ymd_opt(year, month, 1)? - Duration::days(1)
+            }
+        });
+    }
+
+    if let Some(weekday) = c.name("weekday") {
+        return Some(next_weekday(today, weekday.as_str().parse().ok()?));
+    }
+
+    if let Some(count) = c.name("count") {
+        let count: u64 = match count.as_str().to_lowercase().as_str() {
+            "a" | "one" => 1,
+            "two" => 2,
+            "three" => 3,
+            "four" => 4,
+            n => n.parse().ok()?,
+        };
+        let days = if c["unit"].eq_ignore_ascii_case("week") {
+            count.checked_mul(7)?
+        } else {
+            count
+        };
+        // "in 99999999999 days" is past any date there is
+        return today.checked_add_days(Days::new(days));
+    }
+
+    if let Some(iso) = c.name("iso") {
+        return NaiveDate::parse_from_str(iso.as_str(), "%Y-%m-%d").ok();
+    }
+
+    // "March 3rd" / "3rd of March": this year, or next if it has passed
+    let month = c.name("month").or(c.name("month2"))?.as_str();
+    let day: u32 = c.name("day").or(c.name("day2"))?.as_str().parse().ok()?;
+    let month = month_number(month)?;
+    let this_year = NaiveDate::from_ymd_opt(today.year(), month, day)?;
+    if this_year >= today {
+        Some(this_year)
+    } else {
+        NaiveDate::from_ymd_opt(today.year() + 1, month, day)
+    }
+}
+
+/// The first `weekday` after today
+fn next_weekday(today: NaiveDate, weekday: Weekday) -> NaiveDate {
+    let ahead = (weekday.num_days_from_monday() + 7 - today.weekday().num_days_from_monday()) % 7;
+    today + Duration::days(if ahead == 0 { 7 } else { ahead.into() })
+}
+
+fn month_number(name: &str) -> Option<u32> {
+    let prefix: String = name.to_lowercase().chars().take(3).collect();
+    [
+        "jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec",
+    ]
+    .iter()
+    .position(|m| *m == prefix)
+    .map(|i| i as u32 + 1)
+}
+
+/// A pending inbox item for the intent, with the transcript attached
+pub fn to_inbox_item(intent: &VoiceIntent, transcript: &Transcript) -> InboxItem {
+    let mut extra = Map::new();
+    extra.insert("source".into(), json!("voice"));
+    extra.insert("intent".into(), json!(intent.kind));
+    extra.insert("transcript".into(), json!(transcript));
+    if let Some(task_id) = &intent.task_id {
+        extra.insert("taskRef".into(), json!(task_id));
+    }
+    if let Some(due) = &intent.due_date {
+        extra.insert("dueDate".into(), json!(due));
+    }
+
+    InboxItem {
+        id: data::generate_id("inbox-voice"),
+        text: intent.text.clone(),
+        item_type: intent.kind.item_type().to_string(),
+        project: intent.project_id.clone(),
+        priority: intent.priority.clone(),
+        status: "pending".to_string(),
+        created_at: chrono::Utc::now().to_rfc3339(),
+        processed_at: None,
+        task_id: None,
+        read: false,
+        author: "user".to_string(),
+        parent_id: None,
+        replies: vec![],
+        extra,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn board() -> (ProjectsFile, TasksFile) {
+        let projects = json!({ "projects": [
+            { "id": "anycalc", "name": "AnyCalc" },
+            { "id": "orbit", "name": "Orbit Notes" }
+        ]});
+        let tasks = json!({ "tasks": [
+            { "id": "t-1", "projectId": "anycalc", "title": "Add dark mode toggle" },
+            { "id": "t-2", "projectId": "orbit", "title": "Sync offline edits" }
+        ]});
+        (
+            serde_json::from_value(projects).unwrap(),
+            serde_json::from_value(tasks).unwrap(),
+        )
+    }
+
+    fn extract(transcript: &str) -> VoiceIntent {
+        let (projects, tasks) = board();
+        let context = IntentContext {
+            projects: &projects,
+            tasks: &tasks,
+            // A Wednesday
+            today: NaiveDate::from_ymd_opt(2026, 10, 14).unwrap(),
+        };
+        RuleExtractor.extract(transcript, &context).unwrap()
+    }
+
+    #[test]
+    fn extracts_new_tasks_with_priority_and_due_date() {
+        let intent =
+            extract("Add a task for Any Calc: export history to CSV by Friday, it's urgent.");
+        assert_eq!(intent.kind, IntentKind::TaskCreation);
+        assert_eq!(intent.text, "Export history to CSV");
+        assert_eq!(intent.project_id.as_deref(), Some("anycalc"));
+        assert_eq!(intent.priority.as_deref(), Some("P0"));
+        assert_eq!(intent.due_date.as_deref(), Some("2026-10-16"));
+
+        let intent = extract("Remind me to renew the domain tomorrow");
+        assert_eq!(intent.kind, IntentKind::TaskCreation);
+        assert_eq!(intent.text, "Renew the domain");
+        assert_eq!(intent.due_date.as_deref(), Some("2026-10-15"));
+        assert_eq!(intent.priority, None);
+    }
+
+    #[test]
+    fn classifies_instructions_comments_and_questions() {
+        let intent = extract("Claude, refactor the sync code in Orbit Notes. High priority.");
+        assert_eq!(intent.kind, IntentKind::Instruction);
+        assert_eq!(
+            intent.text,
+            "Refactor the sync code in Orbit Notes. High priority."
+        );
+        assert_eq!(intent.project_id.as_deref(), Some("orbit"));
+        assert_eq!(intent.priority.as_deref(), Some("P1"));
+
+        let intent = extract("Comment on dark mode: the toggle is invisible on mobile");
+        assert_eq!(intent.kind, IntentKind::Comment);
+        assert_eq!(intent.text, "the toggle is invisible on mobile");
+        assert_eq!(intent.task_id.as_deref(), Some("t-1"));
+        assert_eq!(intent.project_id.as_deref(), Some("anycalc"));
+
+        let intent = extract("What's blocking the sync offline edits task?");
+        assert_eq!(intent.kind, IntentKind::Question);
+        assert_eq!(intent.task_id.as_deref(), Some("t-2"));
+    }
+
+    #[test]
+    fn resolves_due_dates_against_today() {
+        let due = |said: &str| extract(said).due_date.unwrap();
+
+        assert_eq!(due("Ship it next week"), "2026-10-19");
+        assert_eq!(due("Wrap up by end of the month"), "2026-10-31");
+        assert_eq!(due("Follow up in two weeks"), "2026-10-28");
+        assert_eq!(due("Send the invoice on Wednesday"), "2026-10-21");
+        // Already passed this year
+        assert_eq!(due("Renew the certificate by March 3rd"), "2027-03-03");
+
+        // Out of range rather than a panic
+        assert_eq!(extract("Archive it in 99999999999 days").due_date, None);
+        assert_eq!(
+            extract("Archive it in 9999999999999999999 weeks").due_date,
+            None
+        );
+    }
+
+    #[test]
+    fn inbox_item_carries_the_transcript() {
+        let transcript = Transcript {
+            text: "Remind me to renew the domain".to_string(),
+            segments: vec![],
+            backend: "groq".to_string(),
+            fallback: None,
+        };
+        let intent = extract(&transcript.text);
+
+        let item = to_inbox_item(&intent, &transcript);
+        assert_eq!(item.item_type, "task");
+        assert_eq!(item.status, "pending");
+        assert_eq!(item.extra["source"], "voice");
+        assert_eq!(item.extra["intent"], "taskCreation");
+        assert_eq!(item.extra["transcript"]["backend"], "groq");
+    }
+}
@@ -0,0 +1,97 @@
+//! Local Whisper: in-process when built with the `whisper` feature, with
+//! the whisper.cpp CLI as fallback
+
+use std::path::Path;
+use std::process::Command;
+
+use super::transcriber::{TranscribeError, Transcriber};
+use super::{transcript, EngineCache, Transcript, VoiceConfig};
+
+pub struct LocalWhisper {
+    config: VoiceConfig,
+    engines: EngineCache,
+}
+
+impl LocalWhisper {
+    pub fn new(config: VoiceConfig, engines: EngineCache) -> Self {
+        Self { config, engines }
+    }
+
+    /// Run the whisper CLI, reading its JSON output file
+    fn transcribe_cli(&self, audio_path: &Path, model_path: &Path) -> Result<Transcript, String> {
+        // whisper writes <base>.json next to the given output base
+        let output_base = audio_path.with_extension("");
+        let json_path = output_base.with_extension("json");
+
+        let output = Command::new(&self.config.whisper_path)
+            .args([
+                "-m",
+                model_path.to_str().unwrap(),
+                "-f",
+                audio_path.to_str().unwrap(),
+                "-oj", // output as JSON
+                "-of",
+                output_base.to_str().unwrap(),
+                "-l",
+                &self.config.language,
+            ])
+            .output()
+            .map_err(|e| format!("Failed to run Whisper: {}", e))?;
+
+        if !output.status.success() {
+            return Err(String::from_utf8_lossy(&output.stderr).to_string());
+        }
+
+        // Older builds ignore -oj; fall back to the timestamped lines on stdout
+        let segments = match std::fs::read_to_string(&json_path) {
+            Ok(json) => {
+                let _ = std::fs::remove_file(&json_path);
+                transcript::parse_cli_json(&json)?
+            }
+            Err(_) => transcript::parse_cli_stdout(&String::from_utf8_lossy(&output.stdout)),
+        };
+
+        Ok(Transcript::from_segments(segments, "whisper-cli"))
+    }
+}
+
+impl Transcriber for LocalWhisper {
+    fn name(&self) -> &str {
+        "local whisper"
+    }
+
+    /// The model exists and something can run it
+    fn is_available(&self) -> bool {
+        self.config.model_path().exists()
+            && (self.engines.is_available() || self.config.whisper_path.exists())
+    }
+
+    fn transcribe(&self, audio_path: &Path) -> Result<Transcript, TranscribeError> {
+        let model_path = self.config.model_path();
+
+        if !model_path.exists() {
+            return Err(format!(
+                "Whisper model {} is not installed",
+                self.config.model
+            )
+            .into());
+        }
+
+        if self.engines.is_available() {
+            match self
+                .engines
+                .transcribe(&model_path, audio_path, &self.config.language)
+            {
+                Ok(transcript) => return Ok(transcript),
+                Err(e) if self.config.whisper_path.exists() => {
+                    let reason = format!("In-process Whisper failed: {}", e);
+                    let transcript = self.transcribe_cli(audio_path, &model_path)?;
+                    return Ok(transcript.with_fallback(reason));
+                }
+                Err(e) => return Err(e.into()),
+            }
+        }
+
+        Ok(self.transcribe_cli(audio_path, &model_path)?)
+    }
+}
@@ -0,0 +1,549 @@
+//! Whisper model manager
+//!
+//! Lists the ggml models whisper.cpp publishes, downloads them into
+//! ~/.taskboard/models with progress reporting, and removes them. Downloads
+//! go to `<file>.part` first so an interrupted download resumes where it
+//! stopped (a part already holding the whole file, which the server
+//! answers with 416, goes straight to verification), and the finished
+//! file is checked against the SHA-256 the server publishes for it before
+//! it is moved into place. Hugging Face
+//! sends it as `X-Linked-Etag` on the redirect to its CDN, so it is read
+//! from a request that does not follow redirects.
+
+use std::fs::{self, File, OpenOptions};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use reqwest::blocking::{Client, Response};
+use reqwest::header::{HeaderMap, CONTENT_RANGE, ETAG, RANGE};
+use reqwest::redirect::Policy;
+use reqwest::StatusCode;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+pub const DEFAULT_BASE_URL: &str = "https://huggingface.co/ggerganov/whisper.cpp/resolve/main";
+
+/// How often progress is reported during a download
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Models offered for download: id used in config.json and the
+/// approximate download size in MB
+pub const CATALOG: &[(&str, u32)] = &[
+    ("tiny", 75),
+    ("tiny.en", 75),
+    ("tiny-q5_1", 31),
+    ("tiny.en-q5_1", 31),
+    ("base", 142),
+    ("base.en", 142),
+    ("base-q5_1", 57),
+    ("base.en-q5_1", 57),
+    ("small", 466),
+    ("small.en", 466),
+    ("small-q5_1", 181),
+    ("small.en-q5_1", 181),
+    ("medium", 1500),
+    ("medium.en", 1500),
+    ("medium-q5_0", 514),
+    ("medium.en-q5_0", 514),
+    ("large-v3-turbo", 1500),
+    ("large-v3-turbo-q5_0", 547),
+];
+
+/// File name of a model in the models directory
+pub fn file_name(id: &str) -> String {
+    format!("ggml-{}.bin", id)
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ModelEntry {
+    pub id: String,
+    pub file: String,
+    /// Download size for catalog models, file size once installed
+    pub size_mb: u32,
+    /// Only transcribes English (".en" models)
+    pub english_only: bool,
+    /// Quantised weights: smaller and faster, slightly less accurate
+    pub quantized: bool,
+    pub installed: bool,
+    /// Bytes already fetched by an interrupted download
+    pub partial_bytes: u64,
+    pub selected: bool,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct DownloadProgress {
+    pub model: String,
+    pub downloaded: u64,
+    pub total: Option<u64>,
+}
+
+pub struct ModelManager {
+    pub models_dir: PathBuf,
+    pub base_url: String,
+}
+
+impl ModelManager {
+    pub fn new(models_dir: PathBuf) -> Self {
+        Self {
+            models_dir,
+            base_url: DEFAULT_BASE_URL.to_string(),
+        }
+    }
+
+    pub fn model_path(&self, id: &str) -> PathBuf {
+        self.models_dir.join(file_name(id))
+    }
+
+    fn part_path(&self, id: &str) -> PathBuf {
+        self.models_dir.join(format!("{}.part", file_name(id)))
+    }
+
+    /// Catalog models plus any other ggml models found on disk
+    pub fn list(&self, selected: &str) -> Vec<ModelEntry> {
+        let mut entries: Vec<ModelEntry> = CATALOG
+            .iter()
+            .map(|&(id, size_mb)| self.entry(id, size_mb, selected))
+            .collect();
+
+        let mut extra: Vec<String> = fs::read_dir(&self.models_dir)
+            .into_iter()
+            .flatten()
+            .flatten()
+            .filter_map(|e| {
+                let name = e.file_name().to_string_lossy().to_string();
+                let id = name
+                    .strip_prefix("ggml-")?
+                    .strip_suffix(".bin")?
+                    .to_string();
+                (!CATALOG.iter().any(|&(known, _)| known == id)).then_some(id)
+            })
+            .collect();
+        extra.sort();
+        entries.extend(extra.iter().map(|id| self.entry(id, 0, selected)));
+
+        entries
+    }
+
+    fn entry(&self, id: &str, size_mb: u32, selected: &str) -> ModelEntry {
+        let installed = fs::metadata(self.model_path(id)).ok();
+        let variant = id.split('-').next().unwrap_or(id);
+
+        ModelEntry {
+            id: id.to_string(),
+            file: file_name(id),
+            size_mb: installed
+                .as_ref()
+                .map(|m| (m.len() / 1_000_000) as u32)
+                .unwrap_or(size_mb),
+            english_only: variant.ends_with(".en"),
+            quantized: id.contains("-q"),
+            installed: installed.is_some(),
+            partial_bytes: fs::metadata(self.part_path(id))
+                .map(|m| m.len())
+                .unwrap_or(0),
+            selected: id == selected,
+        }
+    }
+
+    /// Download a catalog model, resuming a partial download. Blocks.
+    pub fn download(
+        &self,
+        id: &str,
+        progress: &dyn Fn(DownloadProgress),
+    ) -> Result<PathBuf, String> {
+        check_known(id)?;
+        let path = self.model_path(id);
+        if path.exists() {
+            return Ok(path);
+        }
+
+        fs::create_dir_all(&self.models_dir)
+            .map_err(|e| format!("Failed to create models dir: {}", e))?;
+        let part = self.part_path(id);
+        let already = fs::metadata(&part).map(|m| m.len()).unwrap_or(0);
+
+        let url = format!("{}/{}", self.base_url, file_name(id));
+        let expected = published_sha256(&url)?;
+
+        let client = Client::builder()
+            .connect_timeout(Duration::from_secs(15))
+            .build()
+            .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
+        let mut request = client.get(&url);
+        if already > 0 {
+            request = request.header(RANGE, format!("bytes={}-", already));
+        }
+        let response = request
+            .send()
+            .map_err(|e| format!("Failed to download model: {}", e))?;
+
+        let status = response.status();
+        // The whole file arrived last time, but was never verified
+        let complete = already > 0
+            && status == StatusCode::RANGE_NOT_SATISFIABLE
+            && full_size(response.headers()) == Some(already);
+        if !status.is_success() && !complete {
+            return Err(format!(
+                "Failed to download model: server returned {}",
+                status
+            ));
+        }
+        let downloaded = if complete {
+            already
+        } else {
+            self.receive(id, response, already, progress)?
+        };
+
+        let actual = sha256_file(&part)?;
+        if actual != expected {
+            let _ = fs::remove_file(&part);
+            return Err(format!(
+                "Checksum mismatch for {} (expected {}, got {})",
+                id, expected, actual
+            ));
+        }
+
+        fs::rename(&part, &path).map_err(|e| format!("Failed to install model: {}", e))?;
+        progress(DownloadProgress {
+            model: id.to_string(),
+            downloaded,
+            total: Some(downloaded),
+        });
+        Ok(path)
+    }
+
+    /// Write the response body to the model's `.part` file; returns the
+    /// bytes it now holds
+    fn receive(
+        &self,
+        id: &str,
+        mut response: Response,
+        already: u64,
+        progress: &dyn Fn(DownloadProgress),
+    ) -> Result<u64, String> {
+        let status = response.status();
+        let part = self.part_path(id);
+
+        // 206 continues the partial file; a plain 200 starts over
+        let resumed = status == StatusCode::PARTIAL_CONTENT;
+        let mut downloaded = if resumed { already } else { 0 };
+        let total = response.content_length().map(|len| len + downloaded);
+        let mut file = OpenOptions::new()
+            .create(true)
+            .write(true)
+            .append(resumed)
+            .truncate(!resumed)
+            .open(&part)
+            .map_err(|e| format!("Failed to write model: {}", e))?;
+
+        let mut buf = vec![0u8; 64 * 1024];
+        let mut last_report = Instant::now();
+        progress(DownloadProgress {
+            model: id.to_string(),
+            downloaded,
+            total,
+        });
+        loop {
+            let n = response
+                .read(&mut buf)
+                .map_err(|e| format!("Download interrupted: {}", e))?;
+            if n == 0 {
+                break;
+            }
+            file.write_all(&buf[..n])
+                .map_err(|e| format!("Failed to write model: {}", e))?;
+            downloaded += n as u64;
+
+            if last_report.elapsed() >= PROGRESS_INTERVAL {
+                last_report = Instant::now();
+                progress(DownloadProgress {
+                    model: id.to_string(),
+                    downloaded,
+                    total,
+                });
+            }
+        }
+        file.flush()
+            .map_err(|e| format!("Failed to write model: {}", e))?;
+        drop(file);
+
+        if total.is_some_and(|t| downloaded < t) {
+            return Err("Download interrupted; it will resume next time".to_string());
+        }
+        Ok(downloaded)
+    }
+
+    /// Remove an installed catalog model and any partial download of it
+    pub fn delete(&self, id: &str) -> Result<(), String> {
+        check_known(id)?;
+        let path = self.model_path(id);
+        let part = self.part_path(id);
+        if !path.exists() && !part.exists() {
+            return Err(format!("Model {} is not installed", id));
+        }
+
+        for file in [path, part] {
+            if file.exists() {
+                fs::remove_file(&file).map_err(|e| format!("Failed to delete model: {}", e))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Only catalog ids are used to build paths in the models directory
+fn check_known(id: &str) -> Result<(), String> {
+    if CATALOG.iter().any(|&(known, _)| known == id) {
+        Ok(())
+    } else {
+        Err(format!("Unknown Whisper model: {}", id))
+    }
+}
+
+/// The full size from a 416's `Content-Range: bytes */<size>`
+fn full_size(headers: &HeaderMap) -> Option<u64> {
+    headers
+        .get(CONTENT_RANGE)?
+        .to_str()
+        .ok()?
+        .strip_prefix("bytes */")?
+        .parse()
+        .ok()
+}
+
+/// The SHA-256 the server publishes for `url`, from the first response
+/// (the redirect, on Hugging Face) rather than the CDN's, whose ETag is not
+/// a SHA-256
+fn published_sha256(url: &str) -> Result<String, String> {
+    let client = Client::builder()
+        .connect_timeout(Duration::from_secs(15))
+        .redirect(Policy::none())
+        .build()
+        .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
+    let response = client
+        .head(url)
+        .send()
+        .map_err(|e| format!("Failed to download model: {}", e))?;
+
+    let status = response.status();
+    if !status.is_success() && !status.is_redirection() {
+        return Err(format!(
+            "Failed to download model: server returned {}",
+            status
+        ));
+    }
+    expected_sha256(response.headers())
+        .ok_or_else(|| "The server did not publish a checksum for this model".to_string())
+}
+
+/// The file's SHA-256 as published by the server: Hugging Face serves LFS
+/// files with the hash as `X-Linked-Etag`; other servers may use `ETag`
+fn expected_sha256(headers: &HeaderMap) -> Option<String> {
+    [headers.get("x-linked-etag"), headers.get(ETAG)]
+        .into_iter()
+        .flatten()
+        .filter_map(|v| v.to_str().ok())
+        .map(|v| v.trim_start_matches("W/").trim_matches('"').to_lowercase())
+        .find(|v| v.len() == 64 && v.chars().all(|c| c.is_ascii_hexdigit()))
+}
+
+fn sha256_file(path: &Path) -> Result<String, String> {
+    let mut file = File::open(path).map_err(|e| format!("Failed to read model: {}", e))?;
+    let mut hasher = Sha256::new();
+    let mut buf = vec![0u8; 64 * 1024];
+
+    loop {
+        let n = file
+            .read(&mut buf)
+            .map_err(|e| format!("Failed to read model: {}", e))?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+    }
+
+    Ok(hasher
+        .finalize()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    const MODEL: &[u8] = b"ggml fake model weights, not really a model at all";
+
+    /// Serves ggml-tiny.bin the way Hugging Face does: a redirect carrying
+    /// the checksum, to a CDN URL that serves MODEL with an ETag of its own
+    /// and honours Range, answering 416 for a range past the end. Records
+    /// the Range header of each download.
+    struct ModelServer {
+        server: Arc<tiny_http::Server>,
+        ranges: Arc<Mutex<Vec<Option<String>>>>,
+    }
+
+    impl ModelServer {
+        fn start(sha: String) -> Self {
+            let server = Arc::new(tiny_http::Server::http("127.0.0.1:0").unwrap());
+            let ranges = Arc::new(Mutex::new(Vec::new()));
+
+            let (srv, log) = (server.clone(), ranges.clone());
+            std::thread::spawn(move || {
+                for request in srv.incoming_requests() {
+                    if !request.url().starts_with("/cdn/") {
+                        let headers = [
+                            format!("X-Linked-Etag: \"{}\"", sha),
+                            format!("Location: /cdn{}", request.url()),
+                        ];
+                        let mut response = tiny_http::Response::empty(302);
+                        for header in headers {
+                            response.add_header(header.parse::<tiny_http::Header>().unwrap());
+                        }
+                        let _ = request.respond(response);
+                        continue;
+                    }
+
+                    let range = request
+                        .headers()
+                        .iter()
+                        .find(|h| h.field.equiv("Range"))
+                        .map(|h| h.value.to_string());
+                    log.lock().unwrap().push(range.clone());
+
+                    let from = range
+                        .as_deref()
+                        .and_then(|r| r.strip_prefix("bytes=")?.strip_suffix('-')?.parse().ok())
+                        .unwrap_or(0usize);
+                    let etag = "ETag: \"cdn-7f3a\"".parse::<tiny_http::Header>().unwrap();
+                    if from >= MODEL.len() {
+                        let size = format!("Content-Range: bytes */{}", MODEL.len());
+                        let response = tiny_http::Response::empty(416)
+                            .with_header(size.parse::<tiny_http::Header>().unwrap());
+                        let _ = request.respond(response);
+                        continue;
+                    }
+                    let response = tiny_http::Response::from_data(&MODEL[from..])
+                        .with_status_code(if from > 0 { 206 } else { 200 })
+                        .with_header(etag);
+                    let _ = request.respond(response);
+                }
+            });
+
+            Self { server, ranges }
+        }
+
+        fn manager(&self, dir: &Path) -> ModelManager {
+            ModelManager {
+                models_dir: dir.to_path_buf(),
+                base_url: format!("http://{}", self.server.server_addr()),
+            }
+        }
+    }
+
+    impl Drop for ModelServer {
+        fn drop(&mut self) {
+            self.server.unblock();
+        }
+    }
+
+    fn sha(data: &[u8]) -> String {
+        Sha256::digest(data)
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect()
+    }
+
+    #[test]
+    fn resumes_verifies_and_deletes() {
+        let dir = tempfile::tempdir().unwrap();
+        let server = ModelServer::start(sha(MODEL));
+        let manager = server.manager(dir.path());
+
+        // An earlier attempt got the first 10 bytes
+        fs::write(manager.part_path("tiny"), &MODEL[..10]).unwrap();
+        assert_eq!(manager.list("tiny")[0].partial_bytes, 10);
+
+        let reports = Mutex::new(Vec::new());
+        let path = manager
+            .download("tiny", &|p| reports.lock().unwrap().push(p))
+            .unwrap();
+
+        assert_eq!(fs::read(&path).unwrap(), MODEL);
+        assert_eq!(
+            server.ranges.lock().unwrap().as_slice(),
+            [Some("bytes=10-".to_string())]
+        );
+        let last = reports.lock().unwrap().last().cloned().unwrap();
+        assert_eq!(last.downloaded, MODEL.len() as u64);
+        assert_eq!(last.total, Some(MODEL.len() as u64));
+
+        let tiny = &manager.list("tiny")[0];
+        assert!(tiny.installed && tiny.selected && tiny.partial_bytes == 0);
+
+        manager.delete("tiny").unwrap();
+        assert!(!manager.list("tiny")[0].installed);
+    }
+
+    #[test]
+    fn installs_a_part_that_is_already_complete() {
+        let dir = tempfile::tempdir().unwrap();
+        let server = ModelServer::start(sha(MODEL));
+        let manager = server.manager(dir.path());
+        fs::write(manager.part_path("tiny"), MODEL).unwrap();
+
+        let path = manager.download("tiny", &|_| {}).unwrap();
+        assert_eq!(fs::read(&path).unwrap(), MODEL);
+        assert!(!manager.part_path("tiny").exists());
+        assert_eq!(
+            server.ranges.lock().unwrap().as_slice(),
+            [Some(format!("bytes={}-", MODEL.len()))]
+        );
+    }
+
+    #[test]
+    fn only_deletes_catalog_models() {
+        let dir = tempfile::tempdir().unwrap();
+        let models = dir.path().join("models");
+        fs::create_dir_all(models.join("ggml-x")).unwrap();
+        fs::write(dir.path().join("notes.bin"), b"x").unwrap();
+        let manager = ModelManager::new(models);
+
+        // ggml-x/../../notes.bin is the file outside the models dir
+        let err = manager.delete("x/../../notes").unwrap_err();
+        assert_eq!(err, "Unknown Whisper model: x/../../notes");
+        assert!(dir.path().join("notes.bin").exists());
+    }
+
+    #[test]
+    fn rejects_a_corrupt_download() {
+        let dir = tempfile::tempdir().unwrap();
+        let server = ModelServer::start(sha(b"something else"));
+        let manager = server.manager(dir.path());
+
+        let err = manager.download("tiny", &|_| {}).unwrap_err();
+        assert!(err.contains("Checksum mismatch"), "{}", err);
+        assert!(!manager.model_path("tiny").exists());
+        assert!(!manager.part_path("tiny").exists());
+    }
+
+    #[test]
+    fn lists_variants_and_local_models() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("ggml-custom-finetune.bin"), b"x").unwrap();
+        let manager = ModelManager::new(dir.path().to_path_buf());
+
+        let models = manager.list("base.en");
+        let find = |id: &str| models.iter().find(|m| m.id == id).unwrap();
+
+        assert!(find("base.en").selected && find("base.en").english_only);
+        assert!(find("base.en-q5_1").english_only && find("base.en-q5_1").quantized);
+        assert!(!find("base").english_only && !find("base").quantized);
+        assert!(find("custom-finetune").installed);
+    }
+}
@@ -0,0 +1,301 @@
+//! OpenAI-compatible `/audio/transcriptions` client (Groq, OpenAI, or a
+//! self-hosted server)
+
+use std::path::Path;
+use std::time::Duration;
+
+use reqwest::blocking::{multipart, Client};
+use serde::Deserialize;
+
+use super::transcriber::{TranscribeError, Transcriber};
+use super::transcript::Segment;
+use super::Transcript;
+use crate::config::{GroqConfig, OpenAiCompatibleConfig};
+
+const GROQ_BASE_URL: &str = "https://api.groq.com/openai/v1";
+
+/// Hosted transcription can take a while for long recordings
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(120);
+
+pub struct OpenAiTranscriber {
+    name: String,
+    base_url: String,
+    api_key: Option<String>,
+    model: String,
+    language: String,
+}
+
+impl OpenAiTranscriber {
+    pub fn new(
+        name: &str,
+        base_url: &str,
+        api_key: Option<String>,
+        model: &str,
+        language: &str,
+    ) -> Self {
+        Self {
+            name: name.to_string(),
+            base_url: base_url.trim_end_matches('/').to_string(),
+            api_key: api_key.filter(|k| !k.trim().is_empty()),
+            model: model.to_string(),
+            language: language.to_string(),
+        }
+    }
+
+    pub fn groq(config: &GroqConfig, language: &str) -> Self {
+        Self::new(
+            "groq",
+            GROQ_BASE_URL,
+            config.api_key.clone(),
+            &config.model,
+            language,
+        )
+    }
+
+    pub fn openai_compatible(config: &OpenAiCompatibleConfig, language: &str) -> Self {
+        Self::new(
+            "openai-compatible",
+            &config.base_url,
+            config.api_key.clone(),
+            &config.model,
+            language,
+        )
+    }
+}
+
+/// `response_format=verbose_json` body
+#[derive(Deserialize)]
+struct VerboseResponse {
+    #[serde(default)]
+    segments: Vec<VerboseSegment>,
+    #[serde(default)]
+    text: String,
+}
+
+#[derive(Deserialize)]
+struct VerboseSegment {
+    start: f64,
+    end: f64,
+    text: String,
+    avg_logprob: Option<f64>,
+}
+
+#[derive(Deserialize)]
+struct ErrorResponse {
+    error: ErrorBody,
+}
+
+#[derive(Deserialize)]
+struct ErrorBody {
+    message: String,
+}
+
+fn parse_response(body: &str, backend: &str) -> Result<Transcript, String> {
+    let response: VerboseResponse = serde_json::from_str(body)
+        .map_err(|e| format!("Failed to parse transcription response: {}", e))?;
+
+    if response.segments.is_empty() {
+        // Servers that ignore verbose_json return the text alone
+        let segments = (!response.text.trim().is_empty())
+            .then(|| Segment {
+                start_ms: 0,
+                end_ms: 0,
+                text: response.text.trim().to_string(),
+                confidence: None,
+            })
+            .into_iter()
+            .collect();
+        return Ok(Transcript::from_segments(segments, backend));
+    }
+
+    let segments = response
+        .segments
+        .into_iter()
+        .map(|s| Segment {
+            start_ms: (s.start * 1000.0).round() as u64,
+            end_ms: (s.end * 1000.0).round() as u64,
+            text: s.text.trim().to_string(),
+            confidence: s.avg_logprob.map(|p| p.exp().clamp(0.0, 1.0) as f32),
+        })
+        .collect();
+
+    Ok(Transcript::from_segments(segments, backend))
+}
+
+impl Transcriber for OpenAiTranscriber {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn is_available(&self) -> bool {
+        true
+    }
+
+    fn transcribe(&self, audio_path: &Path) -> Result<Transcript, TranscribeError> {
+        let audio =
+            std::fs::read(audio_path).map_err(|e| format!("Failed to read audio: {}", e))?;
+        let file_name = audio_path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_else(|| "audio.wav".to_string());
+
+        let mut form = multipart::Form::new()
+            .text("model", self.model.clone())
+            .text("response_format", "verbose_json")
+            .part("file", multipart::Part::bytes(audio).file_name(file_name));
+        // Left out, the server detects the language itself
+        if self.language != "auto" {
+            form = form.text("language", self.language.clone());
+        }
+
+        let client = Client::builder()
+            .timeout(REQUEST_TIMEOUT)
+            .build()
+            .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
+
+        let mut request = client
+            .post(format!("{}/audio/transcriptions", self.base_url))
+            .multipart(form);
+        if let Some(key) = &self.api_key {
+            request = request.bearer_auth(key);
+        }
+
+        // A refused connection can surface as a body error: the upload
+        // fails before the connect error is reported
+        let response = request.send().map_err(|e| {
+            if e.is_connect() || e.is_timeout() || e.is_body() {
+                TranscribeError::Offline(e.to_string())
+            } else {
+                TranscribeError::Failed(format!("Failed to reach {}: {}", self.name, e))
+            }
+        })?;
+
+        let status = response.status();
+        let body = response
+            .text()
+            .map_err(|e| format!("Failed to read {} response: {}", self.name, e))?;
+
+        if !status.is_success() {
+            let message = serde_json::from_str::<ErrorResponse>(&body)
+                .map(|r| r.error.message)
+                .unwrap_or(body);
+            return Err(format!("{} returned {}: {}", self.name, status, message).into());
+        }
+
+        Ok(parse_response(&body, &self.name)?)
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    /// A received request: path, Authorization header and raw body
+    pub struct Recorded {
+        pub url: String,
+        pub authorization: Option<String>,
+        pub body: String,
+    }
+
+    /// Local HTTP server answering every request with one canned response
+    pub struct StandIn {
+        server: Arc<tiny_http::Server>,
+        pub requests: Arc<Mutex<Vec<Recorded>>>,
+    }
+
+    impl StandIn {
+        pub fn start(status: u16, body: &'static str) -> Self {
+            let server = Arc::new(tiny_http::Server::http("127.0.0.1:0").unwrap());
+            let requests = Arc::new(Mutex::new(Vec::new()));
+
+            let (srv, log) = (server.clone(), requests.clone());
+            std::thread::spawn(move || {
+                for mut request in srv.incoming_requests() {
+                    let mut content = Vec::new();
+                    let _ = request.as_reader().read_to_end(&mut content);
+                    log.lock().unwrap().push(Recorded {
+                        url: request.url().to_string(),
+                        authorization: request
+                            .headers()
+                            .iter()
+                            .find(|h| h.field.equiv("Authorization"))
+                            .map(|h| h.value.to_string()),
+                        body: String::from_utf8_lossy(&content).to_string(),
+                    });
+
+                    let header = "Content-Type: application/json".parse::<tiny_http::Header>();
+                    let response = tiny_http::Response::from_string(body)
+                        .with_status_code(status)
+                        .with_header(header.unwrap());
+                    let _ = request.respond(response);
+                }
+            });
+
+            Self { server, requests }
+        }
+
+        pub fn base_url(&self) -> String {
+            format!("http://{}/v1", self.server.server_addr())
+        }
+    }
+
+    impl Drop for StandIn {
+        fn drop(&mut self) {
+            self.server.unblock();
+        }
+    }
+
+    const VERBOSE: &str = r#"{
+        "task": "transcribe",
+        "language": "english",
+        "duration": 3.0,
+        "text": " Add a task to review the PR.",
+        "segments": [
+            { "id": 0, "start": 0.5, "end": 1.24, "text": " Add a task", "avg_logprob": -0.1 },
+            { "id": 1, "start": 1.24, "end": 2.8, "text": " to review the PR.", "avg_logprob": -0.4 }
+        ]
+    }"#;
+
+    #[test]
+    fn posts_audio_and_parses_segments() {
+        let server = StandIn::start(200, VERBOSE);
+        let transcriber = OpenAiTranscriber::new(
+            "groq",
+            &server.base_url(),
+            Some("gsk_test".into()),
+            "whisper-large-v3-turbo",
+            "en",
+        );
+        let wav = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/voice/tone.wav");
+
+        let transcript = transcriber.transcribe(&wav).unwrap();
+        assert_eq!(transcript.text, "Add a task to review the PR.");
+        assert_eq!(transcript.backend, "groq");
+        assert_eq!(transcript.segments[1].start_ms, 1240);
+        let confidence = transcript.segments[0].confidence.unwrap();
+        assert!((confidence - 0.905).abs() < 0.01);
+
+        let requests = server.requests.lock().unwrap();
+        assert_eq!(requests[0].url, "/v1/audio/transcriptions");
+        assert_eq!(
+            requests[0].authorization.as_deref(),
+            Some("Bearer gsk_test")
+        );
+        assert!(requests[0].body.contains("whisper-large-v3-turbo"));
+        assert!(requests[0].body.contains("verbose_json"));
+        assert!(requests[0].body.contains("name=\"language\""));
+    }
+
+    #[test]
+    fn leaves_the_language_out_when_detecting_it() {
+        let server = StandIn::start(200, VERBOSE);
+        let transcriber =
+            OpenAiTranscriber::new("groq", &server.base_url(), None, "whisper-1", "auto");
+        let wav = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/voice/tone.wav");
+
+        transcriber.transcribe(&wav).unwrap();
+        let requests = server.requests.lock().unwrap();
+        assert!(!requests[0].body.contains("name=\"language\""));
+    }
+}
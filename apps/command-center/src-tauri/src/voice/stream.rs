@@ -0,0 +1,459 @@
+//! Streaming transcription
+//!
+//! While a capture records, a worker thread cuts the audio into chunks of a
+//! few seconds and transcribes each as soon as it is complete, reporting the
+//! text so far as a `voice-partial` event. When the capture ends the rest is
+//! transcribed and the joined transcript goes out as `voice-final`. Chunks
+//! are cut at the quietest moment near the boundary so words are not split.
+
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver};
+use std::sync::Arc;
+use std::time::Duration;
+
+use serde::Serialize;
+
+use super::audio::{self, WHISPER_SAMPLE_RATE};
+use super::capture::{Capture, Recording, StopReason};
+use super::{TranscriberChain, Transcript};
+
+const RATE: usize = WHISPER_SAMPLE_RATE as usize;
+
+/// How often the worker checks for new audio
+const POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+/// Span around a chunk boundary searched for a quiet place to cut
+const CUT_WINDOW: usize = RATE / 2;
+
+/// 30 ms frames when looking for the quietest cut point
+const CUT_FRAME: usize = RATE * 30 / 1000;
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct VoicePartial {
+    pub session_id: String,
+    /// Everything transcribed so far
+    pub text: String,
+    /// Chunks transcribed so far
+    pub chunks: usize,
+}
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct VoiceFinal {
+    pub session_id: String,
+    pub transcript: Option<Transcript>,
+    pub stopped_by: Option<StopReason>,
+    pub cancelled: bool,
+    pub error: Option<String>,
+}
+
+pub enum VoiceEvent {
+    Partial(VoicePartial),
+    Final(VoiceFinal),
+}
+
+/// Where the worker reports progress (the app's event bus, or a test)
+pub type EventSink = Box<dyn Fn(VoiceEvent) + Send>;
+
+/// Given the whole recording and its transcript before `voice-final` goes
+/// out, to keep them
+pub type KeepRecording = Box<dyn FnOnce(&Recording, &Transcript) + Send>;
+
+/// A streaming capture in progress
+pub struct VoiceSession {
+    pub id: String,
+    capture_stop: Arc<AtomicBool>,
+    cancelled: Arc<AtomicBool>,
+    done: Arc<AtomicBool>,
+    result: Receiver<Result<Transcript, String>>,
+}
+
+impl VoiceSession {
+    /// Start transcribing `capture` in `chunk` sized pieces
+    pub fn start(
+        id: String,
+        capture: Capture,
+        chain: TranscriberChain,
+        chunk: Duration,
+        sink: EventSink,
+        keep: Option<KeepRecording>,
+    ) -> Self {
+        let capture_stop = Arc::new(AtomicBool::new(false));
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let done = Arc::new(AtomicBool::new(false));
+        let (tx, result) = mpsc::channel();
+
+        let worker = Worker {
+            id: id.clone(),
+            chain,
+            chunk_len: (chunk.as_secs_f64() * RATE as f64) as usize,
+            sink,
+            keep,
+            pieces: Vec::new(),
+            offset: 0,
+        };
+        let (stop, cancel, finished) = (capture_stop.clone(), cancelled.clone(), done.clone());
+        std::thread::spawn(move || {
+            let _ = tx.send(worker.run(capture, &stop, &cancel));
+            finished.store(true, Ordering::SeqCst);
+        });
+
+        Self {
+            id,
+            capture_stop,
+            cancelled,
+            done,
+            result,
+        }
+    }
+
+    /// Stop recording and wait for the final transcript
+    pub fn finish(self) -> Result<Transcript, String> {
+        self.capture_stop.store(true, Ordering::SeqCst);
+        self.wait()
+    }
+
+    /// Wait for the capture to end by itself and return the transcript
+    pub fn wait(self) -> Result<Transcript, String> {
+        self.result
+            .recv()
+            .map_err(|_| "Voice session ended unexpectedly".to_string())?
+    }
+
+    /// Stop recording and discard everything
+    pub fn cancel(self) {
+        self.cancelled.store(true, Ordering::SeqCst);
+    }
+
+    /// Whether the session ended by itself (silence, length limit)
+    pub fn is_finished(&self) -> bool {
+        self.done.load(Ordering::SeqCst)
+    }
+}
+
+/// Voice sessions by id. Several can record at once, each into its own
+/// chunk files, and each is stopped or cancelled by its id.
+#[derive(Default)]
+pub struct SessionRegistry {
+    /// Oldest first
+    sessions: Vec<VoiceSession>,
+}
+
+impl SessionRegistry {
+    /// Track a new session, forgetting any that ended by themselves
+    pub fn insert(&mut self, session: VoiceSession) {
+        self.sessions.retain(|s| !s.is_finished());
+        self.sessions.push(session);
+    }
+
+    /// Remove the session `id`, or the latest one when `id` is None
+    pub fn take(&mut self, id: Option<&str>) -> Option<VoiceSession> {
+        let index = match id {
+            Some(id) => self.sessions.iter().position(|s| s.id == id)?,
+            None => self.sessions.len().checked_sub(1)?,
+        };
+        Some(self.sessions.remove(index))
+    }
+}
+
+struct Worker {
+    id: String,
+    chain: TranscriberChain,
+    chunk_len: usize,
+    sink: EventSink,
+    keep: Option<KeepRecording>,
+    /// (offset ms, transcript) per transcribed chunk
+    pieces: Vec<(u64, Transcript)>,
+    /// First sample not yet transcribed
+    offset: usize,
+}
+
+impl Worker {
+    fn run(
+        mut self,
+        capture: Capture,
+        stop: &AtomicBool,
+        cancelled: &AtomicBool,
+    ) -> Result<Transcript, String> {
+        // Transcribe complete chunks while recording
+        while !capture.is_finished() {
+            if cancelled.load(Ordering::SeqCst) {
+                let _ = capture.stop();
+                return Err(self.cancel());
+            }
+            if stop.load(Ordering::SeqCst) {
+                capture.request_stop();
+            }
+
+            if capture.len() >= self.offset + self.chunk_len + CUT_WINDOW {
+                let window = capture.audio(self.offset..self.offset + self.chunk_len + CUT_WINDOW);
+                let cut = quietest_cut(&window, self.chunk_len);
+                if let Err(e) = self.transcribe_chunk(&window[..cut]) {
+                    capture.request_stop();
+                    let _ = capture.wait();
+                    return Err(self.fail(None, e));
+                }
+            } else {
+                std::thread::sleep(POLL_INTERVAL);
+            }
+        }
+
+        let recording = match capture.wait() {
+            Ok(recording) => recording,
+            Err(e) => return Err(self.fail(None, e)),
+        };
+
+        // Then whatever is left, still in chunk sized pieces
+        let mut rest = &recording.samples[self.offset.min(recording.samples.len())..];
+        while !rest.is_empty() {
+            if cancelled.load(Ordering::SeqCst) {
+                return Err(self.cancel());
+            }
+
+            let cut = if rest.len() > self.chunk_len + CUT_WINDOW {
+                quietest_cut(&rest[..self.chunk_len + CUT_WINDOW], self.chunk_len)
+            } else {
+                rest.len()
+            };
+            if let Err(e) = self.transcribe_chunk(&rest[..cut]) {
+                return Err(self.fail(Some(recording.stopped_by), e));
+            }
+            rest = &rest[cut..];
+        }
+
+        let transcript = Transcript::concat(&self.pieces);
+        if let Some(keep) = self.keep.take() {
+            keep(&recording, &transcript);
+        }
+        (self.sink)(VoiceEvent::Final(VoiceFinal {
+            session_id: self.id.clone(),
+            transcript: Some(transcript.clone()),
+            stopped_by: Some(recording.stopped_by),
+            cancelled: false,
+            error: None,
+        }));
+        Ok(transcript)
+    }
+
+    fn transcribe_chunk(&mut self, samples: &[f32]) -> Result<(), String> {
+        let start_ms = (self.offset * 1000 / RATE) as u64;
+        self.offset += samples.len();
+
+        let path = self.chunk_path();
+        audio::write_wav_16k_mono(&path, samples)?;
+        let result = self.chain.transcribe(&path);
+        let _ = std::fs::remove_file(&path);
+
+        self.pieces.push((start_ms, result?));
+        (self.sink)(VoiceEvent::Partial(VoicePartial {
+            session_id: self.id.clone(),
+            text: Transcript::concat(&self.pieces).text,
+            chunks: self.pieces.len(),
+        }));
+        Ok(())
+    }
+
+    fn chunk_path(&self) -> PathBuf {
+        std::env::temp_dir().join(format!("taskboard-{}-{}.wav", self.id, self.pieces.len()))
+    }
+
+    fn cancel(&self) -> String {
+        (self.sink)(VoiceEvent::Final(VoiceFinal {
+            session_id: self.id.clone(),
+            transcript: None,
+            stopped_by: None,
+            cancelled: true,
+            error: None,
+        }));
+        "Voice capture cancelled".to_string()
+    }
+
+    fn fail(&self, stopped_by: Option<StopReason>, error: String) -> String {
+        (self.sink)(VoiceEvent::Final(VoiceFinal {
+            session_id: self.id.clone(),
+            transcript: None,
+            stopped_by,
+            cancelled: false,
+            error: Some(error.clone()),
+        }));
+        error
+    }
+}
+
+/// Where to end a chunk: the middle of the quietest frame within half a
+/// `CUT_WINDOW` of `target`, or `target` if the window is too short
+fn quietest_cut(window: &[f32], target: usize) -> usize {
+    let start = target.saturating_sub(CUT_WINDOW / 2);
+    let end = (target + CUT_WINDOW / 2).min(window.len());
+
+    (start..end.saturating_sub(CUT_FRAME))
+        .step_by(CUT_FRAME)
+        .min_by(|&a, &b| {
+            energy(&window[a..a + CUT_FRAME]).total_cmp(&energy(&window[b..b + CUT_FRAME]))
+        })
+        .map(|frame| frame + CUT_FRAME / 2)
+        .unwrap_or(target.min(window.len()))
+}
+
+fn energy(frame: &[f32]) -> f32 {
+    frame.iter().map(|s| s * s).sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::voice::capture::{CaptureOptions, WavSource};
+    use crate::voice::transcriber::{TranscribeError, Transcriber};
+    use crate::voice::transcript::Segment;
+    use std::path::Path;
+    use std::sync::Mutex;
+
+    /// Names each chunk by its length in ms, one segment spanning it
+    struct ChunkNamer;
+
+    impl Transcriber for ChunkNamer {
+        fn name(&self) -> &str {
+            "namer"
+        }
+
+        fn is_available(&self) -> bool {
+            true
+        }
+
+        fn transcribe(&self, audio_path: &Path) -> Result<Transcript, TranscribeError> {
+            let wav = audio::read_wav(audio_path)?;
+            let ms = (wav.samples.len() * 1000 / RATE) as u64;
+            let segment = Segment {
+                start_ms: 0,
+                end_ms: ms,
+                text: format!("[{}]", ms),
+                confidence: None,
+            };
+            Ok(Transcript::from_segments(vec![segment], "namer"))
+        }
+    }
+
+    fn session(realtime: bool, events: Arc<Mutex<Vec<VoiceEvent>>>) -> VoiceSession {
+        session_with_id("voice-1", realtime, events)
+    }
+
+    fn session_with_id(
+        id: &str,
+        realtime: bool,
+        events: Arc<Mutex<Vec<VoiceEvent>>>,
+    ) -> VoiceSession {
+        let source = WavSource {
+            path: Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/voice/tone.wav"),
+            realtime,
+        };
+        let options = CaptureOptions {
+            vad: None,
+            ..CaptureOptions::default()
+        };
+        let capture = Capture::start(Box::new(source), options);
+        let chain = TranscriberChain::new(Box::new(ChunkNamer), None);
+
+        VoiceSession::start(
+            id.into(),
+            capture,
+            chain,
+            Duration::from_secs(1),
+            Box::new(move |e| events.lock().unwrap().push(e)),
+            None,
+        )
+    }
+
+    #[test]
+    fn emits_partials_then_a_joined_final() {
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let transcript = session(false, events.clone()).wait().unwrap();
+
+        // 3 s of audio in ~1 s chunks, segments shifted to recording time
+        let ends: Vec<u64> = transcript.segments.iter().map(|s| s.end_ms).collect();
+        assert!(ends.len() >= 3, "{:?}", ends);
+        assert_eq!(*ends.last().unwrap(), 3000);
+        assert!(ends.windows(2).all(|w| w[0] < w[1]));
+
+        let events = events.lock().unwrap();
+        let partials: Vec<&VoicePartial> = events
+            .iter()
+            .filter_map(|e| match e {
+                VoiceEvent::Partial(p) => Some(p),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(partials.len(), ends.len());
+        assert!(partials.iter().all(|p| p.session_id == "voice-1"));
+        assert!(transcript.text.starts_with(&partials[0].text));
+
+        match events.last() {
+            Some(VoiceEvent::Final(f)) => {
+                assert_eq!(f.transcript.as_ref(), Some(&transcript));
+                assert_eq!(f.stopped_by, Some(StopReason::EndOfInput));
+            }
+            _ => panic!("last event should be final"),
+        }
+    }
+
+    #[test]
+    fn cancel_discards_the_recording() {
+        let events = Arc::new(Mutex::new(Vec::new()));
+        session(true, events.clone()).cancel();
+
+        // The worker notices within a poll interval
+        std::thread::sleep(POLL_INTERVAL * 3);
+        let events = events.lock().unwrap();
+        assert!(matches!(
+            events.last(),
+            Some(VoiceEvent::Final(VoiceFinal {
+                cancelled: true,
+                transcript: None,
+                ..
+            }))
+        ));
+    }
+
+    #[test]
+    fn overlapping_sessions_are_kept_apart() {
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let mut registry = SessionRegistry::default();
+        registry.insert(session_with_id("voice-1", true, events.clone()));
+        registry.insert(session_with_id("voice-2", false, events.clone()));
+
+        registry.take(Some("voice-1")).unwrap().cancel();
+        let latest = registry.take(None).unwrap();
+        assert_eq!(latest.id, "voice-2");
+        assert!(registry.take(None).is_none());
+
+        // The other session carries on and finishes normally
+        let transcript = latest.wait().unwrap();
+        assert_eq!(transcript.segments.last().unwrap().end_ms, 3000);
+        std::thread::sleep(POLL_INTERVAL * 3);
+        let events = events.lock().unwrap();
+        let finals: Vec<(&str, bool)> = events
+            .iter()
+            .filter_map(|e| match e {
+                VoiceEvent::Final(f) => Some((f.session_id.as_str(), f.cancelled)),
+                _ => None,
+            })
+            .collect();
+        assert!(finals.contains(&("voice-1", true)));
+        assert!(finals.contains(&("voice-2", false)));
+    }
+
+    #[test]
+    fn cuts_chunks_in_the_quietest_place() {
+        let mut window = vec![0.5f32; RATE + CUT_WINDOW];
+        let quiet = RATE - RATE / 10;
+        window[quiet..quiet + CUT_FRAME * 2].fill(0.0);
+
+        let cut = quietest_cut(&window, RATE);
+        assert!(
+            (quiet..quiet + CUT_FRAME * 2).contains(&cut),
+            "cut at {}",
+            cut
+        );
+    }
+}
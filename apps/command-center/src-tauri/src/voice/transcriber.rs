@@ -0,0 +1,171 @@
+//! Transcription backends
+//!
+//! Voice capture goes through a `Transcriber`: local Whisper, Groq, or any
+//! OpenAI-compatible endpoint, chosen from config.json. A hosted backend
+//! that cannot be reached falls back to local Whisper so capture keeps
+//! working offline.
+
+use std::fmt;
+use std::path::Path;
+
+use super::local::LocalWhisper;
+use super::openai::OpenAiTranscriber;
+use super::{EngineCache, Transcript, VoiceConfig};
+use crate::config::AppConfig;
+
+/// Why a transcription failed
+#[derive(Debug)]
+pub enum TranscribeError {
+    /// The backend could not be reached (no network, DNS, timeout)
+    Offline(String),
+    Failed(String),
+}
+
+impl fmt::Display for TranscribeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TranscribeError::Offline(e) => write!(f, "Transcription service unreachable: {}", e),
+            TranscribeError::Failed(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl From<String> for TranscribeError {
+    fn from(e: String) -> Self {
+        TranscribeError::Failed(e)
+    }
+}
+
+/// Something that turns an audio file into a transcript. Calls block, so
+/// run them off the async runtime.
+pub trait Transcriber: Send + Sync {
+    /// Backend name reported in `Transcript::backend`
+    fn name(&self) -> &str;
+
+    /// Whether this backend is set up well enough to try
+    fn is_available(&self) -> bool;
+
+    fn transcribe(&self, audio_path: &Path) -> Result<Transcript, TranscribeError>;
+}
+
+/// The configured backend plus local Whisper to fall back on
+pub struct TranscriberChain {
+    primary: Box<dyn Transcriber>,
+    fallback: Option<Box<dyn Transcriber>>,
+}
+
+impl TranscriberChain {
+    /// Groq first, then an OpenAI-compatible endpoint, then local Whisper
+    pub fn from_config(config: &AppConfig, voice: &VoiceConfig, engines: &EngineCache) -> Self {
+        let local = LocalWhisper::new(voice.clone(), engines.clone());
+        let integrations = &config.integrations;
+
+        let remote = if integrations.groq.transcribes_voice() {
+            Some(OpenAiTranscriber::groq(&integrations.groq, &voice.language))
+        } else if integrations.openai_compatible.transcribes_voice() {
+            Some(OpenAiTranscriber::openai_compatible(
+                &integrations.openai_compatible,
+                &voice.language,
+            ))
+        } else {
+            None
+        };
+
+        match remote {
+            Some(remote) => Self::new(Box::new(remote), Some(Box::new(local))),
+            None => Self::new(Box::new(local), None),
+        }
+    }
+
+    pub fn new(primary: Box<dyn Transcriber>, fallback: Option<Box<dyn Transcriber>>) -> Self {
+        Self { primary, fallback }
+    }
+
+    pub fn is_available(&self) -> bool {
+        self.primary.is_available() || self.fallback.as_ref().is_some_and(|f| f.is_available())
+    }
+
+    /// Transcribe with the primary backend, using the fallback only when
+    /// the primary is offline
+    pub fn transcribe(&self, audio_path: &Path) -> Result<Transcript, String> {
+        match self.primary.transcribe(audio_path) {
+            Ok(transcript) => Ok(transcript),
+            Err(TranscribeError::Offline(e)) => match &self.fallback {
+                Some(fallback) if fallback.is_available() => {
+                    let reason = format!("{} unreachable: {}", self.primary.name(), e);
+                    fallback
+                        .transcribe(audio_path)
+                        .map(|transcript| transcript.with_fallback(reason))
+                        .map_err(|e| e.to_string())
+                }
+                _ => Err(TranscribeError::Offline(e).to_string()),
+            },
+            Err(e) => Err(e.to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::voice::openai::tests::StandIn;
+
+    /// Local backend stand-in that echoes a fixed transcript
+    struct Echo;
+
+    impl Transcriber for Echo {
+        fn name(&self) -> &str {
+            "echo"
+        }
+
+        fn is_available(&self) -> bool {
+            true
+        }
+
+        fn transcribe(&self, _audio_path: &Path) -> Result<Transcript, TranscribeError> {
+            Ok(Transcript::from_segments(vec![], "echo"))
+        }
+    }
+
+    fn remote(base_url: &str) -> Box<dyn Transcriber> {
+        Box::new(OpenAiTranscriber::new(
+            "stand-in",
+            base_url,
+            Some("key".into()),
+            "whisper-1",
+            "en",
+        ))
+    }
+
+    fn wav() -> std::path::PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/voice/tone.wav")
+    }
+
+    #[test]
+    fn falls_back_to_local_when_offline() {
+        // Bind and drop to get a port nothing listens on
+        let port = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let chain = TranscriberChain::new(
+            remote(&format!("http://127.0.0.1:{}/v1", port)),
+            Some(Box::new(Echo)),
+        );
+
+        let transcript = chain.transcribe(&wav()).unwrap();
+        assert_eq!(transcript.backend, "echo");
+        let reason = transcript.fallback.unwrap();
+        assert!(reason.starts_with("stand-in unreachable"), "{}", reason);
+    }
+
+    #[test]
+    fn api_errors_do_not_fall_back() {
+        let server = StandIn::start(401, r#"{"error":{"message":"Invalid API Key"}}"#);
+        let chain = TranscriberChain::new(remote(&server.base_url()), Some(Box::new(Echo)));
+
+        let err = chain.transcribe(&wav()).unwrap_err();
+        assert!(err.contains("Invalid API Key"), "{}", err);
+    }
+}
@@ -0,0 +1,186 @@
+//! Transcription results
+//!
+//! Every backend returns the same shape: the full text plus timed segments.
+//! The CLI fallback reads whisper.cpp's JSON output (or, failing that, the
+//! timestamped lines on stdout) instead of taking stdout verbatim, so log
+//! lines never end up in the transcript.
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Segment {
+    pub start_ms: u64,
+    pub end_ms: u64,
+    pub text: String,
+    /// Mean token probability (0–1), when the backend reports it
+    pub confidence: Option<f32>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Transcript {
+    pub text: String,
+    pub segments: Vec<Segment>,
+    /// Which backend produced it ("whisper-rs", "whisper-cli", ...)
+    pub backend: String,
+    /// Why the preferred backend was passed over, when it was
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fallback: Option<String>,
+}
+
+impl Transcript {
+    pub fn from_segments(segments: Vec<Segment>, backend: &str) -> Self {
+        let text = segments
+            .iter()
+            .map(|s| s.text.trim())
+            .filter(|t| !t.is_empty())
+            .collect::<Vec<_>>()
+            .join(" ");
+
+        Self {
+            text,
+            segments,
+            backend: backend.to_string(),
+            fallback: None,
+        }
+    }
+
+    /// Note that a preferred backend was passed over, and why
+    pub fn with_fallback(mut self, reason: String) -> Self {
+        self.fallback = Some(match self.fallback {
+            Some(inner) => format!("{}; {}", reason, inner),
+            None => reason,
+        });
+        self
+    }
+
+    /// Join transcripts of consecutive pieces of one recording, shifting
+    /// each piece's segments by the offset (ms) it started at
+    pub fn concat(pieces: &[(u64, Transcript)]) -> Self {
+        let segments = pieces
+            .iter()
+            .flat_map(|(offset, piece)| {
+                piece.segments.iter().map(move |s| Segment {
+                    start_ms: s.start_ms + offset,
+                    end_ms: s.end_ms + offset,
+                    ..s.clone()
+                })
+            })
+            .collect();
+        let backend = pieces
+            .first()
+            .map(|(_, p)| p.backend.as_str())
+            .unwrap_or_default();
+
+        Self {
+            fallback: pieces.iter().find_map(|(_, p)| p.fallback.clone()),
+            ..Self::from_segments(segments, backend)
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct CliOutput {
+    transcription: Vec<CliSegment>,
+}
+
+#[derive(Deserialize)]
+struct CliSegment {
+    offsets: CliOffsets,
+    text: String,
+}
+
+#[derive(Deserialize)]
+struct CliOffsets {
+    from: u64,
+    to: u64,
+}
+
+/// Parse the file written by `whisper-cli -oj`
+pub fn parse_cli_json(json: &str) -> Result<Vec<Segment>, String> {
+    let output: CliOutput =
+        serde_json::from_str(json).map_err(|e| format!("Failed to parse Whisper output: {}", e))?;
+
+    Ok(output
+        .transcription
+        .into_iter()
+        .map(|s| Segment {
+            start_ms: s.offsets.from,
+            end_ms: s.offsets.to,
+            text: s.text.trim().to_string(),
+            confidence: None,
+        })
+        .collect())
+}
+
+/// Parse `[00:00:01.000 --> 00:00:02.500]  text` lines from whisper's
+/// stdout, ignoring everything else
+pub fn parse_cli_stdout(stdout: &str) -> Vec<Segment> {
+    stdout
+        .lines()
+        .filter_map(|line| {
+            let rest = line.trim().strip_prefix('[')?;
+            let (times, text) = rest.split_once(']')?;
+            let (start, end) = times.split_once("-->")?;
+
+            Some(Segment {
+                start_ms: parse_timestamp(start.trim())?,
+                end_ms: parse_timestamp(end.trim())?,
+                text: text.trim().to_string(),
+                confidence: None,
+            })
+        })
+        .collect()
+}
+
+/// "HH:MM:SS.mmm" → milliseconds
+fn parse_timestamp(ts: &str) -> Option<u64> {
+    let (hms, millis) = ts.split_once('.')?;
+    let mut parts = hms.split(':').map(|p| p.parse::<u64>().ok());
+    let (h, m, s) = (parts.next()??, parts.next()??, parts.next()??);
+    Some(((h * 60 + m) * 60 + s) * 1000 + millis.parse::<u64>().ok()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_json_output() {
+        let json = r#"{
+            "systeminfo": "AVX = 1",
+            "transcription": [
+                { "timestamps": { "from": "00:00:00,000", "to": "00:00:02,000" },
+                  "offsets": { "from": 0, "to": 2000 }, "text": " Move the login task" },
+                { "timestamps": { "from": "00:00:02,000", "to": "00:00:03,500" },
+                  "offsets": { "from": 2000, "to": 3500 }, "text": " to done." }
+            ]
+        }"#;
+
+        let transcript = Transcript::from_segments(parse_cli_json(json).unwrap(), "whisper-cli");
+        assert_eq!(transcript.text, "Move the login task to done.");
+        assert_eq!(transcript.segments[1].start_ms, 2000);
+        assert_eq!(transcript.segments[1].end_ms, 3500);
+    }
+
+    #[test]
+    fn stdout_parsing_skips_log_lines() {
+        let stdout = "whisper_init_from_file: loading model\n\
+                      system_info: n_threads = 4\n\
+                      \n\
+                      [00:00:00.000 --> 00:00:01.240]   Hello there.\n\
+                      whisper_print_timings: total time = 812 ms\n";
+
+        let segments = parse_cli_stdout(stdout);
+        assert_eq!(
+            segments,
+            vec![Segment {
+                start_ms: 0,
+                end_ms: 1240,
+                text: "Hello there.".into(),
+                confidence: None,
+            }]
+        );
+    }
+}
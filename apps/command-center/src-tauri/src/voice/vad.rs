@@ -0,0 +1,150 @@
+//! Energy-based voice activity detection
+//!
+//! Decides when a hands-free capture is over: the speaker has talked and
+//! then gone quiet, or never started. Works on 16 kHz mono in 30 ms frames,
+//! comparing each frame's RMS against an adaptive noise floor.
+
+use serde::{Deserialize, Serialize};
+
+use super::audio::WHISPER_SAMPLE_RATE;
+
+const FRAME_MS: u32 = 30;
+const FRAME_LEN: usize = (WHISPER_SAMPLE_RATE * FRAME_MS / 1000) as usize;
+
+/// Speech must be this many times louder than the noise floor
+const NOISE_RATIO: f32 = 3.0;
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default, rename_all = "camelCase")]
+pub struct VadConfig {
+    /// Trailing silence that ends the capture
+    pub silence_ms: u32,
+    /// Give up if nobody speaks within this long
+    pub no_speech_ms: u32,
+    /// Speech shorter than this is treated as a click or cough
+    pub min_speech_ms: u32,
+    /// RMS below which a frame is always silence
+    pub min_level: f32,
+}
+
+impl Default for VadConfig {
+    fn default() -> Self {
+        Self {
+            silence_ms: 1200,
+            no_speech_ms: 8000,
+            min_speech_ms: 200,
+            min_level: 0.01,
+        }
+    }
+}
+
+/// Why the detector thinks the capture is over
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum VadEnd {
+    Silence,
+    NoSpeech,
+}
+
+pub struct Vad {
+    config: VadConfig,
+    frame: Vec<f32>,
+    noise_floor: Option<f32>,
+    elapsed_ms: u32,
+    speech_ms: u32,
+    silence_ms: u32,
+}
+
+impl Vad {
+    pub fn new(config: VadConfig) -> Self {
+        Self {
+            config,
+            frame: Vec::with_capacity(FRAME_LEN),
+            noise_floor: None,
+            elapsed_ms: 0,
+            speech_ms: 0,
+            silence_ms: 0,
+        }
+    }
+
+    /// Whether enough speech has been heard to count
+    pub fn heard_speech(&self) -> bool {
+        self.speech_ms >= self.config.min_speech_ms
+    }
+
+    /// Feed 16 kHz mono samples; returns Some once the capture should stop
+    pub fn push(&mut self, samples: &[f32]) -> Option<VadEnd> {
+        for &sample in samples {
+            self.frame.push(sample);
+            if self.frame.len() < FRAME_LEN {
+                continue;
+            }
+
+            let rms = (self.frame.iter().map(|s| s * s).sum::<f32>() / FRAME_LEN as f32).sqrt();
+            self.frame.clear();
+            if let Some(end) = self.frame_done(rms) {
+                return Some(end);
+            }
+        }
+        None
+    }
+
+    fn frame_done(&mut self, rms: f32) -> Option<VadEnd> {
+        self.elapsed_ms += FRAME_MS;
+
+        // Start no higher than min_level in case the first frame is speech
+        let floor = *self
+            .noise_floor
+            .get_or_insert(rms.min(self.config.min_level));
+        let is_speech = rms > self.config.min_level && rms > floor * NOISE_RATIO;
+
+        if is_speech {
+            self.speech_ms += FRAME_MS;
+            self.silence_ms = 0;
+        } else {
+            self.silence_ms += FRAME_MS;
+            // Track background noise slowly, only from non-speech frames
+            self.noise_floor = Some(floor * 0.95 + rms * 0.05);
+        }
+
+        if self.heard_speech() {
+            (self.silence_ms >= self.config.silence_ms).then_some(VadEnd::Silence)
+        } else {
+            (self.elapsed_ms >= self.config.no_speech_ms).then_some(VadEnd::NoSpeech)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tone(ms: u32, amplitude: f32) -> Vec<f32> {
+        (0..WHISPER_SAMPLE_RATE * ms / 1000)
+            .map(|i| amplitude * (i as f32 * 0.17).sin())
+            .collect()
+    }
+
+    #[test]
+    fn stops_after_trailing_silence() {
+        let mut vad = Vad::new(VadConfig::default());
+
+        assert_eq!(vad.push(&tone(500, 0.002)), None); // room noise
+        assert_eq!(vad.push(&tone(1000, 0.3)), None); // speech
+        assert_eq!(vad.push(&tone(600, 0.002)), None); // a pause
+        assert_eq!(vad.push(&tone(1000, 0.002)), Some(VadEnd::Silence));
+    }
+
+    #[test]
+    fn gives_up_without_speech_and_ignores_clicks() {
+        let config = VadConfig {
+            no_speech_ms: 2000,
+            ..VadConfig::default()
+        };
+        let mut vad = Vad::new(config);
+
+        assert_eq!(vad.push(&tone(500, 0.002)), None);
+        assert_eq!(vad.push(&tone(60, 0.5)), None); // click
+        assert!(!vad.heard_speech());
+        assert_eq!(vad.push(&tone(2000, 0.002)), Some(VadEnd::NoSpeech));
+    }
+}
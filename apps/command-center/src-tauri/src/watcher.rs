@@ -1,261 +1,455 @@
-//! File watcher for inbox.json
+//! File watcher for the data directory and project docs
 //!
-//! Watches ~/.taskboard/inbox.json for changes and sends Windows
-//! desktop notifications when new inbox items appear.
+//! Watches ~/.taskboard (inbox.json, tasks.json, projects.json, config.json)
+//! and every project's `.taskboard/docs` folder. Events are debounced by
+//! `advanced.fileWatchDebounce`, then turned into typed frontend events:
+//!
+//! - `tasks-changed` / `projects-changed` with added, updated and removed ids
+//! - `config-changed` with the top-level config sections that changed
+//! - `doc-changed` with the project id, path and kind of change
+//! - audio files dropped into `voice-drop/` are queued for transcription
+//! - agent hook payloads dropped into `sessions/hooks/` update agent sessions,
+//!   and tasks or projects changed by other programs are credited to the
+//!   agent sessions open at the time
+//! - inbox items and replies go through the inbox watcher, which also
+//!   raises desktop notifications; instructions for Claude start the agent
+//! - changes to projects, tasks or the inbox ask for a gist sync
+//!
+//! Every event carries the `origin` of the change. Writes the frontend made
+//! itself (registered in the `WriteRegistry`) update the snapshots but are
+//! not echoed back; writes by backend commands are reported with origin
+//! `backend` so the frontend reloads them.
+//!
+//! The watcher thread itself is owned by the `WatcherSupervisor`.
 
-use notify::{Config, Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
-use serde::Deserialize;
+mod entities;
+mod inbox;
+mod supervisor;
+mod writes;
+
+use notify::{EventKind, RecursiveMode, Watcher};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashMap;
 use std::fs;
-use std::path::PathBuf;
-use std::sync::{Arc, Mutex};
-use tauri::{AppHandle, Emitter};
-use tauri_plugin_notification::NotificationExt;
-
-/// Minimal inbox structures for detecting new items
-#[derive(Deserialize, Debug)]
-struct InboxData {
-    items: Vec<InboxItem>,
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{Receiver, RecvTimeoutError};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager, State};
+
+use crate::notifications::Notifier;
+use crate::{agents, config, sync, voice, AppState};
+use entities::{changed_keys, EntitySnapshot};
+use inbox::InboxWatcher;
+use supervisor::Message;
+
+pub use supervisor::WatcherSupervisor;
+pub use writes::{WriteOrigin, WriteRegistry};
+
+/// Data files in the data directory that the watcher reacts to
+const DATA_FILES: [&str; 4] = ["inbox.json", "tasks.json", "projects.json", "config.json"];
+
+/// Kind of change reported for a path
+#[derive(Serialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "camelCase")]
+enum ChangeKind {
+    Created,
+    Modified,
+    Removed,
+}
+
+impl ChangeKind {
+    fn from_event(kind: &EventKind) -> Option<Self> {
+        match kind {
+            EventKind::Create(_) => Some(ChangeKind::Created),
+            EventKind::Modify(_) => Some(ChangeKind::Modified),
+            EventKind::Remove(_) => Some(ChangeKind::Removed),
+            _ => None,
+        }
+    }
 }
 
-#[derive(Deserialize, Debug, Clone)]
-struct InboxItem {
-    id: String,
-    text: String,
-    #[serde(default)]
-    author: String,
-    #[serde(default)]
-    read: bool,
-    #[serde(default)]
-    replies: Vec<InboxReply>,
+/// Wraps an event payload with the origin of the change
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+struct Tagged<T: Serialize> {
+    origin: WriteOrigin,
+    #[serde(flatten)]
+    payload: T,
 }
 
-#[derive(Deserialize, Debug, Clone)]
-struct InboxReply {
-    id: String,
-    author: String,
-    text: String,
+/// Payload of `config-changed`
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+struct ConfigChangedEvent {
+    sections: Vec<String>,
 }
 
-/// Track the last known state of inbox to detect changes
-struct WatcherState {
-    last_item_count: usize,
-    last_item_ids: Vec<String>,
+/// Payload of `doc-changed`
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+struct DocChangedEvent {
+    project_id: String,
+    path: String,
+    kind: ChangeKind,
 }
 
-/// Start watching inbox.json for new items
-pub fn start_inbox_watcher(app_handle: AppHandle) {
-    let home = dirs::home_dir().expect("Could not find home directory");
-    let inbox_path = home.join(".taskboard").join("inbox.json");
-
-    // Initialize state from current file
-    let initial_state = read_inbox_state(&inbox_path);
-    let state = Arc::new(Mutex::new(WatcherState {
-        last_item_count: initial_state.0,
-        last_item_ids: initial_state.1,
-    }));
-
-    let watch_path = home.join(".taskboard");
-    let inbox_file = inbox_path.clone();
-    let state_clone = Arc::clone(&state);
-
-    // Spawn watcher on a background thread
-    std::thread::spawn(move || {
-        let (tx, rx) = std::sync::mpsc::channel::<Result<Event, notify::Error>>();
-
-        let mut watcher = RecommendedWatcher::new(tx, Config::default())
-            .expect("Failed to create file watcher");
-
-        watcher
-            .watch(&watch_path, RecursiveMode::NonRecursive)
-            .expect("Failed to watch .taskboard directory");
-
-        // Keep watcher alive and process events
-        for event in rx {
-            if let Ok(event) = event {
-                // Only react to modify/create events on inbox.json
-                let is_inbox_event = event.paths.iter().any(|p| {
-                    p.file_name()
-                        .map(|name| name == "inbox.json")
-                        .unwrap_or(false)
-                });
-
-                if !is_inbox_event {
-                    continue;
-                }
+/// Why the event loop returned
+enum Exit {
+    /// Shutdown requested
+    Stop,
+    /// The data directory went away; wait for it and watch again
+    Rearm,
+}
+
+/// Last known contents of every watched file
+struct DataWatcher {
+    data_dir: PathBuf,
+    inbox: InboxWatcher,
+    tasks: EntitySnapshot,
+    projects: EntitySnapshot,
+    config: Value,
+    writes: WriteRegistry,
+    debounce: Duration,
+    /// Watched docs folder → owning project id
+    doc_dirs: HashMap<PathBuf, String>,
+}
+
+/// Start (or restart) watching the data directory and project docs
+pub fn start_watcher(app_handle: AppHandle) {
+    let (data_dir, writes) = {
+        let state = app_handle.state::<Mutex<AppState>>();
+        let state = state.lock().unwrap();
+        (state.data_dir.clone(), state.writes.clone())
+    };
+
+    app_handle
+        .state::<WatcherSupervisor>()
+        .start(app_handle.clone(), data_dir, writes);
+}
+
+impl DataWatcher {
+    fn new(data_dir: PathBuf, writes: WriteRegistry) -> Self {
+        let tasks = EntitySnapshot::read(&data_dir.join("tasks.json"), "tasks").unwrap_or_default();
+        let projects =
+            EntitySnapshot::read(&data_dir.join("projects.json"), "projects").unwrap_or_default();
+        let config = read_json(&data_dir.join("config.json")).unwrap_or(Value::Null);
+
+        Self {
+            inbox: InboxWatcher::new(&data_dir),
+            debounce: debounce_from_config(&data_dir),
+            data_dir,
+            tasks,
+            projects,
+            config,
+            writes,
+            doc_dirs: HashMap::new(),
+        }
+    }
 
-                match event.kind {
-                    EventKind::Modify(_) | EventKind::Create(_) => {
-                        check_and_notify(
-                            &inbox_file,
-                            &state_clone,
-                            &app_handle,
-                        );
+    /// Process file events until asked to stop or the data directory disappears
+    fn run(
+        &mut self,
+        app_handle: &AppHandle,
+        rx: &Receiver<Message>,
+        watcher: &mut dyn Watcher,
+    ) -> Exit {
+        // Collect paths until no event has arrived for the debounce period
+        let mut pending: HashMap<PathBuf, ChangeKind> = HashMap::new();
+
+        loop {
+            match rx.recv_timeout(self.debounce) {
+                Ok(Message::Fs(Ok(event))) => {
+                    let kind = match ChangeKind::from_event(&event.kind) {
+                        Some(k) => k,
+                        None => continue,
+                    };
+                    if kind == ChangeKind::Removed && event.paths.contains(&self.data_dir) {
+                        return Exit::Rearm;
+                    }
+                    for path in event.paths {
+                        pending.insert(path, kind);
+                    }
+                }
+                Ok(Message::Fs(Err(e))) => supervisor::report_error(app_handle, e.to_string()),
+                Ok(Message::Stop) | Err(RecvTimeoutError::Disconnected) => return Exit::Stop,
+                Err(RecvTimeoutError::Timeout) => {
+                    if !self.data_dir.is_dir() {
+                        return Exit::Rearm;
+                    }
+                    if !pending.is_empty() {
+                        let batch = std::mem::take(&mut pending);
+                        self.process(batch, app_handle, watcher);
                     }
-                    _ => {}
                 }
             }
         }
-    });
-}
+    }
 
-/// Read inbox.json and return (item_count, item_ids)
-fn read_inbox_state(path: &PathBuf) -> (usize, Vec<String>) {
-    if !path.exists() {
-        return (0, vec![]);
+    /// Re-check every data file, e.g. after the directory was recreated
+    fn rescan(&mut self, app_handle: &AppHandle, watcher: &mut dyn Watcher) {
+        let batch = DATA_FILES
+            .iter()
+            .map(|name| (self.data_dir.join(name), ChangeKind::Modified))
+            .collect();
+        self.process(batch, app_handle, watcher);
     }
 
-    match fs::read_to_string(path) {
-        Ok(content) => match serde_json::from_str::<InboxData>(&content) {
-            Ok(data) => {
-                let ids: Vec<String> = data.items.iter().map(|i| i.id.clone()).collect();
-                (data.items.len(), ids)
+    /// Dispatch a debounced batch of changed paths
+    fn process(
+        &mut self,
+        batch: HashMap<PathBuf, ChangeKind>,
+        app_handle: &AppHandle,
+        watcher: &mut dyn Watcher,
+    ) {
+        for (path, kind) in batch {
+            let origin = match kind {
+                ChangeKind::Removed => WriteOrigin::External,
+                _ => self.writes.origin_of(&path),
+            };
+
+            if path.parent() == Some(self.data_dir.as_path()) {
+                let file_name = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
+                match file_name {
+                    "inbox.json" => {
+                        self.inbox.check_and_notify(app_handle, origin);
+                        agents::scan_inbox(app_handle, false);
+                        sync::local_change(app_handle);
+                    }
+                    "tasks.json" => {
+                        self.check_tasks(app_handle, origin);
+                        sync::local_change(app_handle);
+                    }
+                    "projects.json" => {
+                        self.check_projects(app_handle, origin);
+                        self.sync_doc_watches(watcher);
+                        sync::local_change(app_handle);
+                    }
+                    "config.json" => self.check_config(app_handle, origin),
+                    _ => {}
+                }
+            } else if path.parent() == Some(self.drop_dir().as_path()) {
+                if kind != ChangeKind::Removed && voice::is_audio_file(&path) {
+                    voice::queue_dropped_file(app_handle, path);
+                }
+            } else if path.parent() == Some(self.hooks_dir().as_path()) {
+                if kind != ChangeKind::Removed && is_hook_payload(&path) {
+                    if let Err(e) = agents::ingest_session_hook(app_handle, &path) {
+                        supervisor::report_error(app_handle, e);
+                    }
+                }
+            } else if let Some(project_id) = self.project_for_doc(&path) {
+                if origin == WriteOrigin::App {
+                    continue;
+                }
+                let _ = app_handle.emit(
+                    "doc-changed",
+                    Tagged {
+                        origin,
+                        payload: DocChangedEvent {
+                            project_id,
+                            path: path.to_string_lossy().to_string(),
+                            kind,
+                        },
+                    },
+                );
             }
-            Err(_) => (0, vec![]),
-        },
-        Err(_) => (0, vec![]),
+        }
     }
-}
-
-/// Compare current inbox state with last known state and notify if new items found
-fn check_and_notify(
-    inbox_path: &PathBuf,
-    state: &Arc<Mutex<WatcherState>>,
-    app_handle: &AppHandle,
-) {
-    let (current_count, current_ids) = read_inbox_state(inbox_path);
 
-    let mut watcher_state = match state.lock() {
-        Ok(s) => s,
-        Err(_) => return,
-    };
+    fn check_tasks(&mut self, app_handle: &AppHandle, origin: WriteOrigin) {
+        let current = match EntitySnapshot::read(&self.data_dir.join("tasks.json"), "tasks") {
+            Some(s) => s,
+            None => return,
+        };
 
-    // Find new item IDs
-    let new_ids: Vec<String> = current_ids
-        .iter()
-        .filter(|id| !watcher_state.last_item_ids.contains(id))
-        .cloned()
-        .collect();
-
-    if new_ids.is_empty() {
-        // Also check for new replies on existing items
-        if let Some(new_reply_info) = check_for_new_replies(inbox_path) {
-            send_notification(
-                app_handle,
-                "New reply in inbox",
-                &new_reply_info,
+        let changes = self.tasks.diff(&current);
+        if !changes.is_empty() && origin == WriteOrigin::External {
+            let mutations = self.tasks.mutations(&current, &changes, "task");
+            agents::record_board_mutations(app_handle, &mutations);
+        }
+        if !changes.is_empty() && origin != WriteOrigin::App {
+            let _ = app_handle.emit(
+                "tasks-changed",
+                Tagged {
+                    origin,
+                    payload: changes,
+                },
             );
         }
-
-        // Update state even if no new items (ids might have been removed)
-        watcher_state.last_item_count = current_count;
-        watcher_state.last_item_ids = current_ids;
-        return;
+        self.tasks = current;
     }
 
-    // Get the text of new items for the notification body
-    let notification_body = get_new_items_summary(inbox_path, &new_ids);
-
-    send_notification(
-        app_handle,
-        &format!(
-            "{} new inbox item{}",
-            new_ids.len(),
-            if new_ids.len() > 1 { "s" } else { "" }
-        ),
-        &notification_body,
-    );
-
-    // Also emit an event to the frontend so the UI can update
-    let _ = app_handle.emit("inbox-updated", current_count);
-
-    // Update state
-    watcher_state.last_item_count = current_count;
-    watcher_state.last_item_ids = current_ids;
-}
+    fn check_projects(&mut self, app_handle: &AppHandle, origin: WriteOrigin) {
+        let current = match EntitySnapshot::read(&self.data_dir.join("projects.json"), "projects") {
+            Some(s) => s,
+            None => return,
+        };
 
-/// Check if any existing items got new replies (e.g., Claude responded)
-fn check_for_new_replies(inbox_path: &PathBuf) -> Option<String> {
-    if !inbox_path.exists() {
-        return None;
+        let changes = self.projects.diff(&current);
+        if !changes.is_empty() && origin == WriteOrigin::External {
+            let mutations = self.projects.mutations(&current, &changes, "project");
+            agents::record_board_mutations(app_handle, &mutations);
+        }
+        if !changes.is_empty() && origin != WriteOrigin::App {
+            let _ = app_handle.emit(
+                "projects-changed",
+                Tagged {
+                    origin,
+                    payload: changes,
+                },
+            );
+        }
+        self.projects = current;
     }
 
-    let content = fs::read_to_string(inbox_path).ok()?;
-    let data: InboxData = serde_json::from_str(&content).ok()?;
+    fn check_config(&mut self, app_handle: &AppHandle, origin: WriteOrigin) {
+        let current = match read_json(&self.data_dir.join("config.json")) {
+            Some(v) => v,
+            None => return,
+        };
+
+        let sections = changed_keys(&self.config, &current);
+        if !sections.is_empty() {
+            // Cached by the loops that would otherwise read it every tick
+            app_handle.state::<Notifier>().reload_config();
+            agents::reload_policy(app_handle);
+        }
+        if !sections.is_empty() && origin != WriteOrigin::App {
+            let _ = app_handle.emit(
+                "config-changed",
+                Tagged {
+                    origin,
+                    payload: ConfigChangedEvent { sections },
+                },
+            );
+        }
+        self.config = current;
+        self.debounce = debounce_from_config(&self.data_dir);
+    }
 
-    // Find items with unread Claude replies
-    for item in &data.items {
-        if !item.read {
-            for reply in &item.replies {
-                if reply.author == "claude" {
-                    return Some(format!("Claude replied: {}", truncate(&reply.text, 80)));
-                }
+    /// Watch the docs folder of every project that has one, and stop
+    /// watching folders of projects that were removed or moved
+    fn sync_doc_watches(&mut self, watcher: &mut dyn Watcher) {
+        let wanted: HashMap<PathBuf, String> = self
+            .projects
+            .values()
+            .filter_map(|project| {
+                let id = project.get("id")?.as_str()?;
+                let repo_path = project.get("repoPath")?.as_str()?;
+                let docs = PathBuf::from(repo_path).join(".taskboard").join("docs");
+                docs.is_dir().then(|| (docs, id.to_string()))
+            })
+            .collect();
+
+        for dir in self.doc_dirs.keys() {
+            if !wanted.contains_key(dir) {
+                let _ = watcher.unwatch(dir);
             }
         }
-    }
 
-    None
-}
+        self.doc_dirs.retain(|dir, _| wanted.contains_key(dir));
 
-/// Build a summary of new items for the notification body
-fn get_new_items_summary(inbox_path: &PathBuf, new_ids: &[String]) -> String {
-    if !inbox_path.exists() {
-        return String::from("New items in your inbox");
+        for (dir, project_id) in wanted {
+            if self.doc_dirs.contains_key(&dir) {
+                continue;
+            }
+            if watcher.watch(&dir, RecursiveMode::Recursive).is_ok() {
+                self.doc_dirs.insert(dir, project_id);
+            }
+        }
     }
 
-    let content = match fs::read_to_string(inbox_path) {
-        Ok(c) => c,
-        Err(_) => return String::from("New items in your inbox"),
-    };
+    fn drop_dir(&self) -> PathBuf {
+        self.data_dir.join(voice::DROP_DIR)
+    }
 
-    let data: InboxData = match serde_json::from_str(&content) {
-        Ok(d) => d,
-        Err(_) => return String::from("New items in your inbox"),
-    };
+    fn hooks_dir(&self) -> PathBuf {
+        self.data_dir
+            .join(agents::SESSIONS_DIR)
+            .join(agents::HOOKS_DIR)
+    }
 
-    let new_items: Vec<&InboxItem> = data
-        .items
-        .iter()
-        .filter(|item| new_ids.contains(&item.id))
-        .collect();
+    /// Watch the voice-drop folder and queue anything already in it
+    fn watch_voice_drop(&self, app_handle: &AppHandle, watcher: &mut dyn Watcher) {
+        for path in watch_drop_folder(&self.drop_dir(), app_handle, watcher) {
+            if voice::is_audio_file(&path) {
+                voice::queue_dropped_file(app_handle, path);
+            }
+        }
+    }
 
-    if new_items.is_empty() {
-        return String::from("New items in your inbox");
+    /// Watch the folder agent CLI hooks drop payloads into, and apply any
+    /// already there
+    fn watch_session_hooks(&self, app_handle: &AppHandle, watcher: &mut dyn Watcher) {
+        for path in watch_drop_folder(&self.hooks_dir(), app_handle, watcher) {
+            if is_hook_payload(&path) {
+                if let Err(e) = agents::ingest_session_hook(app_handle, &path) {
+                    supervisor::report_error(app_handle, e);
+                }
+            }
+        }
     }
 
-    if new_items.len() == 1 {
-        return truncate(&new_items[0].text, 120);
+    fn project_for_doc(&self, path: &Path) -> Option<String> {
+        self.doc_dirs
+            .iter()
+            .find(|(dir, _)| path.starts_with(dir))
+            .map(|(_, id)| id.clone())
     }
+}
 
-    // Multiple items — show first two
-    let first = truncate(&new_items[0].text, 60);
-    let second = truncate(&new_items[1].text, 60);
-    if new_items.len() == 2 {
-        format!("• {}\n• {}", first, second)
-    } else {
-        format!(
-            "• {}\n• {}\n+{} more",
-            first,
-            second,
-            new_items.len() - 2
-        )
+/// Create and watch a folder other programs drop files into. Returns the
+/// files already in it.
+fn watch_drop_folder(
+    dir: &Path,
+    app_handle: &AppHandle,
+    watcher: &mut dyn Watcher,
+) -> Vec<PathBuf> {
+    if let Err(e) = fs::create_dir_all(dir) {
+        supervisor::report_error(
+            app_handle,
+            format!("Failed to create {}: {}", dir.display(), e),
+        );
+        return vec![];
     }
+    if let Err(e) = watcher.watch(dir, RecursiveMode::NonRecursive) {
+        supervisor::report_error(
+            app_handle,
+            format!("Failed to watch {}: {}", dir.display(), e),
+        );
+        return vec![];
+    }
+
+    let dropped = fs::read_dir(dir).into_iter().flatten().flatten();
+    dropped.map(|entry| entry.path()).collect()
 }
 
-/// Send a Windows toast notification
-fn send_notification(app_handle: &AppHandle, title: &str, body: &str) {
-    let _ = app_handle
-        .notification()
-        .builder()
-        .title(title)
-        .body(body)
-        .show();
+fn is_hook_payload(path: &Path) -> bool {
+    path.is_file() && path.extension().is_some_and(|ext| ext == "json")
 }
 
-/// Truncate a string to max_len, adding "..." if truncated
-fn truncate(s: &str, max_len: usize) -> String {
-    if s.len() <= max_len {
-        s.to_string()
-    } else {
-        format!("{}...", &s[..max_len.min(s.len())])
-    }
+fn read_json(path: &Path) -> Option<Value> {
+    let content = fs::read_to_string(path).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+fn debounce_from_config(data_dir: &Path) -> Duration {
+    let ms = config::load_config(data_dir).advanced.file_watch_debounce;
+    Duration::from_millis(ms.max(50))
+}
+
+// Tauri commands
+
+/// Restart the watcher, e.g. after it reported an error
+#[tauri::command]
+pub fn restart_watcher(app_handle: AppHandle) {
+    start_watcher(app_handle);
+}
+
+/// Stop watching for file changes
+#[tauri::command]
+pub fn stop_watcher(supervisor: State<'_, WatcherSupervisor>) {
+    supervisor.stop();
 }
@@ -0,0 +1,187 @@
+//! Entity-level diffing for tasks.json and projects.json
+//!
+//! Files are compared by entity id rather than by content, so the frontend
+//! can refresh just the cards that changed.
+
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use crate::agents::BoardMutation;
+
+/// Payload of `tasks-changed` / `projects-changed`
+#[derive(Serialize, Debug, Default, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub(super) struct EntityChanges {
+    pub added: Vec<String>,
+    pub updated: Vec<String>,
+    pub removed: Vec<String>,
+}
+
+impl EntityChanges {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.updated.is_empty() && self.removed.is_empty()
+    }
+}
+
+/// Entities in a data file keyed by id
+#[derive(Debug, Default, Clone)]
+pub(super) struct EntitySnapshot(BTreeMap<String, Value>);
+
+impl EntitySnapshot {
+    /// Read the array under `collection` (e.g. "tasks") from a data file.
+    /// Returns None while the file is missing or half-written.
+    pub fn read(path: &Path, collection: &str) -> Option<Self> {
+        let content = fs::read_to_string(path).ok()?;
+        let value: Value = serde_json::from_str(&content).ok()?;
+        Some(Self::from_value(&value, collection))
+    }
+
+    pub fn from_value(value: &Value, collection: &str) -> Self {
+        let entities = value
+            .get(collection)
+            .and_then(|c| c.as_array())
+            .map(|items| {
+                items
+                    .iter()
+                    .filter_map(|item| {
+                        let id = item.get("id")?.as_str()?;
+                        Some((id.to_string(), item.clone()))
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        Self(entities)
+    }
+
+    /// Ids added, updated or removed going from `self` to `newer`
+    pub fn diff(&self, newer: &EntitySnapshot) -> EntityChanges {
+        let mut changes = EntityChanges::default();
+
+        for (id, value) in &newer.0 {
+            match self.0.get(id) {
+                None => changes.added.push(id.clone()),
+                Some(old) if old != value => changes.updated.push(id.clone()),
+                Some(_) => {}
+            }
+        }
+
+        for id in self.0.keys() {
+            if !newer.0.contains_key(id) {
+                changes.removed.push(id.clone());
+            }
+        }
+
+        changes
+    }
+
+    pub fn values(&self) -> impl Iterator<Item = &Value> {
+        self.0.values()
+    }
+
+    /// What each change did, for crediting to agent sessions. `entity` is
+    /// "task" or "project".
+    pub fn mutations(
+        &self,
+        newer: &EntitySnapshot,
+        changes: &EntityChanges,
+        entity: &str,
+    ) -> Vec<BoardMutation> {
+        let at = chrono::Utc::now().to_rfc3339();
+        let kinds = [
+            ("added", &changes.added),
+            ("updated", &changes.updated),
+            ("removed", &changes.removed),
+        ];
+
+        kinds
+            .into_iter()
+            .flat_map(|(change, ids)| ids.iter().map(move |id| (change, id)))
+            .map(|(change, id)| {
+                let old = self.0.get(id);
+                let new = newer.0.get(id);
+                let value = new.or(old).unwrap_or(&Value::Null);
+                let text = |key: &str| value.get(key).and_then(Value::as_str).map(String::from);
+                BoardMutation {
+                    at: at.clone(),
+                    entity: entity.to_string(),
+                    id: id.clone(),
+                    project_id: if entity == "project" {
+                        Some(id.clone())
+                    } else {
+                        text("projectId")
+                    },
+                    title: text("title").or_else(|| text("name")),
+                    change: change.to_string(),
+                    fields: match (old, new) {
+                        (Some(old), Some(new)) => changed_keys(old, new),
+                        _ => vec![],
+                    },
+                }
+            })
+            .collect()
+    }
+}
+
+/// Top-level keys whose values differ between two JSON objects
+pub(super) fn changed_keys(old: &Value, new: &Value) -> Vec<String> {
+    let empty = serde_json::Map::new();
+    let old = old.as_object().unwrap_or(&empty);
+    let new = new.as_object().unwrap_or(&empty);
+
+    let mut keys: Vec<String> = old
+        .keys()
+        .chain(new.keys())
+        .filter(|k| old.get(*k) != new.get(*k))
+        .cloned()
+        .collect();
+    keys.sort();
+    keys.dedup();
+    keys
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn diff_reports_added_updated_and_removed_ids() {
+        let old = EntitySnapshot::from_value(
+            &json!({ "tasks": [
+                { "id": "t-1", "status": "todo" },
+                { "id": "t-2", "status": "todo" },
+                { "id": "t-3", "status": "done" }
+            ]}),
+            "tasks",
+        );
+        let new = EntitySnapshot::from_value(
+            &json!({ "tasks": [
+                { "id": "t-1", "status": "todo" },
+                { "id": "t-2", "status": "in-progress" },
+                { "id": "t-4", "status": "todo" }
+            ]}),
+            "tasks",
+        );
+
+        assert_eq!(
+            old.diff(&new),
+            EntityChanges {
+                added: vec!["t-4".to_string()],
+                updated: vec!["t-2".to_string()],
+                removed: vec!["t-3".to_string()],
+            }
+        );
+    }
+
+    #[test]
+    fn changed_keys_lists_differing_sections() {
+        let old = json!({ "user": { "timezone": "UTC" }, "ui": { "theme": "dark" } });
+        let new = json!({ "user": { "timezone": "Asia/Singapore" }, "ui": { "theme": "dark" }, "advanced": {} });
+
+        assert_eq!(changed_keys(&old, &new), vec!["advanced", "user"]);
+    }
+}
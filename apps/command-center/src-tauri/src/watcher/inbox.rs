@@ -0,0 +1,383 @@
+//! Inbox change detection
+//!
+//! The set of item and reply ids already seen is persisted to
+//! watcher-state.json, so unrelated edits to inbox.json (marking an item
+//! read, changing its status) never re-announce old replies, and replies
+//! that arrived while the app was closed are announced on the next start.
+//! A state that cannot be saved is reported as a `watcher-error`.
+
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Emitter};
+
+use super::{supervisor, Tagged, WriteOrigin};
+use crate::notifications::{self, EntityLink, NotificationSource};
+
+/// Minimal inbox structures for detecting new items
+#[derive(Deserialize, Debug)]
+struct InboxData {
+    items: Vec<InboxItem>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+struct InboxItem {
+    id: String,
+    text: String,
+    #[serde(default)]
+    replies: Vec<InboxReply>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+struct InboxReply {
+    id: String,
+    author: String,
+    text: String,
+}
+
+/// Payload of the `inbox-reply` event
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+struct InboxReplyEvent {
+    item_id: String,
+    reply_id: String,
+    author: String,
+}
+
+/// Item and reply ids already seen, persisted between runs
+#[derive(Serialize, Deserialize, Debug, Default, PartialEq)]
+struct WatcherState {
+    /// Inbox item id → ids of its replies
+    #[serde(default)]
+    items: BTreeMap<String, BTreeSet<String>>,
+}
+
+/// What changed in inbox.json since the last known state
+#[derive(Debug, Default)]
+struct InboxChanges<'a> {
+    new_items: Vec<&'a InboxItem>,
+    new_replies: Vec<(&'a InboxItem, &'a InboxReply)>,
+}
+
+impl WatcherState {
+    /// Snapshot every item and reply currently in the inbox
+    fn from_inbox(data: &InboxData) -> Self {
+        let items = data
+            .items
+            .iter()
+            .map(|item| {
+                let replies = item.replies.iter().map(|r| r.id.clone()).collect();
+                (item.id.clone(), replies)
+            })
+            .collect();
+
+        Self { items }
+    }
+
+    fn load(path: &Path) -> Option<Self> {
+        let content = fs::read_to_string(path).ok()?;
+        serde_json::from_str(&content).ok()
+    }
+
+    fn save(&self, path: &Path) -> Result<(), String> {
+        let content = serde_json::to_string_pretty(self)
+            .map_err(|e| format!("Failed to serialize watcher state: {}", e))?;
+        crate::data::write_atomic(path, &content)
+            .map_err(|e| format!("Failed to write watcher-state.json: {}", e))
+    }
+
+    /// Find items and non-user replies not seen before, including the
+    /// replies already on a new item
+    fn diff<'a>(&self, data: &'a InboxData) -> InboxChanges<'a> {
+        let mut changes = InboxChanges::default();
+
+        for item in &data.items {
+            let seen = self.items.get(&item.id);
+            if seen.is_none() {
+                changes.new_items.push(item);
+            }
+            for reply in &item.replies {
+                if reply.author != "user" && !seen.is_some_and(|seen| seen.contains(&reply.id)) {
+                    changes.new_replies.push((item, reply));
+                }
+            }
+        }
+
+        changes
+    }
+}
+
+/// Inbox side of the data watcher
+pub(super) struct InboxWatcher {
+    inbox_path: PathBuf,
+    state_path: PathBuf,
+    state: WatcherState,
+    /// Whether the state was restored from a previous run
+    resumed: bool,
+    /// Why the first snapshot could not be saved, reported on catch-up
+    unsaved: Option<String>,
+}
+
+impl InboxWatcher {
+    /// Resume from the persisted state; on first run, treat everything
+    /// already in the inbox as seen
+    pub(super) fn new(data_dir: &Path) -> Self {
+        let inbox_path = data_dir.join("inbox.json");
+        let state_path = data_dir.join("watcher-state.json");
+
+        let persisted = WatcherState::load(&state_path);
+        let resumed = persisted.is_some();
+        let mut unsaved = None;
+        let state = persisted.unwrap_or_else(|| {
+            let state = read_inbox(&inbox_path)
+                .map(|data| WatcherState::from_inbox(&data))
+                .unwrap_or_default();
+            unsaved = state.save(&state_path).err();
+            state
+        });
+
+        Self {
+            inbox_path,
+            state_path,
+            state,
+            resumed,
+            unsaved,
+        }
+    }
+
+    /// Announce anything that arrived while the app was closed
+    pub(super) fn catch_up(&mut self, app_handle: &AppHandle) {
+        if let Some(e) = self.unsaved.take() {
+            supervisor::report_error(app_handle, e);
+        }
+        if self.resumed {
+            self.check_and_notify(app_handle, WriteOrigin::External);
+        }
+    }
+
+    /// Compare current inbox with the last known state and notify about new
+    /// items and replies. The app's own writes only refresh the known state.
+    /// Backend writes refresh the UI without raising notifications.
+    pub(super) fn check_and_notify(&mut self, app_handle: &AppHandle, origin: WriteOrigin) {
+        let data = match read_inbox(&self.inbox_path) {
+            Some(d) => d,
+            None => return,
+        };
+
+        if origin == WriteOrigin::App {
+            self.update_state(app_handle, &data);
+            return;
+        }
+
+        let changes = self.state.diff(&data);
+
+        // Backend commands act on the user's request: refresh the UI, but
+        // don't raise notifications about it
+        let notify = origin != WriteOrigin::Backend;
+
+        for (item, reply) in &changes.new_replies {
+            let _ = app_handle.emit(
+                "inbox-reply",
+                Tagged {
+                    origin,
+                    payload: InboxReplyEvent {
+                        item_id: item.id.clone(),
+                        reply_id: reply.id.clone(),
+                        author: reply.author.clone(),
+                    },
+                },
+            );
+
+            // A new item's replies are part of its own notification
+            let on_new_item = changes.new_items.iter().any(|new| new.id == item.id);
+            if notify && !on_new_item {
+                notifications::notify(
+                    app_handle,
+                    NotificationSource::Reply,
+                    Some(EntityLink::InboxItem(item.id.clone())),
+                    "New reply in inbox",
+                    &format!(
+                        "{} replied: {}",
+                        display_author(&reply.author),
+                        truncate(&reply.text, 80)
+                    ),
+                );
+            }
+        }
+
+        if !changes.new_items.is_empty() && notify {
+            // Link straight to the item when there is only one
+            let entity = match changes.new_items.as_slice() {
+                [item] => Some(EntityLink::InboxItem(item.id.clone())),
+                _ => None,
+            };
+
+            notifications::notify(
+                app_handle,
+                NotificationSource::Inbox,
+                entity,
+                &format!(
+                    "{} new inbox item{}",
+                    changes.new_items.len(),
+                    if changes.new_items.len() > 1 { "s" } else { "" }
+                ),
+                &get_new_items_summary(&changes.new_items),
+            );
+        }
+
+        // Also emit an event to the frontend so the UI can update
+        if !changes.new_items.is_empty() || origin == WriteOrigin::Backend {
+            let _ = app_handle.emit("inbox-updated", data.items.len());
+        }
+
+        // Update state (ids might also have been removed)
+        self.update_state(app_handle, &data);
+    }
+
+    fn update_state(&mut self, app_handle: &AppHandle, data: &InboxData) {
+        let new_state = WatcherState::from_inbox(data);
+        if self.state != new_state {
+            if let Err(e) = new_state.save(&self.state_path) {
+                supervisor::report_error(app_handle, e);
+            }
+            self.state = new_state;
+        }
+    }
+}
+
+/// Read and parse inbox.json. Returns None while the file is missing or
+/// half-written so a partial write never resets the known state.
+fn read_inbox(path: &Path) -> Option<InboxData> {
+    let content = fs::read_to_string(path).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+/// Capitalize an author id for display ("claude" → "Claude")
+fn display_author(author: &str) -> String {
+    let mut chars = author.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::from("Someone"),
+    }
+}
+
+/// Build a summary of new items for the notification body
+fn get_new_items_summary(new_items: &[&InboxItem]) -> String {
+    if new_items.is_empty() {
+        return String::from("New items in your inbox");
+    }
+
+    if new_items.len() == 1 {
+        return truncate(&new_items[0].text, 120);
+    }
+
+    // Multiple items — show first two
+    let first = truncate(&new_items[0].text, 60);
+    let second = truncate(&new_items[1].text, 60);
+    if new_items.len() == 2 {
+        format!("• {}\n• {}", first, second)
+    } else {
+        format!("• {}\n• {}\n+{} more", first, second, new_items.len() - 2)
+    }
+}
+
+/// Truncate a string to max_len characters, adding "..." if truncated
+fn truncate(s: &str, max_len: usize) -> String {
+    match s.char_indices().nth(max_len) {
+        Some((cut, _)) => format!("{}...", &s[..cut]),
+        None => s.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixture(name: &str) -> InboxData {
+        let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("tests")
+            .join("fixtures")
+            .join("inbox")
+            .join(name);
+        read_inbox(&path).expect("fixture should parse")
+    }
+
+    fn reply_ids(changes: &InboxChanges) -> Vec<(String, String)> {
+        changes
+            .new_replies
+            .iter()
+            .map(|(item, reply)| (item.id.clone(), reply.id.clone()))
+            .collect()
+    }
+
+    #[test]
+    fn truncates_at_a_character_boundary() {
+        assert_eq!(truncate("Ship it — today", 8), "Ship it ...");
+        assert_eq!(truncate("café 🚀 launch", 6), "café 🚀...");
+        assert_eq!(truncate("short", 80), "short");
+    }
+
+    #[test]
+    fn unrelated_edit_does_not_report_old_replies() {
+        let state = WatcherState::from_inbox(&fixture("initial.json"));
+        let edited = fixture("unrelated_edit.json");
+
+        let changes = state.diff(&edited);
+
+        assert!(changes.new_items.is_empty());
+        assert!(changes.new_replies.is_empty());
+    }
+
+    #[test]
+    fn reports_only_new_non_user_replies() {
+        let state = WatcherState::from_inbox(&fixture("initial.json"));
+        let updated = fixture("new_replies.json");
+
+        let changes = state.diff(&updated);
+
+        assert!(changes.new_items.is_empty());
+        assert_eq!(
+            reply_ids(&changes),
+            vec![
+                ("inbox-1".to_string(), "reply-3".to_string()),
+                ("inbox-2".to_string(), "reply-5".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn replies_on_new_items_are_reported_with_them() {
+        let state = WatcherState::from_inbox(&fixture("initial.json"));
+        let updated = fixture("new_item.json");
+
+        let changes = state.diff(&updated);
+
+        let new_ids: Vec<&str> = changes.new_items.iter().map(|i| i.id.as_str()).collect();
+        assert_eq!(new_ids, vec!["inbox-3"]);
+        assert_eq!(
+            reply_ids(&changes),
+            vec![("inbox-3".to_string(), "reply-6".to_string())]
+        );
+    }
+
+    #[test]
+    fn seen_replies_survive_restart() {
+        let dir = tempfile::tempdir().unwrap();
+        let state_path = dir.path().join("watcher-state.json");
+
+        WatcherState::from_inbox(&fixture("initial.json"))
+            .save(&state_path)
+            .unwrap();
+        let restored = WatcherState::load(&state_path).expect("state should load");
+
+        assert!(restored
+            .diff(&fixture("unrelated_edit.json"))
+            .new_replies
+            .is_empty());
+        assert_eq!(
+            reply_ids(&restored.diff(&fixture("new_replies.json"))).len(),
+            2
+        );
+    }
+}
@@ -0,0 +1,234 @@
+//! Watcher lifecycle
+//!
+//! The supervisor owns the watcher thread and keeps it alive without ever
+//! panicking: failures are reported to the frontend as `watcher-error`
+//! events, a missing or deleted data directory is waited for and watched
+//! again once it reappears, and when the native backend (inotify, FSEvents,
+//! ReadDirectoryChangesW) cannot watch the directory — typically on network
+//! mounts — it falls back to polling. The thread can be stopped and
+//! restarted, e.g. when switching workspaces.
+
+use notify::{Config, Event, PollWatcher, RecommendedWatcher, RecursiveMode, Watcher};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::sync::Mutex;
+use std::thread::JoinHandle;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+
+use super::{DataWatcher, Exit, WriteRegistry};
+use crate::config::{self, FileWatchMode};
+
+/// How often to look for a missing data directory or retry a failed watch
+const RETRY_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Scan interval for the polling fallback
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Everything the watcher thread receives: file events and shutdown
+pub(super) enum Message {
+    Fs(notify::Result<Event>),
+    Stop,
+}
+
+/// Payload of `watcher-error`
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+struct WatcherErrorEvent {
+    message: String,
+}
+
+struct Running {
+    control: Sender<Message>,
+    thread: JoinHandle<()>,
+}
+
+/// Managed state owning the watcher thread
+#[derive(Default)]
+pub struct WatcherSupervisor {
+    running: Mutex<Option<Running>>,
+}
+
+impl WatcherSupervisor {
+    /// Start watching `data_dir`, stopping any previous watcher first
+    pub fn start(&self, app_handle: AppHandle, data_dir: PathBuf, writes: WriteRegistry) {
+        self.stop();
+
+        let (tx, rx) = mpsc::channel();
+        let control = tx.clone();
+        let thread = std::thread::spawn(move || supervise(&app_handle, data_dir, writes, tx, rx));
+
+        if let Ok(mut running) = self.running.lock() {
+            *running = Some(Running { control, thread });
+        }
+    }
+
+    /// Stop the watcher thread and wait for it to exit
+    pub fn stop(&self) {
+        let running = match self.running.lock() {
+            Ok(mut r) => r.take(),
+            Err(_) => None,
+        };
+
+        if let Some(running) = running {
+            let _ = running.control.send(Message::Stop);
+            let _ = running.thread.join();
+        }
+    }
+}
+
+/// Emit a `watcher-error` event
+pub(super) fn report_error(app_handle: &AppHandle, message: String) {
+    let _ = app_handle.emit("watcher-error", WatcherErrorEvent { message });
+}
+
+/// Thread body: (re-)arm the watcher whenever the data directory is available
+fn supervise(
+    app_handle: &AppHandle,
+    data_dir: PathBuf,
+    writes: WriteRegistry,
+    tx: Sender<Message>,
+    rx: Receiver<Message>,
+) {
+    let mut data_watcher: Option<DataWatcher> = None;
+
+    loop {
+        if !data_dir.is_dir() {
+            report_error(
+                app_handle,
+                format!(
+                    "{} is missing; waiting for it to reappear",
+                    data_dir.display()
+                ),
+            );
+            if !wait_for_dir(&data_dir, &rx) {
+                return;
+            }
+        }
+
+        let mut watcher = match arm(app_handle, &data_dir, &tx) {
+            Some(w) => w,
+            None => {
+                if sleep_or_stop(&rx, RETRY_INTERVAL) {
+                    return;
+                }
+                continue;
+            }
+        };
+
+        let rearmed = data_watcher.is_some();
+        let data_watcher =
+            data_watcher.get_or_insert_with(|| DataWatcher::new(data_dir.clone(), writes.clone()));
+
+        if rearmed {
+            // The old backend is gone along with its doc watches
+            data_watcher.doc_dirs.clear();
+            data_watcher.rescan(app_handle, watcher.as_mut());
+        } else {
+            data_watcher.inbox.catch_up(app_handle);
+        }
+        data_watcher.sync_doc_watches(watcher.as_mut());
+        data_watcher.watch_voice_drop(app_handle, watcher.as_mut());
+        data_watcher.watch_session_hooks(app_handle, watcher.as_mut());
+
+        match data_watcher.run(app_handle, &rx, watcher.as_mut()) {
+            Exit::Stop => return,
+            Exit::Rearm => continue,
+        }
+    }
+}
+
+/// Create a watcher on the data directory according to `advanced.fileWatchMode`
+fn arm(
+    app_handle: &AppHandle,
+    data_dir: &Path,
+    tx: &Sender<Message>,
+) -> Option<Box<dyn Watcher + Send>> {
+    let mode = config::load_config(data_dir).advanced.file_watch_mode;
+
+    if mode != FileWatchMode::Poll {
+        match native_watcher(data_dir, tx) {
+            Ok(watcher) => return Some(watcher),
+            Err(e) if mode == FileWatchMode::Native => {
+                report_error(
+                    app_handle,
+                    format!("Failed to watch {}: {}", data_dir.display(), e),
+                );
+                return None;
+            }
+            Err(e) => report_error(
+                app_handle,
+                format!(
+                    "Native file watching unavailable ({}); falling back to polling",
+                    e
+                ),
+            ),
+        }
+    }
+
+    match poll_watcher(data_dir, tx) {
+        Ok(watcher) => Some(watcher),
+        Err(e) => {
+            report_error(
+                app_handle,
+                format!("Failed to watch {}: {}", data_dir.display(), e),
+            );
+            None
+        }
+    }
+}
+
+fn native_watcher(
+    data_dir: &Path,
+    tx: &Sender<Message>,
+) -> notify::Result<Box<dyn Watcher + Send>> {
+    let tx = tx.clone();
+    let mut watcher = RecommendedWatcher::new(
+        move |res| {
+            let _ = tx.send(Message::Fs(res));
+        },
+        Config::default(),
+    )?;
+    watcher.watch(data_dir, RecursiveMode::NonRecursive)?;
+    Ok(Box::new(watcher))
+}
+
+fn poll_watcher(data_dir: &Path, tx: &Sender<Message>) -> notify::Result<Box<dyn Watcher + Send>> {
+    let tx = tx.clone();
+    let mut watcher = PollWatcher::new(
+        move |res| {
+            let _ = tx.send(Message::Fs(res));
+        },
+        Config::default().with_poll_interval(POLL_INTERVAL),
+    )?;
+    watcher.watch(data_dir, RecursiveMode::NonRecursive)?;
+    Ok(Box::new(watcher))
+}
+
+/// Block until `dir` exists. Returns false if a stop was requested meanwhile.
+fn wait_for_dir(dir: &Path, rx: &Receiver<Message>) -> bool {
+    while !dir.is_dir() {
+        if sleep_or_stop(rx, RETRY_INTERVAL) {
+            return false;
+        }
+    }
+    true
+}
+
+/// Wait for `duration`, draining stray file events. Returns true on stop.
+fn sleep_or_stop(rx: &Receiver<Message>, duration: Duration) -> bool {
+    let deadline = std::time::Instant::now() + duration;
+
+    loop {
+        let remaining = deadline.saturating_duration_since(std::time::Instant::now());
+        if remaining.is_zero() {
+            return false;
+        }
+        match rx.recv_timeout(remaining) {
+            Ok(Message::Stop) | Err(RecvTimeoutError::Disconnected) => return true,
+            Ok(Message::Fs(_)) => {}
+            Err(RecvTimeoutError::Timeout) => return false,
+        }
+    }
+}
@@ -0,0 +1,151 @@
+//! Attribution of file writes
+//!
+//! Backend code that writes a watched file registers the content it is
+//! about to write. When the watcher later sees the change it hashes the
+//! file: a matching entry tells it who wrote it, anything else is an
+//! external edit (Claude, another editor, a git checkout).
+
+use serde::Serialize;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Registered writes older than this are forgotten
+const WRITE_TTL: Duration = Duration::from_secs(30);
+
+/// Who produced a change to a watched file
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum WriteOrigin {
+    /// The app's own data layer (UI edits)
+    App,
+    /// Backend commands acting on the user's behalf (quick commands, inbox
+    /// actions). Unlike `App`, the frontend did not make the change itself
+    /// and must reload.
+    Backend,
+    /// Anything not registered: Claude, editors, git
+    External,
+}
+
+struct PendingWrite {
+    hash: u64,
+    origin: WriteOrigin,
+    at: Instant,
+}
+
+/// Shared registry of in-flight writes, cheap to clone
+#[derive(Clone, Default)]
+pub struct WriteRegistry {
+    inner: Arc<Mutex<HashMap<PathBuf, Vec<PendingWrite>>>>,
+}
+
+impl WriteRegistry {
+    /// Record that `content` is about to be written to `path` by `origin`
+    pub fn register(&self, path: &Path, content: &[u8], origin: WriteOrigin) {
+        let mut inner = match self.inner.lock() {
+            Ok(i) => i,
+            Err(_) => return,
+        };
+
+        inner
+            .entry(normalize(path))
+            .or_default()
+            .push(PendingWrite {
+                hash: hash_bytes(content),
+                origin,
+                at: Instant::now(),
+            });
+    }
+
+    /// Register and write in one step
+    pub fn write(&self, path: &Path, content: &str, origin: WriteOrigin) -> std::io::Result<()> {
+        self.register(path, content.as_bytes(), origin);
+        fs::write(path, content)
+    }
+
+    /// Work out who produced the current contents of `path`.
+    /// A matching registration is consumed so it cannot mask a later edit.
+    pub fn origin_of(&self, path: &Path) -> WriteOrigin {
+        let content = match fs::read(path) {
+            Ok(c) => c,
+            Err(_) => return WriteOrigin::External,
+        };
+        let hash = hash_bytes(&content);
+
+        let mut inner = match self.inner.lock() {
+            Ok(i) => i,
+            Err(_) => return WriteOrigin::External,
+        };
+
+        // Drop stale registrations while we're here
+        inner.retain(|_, writes| {
+            writes.retain(|w| w.at.elapsed() < WRITE_TTL);
+            !writes.is_empty()
+        });
+
+        let writes = match inner.get_mut(&normalize(path)) {
+            Some(w) => w,
+            None => return WriteOrigin::External,
+        };
+
+        match writes.iter().position(|w| w.hash == hash) {
+            Some(index) => writes.remove(index).origin,
+            None => WriteOrigin::External,
+        }
+    }
+}
+
+/// Resolve symlinks and separators so registry keys match watcher paths.
+/// Only the parent is canonicalized, since the file may not exist yet.
+fn normalize(path: &Path) -> PathBuf {
+    match (path.parent(), path.file_name()) {
+        (Some(parent), Some(name)) => fs::canonicalize(parent)
+            .map(|p| p.join(name))
+            .unwrap_or_else(|_| path.to_path_buf()),
+        _ => path.to_path_buf(),
+    }
+}
+
+fn hash_bytes(content: &[u8]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    content.hash(&mut hasher);
+    hasher.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn own_write_is_attributed_and_consumed() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("tasks.json");
+        let registry = WriteRegistry::default();
+
+        registry
+            .write(&path, "{\"tasks\":[]}", WriteOrigin::App)
+            .unwrap();
+
+        assert_eq!(registry.origin_of(&path), WriteOrigin::App);
+        // Seen once; the same content showing up again is not ours
+        assert_eq!(registry.origin_of(&path), WriteOrigin::External);
+    }
+
+    #[test]
+    fn external_overwrite_is_reported_as_external() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("inbox.json");
+        let registry = WriteRegistry::default();
+
+        registry
+            .write(&path, "{\"items\":[]}", WriteOrigin::App)
+            .unwrap();
+        fs::write(&path, "{\"items\":[{\"id\":\"inbox-1\"}]}").unwrap();
+
+        assert_eq!(registry.origin_of(&path), WriteOrigin::External);
+    }
+}
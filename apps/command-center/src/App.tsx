@@ -7,6 +7,7 @@ import { QuickLaunch } from './components/ui/QuickLaunch';
 import { NewProjectModal } from './components/ui/NewProjectModal';
 import { SettingsPanel } from './components/ui/SettingsPanel';
 import { VoiceCapture } from './components/ui/VoiceCapture';
+import { fileVoiceTranscript, isTauri } from './lib/tauri';
 import { SplashScreen } from './components/ui/SplashScreen';
 import { PipelineView } from './components/pipeline/PipelineView';
 import { DocsView } from './components/docs/DocsView';
@@ -120,21 +121,28 @@ export default function App() {
       {isSettingsOpen && <SettingsPanel onClose={closeSettings} />}
       {isVoiceCaptureOpen && (
         <VoiceCapture
-          onTranscript={(text) => {
-            // Add voice transcript to inbox
-            addInboxItem({
-              id: `inbox-voice-${Date.now()}`,
-              text,
-              type: 'note',
-              project: null,
-              priority: null,
-              status: 'pending',
-              createdAt: new Date().toISOString(),
-              read: false,
-              author: 'user',
-              parentId: null,
-              replies: [],
-            });
+          onTranscript={(text, transcript) => {
+            if (transcript && isTauri()) {
+              // Filed as a structured item; the file watcher reloads the inbox
+              fileVoiceTranscript(transcript).catch((error) =>
+                console.error('Failed to file voice transcript:', error)
+              );
+            } else {
+              // Add voice transcript to inbox
+              addInboxItem({
+                id: `inbox-voice-${Date.now()}`,
+                text,
+                type: 'note',
+                project: null,
+                priority: null,
+                status: 'pending',
+                createdAt: new Date().toISOString(),
+                read: false,
+                author: 'user',
+                parentId: null,
+                replies: [],
+              });
+            }
             // Notify user
             notifyVoiceTranscript(text);
           }}
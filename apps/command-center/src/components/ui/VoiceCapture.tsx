@@ -1,9 +1,18 @@
 import { useState, useEffect, useCallback } from 'react';
-import { checkVoiceAvailable, voiceCapture } from '@/lib/tauri';
+import { listen } from '@tauri-apps/api/event';
+import {
+  checkVoiceAvailable,
+  startVoiceCapture,
+  stopVoiceCapture,
+  cancelVoiceCapture,
+  type Transcript,
+  type VoicePartial,
+  type VoiceFinal,
+} from '@/lib/tauri';
 import clsx from 'clsx';
 
 interface VoiceCaptureProps {
-  onTranscript: (text: string) => void;
+  onTranscript: (text: string, transcript?: Transcript) => void;
   onClose: () => void;
 }
 
@@ -12,6 +21,8 @@ export function VoiceCapture({ onTranscript, onClose }: VoiceCaptureProps) {
   const [isRecording, setIsRecording] = useState(false);
   const [recordingTime, setRecordingTime] = useState(0);
   const [error, setError] = useState<string | null>(null);
+  const [sessionId, setSessionId] = useState<string | null>(null);
+  const [partialText, setPartialText] = useState('');
 
   // Check voice availability on mount
   useEffect(() => {
@@ -33,33 +44,74 @@ export function VoiceCapture({ onTranscript, onClose }: VoiceCaptureProps) {
     };
   }, [isRecording]);
 
+  // Partial text while recording; the final transcript ends the session
+  useEffect(() => {
+    if (!sessionId) return;
+
+    const unlisteners = [
+      listen<VoicePartial>('voice-partial', (event) => {
+        if (event.payload.sessionId === sessionId) {
+          setPartialText(event.payload.text);
+        }
+      }),
+      listen<VoiceFinal>('voice-final', (event) => {
+        const result = event.payload;
+        if (result.sessionId !== sessionId) return;
+
+        setSessionId(null);
+        setIsRecording(false);
+        if (result.cancelled) return;
+
+        if (result.transcript?.text) {
+          onTranscript(result.transcript.text, result.transcript);
+          onClose();
+        } else if (result.error) {
+          setError('Transcription failed');
+          console.error('Voice capture error:', result.error);
+        } else {
+          setError('No speech detected');
+        }
+      }),
+    ];
+
+    return () => {
+      unlisteners.forEach((p) => p.then((unlisten) => unlisten()));
+    };
+  }, [sessionId, onTranscript, onClose]);
+
   const handleRecord = useCallback(async () => {
     if (!isAvailable) {
       setError('Voice capture not available');
       return;
     }
 
-    if (isRecording) return;
+    // Second click stops; the result arrives as voice-final
+    if (isRecording) {
+      stopVoiceCapture(sessionId ?? undefined).catch((err) =>
+        console.error('Voice capture error:', err)
+      );
+      return;
+    }
 
-    setIsRecording(true);
     setRecordingTime(0);
     setError(null);
+    setPartialText('');
 
     try {
-      const transcript = await voiceCapture(5); // 5 second recording
-      if (transcript) {
-        onTranscript(transcript);
-        onClose();
-      } else {
-        setError('No speech detected');
-      }
+      setSessionId(await startVoiceCapture(true));
+      setIsRecording(true);
     } catch (err) {
       setError('Recording failed');
       console.error('Voice capture error:', err);
-    } finally {
-      setIsRecording(false);
     }
-  }, [isAvailable, isRecording, onTranscript, onClose]);
+  }, [isAvailable, isRecording, sessionId]);
+
+  const handleClose = useCallback(() => {
+    if (sessionId) {
+      cancelVoiceCapture(sessionId).catch(() => {});
+    }
+    onClose();
+  }, [sessionId, onClose]);
 
   const formatTime = (seconds: number) => {
     const mins = Math.floor(seconds / 60);
@@ -70,12 +122,12 @@ export function VoiceCapture({ onTranscript, onClose }: VoiceCaptureProps) {
   return (
     <div className="fixed inset-0 z-50 flex items-center justify-center">
       {/* Backdrop */}
-      <div className="absolute inset-0 bg-black/60 backdrop-blur-sm" onClick={onClose} />
+      <div className="absolute inset-0 bg-black/60 backdrop-blur-sm" onClick={handleClose} />
 
       {/* Modal */}
       <div className="relative bg-zinc-900 rounded-2xl border border-zinc-700 shadow-2xl p-8 animate-slide-in">
         <button
-          onClick={onClose}
+          onClick={handleClose}
           className="absolute top-4 right-4 text-zinc-500 hover:text-zinc-300 transition-colors"
         >
           <svg className="w-5 h-5" fill="none" stroke="currentColor" viewBox="0 0 24 24">
@@ -89,7 +141,7 @@ export function VoiceCapture({ onTranscript, onClose }: VoiceCaptureProps) {
           {/* Recording Button */}
           <button
             onClick={handleRecord}
-            disabled={!isAvailable || isRecording}
+            disabled={!isAvailable}
             className={clsx(
               'w-24 h-24 rounded-full flex items-center justify-center transition-all mx-auto mb-4',
               isRecording
@@ -125,6 +177,11 @@ export function VoiceCapture({ onTranscript, onClose }: VoiceCaptureProps) {
             )}
           </div>
 
+          {/* Live transcript */}
+          {partialText && (
+            <p className="text-sm text-zinc-200 mt-2 max-w-xs mx-auto">{partialText}</p>
+          )}
+
           {/* Error */}
           {error && (
             <div className="text-sm text-red-400 mt-2">{error}</div>
@@ -148,7 +205,7 @@ export function VoiceCapture({ onTranscript, onClose }: VoiceCaptureProps) {
 
           {/* Instructions */}
           <p className="text-xs text-zinc-500 mt-4 max-w-xs mx-auto">
-            Speak clearly into your microphone. Recording will automatically stop after 5 seconds.
+            Speak clearly into your microphone. Recording stops when you pause, or click again to stop.
           </p>
         </div>
       </div>
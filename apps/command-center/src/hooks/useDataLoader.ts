@@ -3,6 +3,7 @@
  */
 
 import { useEffect, useRef, useCallback } from 'react';
+import { listen, UnlistenFn } from '@tauri-apps/api/event';
 import { useAppStore, Activity, InboxItem } from '@/store';
 import {
   readProjects,
@@ -11,13 +12,10 @@ import {
   writeTasks,
   readInboxJson,
   writeInboxJson,
-  writeInbox,
-  generateInboxMarkdown,
   isTauri,
   readSyncConfig,
   MOCK_INBOX_ITEMS,
 } from '@/lib/tauri';
-import { mergeInboxItems, startSyncPoller } from '@/services/gist-sync';
 import { startGitHubSyncPoller, mergeRepoInboxItems, pushInboxToRepo } from '@/services/github-sync';
 import { notifyNewInboxItems } from '@/lib/notifications';
 
@@ -173,7 +171,7 @@ export function useDataLoader() {
 
       if (inboxChanged) {
         previousInboxRef.current = inboxJson;
-        // Save structured JSON data
+        // Save structured JSON data (the backend re-renders inbox.md from it)
         savePromises.push(
           writeInboxJson({
             version: '1.0.0',
@@ -181,9 +179,6 @@ export function useDataLoader() {
             items: inboxItems,
           })
         );
-        // Also generate and save markdown for Claude readability
-        const markdown = generateInboxMarkdown(inboxItems);
-        savePromises.push(writeInbox(markdown));
       }
 
       await Promise.all(savePromises);
@@ -298,40 +293,7 @@ export function useDataLoader() {
     loadData();
   }, [setProjects, setTasks, setInboxItems, setLoading]);
 
-  // Gist sync polling for mobile ↔ desktop sync
-  useEffect(() => {
-    let stopPoller: (() => void) | null = null;
-
-    const startGistSync = async () => {
-      const config = await readSyncConfig();
-      if (!config?.gistToken || !config?.gistId) return;
-
-      const intervalMs = config.pollIntervalMs || 60000; // Default 60s
-
-      stopPoller = startSyncPoller(
-        config.gistToken,
-        config.gistId,
-        intervalMs,
-        (remoteItems) => {
-          const currentInbox = useAppStore.getState().inboxItems;
-          const { merged, newCount } = mergeInboxItems(currentInbox, remoteItems);
-
-          if (newCount > 0) {
-            useAppStore.getState().setInboxItems(merged);
-            const preview = remoteItems[0]?.text;
-            notifyNewInboxItems(newCount, preview);
-            console.log(`[DataLoader] Synced ${newCount} new item(s) from Orbit`);
-          }
-        }
-      );
-    };
-
-    startGistSync();
-
-    return () => {
-      if (stopPoller) stopPoller();
-    };
-  }, []);
+  // Gist sync runs in the backend; its changes arrive as file events below
 
   // GitHub repo sync polling (Orbit ↔ Klarity via .taskboard repo)
   useEffect(() => {
@@ -368,6 +330,34 @@ export function useDataLoader() {
     };
   }, []);
 
+  // Reload files changed by backend commands (quick commands, inbox actions)
+  // or edited outside the app. The frontend's own saves are never echoed.
+  useEffect(() => {
+    if (!isTauri()) return;
+
+    const unlisteners: Promise<UnlistenFn>[] = [
+      listen('tasks-changed', async () => {
+        const { tasks: loaded } = await readTasks();
+        previousTasksRef.current = JSON.stringify(loaded);
+        setTasks(loaded);
+      }),
+      listen('projects-changed', async () => {
+        const { projects: loaded } = await readProjects();
+        previousProjectsRef.current = JSON.stringify(loaded);
+        setProjects(loaded);
+      }),
+      listen('inbox-updated', async () => {
+        const { items: loaded } = await readInboxJson();
+        previousInboxRef.current = JSON.stringify(loaded);
+        setInboxItems(loaded);
+      }),
+    ];
+
+    return () => {
+      unlisteners.forEach((p) => p.then((unlisten) => unlisten()));
+    };
+  }, [setProjects, setTasks, setInboxItems]);
+
   // Cleanup on unmount
   useEffect(() => {
     return () => {
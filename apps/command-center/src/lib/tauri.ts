@@ -5,7 +5,7 @@
  */
 
 import { invoke } from '@tauri-apps/api/core';
-import { Project, Task, InboxItem } from '@/store';
+import { Project, Task, InboxItem, InboxReply } from '@/store';
 
 // Check if we're running in Tauri
 export const isTauri = (): boolean => {
@@ -351,12 +351,132 @@ export async function writeInboxJson(data: InboxData): Promise<void> {
   await invoke('write_inbox_json', { data: JSON.stringify(data, null, 2) });
 }
 
+export interface InboxSyncSummary {
+  itemsAdded: number;
+  itemsUpdated: number;
+  repliesAdded: number;
+}
+
+/**
+ * Reconcile inbox.md edits (new checkboxes, ticked items, replies) into inbox.json
+ */
+export async function syncInbox(): Promise<InboxSyncSummary> {
+  if (!isTauri()) {
+    return { itemsAdded: 0, itemsUpdated: 0, repliesAdded: 0 };
+  }
+
+  return invoke<InboxSyncSummary>('sync_inbox');
+}
+
+// Inbox lifecycle (server-side, the watcher reloads inbox/tasks afterwards)
+
+/**
+ * Mark an inbox item done or skipped (or back to pending)
+ */
+export async function markInboxProcessed(
+  itemId: string,
+  status: 'done' | 'skipped' | 'pending' = 'done'
+): Promise<InboxItem | null> {
+  if (!isTauri()) return null;
+  return invoke<InboxItem>('mark_inbox_processed', { itemId, status });
+}
+
+/**
+ * Move items into ~/.taskboard/archive/inbox-YYYY-MM-DD.json.
+ * Without ids, every processed item is archived.
+ */
+export async function archiveInboxItems(
+  itemIds?: string[]
+): Promise<{ archived: number; path: string | null }> {
+  if (!isTauri()) return { archived: 0, path: null };
+  return invoke('archive_inbox_items', { itemIds: itemIds ?? null });
+}
+
+/**
+ * Create a task from an inbox item (linked via sourceInbox)
+ */
+export async function convertInboxToTask(itemId: string, projectId?: string): Promise<Task | null> {
+  if (!isTauri()) return null;
+  return invoke<Task>('convert_inbox_to_task', { itemId, projectId: projectId ?? null });
+}
+
+/**
+ * Assign an inbox item to a project (null clears it)
+ */
+export async function assignInboxItem(itemId: string, projectId: string | null): Promise<InboxItem | null> {
+  if (!isTauri()) return null;
+  return invoke<InboxItem>('assign_inbox_item', { itemId, projectId });
+}
+
+/**
+ * Add a user reply to an inbox item
+ */
+export async function replyToInboxItem(itemId: string, text: string): Promise<InboxReply | null> {
+  if (!isTauri()) return null;
+  return invoke<InboxReply>('reply_to_inbox_item', { itemId, text });
+}
+
+// Quick Commands
+
+export type QuickCommandOperation =
+  | { op: 'moveTask'; taskId: string; status: string }
+  | { op: 'createTask'; projectId: string; title: string; priority: string }
+  | { op: 'addComment'; taskId: string; text: string }
+  | { op: 'setPriority'; taskId: string; priority: string }
+  | { op: 'setStage'; projectId: string; stage: string };
+
+export interface PlannedOperation {
+  line: string;
+  summary: string;
+  operation: QuickCommandOperation;
+}
+
+export interface QuickCommandPreview {
+  itemId: string;
+  operations: PlannedOperation[];
+  errors: Array<{ line: string; message: string }>;
+}
+
+export interface QuickCommandResult {
+  itemId: string;
+  outcomes: Array<{ summary: string; error: string | null }>;
+  replyId: string;
+}
+
+/**
+ * Parse an inbox item's quick commands and preview the board changes
+ */
+export async function previewQuickCommands(itemId: string): Promise<QuickCommandPreview> {
+  if (!isTauri()) {
+    return { itemId, operations: [], errors: [] };
+  }
+
+  return invoke<QuickCommandPreview>('preview_quick_commands', { itemId });
+}
+
+/**
+ * Apply confirmed operations; the outcome is posted as a reply on the item
+ */
+export async function applyQuickCommands(
+  itemId: string,
+  operations: PlannedOperation[]
+): Promise<QuickCommandResult> {
+  if (!isTauri()) {
+    console.log('Mock: Applying quick commands', itemId, operations);
+    return { itemId, outcomes: [], replyId: '' };
+  }
+
+  return invoke<QuickCommandResult>('apply_quick_commands', { itemId, operations });
+}
+
 // Sync config for mobile ↔ desktop sync
 export interface SyncConfig {
-  // Gist-based sync (legacy)
+  // Gist sync of projects, tasks and inbox (run by the backend)
   gistToken: string;
   gistId: string;
   pollIntervalMs?: number;
+  /** API root for GitHub Enterprise; defaults to https://api.github.com */
+  gistApiUrl?: string;
   // GitHub repo sync (Orbit ↔ Klarity via .taskboard repo)
   github?: {
     token: string;
@@ -393,69 +513,65 @@ export async function writeSyncConfig(config: SyncConfig): Promise<void> {
   await invoke('write_sync_config', { data: JSON.stringify(config, null, 2) });
 }
 
-/**
- * Generate inbox.md content from inbox items (for Claude readability)
- */
-export function generateInboxMarkdown(items: InboxItem[]): string {
-  const pendingItems = items.filter(i => i.status === 'pending');
-  const doneItems = items.filter(i => i.status !== 'pending');
-
-  const formatDate = (dateStr: string) => {
-    const date = new Date(dateStr);
-    return date.toLocaleString('en-US', {
-      month: 'short',
-      day: 'numeric',
-      hour: '2-digit',
-      minute: '2-digit',
-    });
-  };
-
-  const formatItem = (item: InboxItem): string => {
-    const typeLabel = item.type.toUpperCase();
-    const priority = item.priority ? ` [${item.priority}]` : '';
-    const project = item.project ? `\n**Project:** @${item.project}` : '';
-    const readStatus = item.read ? 'read' : 'unread';
-
-    let md = `### [${typeLabel}] ${item.text}${priority}
-**ID:** ${item.id}
-**Created:** ${formatDate(item.createdAt)}
-**Status:** ${item.status} (${readStatus})${project}
-
-> ${item.author === 'claude' ? 'Claude' : 'User'}: ${item.text}
-`;
-
-    if (item.replies.length > 0) {
-      md += '\n**Replies:**\n';
-      item.replies.forEach(reply => {
-        const authorLabel = reply.author === 'claude' ? 'Claude' : 'User';
-        md += `> ${authorLabel} (${formatDate(reply.createdAt)}): ${reply.text}\n\n`;
-      });
-    }
+/** What a gist sync did */
+export interface SyncReport {
+  /** Gist revision the local files now match */
+  revision: string | null;
+  /** Entities changed locally by the gist */
+  pulled: ActivityEntity[];
+  /** Entities changed on the gist */
+  pushed: ActivityEntity[];
+  /** Ids changed on both sides, resolved by the merge */
+  conflicts: string[];
+  /** Whether both sides had changed since the last sync */
+  diverged: boolean;
+}
 
-    md += '\n---\n';
-    return md;
-  };
+/** Payload of `sync-status` */
+export interface SyncStatusEvent {
+  state: 'syncing' | 'synced' | 'failed';
+  at: string;
+  report?: SyncReport;
+  error?: string;
+}
 
-  let markdown = '# Inbox\n\n';
+/**
+ * Sync projects, tasks and inbox with the gist now. Fails when
+ * sync-config.json has no gistToken and gistId.
+ */
+export async function syncNow(): Promise<SyncReport | null> {
+  if (!isTauri()) return null;
+  return invoke<SyncReport>('sync_now');
+}
 
-  if (pendingItems.length > 0) {
-    markdown += '## Active Items\n\n';
-    pendingItems.forEach(item => {
-      markdown += formatItem(item);
-    });
-  }
+/** A local change the gist does not have yet */
+export interface PendingSyncChange extends ActivityEntity {
+  change: 'created' | 'updated' | 'deleted';
+  /** Edits coalesced into this entry */
+  edits: number;
+  firstChangedAt: string;
+  lastChangedAt: string;
+}
 
-  if (doneItems.length > 0) {
-    markdown += '\n## Processed Archive\n\n';
-    doneItems.slice(0, 10).forEach(item => {
-      markdown += formatItem(item);
-    });
-    if (doneItems.length > 10) {
-      markdown += `\n*(${doneItems.length - 10} older items omitted)*\n`;
-    }
-  }
+/** Sync health: last success, pending changes and the last error */
+export interface SyncStatus {
+  enabled: boolean;
+  revision: string | null;
+  lastSuccessAt: string | null;
+  lastAttemptAt: string | null;
+  /** Error of the last sync, when it failed */
+  lastError: string | null;
+  /** Failed syncs in a row */
+  attempts: number;
+  /** When the next automatic sync runs after a failure */
+  retryAt: string | null;
+  pendingCount: number;
+  pending: PendingSyncChange[];
+}
 
-  return markdown;
+export async function getSyncStatus(): Promise<SyncStatus | null> {
+  if (!isTauri()) return null;
+  return invoke<SyncStatus>('get_sync_status');
 }
 
 /**
@@ -856,6 +972,23 @@ export async function writeDocument(path: string, content: string): Promise<void
 
 // Voice API
 
+export interface TranscriptSegment {
+  startMs: number;
+  endMs: number;
+  text: string;
+  /** Mean token probability (0–1), when the backend reports it */
+  confidence: number | null;
+}
+
+export interface Transcript {
+  text: string;
+  segments: TranscriptSegment[];
+  /** Which backend produced it ("whisper-rs", "whisper-cli", ...) */
+  backend: string;
+  /** Why the preferred backend was passed over, when it was */
+  fallback?: string;
+}
+
 /**
  * Check if voice capture is available
  */
@@ -865,14 +998,485 @@ export async function checkVoiceAvailable(): Promise<boolean> {
 }
 
 /**
- * Capture voice and return transcript
+ * Capture voice until the speaker goes quiet (at most durationSecs) and
+ * return the transcript
  */
-export async function voiceCapture(durationSecs: number): Promise<string> {
+export async function voiceCapture(durationSecs: number): Promise<Transcript> {
   if (!isTauri()) {
     throw new Error('Voice capture not available in browser');
   }
 
-  return invoke<string>('voice_capture', { durationSecs });
+  return invoke<Transcript>('voice_capture', { durationSecs });
+}
+
+export interface VoicePartial {
+  sessionId: string;
+  /** Everything transcribed so far */
+  text: string;
+  chunks: number;
+}
+
+export interface VoiceFinal {
+  sessionId: string;
+  transcript: Transcript | null;
+  stoppedBy: 'requested' | 'silence' | 'noSpeech' | 'maxDuration' | 'endOfInput' | null;
+  cancelled: boolean;
+  error: string | null;
+}
+
+/**
+ * Push-to-talk: start a streaming capture and return its session id.
+ * Text arrives as `voice-partial` events while recording and the whole
+ * transcript as `voice-final`. With autoStop, recording also ends when the
+ * speaker goes quiet.
+ */
+export async function startVoiceCapture(autoStop: boolean): Promise<string> {
+  if (!isTauri()) {
+    throw new Error('Voice capture not available in browser');
+  }
+
+  return invoke<string>('start_voice_capture', { autoStop });
+}
+
+/**
+ * Push-to-talk: stop recording and return the final transcript.
+ * Without a session id the latest capture is stopped.
+ */
+export async function stopVoiceCapture(sessionId?: string): Promise<Transcript> {
+  if (!isTauri()) {
+    throw new Error('Voice capture not available in browser');
+  }
+
+  return invoke<Transcript>('stop_voice_capture', { sessionId: sessionId ?? null });
+}
+
+/**
+ * Stop recording and discard it
+ */
+export async function cancelVoiceCapture(sessionId: string): Promise<void> {
+  if (!isTauri()) return;
+  await invoke('cancel_voice_capture', { sessionId });
+}
+
+export interface VoiceInboxItem extends InboxItem {
+  source: 'voice';
+  intent: 'instruction' | 'taskCreation' | 'comment' | 'question';
+  /** Task the transcript refers to */
+  taskRef?: string;
+  /** YYYY-MM-DD */
+  dueDate?: string;
+  transcript: Transcript;
+}
+
+/**
+ * File a transcript as a pending inbox item, classified and linked to the
+ * project/task it mentions, with priority and due date picked out
+ */
+export async function fileVoiceTranscript(transcript: Transcript): Promise<VoiceInboxItem> {
+  return invoke<VoiceInboxItem>('file_voice_transcript', { transcript });
+}
+
+/**
+ * Transcribe an audio file (WAV, MP3, Ogg, M4A) with the configured backend
+ */
+export async function transcribeFile(path: string): Promise<Transcript> {
+  if (!isTauri()) {
+    throw new Error('Transcription not available in browser');
+  }
+  return invoke<Transcript>('transcribe_file', { path });
+}
+
+/** A voice capture kept in ~/.taskboard/voice (voice.keepRecordings) */
+export interface VoiceSessionRecord {
+  id: string;
+  createdAt: string;
+  durationMs: number;
+  /** Whisper or API model that produced the transcript */
+  model: string;
+  language: string;
+  stoppedBy: VoiceFinal['stoppedBy'];
+  transcript: Transcript;
+  retranscribedAt?: string;
+}
+
+/**
+ * Kept voice sessions, newest first
+ */
+export async function listVoiceSessions(): Promise<VoiceSessionRecord[]> {
+  if (!isTauri()) return [];
+  return invoke<VoiceSessionRecord[]>('list_voice_sessions');
+}
+
+/**
+ * Path of a kept session's WAV, for playback
+ */
+export async function getVoiceSessionAudio(sessionId: string): Promise<string> {
+  if (!isTauri()) {
+    throw new Error('Voice sessions not available in browser');
+  }
+  return invoke<string>('get_voice_session_audio', { sessionId });
+}
+
+/**
+ * Transcribe a kept session again with the current model
+ */
+export async function retranscribeVoiceSession(sessionId: string): Promise<VoiceSessionRecord> {
+  if (!isTauri()) {
+    throw new Error('Voice sessions not available in browser');
+  }
+  return invoke<VoiceSessionRecord>('retranscribe_voice_session', { sessionId });
+}
+
+/**
+ * Delete a kept session's audio and transcript
+ */
+export async function deleteVoiceSession(sessionId: string): Promise<void> {
+  if (!isTauri()) return;
+  return invoke<void>('delete_voice_session', { sessionId });
+}
+
+/** Payload of `voice-drop-processed` */
+export interface VoiceDropResult {
+  /** Where the file was moved (voice-drop/processed or voice-drop/failed) */
+  file: string;
+  itemId: string | null;
+  error: string | null;
+}
+
+export interface VoiceModel {
+  id: string;
+  file: string;
+  sizeMb: number;
+  englishOnly: boolean;
+  quantized: boolean;
+  installed: boolean;
+  /** Bytes fetched by an interrupted download */
+  partialBytes: number;
+  selected: boolean;
+}
+
+export interface VoiceModelProgress {
+  model: string;
+  downloaded: number;
+  total: number | null;
+}
+
+/**
+ * Installed and downloadable Whisper models
+ */
+export async function listVoiceModels(): Promise<VoiceModel[]> {
+  if (!isTauri()) return [];
+  return invoke<VoiceModel[]>('list_voice_models');
+}
+
+/**
+ * Download (or resume) a model. Progress arrives as `voice-model-progress`
+ * events; resolves once the checksum is verified.
+ */
+export async function downloadVoiceModel(modelId: string): Promise<void> {
+  if (!isTauri()) {
+    throw new Error('Model download not available in browser');
+  }
+  await invoke('download_voice_model', { modelId });
+}
+
+/**
+ * Delete a downloaded model
+ */
+export async function deleteVoiceModel(modelId: string): Promise<void> {
+  if (!isTauri()) return;
+  await invoke('delete_voice_model', { modelId });
+}
+
+/**
+ * Choose the model and language for local transcription (saved to config)
+ */
+export async function setVoiceModel(modelId: string, language: string): Promise<void> {
+  if (!isTauri()) return;
+  await invoke('set_voice_model', { modelId, language });
+}
+
+// Agent API
+
+/** A finished agent run, as recorded on the task under `agentRuns` */
+export interface AgentRun {
+  id: string;
+  /** null for runs started from an inbox item */
+  taskId: string | null;
+  inboxItemId?: string;
+  projectId: string;
+  startedAt: string;
+  finishedAt: string;
+  durationMs: number;
+  /** null when the CLI was killed at the timeout */
+  exitCode: number | null;
+  status: 'succeeded' | 'failed' | 'timedOut' | 'cancelled';
+  logFile: string;
+}
+
+/** Payload of `agent-run-started` */
+export interface AgentRunStarted {
+  runId: string;
+  taskId: string | null;
+  inboxItemId?: string;
+  projectId: string;
+}
+
+/** Payload of `agent-output`, one per line the agent prints */
+export interface AgentOutput {
+  runId: string;
+  taskId: string | null;
+  inboxItemId?: string;
+  stream: 'stdout' | 'stderr';
+  line: string;
+}
+
+/**
+ * Run the agent CLI on a task in its project's repository. The run is
+ * queued as an agent job; output arrives as `agent-output` events and it
+ * resolves with the last run once the job is done.
+ */
+export async function runAgent(taskId: string): Promise<AgentRun> {
+  if (!isTauri()) {
+    throw new Error('Agents not available in browser');
+  }
+  return invoke<AgentRun>('run_agent', { taskId });
+}
+
+/** A queued agent run; payload of `agent-job` */
+export interface AgentJob {
+  id: string;
+  taskId: string;
+  projectId: string;
+  state: 'queued' | 'running' | 'succeeded' | 'failed' | 'cancelled';
+  attempts: number;
+  maxAttempts: number;
+  createdAt: string;
+  updatedAt: string;
+  /** A retry waits until then */
+  notBefore?: string;
+  /** Run ids, oldest first */
+  runs: string[];
+  error: string | null;
+}
+
+/** Payload of `agent-queue-error`, when the job queue could not be read or saved */
+export interface QueueErrorEvent {
+  message: string;
+}
+
+/**
+ * Queue an agent run of a task. Progress arrives as `agent-job` events.
+ */
+export async function enqueueAgentJob(taskId: string): Promise<AgentJob> {
+  if (!isTauri()) {
+    throw new Error('Agents not available in browser');
+  }
+  return invoke<AgentJob>('enqueue_agent_job', { taskId });
+}
+
+/**
+ * Queued, running and finished agent jobs, newest first
+ */
+export async function listAgentJobs(): Promise<AgentJob[]> {
+  if (!isTauri()) return [];
+  return invoke<AgentJob[]>('list_agent_jobs');
+}
+
+/**
+ * Cancel a queued job, or stop a running one
+ */
+export async function cancelAgentJob(jobId: string): Promise<AgentJob> {
+  if (!isTauri()) {
+    throw new Error('Agents not available in browser');
+  }
+  return invoke<AgentJob>('cancel_agent_job', { jobId });
+}
+
+/**
+ * Full output of a past agent run
+ */
+export async function readAgentLog(runId: string): Promise<string> {
+  if (!isTauri()) return '';
+  return invoke<string>('read_agent_log', { runId });
+}
+
+/** A change to a task or project made while an agent session was open */
+export interface BoardMutation {
+  at: string;
+  entity: 'task' | 'project';
+  id: string;
+  projectId: string | null;
+  title: string | null;
+  change: 'added' | 'updated' | 'removed';
+  /** Top-level fields that changed, for updates */
+  fields: string[];
+}
+
+/** A recorded agent session; also the payload of `agent-session` */
+export interface AgentSession {
+  id: string;
+  source: 'runner' | 'hook';
+  /** The task's assignedAgent, or "claude" */
+  agent: string;
+  taskId: string | null;
+  inboxItemId?: string;
+  projectId: string | null;
+  runId?: string;
+  repo: string | null;
+  startedAt: string;
+  /** null while the session is open */
+  finishedAt: string | null;
+  status: string | null;
+  prompt: string;
+  output: string;
+  files: { path: string; change: 'added' | 'modified' | 'deleted' | 'renamed' }[];
+  board: BoardMutation[];
+}
+
+/**
+ * Recorded agent sessions for a task, newest first
+ */
+export async function listAgentSessions(taskId: string): Promise<AgentSession[]> {
+  if (!isTauri()) return [];
+  return invoke<AgentSession[]>('list_agent_sessions', { taskId });
+}
+
+/** The agent picked for a task by the orchestrator's routing rules */
+export interface AgentSuggestion {
+  /** null when no available agent fits */
+  agent: string | null;
+  reason: string;
+  /** Path of the agent's definition, when the file exists */
+  definition: string | null;
+}
+
+/**
+ * The agent (designer, architect, qa, dev, walkthrough) suited to a task
+ */
+export async function suggestAgent(taskId: string): Promise<AgentSuggestion | null> {
+  if (!isTauri()) return null;
+  return invoke<AgentSuggestion>('suggest_agent', { taskId });
+}
+
+/**
+ * The full prompt for a task: master instructions, agent definition and the
+ * task. Uses the suggested agent unless `agent` names one.
+ */
+export async function buildAgentPrompt(taskId: string, agent?: string): Promise<string> {
+  if (!isTauri()) {
+    throw new Error('Agents not available in browser');
+  }
+  return invoke<string>('build_agent_prompt', { taskId, agent });
+}
+
+/** Payload of `agent-inbox-trigger`, once an inbox item has been answered */
+export interface InboxTriggerOutcome {
+  itemId: string;
+  state: 'succeeded' | 'failed' | 'timedOut' | 'cancelled' | 'unrouted' | 'dryRun';
+  projectId: string | null;
+  run: AgentRun | null;
+  /** Empty when nothing was replied */
+  replyId: string;
+  /** Why the item could not be processed at all */
+  error?: string;
+}
+
+/**
+ * Switch starting the agent from `@claude` inbox items on or off. Switching
+ * it off also stops the item in progress.
+ */
+export async function setInboxTrigger(enabled: boolean, dryRun?: boolean): Promise<void> {
+  if (!isTauri()) return;
+  await invoke('set_inbox_trigger', { enabled, dryRun });
+}
+
+// Activity API
+
+export type ActivityActor = 'user' | 'claude' | 'agent' | 'sync';
+
+/** An entity an action touched */
+export interface ActivityEntity {
+  kind: 'project' | 'task' | 'inboxItem' | 'job' | 'session' | 'document';
+  id: string;
+  projectId?: string;
+}
+
+/** One action from the activity log (advanced.logActions) */
+export interface ActivityEntry {
+  at: string;
+  actor: ActivityActor;
+  command: string;
+  entities: ActivityEntity[];
+  outcome: 'ok' | 'error';
+  error?: string;
+  detail?: string;
+}
+
+export interface ActivityFilter {
+  /** Actions on the project or any of its entities, for a project timeline */
+  projectId?: string;
+  entityId?: string;
+  actor?: ActivityActor;
+  command?: string;
+  outcome?: 'ok' | 'error';
+  /** RFC 3339 bounds, inclusive */
+  since?: string;
+  until?: string;
+  /** Defaults to 200 */
+  limit?: number;
+}
+
+/** Payload of `activity-log-error`, when an entry could not be written */
+export interface ActivityLogErrorEvent {
+  message: string;
+}
+
+/** Logged actions matching `filters`, newest first */
+export async function getActivityFeed(filters?: ActivityFilter): Promise<ActivityEntry[]> {
+  if (!isTauri()) return [];
+  return invoke<ActivityEntry[]>('get_activity_feed', { filters });
+}
+
+// Notification Center API
+
+export type NotificationSource = 'inbox' | 'reply' | 'reminder' | 'agent';
+
+/** A notification from the history, whether or not a toast was shown */
+export interface NotificationRecord {
+  id: string;
+  source: NotificationSource;
+  /** The board entity it refers to */
+  entity: { kind: 'task' | 'inboxItem' | 'project'; id: string } | null;
+  title: string;
+  body: string;
+  read: boolean;
+  createdAt: string;
+}
+
+/** Payload of `notification-history-error`, when a notification could not be stored */
+export interface NotificationHistoryError {
+  message: string;
+}
+
+/** Notification history, newest first */
+export async function listNotifications(
+  unreadOnly?: boolean,
+  limit?: number
+): Promise<NotificationRecord[]> {
+  if (!isTauri()) return [];
+  return invoke<NotificationRecord[]>('list_notifications', { unreadOnly, limit });
+}
+
+export async function markNotificationRead(id: string): Promise<void> {
+  if (!isTauri()) return;
+  await invoke('mark_notification_read', { id });
+}
+
+/** Clear the history, or only the entries already read */
+export async function clearNotifications(readOnly?: boolean): Promise<void> {
+  if (!isTauri()) return;
+  await invoke('clear_notifications', { readOnly });
 }
 
 // External App API
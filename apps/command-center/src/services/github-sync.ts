@@ -8,7 +8,7 @@
  */
 
 import { InboxItem } from '@/store';
-import { writeInboxJson, writeInbox, generateInboxMarkdown } from '@/lib/tauri';
+import { writeInboxJson } from '@/lib/tauri';
 
 const GITHUB_API = 'https://api.github.com';
 
@@ -109,7 +109,7 @@ export async function pullInboxFromRepo(
 
 /**
  * Merge remote inbox items into local, detecting new items.
- * Same logic as gist-sync but kept here for independence.
+ * New items arrive unread; replies of known items are merged.
  */
 export function mergeRepoInboxItems(
   localItems: InboxItem[],
@@ -181,16 +181,12 @@ export function mergeRepoInboxItems(
  * This triggers the Rust file watcher → Windows notification.
  */
 async function writeToLocal(items: InboxItem[]): Promise<void> {
-  // Write structured JSON
+  // Write structured JSON (the backend re-renders inbox.md from it)
   await writeInboxJson({
     version: '1.0.0',
     lastUpdated: new Date().toISOString(),
     items,
   });
-
-  // Also regenerate markdown for Claude readability
-  const markdown = generateInboxMarkdown(items);
-  await writeInbox(markdown);
 }
 
 /**
@@ -104,6 +104,7 @@ export interface Task {
   comments: TaskComment[];
   createdBy: 'agent' | 'user';
   sourceDoc: string | null;
+  sourceInbox?: string; // Inbox item this task was converted from
 }
 
 export interface InboxReply {
@@ -121,6 +122,8 @@ export interface InboxItem {
   priority: Priority | null;
   status: 'pending' | 'done' | 'skipped';
   createdAt: string;
+  processedAt?: string; // Set when the item leaves pending
+  taskId?: string; // Task created from this item
   // Threading & read tracking
   read: boolean;
   author: 'user' | 'claude';